@@ -1,3 +1,5 @@
+#[cfg(feature = "serve")]
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 
 use clap::Parser;
@@ -17,6 +19,11 @@ pub(super) struct Arguments {
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub(super) quiet: u8,
 
+    /// Write the library's spans and events to this file as JSON lines, with timings for each
+    /// frame, observatory, and output. Includes per star and per observation spans with `-vvv`
+    #[arg(long, global = true, value_name = "FILE")]
+    pub(super) trace_json: Option<PathBuf>,
+
     #[command(subcommand)]
     pub(super) sub_command: Commands,
 }
@@ -41,6 +48,29 @@ pub(super) enum Commands {
         /// Filepath to output observatories to.
         #[arg(short, long, default_value = "universe.json")]
         universe_output: PathBuf,
+
+        /// Give every star, planet, and moon a rotation, not just habitable planets
+        #[arg(long)]
+        rotate_all_bodies: bool,
+
+        /// Most moons given to any one planet
+        #[arg(long, default_value_t = 100)]
+        max_moons: usize,
+
+        /// Age of the galaxy in billions of years, so the heavier, shorter lived stars become
+        /// giants or white dwarfs. Every star stays on the main sequence if not given
+        #[arg(long, value_name = "GYR")]
+        galaxy_age_gyr: Option<f64>,
+
+        /// Instead of writing the universe, build it twice with the same seed and a different
+        /// number of threads and report the first file that differs between the builds
+        #[arg(long)]
+        check_determinism: bool,
+    },
+    /// Work with the observatories of an existing universe
+    Observatories {
+        #[command(subcommand)]
+        command: ObservatoriesCommands,
     },
     /// Simulate using given observatories and bodies
     Simulate {
@@ -72,8 +102,180 @@ pub(super) enum Commands {
         program: String,
 
         /// Output directory for observations, output structure will be like
-        /// `/output_path/observatory_id/time.ext`
-        #[arg(short, long, default_value = ".")]
+        /// `/output_path/observatory_id/time.ext`. Defaults to the output file root of the
+        /// program, relative to the program file, or the working directory when simulating a
+        /// universe and observatories
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Instead of an observation every step, make one at each local midnight of the
+        /// observatory with this name, or this ID with --directories-by-id (to the nearest hour)
+        #[arg(long, value_name = "OBSERVATORY")]
+        at_local_midnight: Option<String>,
+
+        /// Also write a thumbnail of each observation to `/output_path/observatory_id/thumbs/`,
+        /// leaving out bodies smaller than this angular radius in degrees. Ignored if the program
+        /// file lists its own outputs
+        #[arg(long, value_name = "DEGREES")]
+        thumbnails: Option<f64>,
+
+        /// Name each observatory's output directory after its ID (the ID of the body it is on and
+        /// its latitude and longitude) instead of its name, so renaming it doesn't move its output
+        #[arg(long)]
+        directories_by_id: bool,
+
+        /// Also draw every position of the body with this name on one chart per observatory, at
+        /// `/output_path/observatory_id/trail-BODY.svg`, like an analemma or star trail
+        #[arg(long, value_name = "BODY")]
+        trail: Option<String>,
+
+        /// Only draw constellations from this sky culture, can be given more than once. Draws
+        /// every constellation if not given
+        #[arg(long = "culture", value_name = "CULTURE")]
+        cultures: Vec<String>,
+
+        /// Instead of writing to the output directory, simulate twice into temporary directories
+        /// with a different number of threads and report the first file that differs between the
+        /// runs
+        #[arg(long)]
+        check_determinism: bool,
+    },
+    /// Check a universe for bodies whose positions may be visibly wrong because of the limited
+    /// precision of floating point numbers
+    Validate {
+        /// Path to a JSON file that represents the bodies in the universe
+        universe: PathBuf,
+
+        /// Latest time in hours the universe will be simulated to, positions drift further from
+        /// their true values later on
+        #[arg(short, long, default_value_t = 87_660.0)]
+        max_time: f64,
+    },
+    /// Print a summary of what a program contains: its bodies, observatories, and outputs
+    Inspect {
+        /// Path to a JSON file that represents the program
+        #[arg(short, long)]
+        program: PathBuf,
+    },
+    /// Write the orbital elements of every body in a universe as CSV
+    Elements {
+        /// Path to a JSON file that represents the bodies in the universe
+        universe: PathBuf,
+
+        /// Path to write the CSV to, or standard output if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Estimate a Hohmann transfer between two bodies orbiting the same parent, and when to
+    /// depart
+    Transfer {
+        /// Path to a JSON file that represents the bodies in the universe
+        #[arg(short, long)]
+        universe: PathBuf,
+
+        /// Name of the body to depart from
+        #[arg(long)]
+        from: String,
+
+        /// Name of the body to arrive at
+        #[arg(long)]
+        to: String,
+
+        /// Earliest departure time to list, in hours
+        #[arg(short, long, default_value_t = 0.0)]
+        start_time: f64,
+
+        /// Latest departure time to list, in hours
+        #[arg(short, long, default_value_t = 87_660.0)]
+        end_time: f64,
+    },
+    /// Serve charts and observations of any time on demand over HTTP, at
+    /// `/chart/<observatory>/<time>.svg` and `/observations/<observatory>/<time>.json`
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Path to a JSON file that represents the bodies in the universe
+        #[arg(short, long)]
+        universe: PathBuf,
+
+        /// Path to a JSON file that represents the observatories
+        #[arg(short = 'b', long)]
+        observatories: PathBuf,
+
+        /// Address to listen on. Only this machine can connect by default, use `0.0.0.0` to serve
+        /// every network interface
+        #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+        bind: IpAddr,
+
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// Largest number of requests answered at once, later requests wait their turn
+        #[arg(short, long, default_value_t = 4)]
+        threads: usize,
+    },
+    /// Merge universes into one, adding the root of each universe after the first as a child of
+    /// the first universe's root. Body IDs in the added universes change, so observatories on
+    /// them will need updating.
+    Merge {
+        /// Path to the universe that the others are added to
+        base: PathBuf,
+
+        /// Paths to the universes to add
+        #[arg(required = true)]
+        others: Vec<PathBuf>,
+
+        /// Rotate each added universe about the root of the first, given as angles in degrees
+        /// about the x, y, then z axes e.g. `--rotate 30,0,0`
+        #[arg(long, value_parser = parse_triple, allow_hyphen_values = true)]
+        rotate: Option<[f64; 3]>,
+
+        /// Move each added universe after rotating it, given in light seconds along the x, y,
+        /// and z axes e.g. `--translate 6.3e7,0,0`
+        #[arg(long, value_parser = parse_triple, allow_hyphen_values = true)]
+        translate: Option<[f64; 3]>,
+
+        /// Filepath to output the merged universe to
+        #[arg(short, long, default_value = "universe.json")]
         output: PathBuf,
     },
 }
+
+/// Parses three comma separated numbers, e.g. `1,-2.5,3e8`
+fn parse_triple(value: &str) -> Result<[f64; 3], String> {
+    let numbers = value
+        .split(',')
+        .map(|n| n.trim().parse::<f64>().map_err(|e| format!("'{n}': {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    numbers
+        .try_into()
+        .map_err(|n: Vec<f64>| format!("expected 3 comma separated numbers, found {}", n.len()))
+}
+
+/// Commands for working with observatories
+#[derive(clap::Subcommand)]
+pub(super) enum ObservatoriesCommands {
+    /// Place new observatories at random locations on the planets of a universe, without
+    /// regenerating the universe. Planets that rotate and orbit a star are used.
+    Generate {
+        /// Path to a JSON file that represents the bodies in the universe
+        #[arg(short, long)]
+        universe: PathBuf,
+
+        /// Seed for the random number generator, leave blank for a random seed
+        #[arg(short, long)]
+        seed: Option<String>,
+
+        /// Number of observatories to place on each planet
+        #[arg(short, long, default_value_t = 1)]
+        per_planet: usize,
+
+        /// Furthest north or south of the equator observatories are placed, in degrees
+        #[arg(long, default_value_t = 90.0)]
+        max_latitude: f64,
+
+        /// Filepath to output observatories to
+        #[arg(short, long, default_value = "observatories.json")]
+        out: PathBuf,
+    },
+}
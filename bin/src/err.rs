@@ -1,11 +1,20 @@
-use serde_json::Error as ParseError;
+use astrograph::generator::Error as GenerateError;
+use astrograph::json::Error as ParseError;
+use astrograph::program::ObservationError;
 use std::io::Error as IoError;
 
 #[derive(Debug)]
 pub(super) enum Error {
     Parse(ParseError),
+    Generate(GenerateError),
     Write(IoError),
     Read(IoError),
+    Transform(String),
+    Transfer(String),
+    Nondeterministic(String),
+    Observe(ObservationError),
+    #[cfg(feature = "serve")]
+    Serve(String),
     Multiple(Vec<Self>),
 }
 
@@ -20,7 +29,20 @@ impl Error {
 
     fn into_vec(self) -> Vec<Self> {
         match self {
-            Self::Parse(_) | Self::Read(_) | Self::Write(_) => vec![self],
+            Self::Parse(_)
+            | Self::Generate(_)
+            | Self::Read(_)
+            | Self::Write(_)
+            | Self::Transform(_)
+            | Self::Transfer(_)
+            | Self::Nondeterministic(_)
+            | Self::Observe(_) => {
+                vec![self]
+            }
+            #[cfg(feature = "serve")]
+            Self::Serve(_) => {
+                vec![self]
+            }
             Self::Multiple(vec) => {
                 // Recursively search for "multiple" type errors to flatten them into one level
                 vec.into_iter().flat_map(Self::into_vec).collect()
@@ -30,7 +52,16 @@ impl Error {
 
     pub fn flatten(self) -> Self {
         match self {
-            Self::Parse(_) | Self::Read(_) | Self::Write(_) => self,
+            Self::Parse(_)
+            | Self::Generate(_)
+            | Self::Read(_)
+            | Self::Write(_)
+            | Self::Transform(_)
+            | Self::Transfer(_)
+            | Self::Nondeterministic(_)
+            | Self::Observe(_) => self,
+            #[cfg(feature = "serve")]
+            Self::Serve(_) => self,
             Self::Multiple(_) => {
                 // Map any nested multiple errors into one level
                 Self::Multiple(self.into_vec())
@@ -55,12 +86,31 @@ impl std::fmt::Display for Error {
             Self::Parse(e) => {
                 write!(f, "Parsing Error: {e}.")
             }
+            Self::Generate(e) => {
+                write!(f, "Generation Error: {e}.")
+            }
             Self::Read(e) => {
                 write!(f, "Read Error: {e}.")
             }
             Self::Write(e) => {
                 write!(f, "Write Error: {e}.")
             }
+            Self::Transform(e) => {
+                write!(f, "Transform Error: {e}.")
+            }
+            Self::Transfer(e) => {
+                write!(f, "Transfer Error: {e}.")
+            }
+            Self::Nondeterministic(e) => {
+                write!(f, "Nondeterministic Output: {e}.")
+            }
+            Self::Observe(e) => {
+                write!(f, "Observation Error: {e}.")
+            }
+            #[cfg(feature = "serve")]
+            Self::Serve(e) => {
+                write!(f, "Serve Error: {e}.")
+            }
         }
     }
 }
@@ -69,6 +119,27 @@ impl std::error::Error for Error {}
 
 impl From<serde_json::Error> for Error {
     fn from(value: serde_json::Error) -> Self {
-        Self::Parse(value)
+        Self::Parse(value.into())
+    }
+}
+
+impl From<ObservationError> for Error {
+    fn from(value: ObservationError) -> Self {
+        Self::Observe(value)
+    }
+}
+
+impl From<GenerateError> for Error {
+    fn from(value: GenerateError) -> Self {
+        Self::Generate(value)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        match value {
+            ParseError::Read(e) => Self::Read(e),
+            _ => Self::Parse(value),
+        }
     }
 }
@@ -6,18 +6,39 @@ use std::{
 };
 
 use astrograph::{
-    body::{observatory::WeakObservatory, Body},
-    generator::{artifexian::ArtifexianBuilder, Generator},
-    output::svg::Svg,
+    body::{
+        observatory::{Observatory, WeakObservatory},
+        Body,
+    },
+    determinism::{self, Artifacts},
+    generator::{
+        artifexian::ArtifexianBuilder,
+        observatories::{self as placement, PlacementOptionsBuilder},
+        Generator,
+    },
+    output::{
+        svg::{comet_tails, effective_horizon, planetary_rings, MultiRes, Svg, Trail},
+        Output,
+    },
     program::{Program, ProgramBuilder},
     projection::StatelessOrthographic,
+    Float,
 };
 use clap::Parser;
+use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
 use log::{debug, error, info, trace, warn};
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 mod cli;
 mod err;
+#[cfg(feature = "serve")]
+mod serve;
+
+/// Number of bytes in a gibibyte
+const GIBIBYTE: u64 = 1 << 30;
+
+/// Warn before simulating if the outputs are estimated to be larger than this
+const OUTPUT_SIZE_WARNING_BYTES: u64 = 10 * GIBIBYTE;
 
 fn main() {
     human_panic::setup_panic!();
@@ -32,6 +53,9 @@ fn main() {
 fn try_main() -> Result<(), err::Error> {
     let args = cli::Arguments::parse();
     setup_log(args.quiet, args.verbose);
+    if let Some(path) = &args.trace_json {
+        setup_trace_json(path, args.verbose)?;
+    }
 
     match args.sub_command {
         cli::Commands::Build {
@@ -39,9 +63,17 @@ fn try_main() -> Result<(), err::Error> {
             seed,
             observatory_output,
             universe_output,
+            rotate_all_bodies,
+            max_moons,
+            galaxy_age_gyr,
+            check_determinism,
         } => build(
             seed.as_ref(),
             star_count,
+            rotate_all_bodies,
+            max_moons,
+            galaxy_age_gyr,
+            check_determinism,
             &universe_output,
             &observatory_output,
         ),
@@ -53,15 +85,63 @@ fn try_main() -> Result<(), err::Error> {
             observatories,
             program,
             output,
+            at_local_midnight,
+            thumbnails,
+            directories_by_id,
+            trail,
+            cultures,
+            check_determinism,
         } => simulate(
             start_time,
             end_time,
             step_size,
+            at_local_midnight.as_deref(),
+            thumbnails,
+            directories_by_id,
+            trail,
+            cultures,
+            check_determinism,
             universe.as_ref(),
             observatories.as_ref(),
             &program,
-            &output,
+            output.as_deref(),
+            args.quiet,
         ),
+        cli::Commands::Observatories {
+            command:
+                cli::ObservatoriesCommands::Generate {
+                    universe,
+                    seed,
+                    per_planet,
+                    max_latitude,
+                    out,
+                },
+        } => generate_observatories(&universe, seed.as_ref(), per_planet, max_latitude, &out),
+        cli::Commands::Validate { universe, max_time } => validate(&universe, max_time),
+        cli::Commands::Transfer {
+            universe,
+            from,
+            to,
+            start_time,
+            end_time,
+        } => transfer(&universe, &from, &to, start_time, end_time),
+        cli::Commands::Inspect { program } => inspect(&program),
+        cli::Commands::Elements { universe, output } => elements(&universe, output.as_deref()),
+        #[cfg(feature = "serve")]
+        cli::Commands::Serve {
+            universe,
+            observatories,
+            bind,
+            port,
+            threads,
+        } => serve::serve(&universe, &observatories, (bind, port).into(), threads),
+        cli::Commands::Merge {
+            base,
+            others,
+            rotate,
+            translate,
+            output,
+        } => merge(&base, &others, rotate, translate, &output),
     }
 }
 
@@ -84,13 +164,69 @@ fn setup_log(quiet: u8, verbosity: u8) {
     builder.init();
 }
 
+/// Writes spans and events from the library to a file as JSON lines, as well as logging them to
+/// the console as usual
+fn setup_trace_json(path: &Path, verbosity: u8) -> Result<(), err::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(err::Error::write_error)?;
+    }
+    let file = fs::File::create(path).map_err(err::Error::write_error)?;
+
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(if verbosity >= 3 {
+            tracing_subscriber::filter::LevelFilter::TRACE
+        } else {
+            tracing_subscriber::filter::LevelFilter::DEBUG
+        })
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::sync::Mutex::new(file))
+        .finish();
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        warn!("Could not write traces to {}: {e}", path.display());
+    }
+    Ok(())
+}
+
 /// Builds a new universe based on the user defined parameters
+#[allow(clippy::too_many_arguments)]
 fn build(
     seed: Option<&String>,
     star_count: usize,
+    rotate_all_bodies: bool,
+    max_moons: usize,
+    galaxy_age_gyr: Option<f64>,
+    check_determinism: bool,
     universe_output: &Path,
     observatory_output: &Path,
 ) -> Result<(), err::Error> {
+    let seed = seed_bytes(seed);
+    let mut generator = ArtifexianBuilder::default();
+    generator
+        .star_count(star_count)
+        .rotate_all_bodies(rotate_all_bodies)
+        .max_moons_per_planet(max_moons);
+    if let Some(age) = galaxy_age_gyr {
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+        generator.galaxy_age_gyr(age as Float);
+    }
+    let generator = generator.build().unwrap();
+
+    if check_determinism {
+        return compare_runs(|_| {
+            let (tree, observatories) = generator.generate(&mut XorShiftRng::from_seed(seed))?;
+            let observatories: Vec<WeakObservatory> =
+                observatories.into_iter().map(Into::into).collect();
+            Ok(Artifacts::from([
+                ("universe.json".into(), determinism::hash_universe(&tree)?),
+                (
+                    "observatories.json".into(),
+                    determinism::hash_bytes(&serde_json::to_vec(&observatories)?),
+                ),
+            ]))
+        });
+    }
+
     for p in [universe_output, observatory_output] {
         if let Some(path) = p.parent() {
             if let Err(e) = fs::create_dir_all(path) {
@@ -103,6 +239,32 @@ fn build(
         }
     }
 
+    let (tree, observatories) = generator.generate(&mut XorShiftRng::from_seed(seed))?;
+
+    // Write universe out
+    let json = serde_json::to_string(&tree)?;
+
+    let mut output_file: PathBuf = universe_output.into();
+    if output_file.is_dir() {
+        output_file.set_file_name("universe.json");
+    }
+    info!(
+        "Writing universe to file {}",
+        output_file.to_str().unwrap_or("UNPRINTABLE PATH")
+    );
+    fs::write(output_file, json).map_err(err::Error::write_error)?;
+
+    write_observatories(observatories, observatory_output)
+}
+
+/// Makes a random number generator from the user's seed, or a random seed if there isn't one or
+/// it isn't a number
+fn seeded_rng(seed: Option<&String>) -> XorShiftRng {
+    XorShiftRng::from_seed(seed_bytes(seed))
+}
+
+/// Returns the user's seed, or a random seed if there isn't one or it isn't a number
+fn seed_bytes(seed: Option<&String>) -> [u8; 16] {
     let seed_num = seed
         .map_or_else(
             || rand::thread_rng().clone().gen(),
@@ -115,27 +277,30 @@ fn build(
 
     debug!("Seed: 0x{:x}", u128::from_be_bytes(seed_num));
 
-    let mut rng = XorShiftRng::from_seed(seed_num);
-    let (tree, observatories) = ArtifexianBuilder::default()
-        .star_count(star_count)
-        .build()
-        .unwrap()
-        .generate(&mut rng);
-
-    // Write universe out
-    let json = serde_json::to_string(&tree)?;
+    seed_num
+}
 
-    let mut output_file: PathBuf = universe_output.into();
-    if output_file.is_dir() {
-        output_file.set_file_name("universe.json");
+/// Runs an operation twice with a different number of threads, see [`determinism::check`], and
+/// returns an error naming the first artifact that differed between the runs
+fn compare_runs<F>(run: F) -> Result<(), err::Error>
+where
+    F: Fn(usize) -> Result<Artifacts, err::Error> + Sync,
+{
+    match determinism::check(run)? {
+        None => {
+            info!("Both runs produced the same output");
+            Ok(())
+        }
+        Some(divergence) => Err(err::Error::Nondeterministic(divergence.to_string())),
     }
-    info!(
-        "Writing universe to file {}",
-        output_file.to_str().unwrap_or("UNPRINTABLE PATH")
-    );
-    fs::write(output_file, json).map_err(err::Error::write_error)?;
+}
 
-    // Write observatories out
+/// Writes observatories to a file in the format `simulate --observatories` reads
+fn write_observatories(
+    observatories: Vec<Observatory>,
+    observatory_output: &Path,
+) -> Result<(), err::Error> {
+    let observatories: Vec<WeakObservatory> = observatories.into_iter().map(Into::into).collect();
     let json = serde_json::to_string(&observatories)?;
     let mut output_file: PathBuf = observatory_output.into();
     if output_file.is_dir() {
@@ -150,26 +315,328 @@ fn build(
     Ok(())
 }
 
+/// Places new observatories on the planets of an existing universe
+fn generate_observatories(
+    universe: &Path,
+    seed: Option<&String>,
+    per_planet: usize,
+    max_latitude: f64,
+    out: &Path,
+) -> Result<(), err::Error> {
+    let json = fs::read_to_string(universe).map_err(err::Error::read_error)?;
+    let root: astrograph::body::Arc = astrograph::json::from_str::<Body>(&json)?.into();
+    Body::hydrate_all(&root, &None);
+
+    // Float is f32 on 32 bit targets
+    #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+    let options = PlacementOptionsBuilder::default()
+        .per_planet(per_planet)
+        .max_latitude(max_latitude as Float)
+        .build()
+        .unwrap();
+    let observatories = placement::place(&root, &mut seeded_rng(seed), &options);
+    info!("Placed {} observatories", observatories.len());
+
+    if let Some(path) = out.parent() {
+        fs::create_dir_all(path).map_err(err::Error::write_error)?;
+    }
+    write_observatories(observatories, out)
+}
+
+/// Merges universes into the base universe, rotating (by angles in degrees about each axis) then
+/// translating each one before adding it
+fn merge(
+    base: &Path,
+    others: &[PathBuf],
+    rotate: Option<[f64; 3]>,
+    translate: Option<[f64; 3]>,
+    output: &Path,
+) -> Result<(), err::Error> {
+    // Float is f32 on 32 bit targets
+    #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+    let to_float = |x: &f64| *x as Float;
+    let read = |path: &Path| -> Result<astrograph::body::Arc, err::Error> {
+        let json = fs::read_to_string(path).map_err(err::Error::read_error)?;
+        Ok(astrograph::json::from_str::<Body>(&json)?.into())
+    };
+
+    // Rotate about x, then y, then z
+    let rotation = rotate.map_or(quaternion::id(), |angles| {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            .into_iter()
+            .zip(angles)
+            .fold(quaternion::id(), |rotation, (axis, angle)| {
+                quaternion::mul(
+                    quaternion::axis_angle(axis, to_float(&angle).to_radians()),
+                    rotation,
+                )
+            })
+    });
+    let translation = translate.map_or(Vector3::ORIGIN, |t| Vector3 {
+        x: to_float(&t[0]),
+        y: to_float(&t[1]),
+        z: to_float(&t[2]),
+    });
+
+    let root = read(base)?;
+    for path in others {
+        let universe = read(path)?;
+        debug!(
+            "Adding {} to the merged universe",
+            path.to_str().unwrap_or("UNPRINTABLE PATH")
+        );
+        Body::transform_subtree(&universe, rotation, translation).map_err(err::Error::Transform)?;
+        Body::add_child(&root, universe);
+    }
+
+    let json = serde_json::to_string(&root)?;
+    info!(
+        "Writing merged universe to file {}",
+        output.to_str().unwrap_or("UNPRINTABLE PATH")
+    );
+    fs::write(output, json).map_err(err::Error::write_error)?;
+
+    Ok(())
+}
+
+/// Returns the SVG output used when simulating, drawing the effective horizons of the
+/// observatories and the tails of any comets and rings of any planets seen from them, and
+/// thumbnails if given a minimum angular radius in degrees
+fn svg_output(thumbnails: Option<f64>) -> Box<dyn Output> {
+    let mut svg = Svg::new(StatelessOrthographic());
+    svg.add_layer(Box::new(comet_tails));
+    svg.add_layer(Box::new(planetary_rings));
+    svg.add_layer(Box::new(effective_horizon));
+    match thumbnails {
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+        Some(min_angular_radius) => {
+            Box::new(MultiRes::new(svg, min_angular_radius.to_radians() as Float))
+        }
+        None => Box::new(svg),
+    }
+}
+
+/// Warns about bodies in the universe whose positions are too imprecise, up to the max time
+fn validate(universe: &Path, max_time: f64) -> Result<(), err::Error> {
+    let json = fs::read_to_string(universe).map_err(err::Error::read_error)?;
+    let root: astrograph::body::Arc = astrograph::json::from_str::<Body>(&json)?.into();
+    Body::hydrate_all(&root, &None);
+
+    // Float is f32 on 32 bit targets
+    #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+    let warnings = astrograph::validate::precision_report(&root, max_time as Float);
+    if warnings.is_empty() {
+        info!("No bodies are too imprecise up to t={max_time}");
+    }
+    for warning in warnings {
+        warn!("{warning}");
+    }
+    Ok(())
+}
+
+/// Prints a summary of the program at `program`
+fn inspect(program: &Path) -> Result<(), err::Error> {
+    let program = Program::from_file(program)?;
+    let summary = program.summary();
+
+    println!("Bodies: {}", summary.body_count);
+    println!("Outputs: {}", summary.outputs);
+    println!("Output root: {}", summary.output_file_root.display());
+    println!("Observatories: {}", summary.observatories.len());
+    for name in summary.observatories {
+        println!("  {name}");
+    }
+    Ok(())
+}
+
+/// Writes the orbital elements of every body in the universe as CSV to `output`, or standard
+/// output
+fn elements(universe: &Path, output: Option<&Path>) -> Result<(), err::Error> {
+    let json = fs::read_to_string(universe).map_err(err::Error::read_error)?;
+    let root: astrograph::body::Arc = astrograph::json::from_str::<Body>(&json)?.into();
+    Body::hydrate_all(&root, &None);
+
+    let rows = astrograph::elements::orbital_elements(&root);
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path).map_err(err::Error::write_error)?;
+            astrograph::elements::write_csv(&rows, std::io::BufWriter::new(file))
+        }
+        None => astrograph::elements::write_csv(&rows, std::io::stdout().lock()),
+    }
+    .map_err(err::Error::write_error)
+}
+
+/// Prints a Hohmann transfer between two bodies and the departure windows between the start and
+/// end times
+fn transfer(
+    universe: &Path,
+    from: &str,
+    to: &str,
+    start_time: f64,
+    end_time: f64,
+) -> Result<(), err::Error> {
+    let json = fs::read_to_string(universe).map_err(err::Error::read_error)?;
+    let root: astrograph::body::Arc = astrograph::json::from_str::<Body>(&json)?.into();
+    Body::hydrate_all(&root, &None);
+
+    let find = |name: &str| {
+        Body::find_by_name(&root, name)
+            .ok_or_else(|| err::Error::Transfer(format!("there is no body named {name}")))
+    };
+    let (from_body, to_body) = (find(from)?, find(to)?);
+    let transfer = astrograph::almanac::hohmann(&from_body, &to_body).ok_or_else(|| {
+        err::Error::Transfer(format!(
+            "{from} and {to} must both follow Keplerian orbits around the same parent"
+        ))
+    })?;
+
+    println!("Transfer from {from} to {to}");
+    println!("  semi-major axis: {} ls", transfer.semi_major_axis);
+    println!("  travel time: {} days", transfer.travel_time / 24.0);
+    println!("  synodic period: {} days", transfer.synodic_period / 24.0);
+    println!("  phase angle: {}°", transfer.phase_angle.to_degrees());
+    // Float is f32 on 32 bit targets
+    #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+    let windows = astrograph::almanac::departure_windows(
+        &from_body,
+        &to_body,
+        &transfer,
+        start_time as Float,
+        end_time as Float,
+    );
+    println!("Departure windows (hours):");
+    for window in windows {
+        println!("  {window}");
+    }
+    Ok(())
+}
+
 /// Simulates the given universe
 #[allow(clippy::too_many_arguments)]
 fn simulate(
     start_time: i128,
     end_time: i128,
     step_size: usize,
+    at_local_midnight: Option<&str>,
+    thumbnails: Option<f64>,
+    directories_by_id: bool,
+    trail: Option<String>,
+    cultures: Vec<String>,
+    check_determinism: bool,
     universe: Option<&PathBuf>,
     observatories: Option<&PathBuf>,
     program: &str,
-    output: &Path,
+    output: Option<&Path>,
+    quiet: u8,
 ) -> Result<(), err::Error> {
     trace!("Entered Simulation function in binary");
+    let step_size = if step_size == 0 {
+        None
+    } else {
+        Some(step_size)
+    };
+    let load = || {
+        load_program(
+            universe,
+            observatories,
+            program,
+            output,
+            thumbnails,
+            directories_by_id,
+            trail.as_deref(),
+            &cultures,
+        )
+    };
+
+    if check_determinism {
+        return compare_runs(|run| {
+            let directory = std::env::temp_dir()
+                .join(format!("astrograph-determinism-{}-{run}", process::id()));
+            // Outputs like the eclipse log append to files left over from earlier runs
+            let _ = fs::remove_dir_all(&directory);
+            let mut program = load()?;
+            program.set_output_path(&directory);
+            let artifacts = observe(&program, start_time, end_time, step_size, at_local_midnight)
+                .and_then(|()| {
+                    determinism::hash_directory(&directory).map_err(err::Error::read_error)
+                });
+            let _ = fs::remove_dir_all(&directory);
+            artifacts
+        });
+    }
+
+    let program = load()?;
+    info!("Simulating {}", program.summary());
+    if at_local_midnight.is_none() {
+        // The library logs these as warnings, which are hidden unless the user asks for them
+        if quiet == 0 && !log::log_enabled!(log::Level::Warn) {
+            for warning in program
+                .run_summary(start_time, end_time, step_size)
+                .warnings()
+            {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        let estimated_bytes = program.estimate_output_bytes(start_time, end_time, step_size);
+        debug!("Estimated output size: {estimated_bytes} bytes");
+        if estimated_bytes > OUTPUT_SIZE_WARNING_BYTES {
+            warn!(
+                "This run is estimated to write {:.1} GiB to {}",
+                estimated_bytes as f64 / GIBIBYTE as f64,
+                program.output_file_root().to_string_lossy()
+            );
+        }
+    }
+
+    observe(&program, start_time, end_time, step_size, at_local_midnight)
+}
+
+/// Makes the observations of a simulation, either every step or at each local midnight of the
+/// observatory with the given name
+fn observe(
+    program: &Program,
+    start_time: i128,
+    end_time: i128,
+    step_size: Option<usize>,
+    at_local_midnight: Option<&str>,
+) -> Result<(), err::Error> {
+    if let Some(observatory_name) = at_local_midnight {
+        let times = program.times_at_local_phase(observatory_name, 0.5, start_time, end_time);
+        info!(
+            "Observing at {} local midnights of {observatory_name}",
+            times.len()
+        );
+
+        trace!("Making observations");
+        program.make_observations_at(&times)?;
+        trace!("Finished Observations");
+        return Ok(());
+    }
+
+    trace!("Making observations");
+    program.make_observations(start_time, end_time, step_size)?;
+    trace!("Finished Observations");
+    Ok(())
+}
 
-    let program_contents = fs::read_to_string(program)
-        .map_err(err::Error::read_error)
-        .and_then(|json| serde_json::from_str::<Program>(&json).map_err(err::Error::from));
+/// Reads the program to simulate, from a universe and observatories if both are given or from a
+/// program file otherwise, and adds the outputs and settings the user asked for
+#[allow(clippy::too_many_arguments)]
+fn load_program(
+    universe: Option<&PathBuf>,
+    observatories: Option<&PathBuf>,
+    program: &str,
+    output: Option<&Path>,
+    thumbnails: Option<f64>,
+    directories_by_id: bool,
+    trail: Option<&str>,
+    cultures: &[String],
+) -> Result<Program, err::Error> {
     let universe_contents = universe.map(|universe| {
         fs::read_to_string(universe)
             .map_err(err::Error::read_error)
-            .and_then(|json| serde_json::from_str::<Body>(&json).map_err(err::Error::from))
+            .and_then(|json| astrograph::json::from_str::<Body>(&json).map_err(err::Error::from))
     });
     let observatory_contents = observatories.map(|observatories| {
         fs::read_to_string(observatories)
@@ -179,7 +646,7 @@ fn simulate(
             })
     });
 
-    let program: Program = match (universe_contents, observatory_contents) {
+    let mut program: Program = match (universe_contents, observatory_contents) {
         (Some(Ok(universe)), Some(Ok(observatories))) => {
             trace!("Reading from parts");
             let root: astrograph::body::Arc = Arc::new(RwLock::new(universe.clone()));
@@ -188,18 +655,21 @@ fn simulate(
             Body::hydrate_all(&root, &None);
 
             trace!("Building the program around these observatories and bodies");
+            let observatories = observatories
+                .into_iter()
+                .map(|o| astrograph::body::observatory::to_observatory(o, &root))
+                .collect::<Result<Vec<_>, _>>()?;
             let mut program_builder = ProgramBuilder::default();
             program_builder
-                .add_output(Box::new(Svg::new(StatelessOrthographic())))
-                .output_file_root(output.to_owned());
+                .add_output(svg_output(thumbnails))
+                .output_file_root(output.unwrap_or(Path::new(".")).to_owned());
             debug!(
                 "Created a program from parts with {} observatories",
                 observatories.len()
             );
 
             for o in observatories {
-                program_builder
-                    .add_observatory(astrograph::body::observatory::to_observatory(o, &root));
+                program_builder.add_observatory(o);
             }
 
             program_builder.root_body(root).build().unwrap()
@@ -234,24 +704,26 @@ fn simulate(
         }
         // If either observatories or universe was not provided
         (_, None) | (None, _) => {
-            let mut program = program_contents?;
             trace!("Reading from program file");
-            program.add_output(Box::new(Svg::new(StatelessOrthographic())));
-            program.set_output_path(output);
+            let mut program = Program::from_file(program)?;
+            if program.output_configs().is_empty() {
+                program.add_output(svg_output(thumbnails));
+            } else if thumbnails.is_some() {
+                warn!("Ignoring --thumbnails, the program file has its own outputs");
+            }
+            if let Some(output) = output {
+                program.set_output_path(output);
+            }
             program
         }
     };
 
-    trace!("Making observations");
-    program.make_observations(
-        start_time,
-        end_time,
-        if step_size == 0 {
-            None
-        } else {
-            Some(step_size)
-        },
-    );
-    trace!("Finished Observations");
-    Ok(())
+    program.set_directories_by_id(directories_by_id);
+    if let Some(body) = trail {
+        program.add_output(Box::new(Trail::new(StatelessOrthographic(), body)));
+    }
+    if !cultures.is_empty() {
+        program.select_cultures(Some(cultures.to_vec()));
+    }
+    Ok(program)
 }
@@ -0,0 +1,70 @@
+use std::{fs, net::SocketAddr, path::Path, thread};
+
+use astrograph::{
+    body::{
+        observatory::{to_observatory, WeakObservatory},
+        Body,
+    },
+    output::svg::{effective_horizon, Svg},
+    program::ProgramBuilder,
+    projection::StatelessOrthographic,
+    serve::Server,
+};
+use log::{info, warn};
+
+use crate::err;
+
+/// Serves charts of the universe seen from the observatories on `address`, answering at most
+/// `threads` requests at once
+pub(super) fn serve(
+    universe: &Path,
+    observatories: &Path,
+    address: SocketAddr,
+    threads: usize,
+) -> Result<(), err::Error> {
+    let json = fs::read_to_string(universe).map_err(err::Error::read_error)?;
+    let root: astrograph::body::Arc = astrograph::json::from_str::<Body>(&json)?.into();
+    Body::hydrate_all(&root, &None);
+    let json = fs::read_to_string(observatories).map_err(err::Error::read_error)?;
+    let observatories = serde_json::from_str::<Vec<WeakObservatory>>(&json)?
+        .into_iter()
+        .map(|o| to_observatory(o, &root))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut svg = Svg::new(StatelessOrthographic());
+    svg.add_layer(Box::new(effective_horizon));
+    let program = ProgramBuilder::default()
+        .root_body(root)
+        .observatories(observatories)
+        .outputs(vec![])
+        .build()
+        .map_err(|e| err::Error::Serve(e.to_string()))?;
+    let server = Server::new(program, svg);
+
+    let http = tiny_http::Server::http(address).map_err(|e| err::Error::Serve(e.to_string()))?;
+    info!("Serving charts on {address}");
+
+    // Each worker answers one request at a time, so at most `threads` are answered at once
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                for request in http.incoming_requests() {
+                    let response = server.respond(request.method().as_str(), request.url());
+                    info!("{} {} {}", request.method(), request.url(), response.status);
+                    let header =
+                        tiny_http::Header::from_bytes("Content-Type", response.content_type)
+                            .expect("content types are valid headers");
+                    let result = request.respond(
+                        tiny_http::Response::from_string(response.body)
+                            .with_status_code(response.status)
+                            .with_header(header),
+                    );
+                    if let Err(e) = result {
+                        warn!("{e}");
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Reads untrusted JSON as a body tree, then observes it like the web demo would.
+#![no_main]
+
+use astrograph::body::{observatory::Observatory, Arc, Body};
+use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(body) = astrograph::json::from_str::<Body>(json) else {
+        return;
+    };
+    let root: Arc = body.into();
+
+    // Observe from the first child if there is one, so there are parents to look up at as well
+    let observer = root
+        .read()
+        .ok()
+        .and_then(|r| r.get_children().first().cloned())
+        .unwrap_or_else(|| root.clone());
+    let observatory = Observatory::new(Spherical::UP, observer, Ok("Fuzz".into()), vec![]);
+    let _ = observatory.observe(1.0);
+});
@@ -0,0 +1,16 @@
+//! Reads untrusted JSON as a program, including observatories that refer to bodies by ID.
+#![no_main]
+
+use astrograph::program::Program;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(mut program) = astrograph::json::from_str::<Program>(json) {
+        // Don't create directories named by the input
+        program.set_output_path(std::env::temp_dir().join("astrograph-fuzz"));
+        let _ = program.make_observations_at(&[1]);
+    }
+});
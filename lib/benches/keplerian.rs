@@ -0,0 +1,85 @@
+use astrograph::{
+    dynamic::{keplerian::Keplerian, Dynamic},
+    Float,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{Rng, SeedableRng};
+
+fn batch_offsets(c: &mut Criterion) {
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([
+        12, 91, 220, 3, 178, 64, 190, 33, 7, 150, 82, 241, 19, 105, 66, 200,
+    ]);
+    let batch: Vec<(Keplerian, Float)> = (0..100_000)
+        .map(|_| {
+            (
+                Keplerian::new_with_period(
+                    rng.gen_range(0.0..0.9),
+                    rng.gen_range(1.0..1_000.0),
+                    rng.gen_range(0.0..0.5),
+                    rng.gen_range(0.0..6.0),
+                    rng.gen_range(0.0..6.0),
+                    rng.gen_range(0.0..6.0),
+                    rng.gen_range(10.0..10_000.0),
+                ),
+                rng.gen_range(0.0..100_000.0),
+            )
+        })
+        .collect();
+
+    // Compare with and without the `parallel-batch` feature for the speedup
+    c.bench_function("offsets 100,000 serial", |b| {
+        b.iter(|| {
+            black_box(&batch)
+                .iter()
+                .map(|(orbit, time)| orbit.get_offset(*time))
+                .collect::<Vec<_>>()
+        });
+    });
+    c.bench_function("offsets 100,000 batch", |b| {
+        b.iter(|| Keplerian::offsets_at(black_box(&batch)));
+    });
+}
+
+fn stepped_offsets(c: &mut Criterion) {
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([
+        88, 4, 199, 61, 230, 17, 142, 95, 36, 211, 120, 8, 173, 54, 247, 29,
+    ]);
+    let orbits: Vec<Keplerian> = (0..10_000)
+        .map(|_| {
+            Keplerian::new_with_period(
+                rng.gen_range(0.0..0.97),
+                rng.gen_range(1.0..1_000.0),
+                rng.gen_range(0.0..0.5),
+                rng.gen_range(0.0..6.0),
+                rng.gen_range(0.0..6.0),
+                rng.gen_range(0.0..6.0),
+                rng.gen_range(1_000.0..100_000.0),
+            )
+        })
+        .collect();
+
+    // Frames an hour apart, like a program observing, with and without starting each orbit from
+    // its last solution
+    for (name, warm_start) in [
+        ("offsets 10,000 hourly", false),
+        ("offsets 10,000 hourly warm", true),
+    ] {
+        let orbits: Vec<_> = orbits
+            .iter()
+            .map(|orbit| orbit.clone().with_warm_start(warm_start))
+            .collect();
+        let mut time: Float = 0.0;
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                time += 1.0;
+                black_box(&orbits)
+                    .iter()
+                    .map(|orbit| orbit.get_offset(time))
+                    .collect::<Vec<_>>()
+            });
+        });
+    }
+}
+
+criterion_group!(benches, batch_offsets, stepped_offsets);
+criterion_main!(benches);
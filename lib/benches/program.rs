@@ -1,29 +1,15 @@
 use astrograph::{
     body::observatory,
+    consts::float,
     generator::{artifexian::ArtifexianBuilder, Generator},
+    output::{csv::Csv, memory::Memory, svg::Svg, FrameInfo, Output},
     program::ProgramBuilder,
+    projection::StatelessOrthographic,
+    Float,
 };
+use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use rand::SeedableRng;
-
-#[derive(Clone, Debug, Default)]
-struct Output;
-
-impl astrograph::output::Output for Output {
-    fn write_observations(
-        &self,
-        _observations: &[astrograph::LocalObservation],
-        _constellations: &[(
-            coordinates::three_dimensional::Spherical<astrograph::Float>,
-            coordinates::three_dimensional::Spherical<astrograph::Float>,
-        )],
-        _observatory_name: &str,
-        _time: i128,
-        _output_path_root: &std::path::Path,
-    ) -> Result<(), std::io::Error> {
-        black_box(Ok(()))
-    }
-}
+use rand::{Rng, SeedableRng};
 
 fn observations(c: &mut Criterion) {
     let mut rng = rand_xorshift::XorShiftRng::from_seed([
@@ -33,7 +19,8 @@ fn observations(c: &mut Criterion) {
         .star_count(1000)
         .build()
         .unwrap()
-        .generate(&mut rng);
+        .generate(&mut rng)
+        .unwrap();
 
     astrograph::body::Body::hydrate_all(&root, &None);
 
@@ -44,21 +31,119 @@ fn observations(c: &mut Criterion) {
     let observatories = observatories
         .into_iter()
         .map(|x| observatory::to_observatory(x, &root))
-        .collect();
+        .collect::<Result<_, _>>()
+        .unwrap();
 
+    let memory = Memory::new();
     let program = ProgramBuilder::default()
         .observatories(observatories)
         .root_body(root)
-        .add_output(Box::new(Output))
+        .add_output(Box::new(memory.clone()))
         .build()
         .unwrap();
 
     // Bench observations
     c.bench_function("observe 1,000", |b| {
-        b.iter(|| program.make_observations(black_box(0), 1_000, None));
+        b.iter(|| {
+            program
+                .make_observations(black_box(0), 1_000, None)
+                .unwrap();
+            memory.clear();
+        });
     });
 }
 
+fn shared_body(c: &mut Criterion) {
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([
+        17, 82, 240, 5, 163, 94, 38, 201, 77, 150, 12, 229, 61, 118, 184, 33,
+    ]);
+    let (root, observatories) = ArtifexianBuilder::default()
+        .star_count(1000)
+        .build()
+        .unwrap()
+        .generate(&mut rng)
+        .unwrap();
+    astrograph::body::Body::hydrate_all(&root, &None);
+
+    // Twenty stations spread over one habitable planet, which share one traversal of the tree
+    let body = observatories[0].body().clone();
+    let observatories = (0..20u8)
+        .map(|i| {
+            let i = Float::from(i);
+            observatory::Observatory::from_lat_long(
+                i * 8.0 - 80.0,
+                i * 18.0,
+                body.clone(),
+                Ok(format!("Station {i}")),
+                vec![],
+            )
+        })
+        .collect();
+
+    let memory = Memory::without_constellations();
+    let program = ProgramBuilder::default()
+        .observatories(observatories)
+        .root_body(root)
+        .add_output(Box::new(memory.clone()))
+        .build()
+        .unwrap();
+
+    c.bench_function("observe 20 stations on one body", |b| {
+        b.iter(|| {
+            program.make_observations(black_box(0), 100, None).unwrap();
+            memory.clear();
+        });
+    });
+}
+
+fn constellations(c: &mut Criterion) {
+    let root: astrograph::body::Arc = serde_json::from_str(include_str!(
+        "../../assets/test/constellation/universe.json"
+    ))
+    .unwrap();
+    let observatories: Vec<astrograph::body::observatory::WeakObservatory> = serde_json::from_str(
+        include_str!("../../assets/test/constellation/observatories.json"),
+    )
+    .unwrap();
+    let observatories: Vec<_> = observatories
+        .into_iter()
+        .map(|x| observatory::to_observatory(x, &root))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    astrograph::body::Body::hydrate_all(&root, &None);
+
+    // Lines are only worked out when an output wants them, like charts do, so a program that
+    // only writes tables skips them
+    let output_root = std::env::temp_dir().join("astrograph-bench-constellations");
+    let outputs: [(&str, Box<dyn Output>); 2] = [
+        (
+            "observe constellations wanted by SVG",
+            Box::new(Svg::new(StatelessOrthographic())),
+        ),
+        (
+            "observe constellations skipped by CSV",
+            Box::new(Csv::new()),
+        ),
+    ];
+    for (name, output) in outputs {
+        let program = ProgramBuilder::default()
+            .observatories(observatories.clone())
+            .root_body(root.clone())
+            .add_output(output)
+            .output_file_root(output_root.clone())
+            .build()
+            .unwrap();
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                program
+                    .make_observations(black_box(0), 1_000, None)
+                    .unwrap();
+            });
+        });
+    }
+    let _ = std::fs::remove_dir_all(output_root);
+}
+
 fn generation(c: &mut Criterion) {
     // Bench universe generation
     let generator = ArtifexianBuilder::default()
@@ -73,5 +158,51 @@ fn generation(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, generation, observations);
+fn svg_frame(c: &mut Criterion) {
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([
+        58, 211, 7, 140, 96, 33, 187, 4, 250, 121, 69, 18, 173, 92, 201, 45,
+    ]);
+    let root = astrograph::body::Body::new(
+        None,
+        astrograph::dynamic::fixed::Fixed::new(Vector3::ORIGIN),
+    );
+    // Distant stars spread over the visible half of the sky
+    let observations: Vec<astrograph::LocalObservation> = (0..100_000)
+        .map(|_| {
+            let body = astrograph::body::Body::new(
+                Some(root.clone()),
+                astrograph::dynamic::fixed::Fixed::new(Vector3::UP),
+            );
+            let location = Spherical {
+                radius: rng.gen_range(1e7..1e9),
+                polar_angle: rng.gen_range(0.0..1.5),
+                azimuthal_angle: rng.gen_range(0.0..float::TAU),
+            };
+            (body, location)
+        })
+        .collect();
+    astrograph::body::Body::hydrate_all(&root, &None);
+
+    let mut output = Svg::new(StatelessOrthographic());
+    c.bench_function("svg 100,000 merged", |b| {
+        b.iter(|| {
+            output.consume_observation(FrameInfo::new("Bench", 0), black_box(&observations), &[])
+        });
+    });
+    output.set_merge_resolution(None);
+    c.bench_function("svg 100,000 unmerged", |b| {
+        b.iter(|| {
+            output.consume_observation(FrameInfo::new("Bench", 0), black_box(&observations), &[])
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    generation,
+    observations,
+    shared_body,
+    constellations,
+    svg_frame
+);
 criterion_main!(benches);
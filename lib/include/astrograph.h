@@ -0,0 +1,126 @@
+#ifndef ASTROGRAPH_H
+#define ASTROGRAPH_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a call through the C interface
+typedef enum AstrographErrorCode {
+  // The call succeeded
+  ASTROGRAPH_ERROR_CODE_OK = 0,
+  // A required pointer was null
+  ASTROGRAPH_ERROR_CODE_NULL_POINTER = 1,
+  // A string was not valid UTF-8
+  ASTROGRAPH_ERROR_CODE_INVALID_UTF8 = 2,
+  // The JSON was invalid or did not describe a body
+  ASTROGRAPH_ERROR_CODE_PARSE = 3,
+  // The JSON was nested too deeply to be read
+  ASTROGRAPH_ERROR_CODE_TOO_DEEP = 4,
+  // No body had the given name
+  ASTROGRAPH_ERROR_CODE_BODY_NOT_FOUND = 5,
+  // The caller's buffer was too small, and results were truncated
+  ASTROGRAPH_ERROR_CODE_BUFFER_TOO_SMALL = 6,
+  // The library panicked, which is a bug
+  ASTROGRAPH_ERROR_CODE_PANIC = 7,
+} AstrographErrorCode;
+
+// An observatory on a body in a [`Universe`]
+typedef struct AstrographObservatoryHandle AstrographObservatoryHandle;
+
+// A tree of bodies loaded from JSON
+typedef struct AstrographUniverse AstrographUniverse;
+
+// A body that was observed, as written to the caller's buffer by [`astrograph_observe`]
+typedef struct AstrographObservation {
+  // Index of the body, its name can be read with [`astrograph_body_name`]
+  uintptr_t name_index;
+  // Compass bearing in radians, from north clockwise through east, between 0 and 2π
+  double azimuth;
+  // Angle from the zenith in radians
+  double polar;
+  // Distance to the body in light seconds
+  double distance;
+} AstrographObservation;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Reads a tree of bodies from a JSON buffer (not null terminated), and writes a handle to it
+// to `universe_out`. The handle must be freed with [`astrograph_universe_free`].
+//
+// # Safety
+// `json` must point to `json_length` readable bytes, and `universe_out` must be a valid pointer
+// to write to.
+enum AstrographErrorCode astrograph_universe_from_json(const uint8_t *json,
+                                                       uintptr_t json_length,
+                                                       struct AstrographUniverse **universe_out);
+
+// Frees a universe. Observatories in it remain valid until they are freed.
+//
+// # Safety
+// `universe` must be null or a handle from [`astrograph_universe_from_json`] that hasn't been
+// freed.
+void astrograph_universe_free(struct AstrographUniverse *universe);
+
+// Returns the number of bodies in a universe through `count_out`, name indexes are less than
+// this.
+//
+// # Safety
+// `universe` must be a live handle and `count_out` a valid pointer to write to.
+enum AstrographErrorCode astrograph_body_count(const struct AstrographUniverse *universe,
+                                               uintptr_t *count_out);
+
+// Returns a pointer to the null terminated name of a body through `name_out`. The name is owned
+// by the universe and is valid until it is freed.
+//
+// # Safety
+// `universe` must be a live handle and `name_out` a valid pointer to write to.
+enum AstrographErrorCode astrograph_body_name(const struct AstrographUniverse *universe,
+                                              uintptr_t name_index,
+                                              const char **name_out);
+
+// Creates an observatory at a latitude and longitude (in degrees) on the body with the given
+// null terminated name, writing a handle to `observatory_out`. The handle must be freed with
+// [`astrograph_observatory_free`].
+//
+// # Safety
+// `universe` must be a live handle, `body_name` a null terminated string, and `observatory_out`
+// a valid pointer to write to.
+enum AstrographErrorCode astrograph_observatory_new(const struct AstrographUniverse *universe,
+                                                    const char *body_name,
+                                                    double latitude,
+                                                    double longitude,
+                                                    struct AstrographObservatoryHandle **observatory_out);
+
+// Frees an observatory.
+//
+// # Safety
+// `observatory` must be null or a handle from [`astrograph_observatory_new`] that hasn't been
+// freed.
+void astrograph_observatory_free(struct AstrographObservatoryHandle *observatory);
+
+// Observes the sky from an observatory at a time in hours, writing up to `capacity` bodies above
+// the horizon to `observations` and the total number visible to `count_out`. If there were more
+// than `capacity` bodies returns [`ErrorCode::BufferTooSmall`], call again with a buffer of at
+// least `count_out` to get every body.
+//
+// # Safety
+// `observatory` must be a live handle, `observations` must point to `capacity` writable
+// [`Observation`]s (it may be null if `capacity` is zero), and `count_out` must be a valid
+// pointer to write to.
+enum AstrographErrorCode astrograph_observe(const struct AstrographObservatoryHandle *observatory,
+                                            double time,
+                                            struct AstrographObservation *observations,
+                                            uintptr_t capacity,
+                                            uintptr_t *count_out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ASTROGRAPH_H */
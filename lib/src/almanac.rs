@@ -0,0 +1,286 @@
+use coordinates::prelude::{Cross3D, Dot, Vector3};
+
+use crate::{
+    body::{observatory::Observatory, Arc},
+    consts::{float, GRAVITATIONAL_CONSTANT},
+    dynamic::keplerian::Keplerian,
+    Float,
+};
+
+/// Number of steps each synodic period is split into while searching for departure windows
+const WINDOW_STEPS_PER_SYNODIC_PERIOD: u8 = 64;
+
+/// Rough numbers for a [Hohmann transfer](https://en.wikipedia.org/wiki/Hohmann_transfer_orbit)
+/// between two bodies orbiting the same parent, see [`hohmann`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransferEstimate {
+    /// Semi-major axis of the transfer orbit in light seconds, halfway between the two orbits
+    pub semi_major_axis: Float,
+    /// Time from departure to arrival in hours, half the period of the transfer orbit
+    pub travel_time: Float,
+    /// Time between departure windows in hours, the time it takes the two bodies to line up
+    /// again
+    pub synodic_period: Float,
+    /// Angle in radians the destination should lead the departure body by at departure, so they
+    /// meet at the far end of the transfer orbit. Negative when it should trail behind.
+    pub phase_angle: Float,
+}
+
+/// Returns the elements of a body's orbit if it follows a [`Keplerian`] orbit
+fn orbit(body: &Arc) -> Option<Keplerian> {
+    body.read()
+        .ok()?
+        .get_dynamic()
+        .as_any()
+        .downcast_ref::<Keplerian>()
+        .cloned()
+}
+
+/// Estimates a Hohmann transfer from `from` to `to`, treating both orbits as circles with radii
+/// of their semi-major axes.
+///
+/// The gravity of the parent comes from its mass if it has one, otherwise from the period of the
+/// departure orbit, as happens in trees with orbital periods but no masses.
+///
+/// Returns `None` unless both bodies follow [`Keplerian`] orbits around the same parent.
+#[must_use]
+pub fn hohmann(from: &Arc, to: &Arc) -> Option<TransferEstimate> {
+    let parent = from.read().ok()?.parent.as_ref()?.upgrade()?;
+    let to_parent = to.read().ok()?.parent.as_ref()?.upgrade()?;
+    if !std::sync::Arc::ptr_eq(&parent, &to_parent) {
+        return None;
+    }
+    let from_elements = orbit(from)?.elements();
+    let to_elements = orbit(to)?.elements();
+
+    let standard_gravitational_parameter = match parent.read().ok()?.get_mass() {
+        Some(mass) => GRAVITATIONAL_CONSTANT * mass,
+        // From Kepler's third law, T² = 4π²a³/μ
+        None => {
+            float::TAU.powi(2) * from_elements.semi_major_axis.powi(3)
+                / from_elements.period.powi(2)
+        }
+    };
+    let period_of = |semi_major_axis: Float| {
+        float::TAU * (semi_major_axis.powi(3) / standard_gravitational_parameter).sqrt()
+    };
+
+    let semi_major_axis = (from_elements.semi_major_axis + to_elements.semi_major_axis) / 2.0;
+    let travel_time = period_of(semi_major_axis) / 2.0;
+    let from_period = period_of(from_elements.semi_major_axis);
+    let to_period = period_of(to_elements.semi_major_axis);
+
+    Some(TransferEstimate {
+        semi_major_axis,
+        travel_time,
+        synodic_period: 1.0 / (1.0 / from_period - 1.0 / to_period).abs(),
+        // The destination moves this far while the craft travels half way around
+        phase_angle: float::PI - float::TAU * travel_time / to_period,
+    })
+}
+
+/// Returns the angle in radians from `from` to `to` as seen from their parent, measured in the
+/// direction `from` orbits, between -π and π
+fn phase(from: &Arc, to: &Arc, normal: Vector3<Float>, time: Float) -> Option<Float> {
+    let from_offset = from.read().ok()?.get_dynamic().get_offset(time);
+    let to_offset = to.read().ok()?.get_dynamic().get_offset(time);
+    Some(
+        normal
+            .dot(&from_offset.cross(&to_offset))
+            .atan2(from_offset.dot(&to_offset)),
+    )
+}
+
+/// Returns the times between `start` and `end` hours when `to` leads `from` by the
+/// [phase angle](TransferEstimate::phase_angle) of the transfer, the best times to depart.
+///
+/// Phases are measured from the bodies' actual positions, so eccentric or inclined orbits shift
+/// the windows from where the synodic period alone would put them.
+#[must_use]
+pub fn departure_windows(
+    from: &Arc,
+    to: &Arc,
+    transfer: &TransferEstimate,
+    start: Float,
+    end: Float,
+) -> Vec<Float> {
+    /// Number of halvings when narrowing down each window, plenty to get within an hour
+    const BISECTIONS: u8 = 32;
+
+    let Some(normal) = orbit(from).map(|orbit| orbit.get_orbit_normal()) else {
+        return Vec::new();
+    };
+    // How far the phase is from the one needed, between -π and π
+    let error = |time: Float| {
+        phase(from, to, normal, time).map(|phase| {
+            (phase - transfer.phase_angle + float::PI).rem_euclid(float::TAU) - float::PI
+        })
+    };
+
+    let step = transfer.synodic_period / Float::from(WINDOW_STEPS_PER_SYNODIC_PERIOD);
+    let mut result = Vec::new();
+    let mut time = start;
+    let Some(mut previous) = error(time) else {
+        return result;
+    };
+    while time < end {
+        let next_time = (time + step).min(end);
+        let Some(next) = error(next_time) else {
+            break;
+        };
+        // A change of sign through zero rather than wrapping around at ±π
+        if previous.signum() != next.signum() && (next - previous).abs() < float::PI {
+            let (mut low, mut high) = (time, next_time);
+            for _ in 0..BISECTIONS {
+                let middle = (low + high) / 2.0;
+                if error(middle).is_some_and(|e| e.signum() == previous.signum()) {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            result.push((low + high) / 2.0);
+        }
+        previous = next;
+        time = next_time;
+    }
+    result
+}
+
+/// Returns the intervals between `start` and `end` hours when `body` is up as seen from
+/// `observatory`, as `(rise, set)` pairs in order. A body is up when its
+/// [altitude](Observatory::altitude) is above the observatory's
+/// [horizon altitude](Observatory::horizon_altitude_deg); terrain from a horizon profile isn't
+/// taken into account.
+///
+/// The altitude is sampled every `step` hours and each rise and set is narrowed down between the
+/// samples either side of it, so windows shorter than `step` may be missed. A window that is open
+/// at `start` or `end` is cut off there.
+#[must_use]
+pub fn observability_windows(
+    observatory: &Observatory,
+    body: &Arc,
+    start: Float,
+    end: Float,
+    step: Float,
+) -> Vec<(Float, Float)> {
+    /// Number of halvings when narrowing down each rise and set
+    const BISECTIONS: u8 = 32;
+
+    let horizon = observatory.horizon_altitude_deg().to_radians();
+    let is_up = |time: Float| {
+        observatory
+            .altitude(body, time)
+            .is_some_and(|altitude| altitude > horizon)
+    };
+    // The time between `low` and `high` when the body rises or sets
+    let crossing = |mut low: Float, mut high: Float, up_at_low: bool| {
+        for _ in 0..BISECTIONS {
+            let middle = (low + high) / 2.0;
+            if is_up(middle) == up_at_low {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    };
+
+    let mut result = Vec::new();
+    if step <= 0.0 || start >= end {
+        return result;
+    }
+    let mut time = start;
+    let mut up = is_up(time);
+    let mut rise = up.then_some(start);
+    while time < end {
+        let next_time = (time + step).min(end);
+        let next_up = is_up(next_time);
+        if next_up != up {
+            let change = crossing(time, next_time, up);
+            match rise.take() {
+                Some(rise) => result.push((rise, change)),
+                None => rise = Some(change),
+            }
+        }
+        up = next_up;
+        time = next_time;
+    }
+    if let Some(rise) = rise {
+        result.push((rise, end));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{rotating::Rotating, Body},
+        dynamic::fixed::Fixed,
+    };
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+    #[test]
+    fn earth_to_mars_takes_eight_and_a_half_months() {
+        let root: Arc =
+            serde_json::from_str(include_str!("../../assets/solar-system.json")).unwrap();
+        Body::hydrate_all(&root, &None);
+        let earth = Body::find_by_name(&root, "Earth").unwrap();
+        let mars = Body::find_by_name(&root, "Mars").unwrap();
+        let luna = Body::find_by_name(&root, "Luna").unwrap();
+
+        let transfer = hohmann(&earth, &mars).unwrap();
+
+        // About 259 days
+        assert_float_relative_eq!(transfer.travel_time / 24.0, 259.0, 0.03);
+        // About 780 days
+        assert_float_relative_eq!(transfer.synodic_period / 24.0, 780.0, 0.03);
+        // Mars leads by about 44°
+        assert_float_absolute_eq!(transfer.phase_angle.to_degrees(), 44.0, 2.0);
+        assert!(hohmann(&earth, &luna).is_none());
+
+        let end = transfer.synodic_period * 3.5;
+        let windows = departure_windows(&earth, &mars, &transfer, 0.0, end);
+        assert!(windows.len() == 3 || windows.len() == 4, "{windows:?}");
+        for pair in windows.windows(2) {
+            assert_float_relative_eq!(pair[1] - pair[0], transfer.synodic_period, 0.15);
+        }
+        let normal = orbit(&earth).unwrap().get_orbit_normal();
+        for window in windows {
+            let phase = phase(&earth, &mars, normal, window).unwrap();
+            assert_float_absolute_eq!(phase, transfer.phase_angle, 1e-3);
+        }
+    }
+
+    #[test]
+    fn star_up_for_half_of_each_rotation() {
+        const SIDEREAL_PERIOD: Float = 24.0;
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(SIDEREAL_PERIOD, Spherical::UP));
+        // On the celestial equator, so up for half of each day seen from the equator
+        let star = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 1e6));
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::from_lat_long(0.0, 30.0, planet, Err(vec![]), vec![]);
+
+        let windows = observability_windows(&observatory, &star, 3.0, 123.0, 1.0);
+        assert!((5..=6).contains(&windows.len()), "{windows:?}");
+        for (index, (rise, set)) in windows.iter().enumerate() {
+            // The first and last windows may be cut off by the start and end
+            if index > 0 && index < windows.len() - 1 {
+                assert_float_absolute_eq!(set - rise, SIDEREAL_PERIOD / 2.0, 1e-3);
+            }
+            if index > 1 {
+                assert_float_absolute_eq!(rise - windows[index - 1].0, SIDEREAL_PERIOD, 1e-3);
+            }
+            assert!(rise < set);
+        }
+        for (_, set) in &windows[..windows.len() - 1] {
+            assert_float_absolute_eq!(observatory.altitude(&star, *set).unwrap(), 0.0, 1e-4);
+        }
+
+        // Never above a horizon at the zenith
+        let observatory = observatory.with_horizon_altitude_deg(90.0);
+        assert!(observability_windows(&observatory, &star, 0.0, 48.0, 1.0).is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use coordinates::prelude::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+/// The altitude of the terrain around an observatory, e.g. mountains or buildings, which hides
+/// bodies behind it.
+///
+/// Made of `[azimuth, altitude]` pairs in degrees, where azimuth is measured clockwise from north
+/// (east is 90°). The altitude between two points is interpolated linearly, wrapping around from
+/// the last point back to the first.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<(Float, Float)>", into = "Vec<(Float, Float)>")]
+pub struct HorizonProfile {
+    /// Azimuth and altitude of each point in degrees, sorted by azimuth in `[0, 360)`
+    points: Vec<(Float, Float)>,
+}
+
+impl HorizonProfile {
+    /// Creates a profile from `(azimuth, altitude)` pairs in degrees, in any order.
+    #[must_use]
+    pub fn new(points: impl IntoIterator<Item = (Float, Float)>) -> Self {
+        let mut points: Vec<_> = points
+            .into_iter()
+            .map(|(azimuth, altitude)| (azimuth.rem_euclid(360.0), altitude))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { points }
+    }
+
+    /// Returns the altitude of the terrain in degrees at an azimuth in degrees, or `None` if the
+    /// profile is empty.
+    #[must_use]
+    pub fn altitude_at(&self, azimuth: Float) -> Option<Float> {
+        let azimuth = azimuth.rem_euclid(360.0);
+        let first = *self.points.first()?;
+        let last = *self.points.last()?;
+
+        // The points either side of the azimuth, wrapping around north
+        let next_index = self.points.partition_point(|(a, _)| *a <= azimuth);
+        let (previous, next) = match next_index {
+            0 => ((last.0 - 360.0, last.1), first),
+            i if i == self.points.len() => (last, (first.0 + 360.0, first.1)),
+            i => (self.points[i - 1], self.points[i]),
+        };
+
+        let span = next.0 - previous.0;
+        if span <= 0.0 {
+            return Some(previous.1);
+        }
+        let t = (azimuth - previous.0) / span;
+        return Some(previous.1 + (next.1 - previous.1) * t);
+    }
+
+    /// Returns true if a direction in an observatory's local coordinates (as returned by
+    /// [`Observatory::observe`](super::observatory::Observatory::observe)) is behind the terrain
+    #[must_use]
+    pub fn hides(&self, direction: &Vector3<Float>) -> bool {
+        // North is -x and east is -y in local coordinates
+        let azimuth = Float::atan2(-direction.y, -direction.x).to_degrees();
+        let altitude = Float::atan2(direction.z, direction.x.hypot(direction.y)).to_degrees();
+        self.altitude_at(azimuth)
+            .is_some_and(|terrain| altitude < terrain)
+    }
+}
+
+impl From<Vec<(Float, Float)>> for HorizonProfile {
+    fn from(value: Vec<(Float, Float)>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<HorizonProfile> for Vec<(Float, Float)> {
+    fn from(value: HorizonProfile) -> Self {
+        value.points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_around_north() {
+        let profile = HorizonProfile::new([(350.0, 10.0), (10.0, 20.0), (180.0, 0.0)]);
+
+        assert_float_absolute_eq!(profile.altitude_at(0.0).unwrap(), 15.0, 1e-4);
+        assert_float_absolute_eq!(profile.altitude_at(-5.0).unwrap(), 12.5, 1e-4);
+        assert_float_absolute_eq!(profile.altitude_at(95.0).unwrap(), 10.0, 1e-4);
+        assert_float_absolute_eq!(profile.altitude_at(180.0).unwrap(), 0.0, 1e-4);
+        assert!(HorizonProfile::default().altitude_at(0.0).is_none());
+    }
+}
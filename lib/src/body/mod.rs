@@ -1,24 +1,72 @@
+/// Contains terrain profiles that hide bodies low on an observatory's horizon
+pub mod horizon;
 /// Contains the definition of observatories that sit on the surface of a body and observe the
 /// motion of other bodies
 pub mod observatory;
 /// Contains logic for rotating bodies
 pub mod rotating;
+/// Contains the stages of a star's life
+pub mod stage;
+/// Contains tails that stream away from a body's light source, like a comet's
+pub mod tail;
+/// Contains changes in the light bodies give off over time, like a variable star's
+pub mod variability;
 
 use std::sync::{Arc as StdArc, RwLock, Weak as StdWeak};
 
-use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+use coordinates::prelude::{Dot, Magnitude, Spherical, ThreeDimensionalConsts, Vector3};
 use derive_builder::Builder;
-use log::{trace, warn};
 use rotating::Rotating;
 use serde::{Deserialize, Serialize};
-
-use crate::{dynamic::Dynamic, EllipticObservation, Float};
+use stage::StellarStage;
+use tail::Tail;
+use tracing::{trace, warn};
+use variability::Variability;
+
+use crate::{
+    consts::{float, ASTRONOMICAL_UNIT},
+    dynamic::Dynamic,
+    units::Length,
+    EllipticObservation, Float,
+};
 
 /// A convenience wrapper for [`std::sync::Arc`]`<`[`std::sync::RwLock`]`<`[`self::Body`]`>>`
 pub type Arc = StdArc<RwLock<Body>>;
 /// A convenience wrapper for [`std::sync::Weak`]`<`[`std::sync::RwLock`]`<`[`self::Body`]`>>`
 type Weak = StdWeak<RwLock<Body>>;
 
+/// Where positions are measured from in [`Body::positions_in_frame`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReferenceFrame {
+    /// The root of the tree
+    Root,
+    /// The body with this name, e.g. a star for heliocentric positions
+    Named(String),
+    /// The [center of mass](Body::get_barycenter) of the whole tree
+    Barycenter,
+}
+
+/// Iterator over every body in a tree, each body before the bodies orbiting it and in order of ID,
+/// see [`Body::iter_tree`]
+#[derive(Clone, Debug)]
+pub struct TreeIter {
+    /// Bodies still to be visited, the next one last
+    stack: Vec<Arc>,
+}
+
+impl Iterator for TreeIter {
+    type Item = Arc;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arc = self.stack.pop()?;
+        if let Ok(body) = arc.read() {
+            // Reversed so children come off the stack in order
+            self.stack.extend(body.children.iter().rev().cloned());
+        }
+        Some(arc)
+    }
+}
+
 /// A representation of a body in the simulation, such as a star, planet, center of mass, or moon.
 #[derive(Debug, Clone, Deserialize, Serialize, Builder)]
 #[serde(rename_all = "camelCase")]
@@ -35,12 +83,59 @@ pub struct Body {
     /// If the body has any o1fservatories it is highly recommended to initialize this.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) rotation: Option<Rotating>,
-    // Getting some parameters ready for a next version
-    // /// Mass of the body in jupiter masses
-    //mass: Float,
+    /// Mass of the body in jupiter masses
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "crate::json::non_negative"
+    )]
+    #[builder(default)]
+    pub(crate) mass: Option<Float>,
     /// Radius of the body in light seconds
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "crate::json::non_negative"
+    )]
     pub(crate) radius: Option<Float>,
+    /// Light given off by the body, in multiples of the Sun's luminosity
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "crate::json::non_negative"
+    )]
+    #[builder(default)]
+    pub(crate) luminosity: Option<Float>,
+    /// How the body's luminosity changes over time, if it does
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(default)]
+    pub(crate) variability: Option<Variability>,
+    /// Fraction of the light from its star that the body reflects, its [geometric
+    /// albedo](https://en.wikipedia.org/wiki/Geometric_albedo)
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "crate::json::non_negative"
+    )]
+    #[builder(default)]
+    pub(crate) albedo: Option<Float>,
+    /// Tail streaming away from the body's light source, if it has one
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(default)]
+    pub(crate) tail: Option<Tail>,
+    /// Stage in the life of the body if it is a star whose stage is known
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(default)]
+    pub(crate) stellar_stage: Option<StellarStage>,
+    /// Inner and outer radius of the body's rings in light seconds, measured in its equatorial
+    /// plane, if it has rings
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "crate::json::ring_radii"
+    )]
+    #[builder(default)]
+    pub(crate) rings: Option<(Float, Float)>,
     //color: [u8,h8,u8],
     #[serde(skip_serializing_if = "Name::is_calculated", default)]
     /// (Preferably unique) Name of the body. Is either user defined or generated from the ID of
@@ -56,13 +151,14 @@ impl From<Body> for Arc {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase", untagged)]
 pub enum Name {
     Named(StdArc<str>),
     #[serde(skip)]
     Id(StdArc<str>),
     #[serde(skip)]
+    #[default]
     Unknown,
 }
 
@@ -82,12 +178,6 @@ impl Name {
     }
 }
 
-impl Default for Name {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl<T: From<StdArc<str>>> From<Name> for Option<T> {
     fn from(value: Name) -> Self {
         match value {
@@ -106,6 +196,19 @@ impl<T: Into<StdArc<str>>> From<Option<T>> for Name {
     }
 }
 
+impl Drop for Body {
+    fn drop(&mut self) {
+        // Dropping children recursively can overflow the stack for very deep trees, so take
+        // ownership of any descendants that would be dropped with this body and drop them here
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(child) = stack.pop() {
+            if let Some(Ok(mut child)) = StdArc::into_inner(child).map(RwLock::into_inner) {
+                stack.append(&mut child.children);
+            }
+        }
+    }
+}
+
 impl PartialEq for Body {
     fn eq(&self, other: &Self) -> bool {
         self.dynamic == other.dynamic && self.rotation == other.rotation
@@ -126,7 +229,14 @@ impl Body {
             children: Vec::new(),
             dynamic: Box::new(dynamic),
             rotation: None,
+            mass: None,
             radius: None,
+            luminosity: None,
+            variability: None,
+            albedo: None,
+            tail: None,
+            stellar_stage: None,
+            rings: None,
             name: Name::Unknown,
         }));
         if let Some(p) = parent {
@@ -147,27 +257,134 @@ impl Body {
             vec![]
         };
 
-        if let Ok(mut child) = this.write() {
-            trace!("Hydrating {:?}", child.dynamic);
-            if parent.is_some() {
-                child.parent.clone_from(parent);
+        // Use an explicit stack rather than recursion so very deep trees don't overflow, and pass
+        // IDs down so very wide trees don't have to search their siblings for each child
+        let mut stack = vec![(this.clone(), parent.clone(), id)];
+        while let Some((body, parent, id)) = stack.pop() {
+            // A weak pointer to this body.
+            let weak = StdArc::downgrade(&body);
+
+            if let Ok(mut child) = body.write() {
+                trace!("Hydrating {:?}", child.dynamic);
+                if parent.is_some() {
+                    child.parent = parent;
+                }
+
+                trace!("Renaming {:?}", child.dynamic);
+                if let Name::Unknown = child.name {
+                    child.name = Name::from_id(&id);
+                }
+
+                for (index, grandchild) in child.children.iter().enumerate() {
+                    let mut grandchild_id = id.clone();
+                    grandchild_id.push(index);
+                    stack.push((grandchild.clone(), Some(weak.clone()), grandchild_id));
+                }
             }
+        }
+    }
+
+    /// Makes `child` (and its descendants) orbit `this`, adding it after any existing children.
+    /// Names that were generated from the child's old ID are generated again for its new place in
+    /// the tree.
+    pub fn add_child(this: &Arc, child: Arc) {
+        Self::forget_generated_names(&child);
+
+        if let Ok(mut parent) = this.write() {
+            parent.children.push(child.clone());
+        }
+        if let Ok(mut child) = child.write() {
+            child.parent = Some(StdArc::downgrade(this));
+        }
+        Self::hydrate_all(&child, &None);
+    }
 
-            trace!("Renaming {:?}", child.dynamic);
-            if let Name::Unknown = child.name {
-                child.name = Name::Id(observatory::to_name(&id).into());
+    /// Detaches the child of `this` at `index` (and its descendants) from the tree and returns it,
+    /// or `None` if there is no such child. The removed body no longer has a parent, so it is the
+    /// root of its own tree. The later children of `this` move down to fill the gap, and names
+    /// that were generated from IDs are generated again both for them and for the removed bodies.
+    pub fn remove_child(this: &Arc, index: usize) -> Option<Arc> {
+        let (child, later_children) = {
+            let mut parent = this.write().ok()?;
+            if index >= parent.children.len() {
+                return None;
             }
+            let child = parent.children.remove(index);
+            (child, parent.children[index..].to_vec())
+        };
+
+        if let Ok(mut child) = child.write() {
+            child.parent = None;
         }
+        Self::forget_generated_names(&child);
+        Self::hydrate_all(&child, &None);
 
-        // A weak pointer to this body.
-        let weak = StdArc::downgrade(this);
-        if let Ok(this) = this.read() {
-            for child in &this.children {
-                Self::hydrate_all(child, &Some(weak.clone()));
+        let parent = Some(StdArc::downgrade(this));
+        for sibling in later_children {
+            Self::forget_generated_names(&sibling);
+            Self::hydrate_all(&sibling, &parent);
+        }
+        Some(child)
+    }
+
+    /// Clears names generated from IDs in the tree under `root` (including `root`), so
+    /// [`Self::hydrate_all`] generates them again after the tree has moved
+    fn forget_generated_names(root: &Arc) {
+        let mut stack = vec![root.clone()];
+        while let Some(body) = stack.pop() {
+            if let Ok(mut body) = body.write() {
+                if let Name::Id(_) = body.name {
+                    body.name = Name::Unknown;
+                }
+                stack.extend(body.children.iter().cloned());
             }
         }
     }
 
+    /// Rotates every body in the tree below `root` (including `root`) about `root`'s parent by a
+    /// unit quaternion, then moves `root` by `translation`. Every position in the tree relative to `root`'s parent
+    /// becomes `rotation * position + translation`, so observations made from inside the tree of
+    /// bodies outside it (or the other way around) are transformed the same way. The rotation is
+    /// also applied to the [rotation](rotating::Rotating) axes of the bodies, so observations made
+    /// from the surface of a body of other bodies in the tree don't change.
+    ///
+    /// # Errors
+    /// Returns an error, leaving the tree unchanged, if any of the bodies has a dynamic that can't
+    /// be [transformed](Dynamic::transform), e.g. if `root` orbits its parent it can be rotated
+    /// but not translated.
+    pub fn transform_subtree(
+        root: &Arc,
+        rotation: quaternion::Quaternion<Float>,
+        translation: Vector3<Float>,
+    ) -> Result<(), String> {
+        // Transform copies first, so nothing changes if any of them fail
+        let mut transformed = Vec::new();
+        let mut stack = vec![(root.clone(), translation)];
+        while let Some((body, translation)) = stack.pop() {
+            let Ok(b) = body.read() else {
+                return Err("a body in the tree was poisoned".into());
+            };
+            let mut dynamic = dyn_clone::clone_box(&*b.dynamic);
+            dynamic.transform(rotation, translation)?;
+            let mut spin = b.rotation.clone();
+            if let Some(spin) = &mut spin {
+                spin.transform(rotation);
+            }
+            stack.extend(b.children.iter().map(|c| (c.clone(), Vector3::ORIGIN)));
+            drop(b);
+
+            transformed.push((body, dynamic, spin));
+        }
+
+        for (body, dynamic, spin) in transformed {
+            if let Ok(mut body) = body.write() {
+                body.dynamic = dynamic;
+                body.rotation = spin;
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the indexes of each child that must be decended into to reach this body.
     ///
     /// # Examples
@@ -207,12 +424,430 @@ impl Body {
         }
     }
 
+    /// Get the radius of this body in light seconds, if it is known
+    #[must_use]
+    pub fn get_radius(&self) -> Option<Float> {
+        self.radius
+    }
+
+    /// Get the radius of this body in the given unit, if it is known
+    #[must_use]
+    pub fn get_radius_in(&self, unit: Length) -> Option<Float> {
+        self.radius.map(|radius| unit.from_light_seconds(radius))
+    }
+
+    /// Sets the radius of this body, given in `unit`, e.g. `set_radius(1.0, Length::EarthRadii)`
+    /// for an Earth sized planet
+    pub fn set_radius(&mut self, radius: Float, unit: Length) {
+        self.radius = Some(unit.to_light_seconds(radius));
+    }
+
     /// Get the angular radius (`angular diameter / 2`) in radians
     #[must_use]
     pub fn get_angular_radius(&self, distance: Float) -> Float {
         self.radius.map_or(0.01, |r| (r / distance).asin())
     }
 
+    /// Get the [solid angle](https://en.wikipedia.org/wiki/Solid_angle) covered by this body's
+    /// disc in steradians when seen from a given distance, if its radius is known
+    #[must_use]
+    pub fn get_solid_angle(&self, distance: Float) -> Option<Float> {
+        self.radius.map(|r| {
+            // Inside the body it covers the whole sky, but we can only see half of it
+            let angular_radius = (r / distance).min(1.0).asin();
+            float::TAU * (1.0 - angular_radius.cos())
+        })
+    }
+
+    /// Get the mass of this body in jupiter masses, if it is known
+    #[must_use]
+    pub fn get_mass(&self) -> Option<Float> {
+        self.mass
+    }
+
+    /// Get the luminosity of this body in solar luminosities, if it gives off light
+    #[must_use]
+    pub fn get_luminosity(&self) -> Option<Float> {
+        self.luminosity
+    }
+
+    /// Get the luminosity of this body in solar luminosities at the given time, following its
+    /// [variability](Self::get_variability) if it has one, or `None` if it gives off no light
+    #[must_use]
+    pub fn get_luminosity_at(&self, time: Float) -> Option<Float> {
+        let luminosity = self.luminosity?;
+        Some(self.variability.map_or(luminosity, |variability| {
+            luminosity * variability.factor_at(time)
+        }))
+    }
+
+    /// Get how the luminosity of this body changes over time, if it does
+    #[must_use]
+    pub fn get_variability(&self) -> Option<&Variability> {
+        self.variability.as_ref()
+    }
+
+    /// Get the geometric albedo of this body, if it is known
+    #[must_use]
+    pub fn get_albedo(&self) -> Option<Float> {
+        self.albedo
+    }
+
+    /// Get the tail streaming away from this body's light source, if it has one
+    #[must_use]
+    pub fn get_tail(&self) -> Option<&Tail> {
+        self.tail.as_ref()
+    }
+
+    /// Get the inner and outer radius of this body's rings in light seconds, measured in its
+    /// equatorial plane, if it has rings
+    #[must_use]
+    pub fn get_rings(&self) -> Option<(Float, Float)> {
+        self.rings
+    }
+
+    /// Get the stage in the life of this body if it is a star whose stage is known
+    #[must_use]
+    pub fn get_stellar_stage(&self) -> Option<StellarStage> {
+        self.stellar_stage
+    }
+
+    /// Returns the closest ancestor that gives off light, which this body reflects
+    #[must_use]
+    pub fn get_light_source(&self) -> Option<Arc> {
+        let mut parent = self.parent.as_ref().and_then(StdWeak::upgrade);
+        while let Some(ancestor) = parent {
+            let next = match ancestor.read() {
+                Ok(a) if a.luminosity.is_some() => return Some(ancestor.clone()),
+                Ok(a) => a.parent.as_ref().and_then(StdWeak::upgrade),
+                Err(_) => None,
+            };
+            parent = next;
+        }
+        None
+    }
+
+    /// Returns the position of this body relative to the root of the tree at a given time.
+    ///
+    /// Like [`Self::get_observations_from_here`], bodies with a [mass](Self::get_mass) orbit the
+    /// barycenter they share with their massive parent, so this includes the reflex motion caused
+    /// by massive children. Trees without masses are a plain sum of offsets from each parent.
+    #[must_use]
+    pub fn get_absolute_position(&self, time: Float) -> Vector3<Float> {
+        let mut offset = self.dynamic.get_offset(time);
+        let mut mass = self.mass;
+        let mut position = offset - self.reflex(time);
+        let mut parent = self.parent.as_ref().and_then(StdWeak::upgrade);
+        while let Some(ancestor) = parent {
+            let Ok(ancestor) = ancestor.read() else {
+                break;
+            };
+            // The offset is from the shared barycenter, not the parent itself
+            position = position - offset * Self::mass_ratio(ancestor.mass, mass);
+            offset = ancestor.dynamic.get_offset(time);
+            mass = ancestor.mass;
+            position = position + offset;
+            parent = ancestor.parent.as_ref().and_then(StdWeak::upgrade);
+        }
+        position
+    }
+
+    /// Returns the fraction of the time between `start` and `end` hours that the center of this
+    /// body spends in the shadow `occluder` casts away from the light `source`, sampled every
+    /// `step` hours. Both the [umbra and penumbra](https://en.wikipedia.org/wiki/Umbra,_penumbra_and_antumbra)
+    /// count, so any eclipse of the source, partial or total, is included.
+    ///
+    /// Sources without a radius are treated as points, and occluders without one cast no shadow.
+    /// Returns 0 if there are no samples in the window.
+    #[must_use]
+    pub fn time_in_shadow(
+        &self,
+        source: &Arc,
+        occluder: &Arc,
+        start: Float,
+        end: Float,
+        step: Float,
+    ) -> Float {
+        let (Ok(source), Ok(occluder)) = (source.read(), occluder.read()) else {
+            return 0.0;
+        };
+        let Some(occluder_radius) = occluder.radius else {
+            return 0.0;
+        };
+        let source_radius = source.radius.unwrap_or(0.0);
+
+        let in_shadow = |time: Float| {
+            let occluder_position = occluder.get_absolute_position(time);
+            let axis = occluder_position - source.get_absolute_position(time);
+            let separation = axis.magnitude();
+            if separation == 0.0 {
+                return false;
+            }
+            let axis = axis / separation;
+            let offset = self.get_absolute_position(time) - occluder_position;
+            // Distance behind the occluder along the shadow's axis, and away from that axis
+            let behind = offset.dot(&axis);
+            let from_axis = (offset - axis * behind).magnitude();
+            // The penumbra is a cone widening away from the occluder, its edge touching the
+            // opposite sides of the source and occluder
+            behind > 0.0
+                && from_axis
+                    < occluder_radius + behind * (source_radius + occluder_radius) / separation
+        };
+
+        if step <= 0.0 {
+            return 0.0;
+        }
+        let (mut samples, mut shadowed) = (0_usize, 0_usize);
+        let mut time = start;
+        while time < end {
+            samples += 1;
+            if in_shadow(time) {
+                shadowed += 1;
+            }
+            time += step;
+        }
+        if samples == 0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)] // Far fewer samples than floats can count exactly
+        let fraction = shadowed as Float / samples as Float;
+        fraction
+    }
+
+    /// Returns the first body named `name` in the tree under `this` (including `this`), searching
+    /// in order of ID
+    #[must_use]
+    pub fn find_by_name(this: &Arc, name: &str) -> Option<Arc> {
+        Self::iter_tree(this).find(|arc| arc.read().is_ok_and(|body| &*body.get_name() == name))
+    }
+
+    /// Returns the body in the tree under `this` with the given ID relative to `this`, i.e. the
+    /// index of each child to follow from `this` down to it, or `None` if there isn't one
+    #[must_use]
+    pub fn find_by_id(this: &Arc, id: &[usize]) -> Option<Arc> {
+        id.iter().try_fold(this.clone(), |body, index| {
+            body.read().ok()?.children.get(*index).cloned()
+        })
+    }
+
+    /// Returns the number of bodies orbiting `this`, directly or indirectly
+    #[must_use]
+    pub fn descendant_count(this: &Arc) -> usize {
+        Self::iter_tree(this).count() - 1
+    }
+
+    /// Returns the pairs of this body's children whose orbital periods are close to a ratio of
+    /// small whole numbers, i.e. in [mean-motion
+    /// resonance](https://en.wikipedia.org/wiki/Orbital_resonance) like the 1:2:4 of Io, Europa,
+    /// and Ganymede.
+    ///
+    /// Each pair is given as `(i, j, (p, q))` where `i < j` are the indexes of the children and
+    /// their periods are within 1% of `p:q`, in lowest terms with neither more than 9. Children
+    /// without a [period](Dynamic::get_period) are never in resonance.
+    #[must_use]
+    pub fn resonances(&self) -> Vec<(usize, usize, (u32, u32))> {
+        /// How close the ratio of periods must be to a ratio of whole numbers, as a fraction of it
+        const TOLERANCE: Float = 0.01;
+        /// Largest whole number in a ratio
+        const MAX_ORDER: u8 = 9;
+
+        let periods: Vec<_> = self
+            .children
+            .iter()
+            .map(|child| {
+                child
+                    .read()
+                    .ok()
+                    .and_then(|c| c.dynamic.get_period())
+                    .filter(|period| period.is_finite() && *period > 0.0)
+            })
+            .collect();
+
+        let mut result = Vec::new();
+        for (i, a) in periods.iter().enumerate() {
+            for (j, b) in periods.iter().enumerate().skip(i + 1) {
+                let (Some(a), Some(b)) = (a, b) else {
+                    continue;
+                };
+                let ratio = a / b;
+                // Smaller numbers first, so the simplest ratio that fits is found
+                let resonance = (1..=MAX_ORDER).find_map(|order| {
+                    (1..=order)
+                        .flat_map(|other| [(order, other), (other, order)])
+                        .find(|(p, q)| {
+                            gcd(*p, *q) == 1
+                                && (Float::from(*p) / Float::from(*q) - ratio).abs()
+                                    <= TOLERANCE * ratio
+                        })
+                });
+                if let Some((p, q)) = resonance {
+                    result.push((i, j, (u32::from(p), u32::from(q))));
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns an iterator over every body in the tree under `this` (including `this`), in order
+    /// of ID
+    #[must_use]
+    pub fn iter_tree(this: &Arc) -> TreeIter {
+        TreeIter {
+            stack: vec![this.clone()],
+        }
+    }
+
+    /// Returns the center of mass of this body and everything orbiting it at a given time,
+    /// relative to the root of the tree. Only bodies with a [mass](Self::get_mass) count towards
+    /// it, so it's `None` if none of them have one.
+    #[must_use]
+    pub fn get_barycenter(this: &Arc, time: Float) -> Option<Vector3<Float>> {
+        let mut total_mass = 0.0;
+        let mut weighted_sum = Vector3::ORIGIN;
+        for (body, position) in Self::absolute_positions(this, time) {
+            if let Some(mass) = body.read().ok().and_then(|b| b.mass) {
+                total_mass += mass;
+                weighted_sum = weighted_sum + position * mass;
+            }
+        }
+        (total_mass > 0.0).then(|| weighted_sum / total_mass)
+    }
+
+    /// Returns every body in the tree under `root` (including `root`) with its position relative
+    /// to the origin of `frame` at a given time, e.g. heliocentric positions with
+    /// [`ReferenceFrame::Named`] and the name of the star.
+    ///
+    /// Returns `None` if the origin can't be found: no body has the given name, or no body has a
+    /// mass to find the barycenter from.
+    #[must_use]
+    pub fn positions_in_frame(
+        root: &Arc,
+        frame: &ReferenceFrame,
+        time: Float,
+    ) -> Option<Vec<EllipticObservation>> {
+        let positions = Self::absolute_positions(root, time);
+        let origin = match frame {
+            ReferenceFrame::Root => root.read().ok()?.get_absolute_position(time),
+            ReferenceFrame::Named(name) => positions
+                .iter()
+                .find(|(body, _)| body.read().is_ok_and(|b| &*b.get_name() == name))
+                .map(|(_, position)| *position)?,
+            ReferenceFrame::Barycenter => Self::get_barycenter(root, time)?,
+        };
+
+        Some(
+            positions
+                .into_iter()
+                .map(|(body, position)| (body, position - origin))
+                .collect(),
+        )
+    }
+
+    /// Returns this body and everything orbiting it with their
+    /// [absolute positions](Self::get_absolute_position), finding each position from its
+    /// parent's barycenter like [`Self::traverse_down`] rather than walking up the tree for every
+    /// body
+    fn absolute_positions(this: &Arc, time: Float) -> Vec<EllipticObservation> {
+        let Some(start) = this.read().ok().map(|b| b.get_absolute_position(time)) else {
+            return Vec::new();
+        };
+        let mut result = Vec::new();
+        let mut stack = vec![(this.clone(), start)];
+        while let Some((arc, position)) = stack.pop() {
+            if let Ok(body) = arc.read() {
+                let barycenter = position + body.reflex(time);
+                for child in &body.children {
+                    if let Ok(c) = child.read() {
+                        let offset = c.dynamic.get_offset(time)
+                            * (1.0 - Self::mass_ratio(body.mass, c.mass));
+                        stack.push((child.clone(), barycenter + offset - c.reflex(time)));
+                    }
+                }
+            }
+            result.push((arc, position));
+        }
+        result
+    }
+
+    /// Returns the star this body orbits, directly or as a moon. This is the outermost ancestor
+    /// with a known radius, or if no ancestors have a radius, the ancestor just below the root of
+    /// the tree (as in generated universes).
+    #[must_use]
+    pub fn get_star(&self) -> Option<Arc> {
+        let mut ancestors = Vec::new();
+        let mut parent = self.parent.as_ref().and_then(StdWeak::upgrade);
+        while let Some(ancestor) = parent {
+            parent = ancestor
+                .read()
+                .ok()
+                .and_then(|a| a.parent.as_ref().and_then(StdWeak::upgrade));
+            ancestors.push(ancestor);
+        }
+
+        let outermost_with_radius = ancestors
+            .iter()
+            .rev()
+            .find(|a| a.read().is_ok_and(|a| a.radius.is_some()));
+        return outermost_with_radius
+            .or_else(|| ancestors.len().checked_sub(2).map(|i| &ancestors[i]))
+            .cloned();
+    }
+
+    /// Get the direction of this body's geographic north pole, if it rotates
+    #[must_use]
+    pub fn get_rotation_axis(&self) -> Option<Vector3<Float>> {
+        self.rotation.as_ref().map(Rotating::get_axis)
+    }
+
+    /// Returns the point on this body's surface with its [star](Self::get_star) directly overhead
+    /// at the given time, as a unit vector in spherical coordinates relative to the body like the
+    /// locations of [observatories](observatory::Observatory::new). The polar angle is measured
+    /// from the geographic north pole, so the subsolar latitude is `π/2 - polar_angle`, and it
+    /// swings between plus and minus the axial tilt over the course of the body's year.
+    ///
+    /// Returns `None` if the body doesn't rotate or doesn't orbit a star.
+    #[must_use]
+    pub fn get_subsolar_point(&self, time: Float) -> Option<Spherical<Float>> {
+        let rotation = self.rotation.as_ref()?;
+        let star_offset = self.get_star()?.read().ok()?.get_absolute_position(time)
+            - self.get_absolute_position(time);
+
+        let mut subsolar_point: Spherical<Float> = rotation.to_equatorial(time, star_offset).into();
+        subsolar_point.radius = 1.0;
+        Some(subsolar_point)
+    }
+
+    /// Returns the sunlight falling on a flat patch of ground at the given location on this body
+    /// (in the same coordinates as [`Self::get_subsolar_point`]), relative to the sunlight Earth
+    /// gets from the Sun directly overhead. It falls off with the inverse square of the distance
+    /// to the star and the cosine of the angle the light comes in at, and is zero at night.
+    ///
+    /// Stars without a [luminosity](Self::get_luminosity) are treated as being as bright as the
+    /// Sun. Returns `None` if the body doesn't rotate or doesn't orbit a star.
+    #[must_use]
+    pub fn get_relative_insolation(
+        &self,
+        location: &Spherical<Float>,
+        time: Float,
+    ) -> Option<Float> {
+        let subsolar_point: Vector3<Float> = self.get_subsolar_point(time)?.into();
+        let star = self.get_star()?;
+        let star = star.read().ok()?;
+        let distance =
+            (star.get_absolute_position(time) - self.get_absolute_position(time)).magnitude();
+
+        let mut location = *location;
+        location.radius = 1.0;
+        let incidence = Vector3::from(location).dot(&subsolar_point).max(0.0);
+
+        Some(
+            star.get_luminosity_at(time).unwrap_or(1.0)
+                * (ASTRONOMICAL_UNIT / distance).powi(2)
+                * incidence,
+        )
+    }
+
     /// # Panics
     ///
     /// Panics if name is [`Name::Unknown`], this occurs if the serialized body doesn't have a name
@@ -241,15 +876,26 @@ impl Body {
         &*self.dynamic
     }
 
+    /// Returns what every other body in the tree looks like from this one at a given time,
+    /// relative to this body and rotated into its equatorial frame if it rotates.
+    ///
+    /// Where a body and its parent both have a [mass](Self::get_mass), they orbit their common
+    /// barycenter: the parent is shifted by `-child_mass / (parent_mass + child_mass)` times the
+    /// child's offset and the child makes up the rest of it, so the two stay the same distance
+    /// apart. Bodies without a mass don't pull their parent about, as if they were massless.
     #[must_use]
     pub fn get_observations_from_here(&self, time: Float) -> Vec<EllipticObservation> {
-        let mut results = self.traverse_down(time, Vector3::ORIGIN);
+        // This body is at the origin, so the barycenter it's pulled away from is the other way
+        let (offsets, reflex) = self.barycentric_offsets(time);
+        let mut results = self.traverse_down(time, reflex, &offsets);
         if let Some(parent) = self.parent.clone().and_then(|p| p.upgrade()) {
             if let Ok(parent) = parent.read() {
+                let offset = self.dynamic.get_offset(time)
+                    * (1.0 - Self::mass_ratio(parent.mass, self.mass));
                 // PERF: return an iterator instead of copying all elements into a single vector
                 results.extend(
                     parent
-                        .traverse_up(time, Vector3::ORIGIN - self.dynamic.get_offset(time))
+                        .traverse_up(time, reflex - offset)
                         .into_iter()
                         // Remove current body from the results
                         .filter(|(b, _)| b.read().is_ok_and(|b| b.get_name() != self.get_name())),
@@ -263,55 +909,103 @@ impl Body {
         results
     }
 
-    /// Returns the locations of the children relative to `current_position`
+    /// Returns the fraction of the distance between a parent and child of the given masses that
+    /// the parent sits from their barycenter, or 0 if either of them has no mass
+    fn mass_ratio(parent_mass: Option<Float>, child_mass: Option<Float>) -> Float {
+        match (parent_mass, child_mass) {
+            (Some(parent_mass), Some(child_mass)) if parent_mass + child_mass > 0.0 => {
+                child_mass / (parent_mass + child_mass)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Returns each child with its offset from the barycenter it shares with this body, along
+    /// with how far the children pull this body from that barycenter, so this body is at the
+    /// barycenter minus the returned shift
+    fn barycentric_offsets(&self, time: Float) -> (Vec<(Arc, Vector3<Float>)>, Vector3<Float>) {
+        let mut offsets = Vec::with_capacity(self.children.len());
+        let mut reflex = Vector3::ORIGIN;
+        for c in &self.children {
+            if let Ok(child) = c.read() {
+                let offset = child.dynamic.get_offset(time);
+                let ratio = Self::mass_ratio(self.mass, child.mass);
+                reflex = reflex + offset * ratio;
+                offsets.push((c.clone(), offset * (1.0 - ratio)));
+            }
+        }
+        (offsets, reflex)
+    }
+
+    /// Returns how far this body's children pull it from the barycenter it shares with them,
+    /// without finding the offsets of children that can't pull on it
+    fn reflex(&self, time: Float) -> Vector3<Float> {
+        if self.mass.is_none() {
+            return Vector3::ORIGIN;
+        }
+        let mut reflex = Vector3::ORIGIN;
+        for c in &self.children {
+            if let Ok(child) = c.read() {
+                if child.mass.is_some() {
+                    reflex = reflex
+                        + child.dynamic.get_offset(time) * Self::mass_ratio(self.mass, child.mass);
+                }
+            }
+        }
+        reflex
+    }
+
+    /// Returns the locations of the descendants of this body, given the location of the
+    /// barycenter it shares with its children and their offsets from it
     #[must_use]
     fn traverse_down(
         &self,
         time: Float,
-        current_position: Vector3<Float>,
+        barycenter: Vector3<Float>,
+        offsets: &[(Arc, Vector3<Float>)],
     ) -> Vec<EllipticObservation> {
-        let mut results = Vec::with_capacity(self.children.len());
+        let mut results = Vec::with_capacity(offsets.len());
 
         // For each child
-        for c in &self.children {
+        for (c, offset) in offsets {
             if let Ok(child) = c.read() {
-                // Get the child position relative to here
-                let location = child.dynamic.get_offset(time) + current_position;
+                // Get the child's barycenter relative to here
+                let child_barycenter = barycenter + *offset;
+                let (grandchildren, reflex) = child.barycentric_offsets(time);
                 // Add grandchildren, great-grandchildren, etc.
-                results.extend(child.traverse_down(time, location));
+                results.extend(child.traverse_down(time, child_barycenter, &grandchildren));
 
                 // Add that child
-                results.push((c.clone(), location));
+                results.push((c.clone(), child_barycenter - reflex));
             }
         }
 
         results
     }
 
-    /// Returns the location of parents relative to the `current_position`
+    /// Returns the location of parents and their children, given the location of the barycenter
+    /// this body shares with its children
     #[must_use]
-    fn traverse_up(
-        &self,
-        time: Float,
-        current_position: Vector3<Float>,
-    ) -> Vec<EllipticObservation> {
+    fn traverse_up(&self, time: Float, barycenter: Vector3<Float>) -> Vec<EllipticObservation> {
         let mut results = Vec::with_capacity(self.children.len() + 2);
-        for c in &self.children {
+        let (offsets, reflex) = self.barycentric_offsets(time);
+        for (c, offset) in offsets {
             // Add parents and cousins
             if let Ok(child) = c.read() {
-                let child_location = current_position + child.dynamic.get_offset(time);
+                let child_location = barycenter + offset - child.reflex(time);
                 results.push((c.clone(), child_location));
             }
         }
 
         // If the parent still exists
         if let Some(p) = &self.parent.clone().and_then(|weak| weak.upgrade()) {
-            // Calculate the parent's location by getting our offset
-            let parent_location = current_position - self.dynamic.get_offset(time);
-
             if let Ok(parent) = p.read() {
+                // Calculate the parent's barycenter by getting our offset from it
+                let parent_barycenter = barycenter
+                    - self.dynamic.get_offset(time)
+                        * (1.0 - Self::mass_ratio(parent.mass, self.mass));
                 // Add the grandparent, great-grandparent, etc.
-                results.append(&mut parent.traverse_up(time, parent_location));
+                results.append(&mut parent.traverse_up(time, parent_barycenter));
             }
         } else {
             // This body is the root. We need to add it manually since it can't be added by a parent
@@ -326,7 +1020,7 @@ impl Body {
                     .ok()
                     .and_then(|c| c.parent.clone().and_then(|x| x.upgrade()))
                     .unwrap(),
-                current_position,
+                barycenter - reflex,
             ));
         }
 
@@ -334,11 +1028,307 @@ impl Body {
     }
 }
 
+/// Returns the greatest common divisor of two numbers
+fn gcd(mut a: u8, mut b: u8) -> u8 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
 #[cfg(test)]
 mod tests {
     use crate::dynamic::{fixed::Fixed, keplerian::Keplerian};
+    use coordinates::prelude::Magnitude;
 
     use super::*;
+
+    #[test]
+    fn finds_resonant_children() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // Periods of 100, 201, and 317 hours, so only the first two are close to 1:2
+        for (semi_major_axis, period) in [(1.0, 100.0), (1.6, 201.0), (2.2, 317.0)] {
+            Body::new(
+                Some(root.clone()),
+                Keplerian::new_with_period(0.0, semi_major_axis, 0.0, 0.0, 0.0, 0.0, period),
+            );
+        }
+        Body::new(Some(root.clone()), Fixed::new(Vector3::UP));
+
+        let root = root.read().unwrap();
+        assert_eq!(root.resonances(), vec![(0, 1, (1, 2))]);
+    }
+
+    #[test]
+    fn rings_read_and_written() {
+        let json = r#"{"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "rings": [2.0, 5.0]}"#;
+        let body: Body = crate::json::from_str(json).unwrap();
+        assert_eq!(body.get_rings(), Some((2.0, 5.0)));
+        assert!(serde_json::to_string(&body)
+            .unwrap()
+            .contains(r#""rings":[2.0,5.0]"#));
+
+        // Left out when there are none, and optional when building
+        let body = BodyBuilder::default()
+            .parent(None)
+            .children(vec![])
+            .rotation(None)
+            .radius(None)
+            .name(Name::Unknown)
+            .dynamic(Box::new(Fixed::new(Vector3::ORIGIN)))
+            .build()
+            .unwrap();
+        assert_eq!(body.get_rings(), None);
+        assert!(!serde_json::to_string(&body).unwrap().contains("rings"));
+
+        for rings in ["[5.0, 2.0]", "[-1.0, 2.0]", "[1.0, 1e999]"] {
+            let json = format!(
+                r#"{{"dynamic": {{"Fixed": {{"x": 0, "y": 0, "z": 0}}}}, "rings": {rings}}}"#
+            );
+            assert!(crate::json::from_str::<Body>(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn radius_in_earth_radii() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let mut planet = planet.write().unwrap();
+
+        planet.set_radius(2.0, Length::EarthRadii);
+        assert_float_relative_eq!(planet.get_radius().unwrap(), 0.042_502_796, 1e-6);
+        assert_float_relative_eq!(
+            planet.get_radius_in(Length::Kilometres).unwrap(),
+            12_742.0,
+            1e-4
+        );
+
+        planet.set_radius(1.0, Length::SolarRadii);
+        assert_float_relative_eq!(
+            planet.get_radius_in(Length::EarthRadii).unwrap(),
+            109.2,
+            1e-3
+        );
+    }
+
+    #[test]
+    fn moon_shadows_planet_once_an_orbit() {
+        const PERIOD: Float = 100.0;
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let moon = Body::new(
+            Some(planet.clone()),
+            Keplerian::new_with_period(0.0, 3.0, 0.0, 0.0, 0.0, 0.0, PERIOD),
+        );
+        // Put the star behind the moon at the start of its orbit
+        let towards_moon = moon.read().unwrap().get_dynamic().get_offset(0.0) / 3.0;
+        let star = Body::new(Some(planet.clone()), Fixed::new(towards_moon * 500.0));
+        star.write().unwrap().radius = Some(2.0);
+        moon.write().unwrap().radius = Some(0.5);
+
+        // The penumbra is this wide where the planet is, and the moon sweeps through it
+        let width: Float = 0.5 + 3.0 * 2.5 / 500.0;
+        let expected = (width / 3.0).asin() / float::PI;
+
+        let planet = planet.read().unwrap();
+        let fraction = planet.time_in_shadow(&star, &moon, 0.0, 10.0 * PERIOD, 0.1);
+        assert_float_relative_eq!(fraction, expected, 0.05);
+        // The star is never between the moon and planet
+        assert_float_absolute_eq!(planet.time_in_shadow(&moon, &star, 0.0, PERIOD, 0.1), 0.0);
+        assert_float_absolute_eq!(planet.time_in_shadow(&star, &moon, 0.0, 0.0, 0.1), 0.0);
+    }
+
+    #[test]
+    fn equal_mass_binary_orbits_barycenter() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let primary = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let secondary = Body::new(
+            Some(primary.clone()),
+            Keplerian::new_with_period(0.0, 2.0, 0.3, 0.0, 0.0, 0.0, 10.0),
+        );
+        // A massless moon of the primary far out, so it sees the barycenter and both stars
+        let observer = Body::new(Some(primary.clone()), Fixed::new(Vector3::UP * 100.0));
+        primary.write().unwrap().mass = Some(1.0);
+        secondary.write().unwrap().mass = Some(1.0);
+        Body::hydrate_all(&root, &None);
+
+        let position_of = |observations: &[EllipticObservation], body: &Arc| {
+            observations
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+                .map(|(_, position)| *position)
+                .unwrap()
+        };
+        for time in [0.0, 2.5, 7.0] {
+            let observations = observer.read().unwrap().get_observations_from_here(time);
+            let barycenter = position_of(&observations, &root);
+            let primary_offset = position_of(&observations, &primary) - barycenter;
+            let secondary_offset = position_of(&observations, &secondary) - barycenter;
+            assert_float_absolute_eq!((primary_offset + secondary_offset).magnitude(), 0.0, 1e-4);
+            assert_float_absolute_eq!(secondary_offset.magnitude(), 1.0, 1e-4);
+
+            // They stay the same distance apart seen from either of them
+            let from_primary = primary.read().unwrap().get_observations_from_here(time);
+            assert_float_absolute_eq!(
+                position_of(&from_primary, &secondary).magnitude(),
+                2.0,
+                1e-4
+            );
+            let from_secondary = secondary.read().unwrap().get_observations_from_here(time);
+            let primary_from_secondary = position_of(&from_secondary, &primary);
+            assert_float_absolute_eq!(
+                (primary_from_secondary + secondary_offset * 2.0).magnitude(),
+                0.0,
+                1e-4
+            );
+        }
+
+        // Without a mass the secondary doesn't move the primary
+        secondary.write().unwrap().mass = None;
+        let observations = observer.read().unwrap().get_observations_from_here(2.5);
+        let primary_offset =
+            position_of(&observations, &primary) - position_of(&observations, &root);
+        assert_float_absolute_eq!(primary_offset.magnitude(), 0.0, 1e-4);
+    }
+
+    #[test]
+    fn primary_shows_reflex_motion() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let primary = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let secondary = Body::new(
+            Some(primary.clone()),
+            Keplerian::new_with_period(0.0, 4.0, 0.0, 0.0, 0.0, 0.0, 10.0),
+        );
+        primary.write().unwrap().mass = Some(3.0);
+        secondary.write().unwrap().mass = Some(1.0);
+        Body::hydrate_all(&root, &None);
+
+        let mut previous: Option<Vector3<Float>> = None;
+        for time in [0.0, 2.5, 5.0, 7.5] {
+            let primary_position = primary.read().unwrap().get_absolute_position(time);
+            let secondary_position = secondary.read().unwrap().get_absolute_position(time);
+            // A quarter of the way from the primary to the secondary
+            assert_float_absolute_eq!(primary_position.magnitude(), 1.0, 1e-4);
+            assert_float_absolute_eq!(secondary_position.magnitude(), 3.0, 1e-4);
+            assert_float_absolute_eq!(
+                (primary_position * 3.0 + secondary_position).magnitude(),
+                0.0,
+                1e-4
+            );
+            // The primary moves around the barycenter as the secondary orbits
+            if let Some(previous) = previous {
+                assert!((primary_position - previous).magnitude() > 1.0);
+            }
+            previous = Some(primary_position);
+
+            // The same positions as the tree is observed from the root
+            let observations = root.read().unwrap().get_observations_from_here(time);
+            let observed = observations
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, &primary))
+                .unwrap()
+                .1;
+            assert_float_absolute_eq!((observed - primary_position).magnitude(), 0.0, 1e-4);
+        }
+
+        // Without masses the primary stays at the root
+        secondary.write().unwrap().mass = None;
+        assert_float_absolute_eq!(
+            primary
+                .read()
+                .unwrap()
+                .get_absolute_position(2.5)
+                .magnitude(),
+            0.0,
+            1e-4
+        );
+    }
+
+    #[test]
+    fn heliocentric_positions_are_relative_to_star() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(
+            Some(root.clone()),
+            Keplerian::new(0.1, 3.0, 0.2, 0.3, 0.4, 0.5, 1.0),
+        );
+        let planets: Vec<_> = (1_u8..4)
+            .map(|i| {
+                Body::new(
+                    Some(star.clone()),
+                    Keplerian::new(0.05, 500.0 * Float::from(i), 0.1, 0.2, 0.3, 0.4, 1_000.0),
+                )
+            })
+            .collect();
+        star.write().unwrap().name = Name::Named("Star".into());
+        star.write().unwrap().mass = Some(1_000.0);
+        for planet in &planets {
+            planet.write().unwrap().mass = Some(1.0);
+        }
+        Body::hydrate_all(&root, &None);
+
+        let time = 1_234.0;
+        let heliocentric =
+            Body::positions_in_frame(&root, &ReferenceFrame::Named("Star".into()), time).unwrap();
+        assert_eq!(heliocentric.len(), 5);
+        for planet in &planets {
+            let (_, position) = heliocentric
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, planet))
+                .unwrap();
+            let expected = planet.read().unwrap().get_absolute_position(time)
+                - star.read().unwrap().get_absolute_position(time);
+            assert_float_absolute_eq!((*position - expected).magnitude(), 0.0, 1e-3);
+        }
+
+        let barycentric =
+            Body::positions_in_frame(&root, &ReferenceFrame::Barycenter, time).unwrap();
+        let weighted_sum = barycentric
+            .iter()
+            .filter_map(|(b, position)| Some(*position * b.read().unwrap().mass?))
+            .fold(Vector3::ORIGIN, |sum, x| sum + x);
+        assert_float_absolute_eq!(weighted_sum.magnitude() / 1_003.0, 0.0, 1e-3);
+
+        assert!(
+            Body::positions_in_frame(&root, &ReferenceFrame::Named("Nope".into()), time).is_none()
+        );
+        let root_frame = Body::positions_in_frame(&root, &ReferenceFrame::Root, time).unwrap();
+        assert!(root_frame
+            .iter()
+            .any(|(b, position)| std::sync::Arc::ptr_eq(b, &root) && position.magnitude() == 0.0));
+    }
+
+    #[test]
+    fn positions_in_frame_match_absolute_positions() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let primary = Body::new(
+            Some(root.clone()),
+            Keplerian::new(0.1, 3.0, 0.2, 0.3, 0.4, 0.5, 1.0),
+        );
+        let children: Vec<_> = [(1.0, 100.0), (2.0, 250.0)]
+            .into_iter()
+            .map(|(mass, semi_major_axis)| {
+                let child = Body::new(
+                    Some(primary.clone()),
+                    Keplerian::new(0.2, semi_major_axis, 0.1, 0.2, 0.3, 0.4, 1_000.0),
+                );
+                child.write().unwrap().mass = Some(mass);
+                child
+            })
+            .collect();
+        let moon = Body::new(
+            Some(children[1].clone()),
+            Keplerian::new(0.0, 5.0, 0.0, 0.0, 0.0, 0.0, 2.0),
+        );
+        moon.write().unwrap().mass = Some(0.5);
+        primary.write().unwrap().mass = Some(10.0);
+        Body::hydrate_all(&root, &None);
+
+        let time = 42.0;
+        let positions = Body::positions_in_frame(&root, &ReferenceFrame::Root, time).unwrap();
+        assert_eq!(positions.len(), 5);
+        for (body, position) in positions {
+            let expected = body.read().unwrap().get_absolute_position(time);
+            assert_float_absolute_eq!((position - expected).magnitude(), 0.0, 1e-6);
+        }
+    }
     macro_rules! new_planet {
         ($name:ident, $parent:ident, $period:tt, $sma:tt, $ecc:tt, $inc:tt, $lan:expr, $aop:tt, $mae:tt) => {
             let $name = Body::new(
@@ -652,4 +1642,222 @@ mod tests {
         });
         matches!(Option::<StdArc<str>>::None.into(), Name::Unknown);
     }
+
+    /// A star with a rotating planet and a moon, and the bodies observed from them
+    fn transform_example() -> (Arc, Arc, Arc) {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(
+            Some(root.clone()),
+            Fixed::new(Vector3 {
+                x: 100.0,
+                y: 20.0,
+                z: -5.0,
+            }),
+        );
+        let planet = Body::new(
+            Some(star.clone()),
+            Keplerian::new_with_period(0.1, 500.0, 0.3, 1.2, 0.4, 0.5, 8_000.0),
+        );
+        planet.write().unwrap().rotation = Some(Rotating::new(
+            24.0,
+            coordinates::prelude::Spherical {
+                radius: 1.0,
+                polar_angle: 0.4,
+                azimuthal_angle: 2.0,
+            },
+        ));
+        let moon = Body::new(
+            Some(planet.clone()),
+            Keplerian::new_with_period(0.05, 1.3, 0.0, 0.0, 0.2, 1.0, 650.0),
+        );
+        let _other_moon = Body::new(
+            Some(planet.clone()),
+            Keplerian::new_with_period(0.2, 2.0, 2.5, 4.0, 1.0, 3.0, 900.0),
+        );
+        Body::hydrate_all(&root, &None);
+        (root, planet, moon)
+    }
+
+    /// Returns what is observed from a body, by name
+    fn observed_from(body: &Arc, time: Float) -> Vec<(StdArc<str>, Vector3<Float>)> {
+        let mut result: Vec<_> = body
+            .read()
+            .unwrap()
+            .get_observations_from_here(time)
+            .into_iter()
+            .map(|(b, location)| (b.read().unwrap().get_name(), location))
+            .collect();
+        result.sort_by(|(a, _), (b, _)| a.cmp(b));
+        result
+    }
+
+    #[test]
+    fn transformed_tree_is_observed_transformed() {
+        let (root, planet, moon) = transform_example();
+        let rotation = quaternion::mul(
+            quaternion::axis_angle([1.0, 0.0, 0.0], 0.5),
+            quaternion::axis_angle([0.0, 0.6, 0.8], -1.0),
+        );
+        let times = [0.0, 7.0, 1_234.5];
+        let before: Vec<_> = times
+            .iter()
+            .map(|t| (observed_from(&planet, *t), observed_from(&moon, *t)))
+            .collect();
+        let star_before = root.read().unwrap().children[0]
+            .read()
+            .unwrap()
+            .get_absolute_position(0.0);
+
+        Body::transform_subtree(&root, rotation, Vector3::RIGHT * 1e4).unwrap();
+
+        let star_after = root.read().unwrap().children[0]
+            .read()
+            .unwrap()
+            .get_absolute_position(0.0);
+        let expected = Vector3::from(quaternion::rotate_vector(rotation, star_before.into()))
+            + Vector3::RIGHT * 1e4;
+        assert_float_absolute_eq!((star_after - expected).magnitude(), 0.0, 1e-2);
+
+        for (time, (from_planet, from_moon)) in times.iter().zip(before) {
+            // The planet rotates with the tree, so its sky doesn't change
+            for ((name, a), (name_after, b)) in
+                from_planet.iter().zip(observed_from(&planet, *time))
+            {
+                assert_eq!(*name, name_after);
+                assert_float_absolute_eq!((*a - b).magnitude() / a.magnitude(), 0.0, 1e-3);
+            }
+            // The moon doesn't rotate, so its sky rotates with the tree
+            for ((name, a), (name_after, b)) in from_moon.iter().zip(observed_from(&moon, *time)) {
+                assert_eq!(*name, name_after);
+                let a = Vector3::from(quaternion::rotate_vector(rotation, (*a).into()));
+                assert_float_absolute_eq!((a - b).magnitude() / a.magnitude(), 0.0, 1e-3);
+            }
+        }
+
+        // The transformed orbits and rotations serialize to the same tree
+        let json = serde_json::to_string(&*root.read().unwrap()).unwrap();
+        let reread: Arc = crate::json::from_str::<Body>(&json).unwrap().into();
+        let reread_planet = reread.read().unwrap().children[0].read().unwrap().children[0].clone();
+        for ((_, a), (_, b)) in observed_from(&planet, 7.0)
+            .iter()
+            .zip(observed_from(&reread_planet, 7.0))
+        {
+            assert_float_absolute_eq!((*a - b).magnitude() / a.magnitude(), 0.0, 1e-3);
+        }
+    }
+
+    #[test]
+    fn orbits_cannot_be_translated() {
+        let (_root, planet, moon) = transform_example();
+        let before = format!("{:?}", planet.read().unwrap().children);
+
+        let result = Body::transform_subtree(&planet, quaternion::id(), Vector3::UP);
+
+        assert!(result.is_err());
+        assert_eq!(format!("{:?}", planet.read().unwrap().children), before);
+        // Orbits can still be rotated in place
+        assert!(Body::transform_subtree(&moon, quaternion::id(), Vector3::ORIGIN).is_ok());
+    }
+
+    #[test]
+    fn adopted_child_is_renamed() {
+        let (root, planet, _moon) = transform_example();
+        let (other_root, _, _) = transform_example();
+
+        Body::add_child(&planet, other_root.clone());
+
+        let other = other_root.read().unwrap();
+        assert_eq!(&*other.get_name(), "0-0-2");
+        assert_eq!(other.get_id(), vec![0, 0, 2]);
+        assert_eq!(&*other.children[0].read().unwrap().get_name(), "0-0-2-0");
+        assert!(root.read().unwrap().get_observations_from_here(0.0).len() > 5);
+    }
+
+    #[test]
+    fn removed_child_detached_from_tree() {
+        let (root, planet, moon) = transform_example();
+        let star = Body::find_by_id(&root, &[0]).unwrap();
+        let sibling = Body::new(Some(root.clone()), Fixed::new(Vector3::RIGHT));
+        let later_sibling = Body::new(Some(root.clone()), Fixed::new(Vector3::LEFT));
+        Body::hydrate_all(&root, &None);
+        let before = Body::descendant_count(&root);
+        let removed_count = Body::descendant_count(&star) + 1;
+        assert_eq!(&*later_sibling.read().unwrap().get_name(), "2");
+
+        assert!(Body::remove_child(&root, 3).is_none());
+        let removed = Body::remove_child(&root, 0).unwrap();
+
+        assert!(StdArc::ptr_eq(&removed, &star));
+        assert_eq!(Body::descendant_count(&root), before - removed_count);
+        assert!(removed.read().unwrap().parent.is_none());
+        assert_eq!(&*removed.read().unwrap().get_name(), "");
+        assert_eq!(&*planet.read().unwrap().get_name(), "0");
+        assert_eq!(&*moon.read().unwrap().get_name(), "0-0");
+        for body in Body::iter_tree(&removed) {
+            let id = body.read().unwrap().get_id();
+            let found = Body::find_by_id(&root, &id);
+            assert!(found.is_none_or(|found| !StdArc::ptr_eq(&found, &body)));
+            assert!(Body::iter_tree(&root).all(|other| !StdArc::ptr_eq(&other, &body)));
+        }
+
+        // Later children moved down and were renamed, and still reach their parent
+        assert!(StdArc::ptr_eq(
+            &Body::find_by_id(&root, &[0]).unwrap(),
+            &sibling
+        ));
+        let later = later_sibling.read().unwrap();
+        assert_eq!(&*later.get_name(), "1");
+        assert_eq!(later.get_id(), vec![1]);
+        let parent = later.parent.as_ref().and_then(StdWeak::upgrade).unwrap();
+        assert!(StdArc::ptr_eq(&parent, &root));
+        assert!(Body::find_by_id(&root, &[2]).is_none());
+    }
+
+    #[test]
+    fn untilted_subsolar_point_on_equator() {
+        let star = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        star.write().unwrap().luminosity = Some(1.0);
+        star.write().unwrap().radius = Some(2.3);
+        let orbit = Keplerian::new(0.0, ASTRONOMICAL_UNIT, 0.3, 1.0, 0.0, 0.0, 1_048.0);
+        let year = orbit.get_period().unwrap();
+        let planet = Body::new(Some(star.clone()), orbit.clone());
+        let equinox_axis = Spherical::from(orbit.get_orbit_normal());
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, equinox_axis));
+        Body::hydrate_all(&star, &None);
+
+        let mut tilted_axis = equinox_axis;
+        tilted_axis.polar_angle += 0.4;
+        let mut furthest_from_equator: Float = 0.0;
+        for i in 0_u8..100 {
+            let time = Float::from(i) / 100.0 * year;
+            let planet = planet.read().unwrap();
+            let subsolar_point = planet.get_subsolar_point(time).unwrap();
+            assert_float_absolute_eq!(subsolar_point.polar_angle, float::FRAC_PI_2, 1e-4);
+
+            // Overhead at one AU, and night on the other side
+            assert_float_absolute_eq!(
+                planet
+                    .get_relative_insolation(&subsolar_point, time)
+                    .unwrap(),
+                1.0,
+                1e-4
+            );
+            let mut antisolar_point = subsolar_point;
+            antisolar_point.polar_angle = float::PI - antisolar_point.polar_angle;
+            antisolar_point.azimuthal_angle += float::PI;
+            assert_float_absolute_eq!(
+                planet
+                    .get_relative_insolation(&antisolar_point, time)
+                    .unwrap(),
+                0.0
+            );
+
+            // With a tilted axis the seasons take it up to the tilt away from the equator
+            let mut tilted = planet.clone();
+            tilted.rotation = Some(Rotating::new(24.0, tilted_axis));
+            let latitude = float::FRAC_PI_2 - tilted.get_subsolar_point(time).unwrap().polar_angle;
+            furthest_from_equator = furthest_from_equator.max(latitude.abs());
+        }
+        assert_float_absolute_eq!(furthest_from_equator, 0.4, 1e-2);
+    }
 }
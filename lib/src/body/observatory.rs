@@ -1,11 +1,16 @@
-use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
-use log::warn;
+use std::collections::BTreeMap;
+
+use coordinates::prelude::{Cross3D, Dot, Magnitude, Spherical, ThreeDimensionalConsts, Vector3};
 use quaternion::Quaternion;
 use serde::{Deserialize, Serialize};
+use tracing::{field, span::EnteredSpan, trace_span, warn};
 
-use crate::{Float, LocalObservation};
+use crate::{
+    consts::{float, ASTRONOMICAL_UNIT, SUN_APPARENT_MAGNITUDE},
+    json, EllipticObservation, Float, LocalObservation,
+};
 
-use super::Arc;
+use super::{horizon::HorizonProfile, Arc};
 
 /// Defines a place on the surface of a body where observations are made of the motion of bodies.
 #[derive(Debug, Clone, Serialize)]
@@ -22,6 +27,26 @@ pub struct Observatory {
 
     /// List of constellations that could be visible from this observatory
     constellations: Vec<crate::constellation::Constellation>,
+
+    /// Terrain that hides bodies low on the horizon, if any
+    horizon_profile: Option<HorizonProfile>,
+
+    /// Hours added to the time of each frame before observing, so observatories can keep their
+    /// own schedules
+    time_offset_hours: Float,
+
+    /// Whether observations include the body the observatory is on, as the ground beneath it
+    include_host: bool,
+
+    /// Whether observations include bodies below the horizon or behind terrain
+    include_below_horizon: bool,
+
+    /// Altitude in degrees that bodies have to be above to be seen, 0 for the true horizon
+    horizon_altitude_deg: Float,
+
+    /// Rotation from the body's coordinates to local coordinates with a
+    /// [custom up](Self::with_reference_up) at the zenith, instead of [`Self::location`]
+    reference_up: Option<(Spherical<Float>, Quaternion<Float>)>,
 }
 
 impl Observatory {
@@ -40,40 +65,652 @@ impl Observatory {
             body,
             name,
             constellations,
+            horizon_profile: None,
+            time_offset_hours: 0.0,
+            include_host: false,
+            include_below_horizon: false,
+            horizon_altitude_deg: 0.0,
+            reference_up: None,
         }
     }
 
+    /// Generates an observatory on the given body at a latitude and longitude in degrees.
+    ///
+    /// Latitude is measured north from the body's equator, from -90° at the south pole to 90° at
+    /// the north pole (the body's rotation axis). Longitude is measured east from the prime
+    /// meridian, the body's x axis, and may be any angle.
+    #[must_use]
+    pub fn from_lat_long(
+        lat_deg: Float,
+        long_deg: Float,
+        body: Arc,
+        name: Result<String, Vec<usize>>,
+        constellations: Vec<crate::constellation::Constellation>,
+    ) -> Self {
+        let location = Spherical {
+            radius: 1.0,
+            polar_angle: (90.0 - lat_deg).to_radians(),
+            azimuthal_angle: long_deg.to_radians(),
+        };
+        Self::new(location, body, name, constellations)
+    }
+
+    /// Returns the body the observatory is on
+    #[must_use]
+    pub fn body(&self) -> &Arc {
+        &self.body
+    }
+
+    /// Returns the latitude and longitude of the observatory in degrees, as they are given to
+    /// [`Self::from_lat_long`], with the longitude between -180° and 180°
+    #[must_use]
+    pub fn lat_long_deg(&self) -> (Float, Float) {
+        let zenith = Spherical::from(Vector3::from(quaternion::rotate_vector(
+            quaternion::conj(self.location),
+            Vector3::UP.into(),
+        )));
+        (
+            90.0 - zenith.polar_angle.to_degrees(),
+            zenith.azimuthal_angle.to_degrees(),
+        )
+    }
+
+    /// Hides bodies that are below the given terrain when observing
+    #[must_use]
+    pub fn with_horizon_profile(mut self, horizon_profile: HorizonProfile) -> Self {
+        self.horizon_profile = Some(horizon_profile);
+        self
+    }
+
+    /// Returns the terrain that hides bodies low on the horizon, if any
+    #[must_use]
+    pub fn horizon_profile(&self) -> Option<&HorizonProfile> {
+        self.horizon_profile.as_ref()
+    }
+
+    /// Only sees bodies at least this many degrees above the horizon, e.g. 5° for an observatory
+    /// in a valley, or -2° for one on a tall tower that can see a little over the horizon. The
+    /// cutoff is the same in every direction, a [horizon profile](Self::with_horizon_profile)
+    /// still hides bodies behind terrain above it.
+    #[must_use]
+    pub fn with_horizon_altitude_deg(mut self, horizon_altitude_deg: Float) -> Self {
+        self.horizon_altitude_deg = horizon_altitude_deg;
+        self
+    }
+
+    /// Returns the altitude in degrees that bodies have to be above to be seen
+    #[must_use]
+    pub fn horizon_altitude_deg(&self) -> Float {
+        self.horizon_altitude_deg
+    }
+
+    /// Observes this many hours after the nominal time of each frame a
+    /// [`Program`](crate::program::Program) makes, files are still named after the nominal time
+    #[must_use]
+    pub fn with_time_offset_hours(mut self, time_offset_hours: Float) -> Self {
+        self.time_offset_hours = time_offset_hours;
+        self
+    }
+
+    /// Returns how many hours after the nominal time of each frame observations are made
+    #[must_use]
+    pub fn time_offset_hours(&self) -> Float {
+        self.time_offset_hours
+    }
+
+    /// Includes the body the observatory is on in its observations if it has a radius, as seen
+    /// from its surface. It is directly below the observatory and fills the sky below the
+    /// horizon, so outputs can draw the ground and treat it as an occluder.
+    #[must_use]
+    pub fn with_include_host(mut self, include_host: bool) -> Self {
+        self.include_host = include_host;
+        self
+    }
+
+    /// Returns whether observations include the body the observatory is on
+    #[must_use]
+    pub fn include_host(&self) -> bool {
+        self.include_host
+    }
+
+    /// Observes as if `up`, a direction in the body's coordinates like the observatory's
+    /// location, were the zenith instead of the surface normal, e.g. for a tilted view or one
+    /// referenced to a sloping horizon. Observations, the horizon, and directions from the other
+    /// methods that work in local coordinates are all relative to it, while the name, ID, and
+    /// [solar phase](Self::solar_phase) still use the observatory's location.
+    #[must_use]
+    pub fn with_reference_up(mut self, up: Spherical<Float>) -> Self {
+        let direction: Vector3<Float> = up.into();
+        self.reference_up = Some((
+            up,
+            quaternion::rotation_from_to(direction.into(), Vector3::UP.into()),
+        ));
+        self
+    }
+
+    /// Returns the direction used as the zenith instead of the surface normal, if any
+    #[must_use]
+    pub fn reference_up(&self) -> Option<Spherical<Float>> {
+        self.reference_up.map(|(up, _)| up)
+    }
+
+    /// Returns the rotation from the body's coordinates to local coordinates, with the
+    /// [reference up](Self::with_reference_up) or the surface normal at the zenith
+    fn local_rotation(&self) -> Quaternion<Float> {
+        self.reference_up
+            .map_or(self.location, |(_, rotation)| rotation)
+    }
+
+    /// Returns the observatory's position relative to the center of its body, in the body's
+    /// coordinates like its location: the zenith scaled by the body's radius, or the origin if the
+    /// body has no radius. The [reference up](Self::with_reference_up) isn't used, since it
+    /// doesn't move the observatory.
+    #[must_use]
+    pub fn surface_offset(&self) -> Vector3<Float> {
+        let radius = self.body.read().ok().and_then(|body| body.get_radius());
+        self.surface_offset_for(radius)
+    }
+
+    /// Returns the [surface offset](Self::surface_offset) on a body with the given radius, for
+    /// when the body is already locked
+    fn surface_offset_for(&self, radius: Option<Float>) -> Vector3<Float> {
+        let zenith: Vector3<Float> =
+            quaternion::rotate_vector(quaternion::conj(self.location), Vector3::UP.into()).into();
+        zenith * radius.unwrap_or(0.0)
+    }
+
+    /// Includes bodies below the horizon and behind terrain in its observations, so the whole sky
+    /// can be drawn, e.g. with an [`Equirectangular`](crate::projection::Equirectangular)
+    /// projection. The [horizon altitude](Self::with_horizon_altitude_deg) and
+    /// [horizon profile](Self::with_horizon_profile) are ignored while this is set.
+    #[must_use]
+    pub fn with_include_below_horizon(mut self, include_below_horizon: bool) -> Self {
+        self.include_below_horizon = include_below_horizon;
+        self
+    }
+
+    /// Returns whether observations include bodies below the horizon
+    #[must_use]
+    pub fn include_below_horizon(&self) -> bool {
+        self.include_below_horizon
+    }
+
     /// Takes bodies from a universal coordinate space and converts them to local coordinates
-    /// relative to the observatory
+    /// relative to the observatory. Bodies are seen from the observatory's place on the
+    /// [surface](Self::surface_offset), so nearby moons shift with parallax as the body turns and
+    /// the horizon is where the body hides them.
     #[must_use]
     pub fn observe(&self, time: Float) -> Vec<LocalObservation> {
+        let span = self.observe_span(time);
         if let Ok(body) = self.body.read() {
             let raw_observations = body.get_observations_from_here(time);
+            let observations = self.local_observations(&body, &raw_observations);
+            span.record("bodies", observations.len());
+            observations
+        } else {
+            warn!("The body was poisoned, could not make observations from it");
+            vec![]
+        }
+    }
 
-            // Rotate observations to put them in the local coordinate space from equatorial coordinate
-            // space
-            raw_observations
-                .iter()
-                .filter_map(|(body, pos)| {
-                    let local_coordinates =
-                        Vector3::from(quaternion::rotate_vector(self.location, (*pos).into()));
-                    // FIXME: adjust z based on the body's radius since we aren't observing from the
-                    // center of the body
-
-                    // Filter out bodies below the horizon
-                    if local_coordinates.z >= 0.0 {
-                        Some((body.clone(), local_coordinates.into()))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+    /// Observes like [`Self::observe`], given the observations of the observatory's body at
+    /// `time` from [`Body::get_observations_from_here`](super::Body::get_observations_from_here).
+    /// Observatories on the same body can share them, so the tree of bodies is only traversed
+    /// once however many of them there are.
+    #[must_use]
+    pub fn observe_precomputed(
+        &self,
+        time: Float,
+        raw_observations: &[EllipticObservation],
+    ) -> Vec<LocalObservation> {
+        let span = self.observe_span(time);
+        if let Ok(body) = self.body.read() {
+            let observations = self.local_observations(&body, raw_observations);
+            span.record("bodies", observations.len());
+            observations
         } else {
             warn!("The body was poisoned, could not make observations from it");
             vec![]
         }
     }
 
+    /// Returns the entered span observations at `time` are made in
+    fn observe_span(&self, time: Float) -> EnteredSpan {
+        trace_span!(
+            "observe",
+            observatory = %self.get_name(),
+            time,
+            bodies = field::Empty,
+        )
+        .entered()
+    }
+
+    /// Returns the bodies above the horizon in local coordinates, given the observations of the
+    /// observatory's `body`
+    fn local_observations(
+        &self,
+        body: &super::Body,
+        raw_observations: &[EllipticObservation],
+    ) -> Vec<LocalObservation> {
+        // Rotate observations to put them in the local coordinate space from equatorial coordinate
+        // space
+        let rotation = self.local_rotation();
+        let min_height = self.min_height();
+        let surface = self.surface_offset_for(body.radius);
+        let mut observations: Vec<_> = raw_observations
+            .iter()
+            .filter_map(|(body, pos)| {
+                self.visible_local_coordinates(rotation, min_height, *pos - surface)
+                    .map(|local_coordinates| (body.clone(), local_coordinates.into()))
+            })
+            .collect();
+
+        if let (true, Some(radius)) = (self.include_host, body.radius) {
+            // Seen from the surface the host is directly below us, covering everything below
+            // the horizon
+            observations.push((
+                self.body.clone(),
+                Spherical {
+                    radius,
+                    polar_angle: float::PI,
+                    azimuthal_angle: 0.0,
+                },
+            ));
+        }
+        observations
+    }
+
+    /// Returns how many bodies [`Self::observe`] would return at the given time, without working
+    /// out where each of them is in the sky
+    #[must_use]
+    pub fn visible_count(&self, time: Float) -> usize {
+        let Ok(body) = self.body.read() else {
+            warn!("The body was poisoned, could not make observations from it");
+            return 0;
+        };
+        let rotation = self.local_rotation();
+        let min_height = self.min_height();
+        let surface = self.surface_offset_for(body.radius);
+        let visible = body
+            .get_observations_from_here(time)
+            .iter()
+            .filter(|(_, pos)| {
+                self.visible_local_coordinates(rotation, min_height, *pos - surface)
+                    .is_some()
+            })
+            .count();
+        visible + usize::from(self.include_host && body.radius.is_some())
+    }
+
+    /// Returns the height above the horizon, as a fraction of their distance, that bodies need to
+    /// be at least to be seen
+    fn min_height(&self) -> Float {
+        self.horizon_altitude_deg.to_radians().sin()
+    }
+
+    /// Returns a position relative to the observatory in local coordinates, given the
+    /// [local rotation](Self::local_rotation), or `None` if it is below the horizon or behind the
+    /// terrain. Since the observatory is on the surface the horizon is tangent to the body, so
+    /// nothing it hides is ever above it.
+    fn visible_local_coordinates(
+        &self,
+        rotation: Quaternion<Float>,
+        min_height: Float,
+        position: Vector3<Float>,
+    ) -> Option<Vector3<Float>> {
+        let local_coordinates = Vector3::from(quaternion::rotate_vector(rotation, position.into()));
+
+        // Filter out bodies below the horizon, or behind the terrain
+        let hidden = self
+            .horizon_profile
+            .as_ref()
+            .is_some_and(|profile| profile.hides(&local_coordinates));
+        (self.include_below_horizon
+            || (local_coordinates.z >= local_coordinates.magnitude() * min_height && !hidden))
+            .then_some(local_coordinates)
+    }
+
+    /// Returns the [solid angle](https://en.wikipedia.org/wiki/Solid_angle) in steradians of the
+    /// visible hemisphere that is covered by bodies at the given time, see
+    /// [`covered_solid_angle`]. The [host](Self::with_include_host) is below the horizon so it
+    /// never counts.
+    #[must_use]
+    pub fn covered_solid_angle(&self, time: Float) -> Float {
+        let mut observations = self.observe(time);
+        observations.retain(|(body, _)| !std::sync::Arc::ptr_eq(body, &self.body));
+        covered_solid_angle(&observations)
+    }
+
+    /// Returns the [apparent magnitude](https://en.wikipedia.org/wiki/Apparent_magnitude) of a
+    /// body at the given distance from this observatory, where lower values are brighter.
+    ///
+    /// Bodies with a [luminosity](super::Body::get_luminosity_at) shine with their own light, bodies
+    /// with an [albedo](super::Body::get_albedo) and radius reflect the light of their
+    /// [light source](super::Body::get_light_source), dimming as they show less of their lit
+    /// side (treating them as [Lambertian](https://en.wikipedia.org/wiki/Lambertian_reflectance)
+    /// spheres). Returns `None` for bodies that give off no light either way.
+    #[must_use]
+    pub fn apparent_magnitude(&self, body: &Arc, distance: Float, time: Float) -> Option<Float> {
+        let target = body.read().ok()?;
+
+        // Flux relative to the Sun seen from one astronomical unit
+        let flux = if let Some(luminosity) = target.get_luminosity_at(time) {
+            luminosity * (ASTRONOMICAL_UNIT / distance).powi(2)
+        } else {
+            let albedo = target.get_albedo()?;
+            let radius = target.radius?;
+            let source_arc = target.get_light_source()?;
+            let source = source_arc.read().ok()?;
+
+            let to_source = source.get_absolute_position(time) - target.get_absolute_position(time);
+            let phase_angle = self.phase_angle(body, &source_arc, time)?;
+            let phase =
+                (phase_angle.sin() + (float::PI - phase_angle) * phase_angle.cos()) / float::PI;
+
+            source.get_luminosity_at(time)?
+                * (ASTRONOMICAL_UNIT / to_source.magnitude()).powi(2)
+                * albedo
+                * (radius / distance).powi(2)
+                * phase
+        };
+
+        return Some(SUN_APPARENT_MAGNITUDE - 2.5 * flux.log10());
+    }
+
+    /// Returns the [elongation](https://en.wikipedia.org/wiki/Elongation_(astronomy)) of a body
+    /// from a star in radians at the given time, i.e. the angle between them as seen from this
+    /// observatory. Inner planets are only visible as morning or evening stars near their
+    /// greatest elongation.
+    ///
+    /// Returns `None` if any of the bodies are poisoned, or the body or star is at the observatory.
+    #[must_use]
+    pub fn elongation(&self, body: &Arc, star: &Arc, time: Float) -> Option<Float> {
+        let observer = self.body.read().ok()?.get_absolute_position(time);
+        let to_body = body.read().ok()?.get_absolute_position(time) - observer;
+        let to_star = star.read().ok()?.get_absolute_position(time) - observer;
+        return angle_between(&to_body, &to_star);
+    }
+
+    /// Returns the altitude of a body in radians at the given time, the angle between it and the
+    /// horizon as seen from this observatory, negative when it is below the horizon. Unlike
+    /// [`Self::observe`] it is found for bodies below the horizon too.
+    ///
+    /// Returns `None` if either body is poisoned or the body is at the observatory.
+    #[must_use]
+    pub fn altitude(&self, body: &Arc, time: Float) -> Option<Float> {
+        let local = self.local_direction(body, time)?;
+        Some(local.z.clamp(-1.0, 1.0).asin())
+    }
+
+    /// Returns how fast a body moves across the sky at the given time, as seen from this
+    /// observatory, e.g. to draw arrows showing where bodies are heading on a chart. Found for
+    /// bodies below the horizon too, like [`Self::altitude`].
+    ///
+    /// The first value is the rate in radians per hour. The second is the direction it is moving
+    /// in radians, measured from straight up towards the zenith and turning towards increasing
+    /// azimuth, so `π/2` is along the horizon the way azimuth increases. The direction is 0 for
+    /// bodies at the zenith or nadir, where up isn't defined.
+    ///
+    /// Returns `None` if either body is poisoned or the body is at the observatory.
+    #[must_use]
+    pub fn proper_motion(&self, body: &Arc, time: Float) -> Option<(Float, Float)> {
+        /// Time either side of `time` that directions are compared at, in hours
+        const STEP: Float = 1.0 / 60.0;
+
+        let before = self.local_direction(body, time - STEP)?;
+        let after = self.local_direction(body, time + STEP)?;
+        let direction = self.local_direction(body, time)?;
+        let velocity = (after - before) / (2.0 * STEP);
+
+        // Unit vectors towards increasing azimuth and altitude where the body is
+        let azimuthal = Vector3::UP.cross(&direction);
+        let up = direction.cross(&azimuthal);
+        let heading = if azimuthal.magnitude() == 0.0 {
+            0.0
+        } else {
+            velocity
+                .dot(&azimuthal.normalize())
+                .atan2(velocity.dot(&up.normalize()))
+        };
+        Some((velocity.magnitude(), heading))
+    }
+
+    /// Returns the direction of a body at the given time as a unit vector in local coordinates,
+    /// or `None` if either body is poisoned or the body is at the observatory
+    fn local_direction(&self, body: &Arc, time: Float) -> Option<Vector3<Float>> {
+        let observer = self.body.read().ok()?;
+        let offset =
+            body.read().ok()?.get_absolute_position(time) - observer.get_absolute_position(time);
+        let local = self.to_local(&observer, offset, time);
+        let distance = local.magnitude();
+        if distance == 0.0 {
+            return None;
+        }
+        Some(local / distance)
+    }
+
+    /// Returns the [phase angle](https://en.wikipedia.org/wiki/Phase_angle_(astronomy)) of a body
+    /// lit by a star in radians at the given time, i.e. the angle between the star and this
+    /// observatory as seen from the body. Zero when the body is fully lit, `π` when only its dark
+    /// side faces the observatory.
+    ///
+    /// Returns `None` if any of the bodies are poisoned, or the star or observatory is at the body.
+    #[must_use]
+    pub fn phase_angle(&self, body: &Arc, star: &Arc, time: Float) -> Option<Float> {
+        let position = body.read().ok()?.get_absolute_position(time);
+        let to_star = star.read().ok()?.get_absolute_position(time) - position;
+        let to_observer = self.body.read().ok()?.get_absolute_position(time) - position;
+        return angle_between(&to_star, &to_observer);
+    }
+
+    /// Returns up to `n` bodies above the horizon at the given time with their
+    /// [apparent magnitudes](Self::apparent_magnitude), brightest first. Bodies that give off no
+    /// light are left out.
+    #[must_use]
+    pub fn brightest(&self, time: Float, n: usize) -> Vec<(Arc, Float)> {
+        let mut result: Vec<_> = self
+            .observe(time)
+            .into_iter()
+            .filter_map(|(body, location)| {
+                let magnitude = self.apparent_magnitude(&body, location.radius, time)?;
+                Some((body, magnitude))
+            })
+            .collect();
+        result.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        result.truncate(n);
+        result
+    }
+
+    /// Returns where the end of a body's [tail](super::tail::Tail) appears in local coordinates at
+    /// the given time, streaming away from the body's
+    /// [light source](super::Body::get_light_source).
+    ///
+    /// Returns `None` if the body has no tail or no light source. The end may be below the
+    /// horizon even if the body is not.
+    #[must_use]
+    pub fn tail_end(&self, body: &Arc, time: Float) -> Option<Spherical<Float>> {
+        let target = body.read().ok()?;
+        let tail = target.get_tail()?;
+        let source = target.get_light_source()?;
+
+        let position = target.get_absolute_position(time);
+        let away_from_source = position - source.read().ok()?.get_absolute_position(time);
+        let distance = away_from_source.magnitude();
+        let end = position + away_from_source * (tail.length_at(distance) / distance);
+
+        let observer = self.body.read().ok()?;
+        Some(
+            self.to_local(&observer, end - observer.get_absolute_position(time), time)
+                .into(),
+        )
+    }
+
+    /// Returns points around the inner and outer edges of a body's
+    /// [rings](super::Body::get_rings) at the given time, in local coordinates relative to the
+    /// observatory like the positions returned by [`Self::observe`], `segments` points evenly
+    /// spaced around each edge. The rings lie in the body's equatorial plane, or its reference
+    /// plane if it doesn't [rotate](super::rotating::Rotating).
+    ///
+    /// Returns `None` if the body has no rings or either body is poisoned.
+    #[must_use]
+    pub fn ring_edges(
+        &self,
+        body: &Arc,
+        time: Float,
+        segments: u8,
+    ) -> Option<[Vec<Vector3<Float>>; 2]> {
+        let target = body.read().ok()?;
+        let (inner, outer) = target.get_rings()?;
+        let axis = target
+            .rotation
+            .as_ref()
+            .map_or(Vector3::UP, super::rotating::Rotating::get_axis);
+        // Two directions in the equatorial plane, at right angles to each other
+        let across = if axis.x.abs() < 0.9 {
+            Vector3::RIGHT
+        } else {
+            Vector3::FORWARD
+        };
+        let u = axis.cross(&across).normalize();
+        let v = axis.cross(&u).normalize();
+
+        let observer = self.body.read().ok()?;
+        let center = target.get_absolute_position(time) - observer.get_absolute_position(time);
+        let edge = |radius: Float| {
+            (0..segments)
+                .map(|i| {
+                    let angle = float::TAU * Float::from(i) / Float::from(segments);
+                    let offset = center + (u * angle.cos() + v * angle.sin()) * radius;
+                    self.to_local(&observer, offset, time)
+                })
+                .collect()
+        };
+        Some([edge(inner), edge(outer)])
+    }
+
+    /// Returns an offset from the center of the observatory's `body` in the universe's
+    /// coordinates in local coordinates relative to the observatory, like the positions returned
+    /// by [`Self::observe`]
+    fn to_local(
+        &self,
+        body: &super::Body,
+        mut offset: Vector3<Float>,
+        time: Float,
+    ) -> Vector3<Float> {
+        if let Some(rotation) = &body.rotation {
+            offset = rotation.to_equatorial(time, offset);
+        }
+        offset = offset - self.surface_offset_for(body.radius);
+        quaternion::rotate_vector(self.local_rotation(), offset.into()).into()
+    }
+
+    /// Returns how far through its [solar day](https://en.wikipedia.org/wiki/Solar_time) the
+    /// observatory is at the given time, from 0 as its [star](super::Body::get_star) crosses the
+    /// meridian (local noon), through 0.5 at local midnight, up to 1.
+    ///
+    /// Returns `None` if the body doesn't rotate or doesn't orbit a star.
+    #[must_use]
+    pub fn solar_phase(&self, time: Float) -> Option<Float> {
+        let body = self.body.read().ok()?;
+        let rotation = body.rotation.as_ref()?;
+        let star = body.get_star()?;
+        let star_offset =
+            star.read().ok()?.get_absolute_position(time) - body.get_absolute_position(time);
+
+        let to_local = |direction| -> Vector3<Float> {
+            quaternion::rotate_vector(
+                self.location,
+                rotation.to_equatorial(time, direction).into(),
+            )
+            .into()
+        };
+        let pole = to_local(rotation.get_axis());
+        let star = to_local(star_offset);
+
+        // Hour angle of the star, measured around the pole from the meridian
+        let towards_zenith = Vector3::UP - pole * pole.z;
+        let towards_star = star - pole * star.dot(&pole);
+        let hour_angle = Float::atan2(
+            pole.dot(&towards_star.cross(&towards_zenith)),
+            towards_zenith.dot(&towards_star),
+        );
+
+        return Some(hour_angle.rem_euclid(float::TAU) / float::TAU);
+    }
+
+    /// Returns the direction of the celestial north pole (the body's rotation axis) at the given
+    /// time as a unit vector in local coordinates, like the directions returned by
+    /// [`Self::observe`].
+    ///
+    /// Returns `None` if the body doesn't [rotate](super::rotating::Rotating).
+    #[must_use]
+    pub fn celestial_pole(&self, time: Float) -> Option<Vector3<Float>> {
+        let body = self.body.read().ok()?;
+        let rotation = body.rotation.as_ref()?;
+
+        Some(Vector3::from(quaternion::rotate_vector(
+            self.local_rotation(),
+            rotation.to_equatorial(time, rotation.get_axis()).into(),
+        )))
+    }
+
+    /// Returns the [parallactic angle](https://en.wikipedia.org/wiki/Parallactic_angle) in radians
+    /// of a target in the direction given in local coordinates (as returned by [`Self::observe`]),
+    /// i.e. the angle at the target between the directions to the zenith and to the celestial
+    /// north pole. It is zero as the target crosses the meridian and positive once it has passed
+    /// it.
+    ///
+    /// Returns `None` if the body doesn't [rotate](super::rotating::Rotating), or if the target is
+    /// at the zenith or a celestial pole where the angle is undefined.
+    #[must_use]
+    pub fn parallactic_angle(
+        &self,
+        target_direction: Spherical<Float>,
+        time: Float,
+    ) -> Option<Float> {
+        let pole = self.celestial_pole(time)?;
+        let target = Vector3::from(target_direction);
+        let target = target / target.magnitude();
+
+        // Directions from the target along the great circles to the zenith and the pole
+        let towards_zenith = Vector3::UP - target * target.z;
+        let towards_pole = pole - target * pole.dot(&target);
+        if towards_zenith.magnitude() < Float::EPSILON.sqrt()
+            || towards_pole.magnitude() < Float::EPSILON.sqrt()
+        {
+            return None;
+        }
+
+        return Some(Float::atan2(
+            target.dot(&towards_zenith.cross(&towards_pole)),
+            towards_zenith.dot(&towards_pole),
+        ));
+    }
+
+    /// Returns an identifier for the observatory that doesn't depend on its name, made from the
+    /// ID of the body it is on and its latitude and longitude in degrees, e.g. `0-3@-33.8700,151.2100`
+    ///
+    /// The longitude is given between -180° (exclusive) and 180°, and as 0 at the poles, so the
+    /// same place always has the same ID.
+    #[must_use]
+    pub fn get_id(&self) -> String {
+        // Rounded as they are printed, and without negative zeros
+        let round = |degrees: Float| (degrees * 1e4).round() / 1e4 + 0.0;
+        let (lat, long) = self.lat_long_deg();
+        let lat = round(lat);
+        let long = match round(long) {
+            _ if lat.abs() == 90.0 => 0.0,
+            -180.0 => 180.0,
+            long => long,
+        };
+        let body_id = self
+            .body
+            .read()
+            .map(|body| body.get_id())
+            .unwrap_or_default();
+        format!("{}@{:.4},{:.4}", to_name(&body_id), lat, long)
+    }
+
     #[must_use]
     pub fn get_name(&self) -> String {
         let lat_long = Spherical::from(Vector3::from(quaternion::rotate_vector(
@@ -95,13 +732,41 @@ impl Observatory {
         &self.constellations
     }
 
+    /// Groups the observatory's constellations by the sky culture they belong to, constellations
+    /// without a culture are grouped under `None`
+    #[must_use]
+    pub fn constellations_by_culture(
+        &self,
+    ) -> BTreeMap<Option<&str>, Vec<&crate::constellation::Constellation>> {
+        let mut result: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for constellation in &self.constellations {
+            result
+                .entry(constellation.culture())
+                .or_default()
+                .push(constellation);
+        }
+        result
+    }
+
     #[must_use]
     pub fn add_constelatations(
         &self,
         bodies: &[LocalObservation],
-    ) -> Vec<(Spherical<Float>, Spherical<Float>)> {
+    ) -> Vec<crate::constellation::Line> {
+        self.add_constellations_in(bodies, None)
+    }
+
+    /// Like [`Self::add_constelatations`], but only for constellations in one of the `cultures`,
+    /// or every constellation if `cultures` is `None`
+    #[must_use]
+    pub fn add_constellations_in(
+        &self,
+        bodies: &[LocalObservation],
+        cultures: Option<&[String]>,
+    ) -> Vec<crate::constellation::Line> {
         self.constellations
             .iter()
+            .filter(|c| c.is_in(cultures))
             .flat_map(|c| c.add_edges(bodies))
             .collect()
     }
@@ -125,22 +790,59 @@ pub struct WeakObservatory {
     /// [Modern](https://en.wikipedia.org/wiki/IAU_designated_constellations))
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     constellations: Vec<crate::constellation::weak::Weak>,
+
+    /// Terrain around the observatory as `[azimuth, altitude]` pairs in degrees, see
+    /// [`HorizonProfile`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    horizon_profile: Option<HorizonProfile>,
+
+    /// Hours added to the time of each frame before observing, see
+    /// [`Observatory::with_time_offset_hours`]
+    #[serde(skip_serializing_if = "crate::json::is_zero", default)]
+    time_offset_hours: Float,
+
+    /// Whether observations include the body the observatory is on, see
+    /// [`Observatory::with_include_host`]
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    include_host: bool,
+
+    /// Whether observations include bodies below the horizon, see
+    /// [`Observatory::with_include_below_horizon`]
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    include_below_horizon: bool,
+
+    /// Altitude in degrees that bodies have to be above to be seen, see
+    /// [`Observatory::with_horizon_altitude_deg`]
+    #[serde(skip_serializing_if = "crate::json::is_zero", default)]
+    horizon_altitude_deg: Float,
+
+    /// Direction used as the zenith instead of the surface normal, see
+    /// [`Observatory::with_reference_up`]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    reference_up: Option<Spherical<Float>>,
 }
 
 /// Converts a [`WeakObservatory`] to a regular [`Observatory`] by adding back reference counted
 /// variables correctly.
 ///
-/// # Panics
+/// # Errors
 ///
-/// Panics if a body in the tree has a poisoned lock
-pub fn to_observatory(weak_observatory: WeakObservatory, root: &Arc) -> Observatory {
+/// Returns [`json::Error::UnknownBody`] if the observatory's body ID isn't in the tree
+pub fn to_observatory(
+    weak_observatory: WeakObservatory,
+    root: &Arc,
+) -> Result<Observatory, json::Error> {
     let mut body = root.clone();
     for child_id in &weak_observatory.body_id {
-        // HACK: remove unwrap here, probably by returning an Option<Observatory>
-        let b = body.read().unwrap().children[*child_id].clone();
-        body = b;
+        let child = body
+            .read()
+            .ok()
+            .and_then(|b| b.children.get(*child_id).cloned());
+        body = child.ok_or_else(|| json::Error::UnknownBody {
+            id: weak_observatory.body_id.clone(),
+        })?;
     }
-    Observatory::new(
+    let observatory = Observatory::new(
         weak_observatory.location,
         body.clone(),
         weak_observatory.name.ok_or(weak_observatory.body_id),
@@ -149,11 +851,45 @@ pub fn to_observatory(weak_observatory: WeakObservatory, root: &Arc) -> Observat
             .into_iter()
             .map(|weak| weak.upgrade(root))
             .collect(),
-    )
+    );
+    let observatory = observatory
+        .with_time_offset_hours(weak_observatory.time_offset_hours)
+        .with_include_host(weak_observatory.include_host)
+        .with_include_below_horizon(weak_observatory.include_below_horizon)
+        .with_horizon_altitude_deg(weak_observatory.horizon_altitude_deg);
+    let observatory = match weak_observatory.reference_up {
+        Some(up) => observatory.with_reference_up(up),
+        None => observatory,
+    };
+    Ok(match weak_observatory.horizon_profile {
+        Some(profile) => observatory.with_horizon_profile(profile),
+        None => observatory,
+    })
+}
+
+/// Returns the angle in radians between two directions, or `None` if either has no length
+fn angle_between(a: &Vector3<Float>, b: &Vector3<Float>) -> Option<Float> {
+    let lengths = a.magnitude() * b.magnitude();
+    if lengths == 0.0 {
+        return None;
+    }
+    Some(Float::acos((a.dot(b) / lengths).clamp(-1.0, 1.0)))
+}
+
+/// Sums the solid angles in steradians of the discs of the observed bodies, up to the `2π` of the
+/// visible hemisphere. Overlapping discs are counted twice, and bodies with an unknown radius are
+/// ignored.
+#[must_use]
+pub fn covered_solid_angle(observations: &[LocalObservation]) -> Float {
+    observations
+        .iter()
+        .filter_map(|(body, location)| body.read().ok()?.get_solid_angle(location.radius))
+        .sum::<Float>()
+        .min(float::TAU)
 }
 
 /// Converts a ID to a string of dash ("-") separated values that is adequite for generating names
-pub(super) fn to_name(id: &[usize]) -> String {
+pub(crate) fn to_name(id: &[usize]) -> String {
     if id.is_empty() {
         String::new()
     } else {
@@ -171,16 +907,11 @@ pub(super) fn to_name(id: &[usize]) -> String {
 
 impl From<Observatory> for WeakObservatory {
     fn from(value: Observatory) -> Self {
-        let (qw, [qx, qy, qz]) = value.location;
-        // From https://www.euclideanspace.com/maths/geometry/rotations/conversions/quaternionToEuler/
-
-        // asin(2*qx*qy + 2*qz*qw)
-        // We take acos because we are using a polar angle, not a latitude
-        let polar_angle = (2.0 - qx * qy + 2.0 * qz * qw).acos();
-
-        // atan2(2*qy*qw-2*qx*qz , 1 - 2*qy^2 - 2*qz^2)
-        let azimuthal_angle =
-            (2.0 * qy * qw - 2.0 * qx * qz).atan2(1.0 - 2.0 * qy * qy - 2.0 * qz * qz);
+        // The location rotates the observatory's position to the top of the body, so undo it
+        let location = Spherical::from(Vector3::from(quaternion::rotate_vector(
+            quaternion::conj(value.location),
+            Vector3::UP.into(),
+        )));
 
         let body_id = value
             .body
@@ -188,18 +919,20 @@ impl From<Observatory> for WeakObservatory {
             .map(|body| body.get_id())
             .unwrap_or_default();
         WeakObservatory {
-            location: Spherical {
-                polar_angle,
-                azimuthal_angle,
-                radius: 1.0,
-            },
+            location,
             body_id,
-            name: None,
+            name: value.name.ok(),
             constellations: value
                 .constellations
                 .into_iter()
                 .map(crate::constellation::weak::Weak::from)
                 .collect(),
+            horizon_profile: value.horizon_profile,
+            time_offset_hours: value.time_offset_hours,
+            include_host: value.include_host,
+            include_below_horizon: value.include_below_horizon,
+            horizon_altitude_deg: value.horizon_altitude_deg,
+            reference_up: value.reference_up.map(|(up, _)| up),
         }
     }
 }
@@ -209,8 +942,15 @@ mod tests {
     use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
 
     use crate::{
-        body::{observatory::WeakObservatory, rotating::Rotating, Arc, Body},
+        body::{
+            horizon::HorizonProfile,
+            observatory::{Observatory, WeakObservatory},
+            rotating::Rotating,
+            Arc, Body,
+        },
+        consts::float,
         dynamic::fixed::Fixed,
+        Float,
     };
 
     #[allow(dead_code)] // Will be useful if we rewrite that old test
@@ -226,6 +966,317 @@ mod tests {
         body
     }
 
+    /// Parallactic angle of a star at declination 20° from latitude 40°N on an Earth-like body
+    /// with an axis perpendicular to its orbit. The star crosses the meridian at t=12.
+    fn earth_like_parallactic_angle(time: Float) -> Option<Float> {
+        let earth = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        earth.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        let star = Body::new(
+            Some(earth.clone()),
+            Fixed::new(
+                Spherical {
+                    radius: 1e3,
+                    polar_angle: Float::to_radians(70.0),
+                    azimuthal_angle: float::PI,
+                }
+                .into(),
+            ),
+        );
+        Body::hydrate_all(&earth, &None);
+
+        let observatory = Observatory::new(
+            Spherical {
+                radius: 1.0,
+                polar_angle: Float::to_radians(50.0),
+                azimuthal_angle: 0.0,
+            },
+            earth,
+            Ok("Earth-like".into()),
+            vec![],
+        );
+        let (_, direction) = observatory
+            .observe(time)
+            .into_iter()
+            .find(|(b, _)| std::sync::Arc::ptr_eq(b, &star))?;
+
+        observatory.parallactic_angle(direction, time)
+    }
+
+    #[test]
+    fn parallactic_angle_zero_at_meridian() {
+        let (latitude, declination) = (40_f32.to_radians(), 20_f32.to_radians());
+        let latitude = Float::from(latitude);
+        let declination = Float::from(declination);
+
+        assert_float_absolute_eq!(earth_like_parallactic_angle(12.0).unwrap(), 0.0, 1e-3);
+
+        for hour_angle in [-3.0, -1.0, 1.0, 3.0] {
+            let h = (hour_angle * 15.0 as Float).to_radians();
+            let expected = h
+                .sin()
+                .atan2(latitude.tan() * declination.cos() - declination.sin() * h.cos());
+
+            assert_float_absolute_eq!(
+                earth_like_parallactic_angle(12.0 + hour_angle).unwrap(),
+                expected,
+                1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn solid_angle_of_disc() {
+        const DISTANCE: Float = 100.0;
+        const ANGULAR_RADIUS: Float = 0.1;
+        let body = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let moon = Body::new(
+            Some(body.clone()),
+            Fixed::new(Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: DISTANCE,
+            }),
+        );
+        // Too far below the horizon to be seen
+        let hidden = Body::new(
+            Some(body.clone()),
+            Fixed::new(Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: -DISTANCE,
+            }),
+        );
+        moon.write().unwrap().radius = Some(DISTANCE * ANGULAR_RADIUS.sin());
+        hidden.write().unwrap().radius = Some(DISTANCE);
+        Body::hydrate_all(&body, &None);
+
+        let observatory = Observatory::new(Spherical::UP, body, Ok("Coverage".into()), vec![]);
+
+        assert_float_relative_eq!(
+            observatory.covered_solid_angle(0.0),
+            float::TAU * (1.0 - ANGULAR_RADIUS.cos()),
+            1e-4
+        );
+    }
+
+    #[test]
+    fn solid_angle_clamped_to_hemisphere() {
+        let body = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        for _ in 0..3 {
+            let neighbour = Body::new(Some(body.clone()), Fixed::new(Vector3::UP));
+            // Big enough to cover the whole sky
+            neighbour.write().unwrap().radius = Some(2.0);
+        }
+        Body::hydrate_all(&body, &None);
+
+        let observatory = Observatory::new(Spherical::UP, body, Ok("Crowded".into()), vec![]);
+
+        assert_float_absolute_eq!(observatory.covered_solid_angle(0.0), float::TAU);
+    }
+
+    #[test]
+    fn parallactic_angle_needs_rotation() {
+        let body = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let observatory = Observatory::new(Spherical::UP, body, Ok("Still".into()), vec![]);
+
+        assert!(observatory
+            .parallactic_angle(Spherical::RIGHT, 0.0)
+            .is_none());
+    }
+
+    #[test]
+    fn terrain_hides_low_northern_body() {
+        let body = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // 5° above the northern horizon
+        let low_north = Body::new(
+            Some(body.clone()),
+            Fixed::new(Vector3 {
+                x: -Float::to_radians(5.0).cos(),
+                y: 0.0,
+                z: Float::to_radians(5.0).sin(),
+            }),
+        );
+        // 30° above the southern horizon
+        let high_south = Body::new(
+            Some(body.clone()),
+            Fixed::new(Vector3 {
+                x: Float::to_radians(30.0).cos(),
+                y: 0.0,
+                z: Float::to_radians(30.0).sin(),
+            }),
+        );
+        Body::hydrate_all(&body, &None);
+
+        let observatory = Observatory::new(Spherical::UP, body, Ok("Valley".into()), vec![]);
+        assert_eq!(observatory.observe(0.0).len(), 2);
+
+        // A ridge 10° high across the northern horizon
+        let observatory = observatory.with_horizon_profile(HorizonProfile::new([
+            (270.0, 0.0),
+            (300.0, 10.0),
+            (60.0, 10.0),
+            (90.0, 0.0),
+        ]));
+        let observed = observatory.observe(0.0);
+
+        assert_eq!(observed.len(), 1);
+        assert!(Arc::ptr_eq(&observed[0].0, &high_south));
+        assert!(!observed.iter().any(|(b, _)| Arc::ptr_eq(b, &low_north)));
+    }
+
+    #[test]
+    #[allow(clippy::excessive_precision)] // Tests need to pass with f64 as well as f32
+    fn brightest_in_order() {
+        const AU: Float = crate::consts::ASTRONOMICAL_UNIT;
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let sun = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let earth = Body::new(
+            Some(sun.clone()),
+            Fixed::new(Vector3 {
+                x: AU,
+                y: 0.0,
+                z: 0.0,
+            }),
+        );
+        // Full moon, straight up from the Earth
+        let moon = Body::new(
+            Some(earth.clone()),
+            Fixed::new(Vector3 {
+                x: 1.282_2,
+                y: 0.0,
+                z: 0.0,
+            }),
+        );
+        // A faint star, and a bright one that is further away
+        let faint = Body::new(
+            Some(root.clone()),
+            Fixed::new(Vector3 {
+                x: 1e8,
+                y: 1e3,
+                z: 0.0,
+            }),
+        );
+        let bright = Body::new(
+            Some(root.clone()),
+            Fixed::new(Vector3 {
+                x: 2e8,
+                y: -1e3,
+                z: 0.0,
+            }),
+        );
+        // Doesn't give off any light
+        let _dark = Body::new(Some(earth.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+
+        sun.write().unwrap().luminosity = Some(1.0);
+        if let Ok(mut moon) = moon.write() {
+            moon.radius = Some(0.005_794);
+            moon.albedo = Some(0.12);
+        }
+        faint.write().unwrap().luminosity = Some(1.0);
+        bright.write().unwrap().luminosity = Some(100.0);
+        Body::hydrate_all(&root, &None);
+
+        // At midnight, looking away from the sun
+        let observatory = Observatory::new(Spherical::RIGHT, earth, Ok("Night".into()), vec![]);
+        let brightest = observatory.brightest(0.0, 10);
+
+        assert_eq!(brightest.len(), 3);
+        assert!(Arc::ptr_eq(&brightest[0].0, &moon));
+        assert!(Arc::ptr_eq(&brightest[1].0, &bright));
+        assert!(Arc::ptr_eq(&brightest[2].0, &faint));
+        // The full moon is around magnitude -12.7
+        assert_float_absolute_eq!(brightest[0].1, -12.7, 0.1);
+        // 100 times brighter is 5 magnitudes, twice as far is 1.5 magnitudes dimmer
+        assert_float_absolute_eq!(
+            brightest[2].1 - brightest[1].1,
+            5.0 - 5.0 * Float::log10(2.0),
+            1e-2
+        );
+
+        assert_eq!(observatory.brightest(0.0, 1).len(), 1);
+    }
+
+    #[test]
+    fn variable_star_magnitude_oscillates() {
+        let earth = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(Some(earth.clone()), Fixed::new(Vector3::UP * 1e9));
+        if let Ok(mut star) = star.write() {
+            star.luminosity = Some(1_000.0);
+            star.variability = Some(super::super::variability::Variability::new(0.5, 100.0));
+        }
+        Body::hydrate_all(&earth, &None);
+        let observatory = Observatory::new(Spherical::UP, earth, Ok("Variable".into()), vec![]);
+
+        let magnitudes: Vec<Float> = (0_u8..=100)
+            .map(|hour| {
+                observatory
+                    .apparent_magnitude(&star, 1e9, Float::from(hour))
+                    .unwrap()
+            })
+            .collect();
+        let brightest = magnitudes.iter().copied().fold(Float::INFINITY, Float::min);
+        let faintest = magnitudes
+            .iter()
+            .copied()
+            .fold(-Float::INFINITY, Float::max);
+        // From 1.5 to 0.5 times the mean luminosity
+        assert_float_absolute_eq!(faintest - brightest, 2.5 * Float::log10(3.0), 1e-3);
+        // Brightest a quarter of the way through each period, and back where it started after one
+        assert_float_absolute_eq!(magnitudes[25], brightest, 1e-4);
+        assert_float_absolute_eq!(magnitudes[75], faintest, 1e-4);
+        assert_float_absolute_eq!(magnitudes[100], magnitudes[0], 1e-4);
+    }
+
+    #[test]
+    fn inner_planet_greatest_elongation() {
+        const AU: Float = crate::consts::ASTRONOMICAL_UNIT;
+        const VENUS_ORBIT: Float = 0.723;
+        let sun = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let earth = Body::new(Some(sun.clone()), Fixed::new(Vector3::RIGHT * AU));
+        // Venus at an angle around the sun from the Earth
+        let venus_at = |angle: Float| {
+            Body::new(
+                Some(sun.clone()),
+                Fixed::new(Vector3 {
+                    x: angle.cos() * VENUS_ORBIT * AU,
+                    y: angle.sin() * VENUS_ORBIT * AU,
+                    z: 0.0,
+                }),
+            )
+        };
+        // The line of sight is tangent to the orbit at greatest elongation
+        let greatest = venus_at(VENUS_ORBIT.acos());
+        let others: Vec<_> = (0_u8..36)
+            .map(|i| venus_at(Float::from(i) / 36.0 * float::TAU))
+            .collect();
+        Body::hydrate_all(&sun, &None);
+        let observatory = Observatory::new(Spherical::UP, earth, Ok("Dawn".into()), vec![]);
+
+        let elongation = observatory.elongation(&greatest, &sun, 0.0).unwrap();
+        assert_float_absolute_eq!(elongation, VENUS_ORBIT.asin(), 1e-4);
+        assert_float_absolute_eq!(elongation.to_degrees(), 46.3, 0.1);
+        // Half lit
+        assert_float_absolute_eq!(
+            observatory.phase_angle(&greatest, &sun, 0.0).unwrap(),
+            float::FRAC_PI_2,
+            1e-4
+        );
+        for venus in &others {
+            assert!(observatory.elongation(venus, &sun, 0.0).unwrap() <= elongation + 1e-4);
+        }
+        // Full at superior conjunction, new at inferior conjunction
+        assert_float_absolute_eq!(
+            observatory.phase_angle(&others[18], &sun, 0.0).unwrap(),
+            0.0,
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            observatory.phase_angle(&others[0], &sun, 0.0).unwrap(),
+            float::PI,
+            1e-4
+        );
+    }
+
     #[test]
     fn load_from_file() {
         let file = include_str!("../../../assets/solar-system.observatories.json");
@@ -234,4 +1285,353 @@ mod tests {
 
         assert_eq!(observatories.len(), 6);
     }
+
+    #[test]
+    fn from_lat_long_zenith() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let zenith = |lat, long| -> Vector3<Float> {
+            let observatory =
+                Observatory::from_lat_long(lat, long, root.clone(), Err(vec![]), vec![]);
+            quaternion::rotate_vector(quaternion::conj(observatory.location), Vector3::UP.into())
+                .into()
+        };
+
+        let equator = zenith(0.0, 0.0);
+        assert_float_absolute_eq!(equator.x, 1.0, 1e-4);
+        assert_float_absolute_eq!(equator.y, 0.0, 1e-4);
+        assert_float_absolute_eq!(equator.z, 0.0, 1e-4);
+
+        let north_pole = zenith(90.0, 123.0);
+        assert_float_absolute_eq!(north_pole.z, 1.0, 1e-4);
+
+        let east = zenith(0.0, 90.0);
+        assert_float_absolute_eq!(east.y, 1.0, 1e-4);
+
+        let siding_spring =
+            Observatory::from_lat_long(-31.27, 149.06, root.clone(), Err(vec![]), vec![]);
+        let (lat, long) = siding_spring.lat_long_deg();
+        assert_float_absolute_eq!(lat, -31.27, 1e-3);
+        assert_float_absolute_eq!(long, 149.06, 1e-3);
+        let (_, long) =
+            Observatory::from_lat_long(10.0, 270.0, root, Err(vec![]), vec![]).lat_long_deg();
+        assert_float_absolute_eq!(long, -90.0, 1e-3);
+    }
+
+    #[test]
+    fn id_has_lat_long() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let earth = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let moon = Body::new(Some(earth.clone()), Fixed::new(Vector3::ORIGIN));
+
+        let sydney = Observatory::from_lat_long(-33.87, 151.21, moon, Err(vec![]), vec![]);
+        assert_eq!(sydney.get_id(), "0-0@-33.8700,151.2100");
+        let east = Observatory::from_lat_long(45.0, 90.0, earth.clone(), Err(vec![]), vec![]);
+        assert_eq!(east.get_id(), "0@45.0000,90.0000");
+
+        let id = |lat, long| {
+            Observatory::from_lat_long(lat, long, earth.clone(), Err(vec![]), vec![]).get_id()
+        };
+        assert_eq!(id(10.0, 180.0), "0@10.0000,180.0000");
+        assert_eq!(id(10.0, -180.0), "0@10.0000,180.0000");
+        assert_eq!(id(90.0, 123.0), "0@90.0000,0.0000");
+        assert_eq!(id(-90.0, -45.0), "0@-90.0000,0.0000");
+        assert_eq!(id(0.0, 0.0), "0@0.0000,0.0000");
+        assert_eq!(id(-0.00001, -0.00001), "0@0.0000,0.0000");
+    }
+
+    #[test]
+    fn horizon_altitude_cutoff() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // 3° above the horizon seen from the north pole
+        let altitude = Float::to_radians(3.0);
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(
+                Vector3 {
+                    x: altitude.cos(),
+                    y: 0.0,
+                    z: altitude.sin(),
+                } * 1_000.0,
+            ),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::from_lat_long(90.0, 0.0, planet, Err(vec![]), vec![]);
+
+        assert_eq!(observatory.observe(0.0).len(), 1);
+        assert!(observatory
+            .clone()
+            .with_horizon_altitude_deg(5.0)
+            .observe(0.0)
+            .is_empty());
+        assert_eq!(
+            observatory
+                .with_horizon_altitude_deg(-2.0)
+                .observe(0.0)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn surface_offset_along_zenith() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        Body::hydrate_all(&planet, &None);
+        let observatory =
+            Observatory::from_lat_long(30.0, 45.0, planet.clone(), Err(vec![]), vec![]);
+        let magnitude = |v: Vector3<Float>| (v.x * v.x + v.y * v.y + v.z * v.z).sqrt();
+        assert_float_absolute_eq!(magnitude(observatory.surface_offset()), 0.0);
+
+        planet.write().unwrap().radius = Some(2.0);
+        let offset = observatory.surface_offset();
+        assert_float_relative_eq!(magnitude(offset), 2.0);
+        let zenith: Vector3<Float> = WeakObservatory::from(observatory).location.into();
+        assert_float_absolute_eq!(super::angle_between(&offset, &zenith).unwrap(), 0.0, 1e-3);
+        // 30° north of the equator
+        assert_float_relative_eq!(offset.z, 1.0, 1e-5);
+    }
+
+    #[test]
+    fn proper_motion_of_fast_orbit() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // Once around the sky every day, in the plane of the horizon
+        let moon = Body::new(
+            Some(planet.clone()),
+            crate::dynamic::keplerian::Keplerian::new_with_period(
+                0.0, 10.0, 0.0, 0.0, 0.0, 0.0, 24.0,
+            ),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::new(
+            Spherical::from(Vector3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            }),
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+
+        let observatory = observatory.with_include_below_horizon(true);
+        let azimuth_at = |time| observatory.observe(time)[0].1.azimuthal_angle;
+        for time in [0.0, 5.0, 17.5] {
+            let (rate, direction) = observatory.proper_motion(&moon, time).unwrap();
+            assert_float_relative_eq!(rate, float::TAU / 24.0, 1e-4);
+            // Along the horizon the way azimuth decreases
+            assert_float_absolute_eq!(direction, -float::FRAC_PI_2, 1e-3);
+            assert!((azimuth_at(time + 0.1) - azimuth_at(time)).rem_euclid(float::TAU) > float::PI);
+        }
+        // Still, seen from itself
+        assert!(observatory.proper_motion(&planet, 0.0).is_none());
+    }
+
+    #[test]
+    fn observes_from_surface() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // On the horizon seen from the center, so below it seen from the surface
+        let setting = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3 {
+                x: 0.0,
+                y: 2.0,
+                z: 0.0,
+            }),
+        );
+        // 45° up seen from the center
+        let moon = Body::new(
+            Some(planet.clone()),
+            Fixed::new(
+                Vector3 {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 0.0,
+                } * float::SQRT_2,
+            ),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::from_lat_long(0.0, 0.0, planet.clone(), Err(vec![]), vec![]);
+        let find = |observations: &[crate::LocalObservation], body: &Arc| {
+            observations
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+                .map(|(_, location)| *location)
+        };
+
+        // Without a radius bodies are seen from the center
+        let observations = observatory.observe(0.0);
+        assert!(find(&observations, &setting).is_some());
+        let location = find(&observations, &moon).unwrap();
+        assert_float_absolute_eq!(location.polar_angle, float::FRAC_PI_4, 1e-5);
+        assert_float_relative_eq!(location.radius, 2.0, 1e-5);
+
+        planet.write().unwrap().radius = Some(1.0);
+        let observations = observatory.observe(0.0);
+        assert!(find(&observations, &setting).is_none());
+        assert_eq!(observatory.visible_count(0.0), 1);
+        // Seen from the surface the moon is (√2 - 1) up and √2 across
+        let location = find(&observations, &moon).unwrap();
+        let up = float::SQRT_2 - 1.0;
+        assert_float_absolute_eq!(
+            location.polar_angle,
+            float::FRAC_PI_2 - (up / float::SQRT_2).atan(),
+            1e-5
+        );
+        assert_float_relative_eq!(location.radius, (up * up + 2.0).sqrt(), 1e-5);
+        assert_float_absolute_eq!(
+            observatory.altitude(&moon, 0.0).unwrap(),
+            (up / float::SQRT_2).atan(),
+            1e-5
+        );
+    }
+
+    #[test]
+    fn tail_end_from_surface() {
+        let star = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        star.write().unwrap().luminosity = Some(1.0);
+        let planet = Body::new(
+            Some(star.clone()),
+            Fixed::new(Vector3 {
+                x: -10.0,
+                y: 0.0,
+                z: 0.0,
+            }),
+        );
+        planet.write().unwrap().radius = Some(1.0);
+        // Just above the observatory, close enough to shift with parallax
+        let comet = Body::new(
+            Some(star.clone()),
+            Fixed::new(Vector3 {
+                x: -8.5,
+                y: 0.5,
+                z: 0.0,
+            }),
+        );
+        // No length, so the tail ends where the comet is
+        comet.write().unwrap().tail = Some(super::super::tail::Tail::new(0.0));
+        Body::hydrate_all(&star, &None);
+        let observatory = Observatory::from_lat_long(0.0, 0.0, planet, Err(vec![]), vec![]);
+
+        let location = observatory
+            .observe(0.0)
+            .into_iter()
+            .find(|(b, _)| std::sync::Arc::ptr_eq(b, &comet))
+            .unwrap()
+            .1;
+        let end = observatory.tail_end(&comet, 0.0).unwrap();
+        assert_float_absolute_eq!(end.polar_angle, location.polar_angle, 1e-5);
+        assert_float_absolute_eq!(end.azimuthal_angle, location.azimuthal_angle, 1e-5);
+        assert_float_relative_eq!(end.radius, location.radius, 1e-5);
+    }
+
+    #[test]
+    fn reference_up_rotates_frame() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let overhead = Body::new(Some(planet.clone()), Fixed::new(Vector3::UP * 1_000.0));
+        // Just below the horizon seen from the north pole, towards x
+        let low = Body::new(
+            Some(planet.clone()),
+            Fixed::new(
+                Vector3 {
+                    x: 1.0,
+                    y: 0.0,
+                    z: -0.2,
+                } * 1_000.0,
+            ),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::from_lat_long(90.0, 0.0, planet, Err(vec![]), vec![]);
+        let find = |observations: &[crate::LocalObservation], body: &Arc| {
+            observations
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+                .map(|(_, location)| *location)
+        };
+
+        let observations = observatory.observe(0.0);
+        assert_eq!(observatory.reference_up(), None);
+        assert_float_absolute_eq!(find(&observations, &overhead).unwrap().polar_angle, 0.0);
+        assert!(find(&observations, &low).is_none());
+
+        // Up tilted 30° towards x, so the body overhead is 30° from the zenith and the low body
+        // comes up over the horizon
+        let up = Spherical {
+            radius: 1.0,
+            polar_angle: Float::to_radians(30.0),
+            azimuthal_angle: 0.0,
+        };
+        let tilted = observatory.clone().with_reference_up(up);
+        let observations = tilted.observe(0.0);
+        assert_float_absolute_eq!(
+            find(&observations, &overhead).unwrap().polar_angle,
+            Float::to_radians(30.0),
+            1e-4
+        );
+        let low_direction = Vector3 {
+            x: 1.0,
+            y: 0.0,
+            z: -0.2,
+        };
+        let expected = super::angle_between(&Vector3::from(up), &low_direction).unwrap();
+        assert_float_absolute_eq!(
+            find(&observations, &low).unwrap().polar_angle,
+            expected,
+            1e-4
+        );
+        // The observatory is still named after where it is
+        assert_eq!(tilted.get_name(), observatory.get_name());
+
+        let weak: WeakObservatory = tilted.into();
+        let json = serde_json::to_string(&weak).unwrap();
+        assert!(json.contains("referenceUp"));
+    }
+
+    #[test]
+    fn below_horizon_included_on_request() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let _ = Body::new(Some(planet.clone()), Fixed::new(Vector3::UP * 1_000.0));
+        let below = Body::new(Some(planet.clone()), Fixed::new(Vector3::DOWN * 1_000.0));
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::from_lat_long(90.0, 0.0, planet, Err(vec![]), vec![])
+            .with_horizon_altitude_deg(10.0);
+
+        assert_eq!(observatory.observe(0.0).len(), 1);
+        let everything = observatory.with_include_below_horizon(true);
+        let observations = everything.observe(0.0);
+        assert_eq!(observations.len(), 2);
+        let (_, location) = observations
+            .iter()
+            .find(|(body, _)| std::sync::Arc::ptr_eq(body, &below))
+            .unwrap();
+        assert_float_absolute_eq!(location.polar_angle, float::PI, 1e-4);
+
+        let weak: WeakObservatory = everything.into();
+        assert!(serde_json::to_string(&weak)
+            .unwrap()
+            .contains("\"includeBelowHorizon\":true"));
+    }
+
+    #[test]
+    fn round_trip_through_weak() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let planet = Body::new(Some(root.clone()), Fixed::new(Vector3::RIGHT));
+        Body::hydrate_all(&root, &None);
+        let location = Spherical {
+            radius: 1.0,
+            polar_angle: 1.0,
+            azimuthal_angle: -2.0,
+        };
+
+        for name in [Ok("Named".to_string()), Err(vec![0])] {
+            let observatory = Observatory::new(location, planet.clone(), name, vec![]);
+            let weak: WeakObservatory = observatory.clone().into();
+            assert_float_absolute_eq!(weak.location.polar_angle, 1.0, 1e-4);
+            assert_float_absolute_eq!(weak.location.azimuthal_angle, -2.0, 1e-4);
+
+            let json = serde_json::to_string(&weak).unwrap();
+            let weak: WeakObservatory = serde_json::from_str(&json).unwrap();
+            let loaded = super::to_observatory(weak, &root).unwrap();
+            assert_eq!(loaded.get_name(), observatory.get_name());
+            assert_eq!(loaded.get_id(), observatory.get_id());
+        }
+    }
 }
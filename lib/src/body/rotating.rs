@@ -6,14 +6,17 @@ use crate::{consts::float, EllipticObservation, Float};
 
 /// A struct that defines the rotation of a body.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(from = "SerializableRotating", into = "SerializableRotating")]
+#[serde(try_from = "SerializableRotating", into = "SerializableRotating")]
 pub struct Rotating {
     /// The time for the body to rotate 360 degrees, as opposed to a [solar day](https://en.wikipedia.org/wiki/Synodic_day)
     sidereal_period: Float,
     /// The direction of the geographic north pole.
     axis: Vector3<Float>,
+    /// Angle in radians the body had already turned through at t=0
+    angle_at_epoch: Float,
 }
 
+/// Intermediate representation of [`Rotating`] that stores the axis in spherical coordinates
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 struct SerializableRotating {
@@ -22,6 +25,9 @@ struct SerializableRotating {
     sidereal_period: Float,
     /// Location of the true-north pole
     axis: Spherical<Float>,
+    /// Angle in radians the body had already turned through at t=0
+    #[serde(skip_serializing_if = "crate::json::is_zero", default)]
+    angle_at_epoch: Float,
 }
 
 impl From<Rotating> for SerializableRotating {
@@ -29,16 +35,31 @@ impl From<Rotating> for SerializableRotating {
         SerializableRotating {
             sidereal_period: value.sidereal_period,
             axis: value.axis.into(),
+            angle_at_epoch: value.angle_at_epoch,
         }
     }
 }
 
-impl From<SerializableRotating> for Rotating {
-    fn from(value: SerializableRotating) -> Self {
-        Rotating {
+impl TryFrom<SerializableRotating> for Rotating {
+    type Error = String;
+
+    fn try_from(value: SerializableRotating) -> Result<Self, Self::Error> {
+        crate::json::check_finite(
+            "Rotation",
+            &[
+                value.sidereal_period,
+                value.axis.radius,
+                value.axis.polar_angle,
+                value.axis.azimuthal_angle,
+                value.angle_at_epoch,
+            ],
+        )?;
+
+        Ok(Rotating {
             sidereal_period: value.sidereal_period,
             axis: value.axis.into(),
-        }
+            angle_at_epoch: value.angle_at_epoch,
+        })
     }
 }
 
@@ -53,9 +74,33 @@ impl Rotating {
         Self {
             sidereal_period,
             axis: axis.into(),
+            angle_at_epoch: 0.0,
         }
     }
 
+    /// Returns the direction of the geographic north pole, as a unit vector relative to the body's
+    /// parent
+    #[must_use]
+    pub fn get_axis(&self) -> Vector3<Float> {
+        self.axis
+    }
+
+    /// Returns the time in hours for the body to rotate 360 degrees relative to the distant
+    /// stars
+    #[must_use]
+    pub fn get_sidereal_period(&self) -> Float {
+        self.sidereal_period
+    }
+
+    /// Converts a direction relative to the body into the equatorial coordinates used for
+    /// observations at the given time
+    #[must_use]
+    pub(crate) fn to_equatorial(&self, time: Float, direction: Vector3<Float>) -> Vector3<Float> {
+        let obliquity_rotation = quaternion::rotation_from_to(self.axis.into(), Vector3::UP.into());
+        let vector = quaternion::rotate_vector(self.get_rotation(time), direction.into());
+        quaternion::rotate_vector(obliquity_rotation, vector).into()
+    }
+
     /// Returns a rotation for a given time, should just adjust the longitude the observed body is
     /// over not the latitude.
     #[must_use]
@@ -65,7 +110,27 @@ impl Rotating {
 
     /// Gets angle relative to the reference direction since last complete revolution
     fn get_mean_angle(&self, time: Float) -> Float {
-        time % self.sidereal_period / self.sidereal_period * float::TAU
+        time % self.sidereal_period / self.sidereal_period * float::TAU + self.angle_at_epoch
+    }
+
+    /// Rotates the axis along with the rest of a [transformed](super::Body::transform_subtree)
+    /// tree. The prime meridian is found from the axis, so the angle at epoch is corrected for the
+    /// twist the new axis gives it, keeping observations from the surface unchanged.
+    pub(crate) fn transform(&mut self, rotation: Quaternion<Float>) {
+        let axis = Vector3::from(quaternion::rotate_vector(rotation, self.axis.into()));
+
+        // Maps the old equatorial coordinates to the new ones, which only differ by a turn about
+        // the pole
+        let old_obliquity = quaternion::rotation_from_to(self.axis.into(), Vector3::UP.into());
+        let new_obliquity = quaternion::rotation_from_to(axis.into(), Vector3::UP.into());
+        let twist = quaternion::mul(
+            new_obliquity,
+            quaternion::mul(rotation, quaternion::conj(old_obliquity)),
+        );
+        let [x, y, _] = quaternion::rotate_vector(twist, Vector3::RIGHT.into());
+
+        self.angle_at_epoch += y.atan2(x);
+        self.axis = axis;
     }
 
     pub fn rotate_observed_bodies_equatorial_coordinates(
@@ -233,12 +298,14 @@ mod test {
         let rotating = Rotating {
             sidereal_period: 24.0,
             axis: Vector3::UP,
+            angle_at_epoch: 1.0,
         };
 
         let serializable: SerializableRotating = rotating.clone().into();
-        let new_rotating: Rotating = serializable.into();
+        let new_rotating: Rotating = serializable.try_into().unwrap();
 
         assert_float_absolute_eq!(rotating.sidereal_period, new_rotating.sidereal_period);
         assert_eq!(rotating.axis, new_rotating.axis);
+        assert_float_absolute_eq!(rotating.angle_at_epoch, new_rotating.angle_at_epoch);
     }
 }
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+/// Stage in the life of a star, see
+/// [`ArtifexianBuilder::galaxy_age_gyr`](crate::generator::artifexian::ArtifexianBuilder::galaxy_age_gyr)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StellarStage {
+    /// Fusing hydrogen in its core, like the Sun
+    MainSequence,
+    /// Swollen to many times its former radius after running out of hydrogen in its core, like
+    /// Aldebaran
+    Giant,
+    /// The small, dim core left behind once a star has shed its outer layers, like Sirius B
+    WhiteDwarf,
+}
+
+impl StellarStage {
+    /// Returns the time in billions of years a star with a mass of `mass` solar masses spends on
+    /// the main sequence, about 10 billion years for the Sun
+    #[must_use]
+    pub fn main_sequence_lifetime_gyr(mass: Float) -> Float {
+        10.0 * mass.powf(-2.5)
+    }
+
+    /// Returns the stage of a star with a mass of `mass` solar masses that is `age_gyr` billion
+    /// years old. Stars spend a tenth of their main sequence lifetime as giants, and are white
+    /// dwarfs from then on.
+    #[must_use]
+    pub fn at_age(mass: Float, age_gyr: Float) -> Self {
+        let lifetime = Self::main_sequence_lifetime_gyr(mass);
+        if age_gyr <= lifetime {
+            Self::MainSequence
+        } else if age_gyr <= lifetime * 1.1 {
+            Self::Giant
+        } else {
+            Self::WhiteDwarf
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heavier_stars_evolve_sooner() {
+        assert_float_relative_eq!(StellarStage::main_sequence_lifetime_gyr(1.0), 10.0);
+        assert_eq!(StellarStage::at_age(1.0, 4.6), StellarStage::MainSequence);
+        assert_eq!(StellarStage::at_age(1.0, 10.5), StellarStage::Giant);
+        assert_eq!(StellarStage::at_age(1.0, 13.0), StellarStage::WhiteDwarf);
+        assert_eq!(StellarStage::at_age(3.0, 4.6), StellarStage::WhiteDwarf);
+        assert_eq!(StellarStage::at_age(0.1, 13.0), StellarStage::MainSequence);
+    }
+}
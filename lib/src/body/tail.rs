@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::ASTRONOMICAL_UNIT, Float};
+
+/// A tail of gas and dust streaming away from a body's [light
+/// source](super::Body::get_light_source), like a comet's.
+///
+/// The tail grows with the inverse square of the distance to the light source, so it is longest
+/// close to the star and all but disappears far from it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "SerializableTail")]
+pub struct Tail {
+    /// Length of the tail in light seconds when the body is one [`ASTRONOMICAL_UNIT`] from its
+    /// light source
+    length: Float,
+}
+
+/// An unchecked [`Tail`] as read from JSON
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializableTail {
+    /// See [`Tail::length`]
+    length: Float,
+}
+
+impl TryFrom<SerializableTail> for Tail {
+    type Error = String;
+
+    fn try_from(value: SerializableTail) -> Result<Self, Self::Error> {
+        crate::json::check_finite("Tail", &[value.length])?;
+        if value.length < 0.0 {
+            return Err(format!(
+                "Tail length must not be negative, got {}",
+                value.length
+            ));
+        }
+        Ok(Self::new(value.length))
+    }
+}
+
+impl Tail {
+    /// Creates a tail that is `length` light seconds long when the body is one
+    /// [`ASTRONOMICAL_UNIT`] from its light source
+    #[must_use]
+    pub fn new(length: Float) -> Self {
+        Self { length }
+    }
+
+    /// Returns the length of the tail in light seconds when the body is `distance` light seconds
+    /// from its light source
+    #[must_use]
+    pub fn length_at(&self, distance: Float) -> Float {
+        self.length * (ASTRONOMICAL_UNIT / distance).powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinks_away_from_star() {
+        let tail = Tail::new(100.0);
+
+        assert_float_relative_eq!(tail.length_at(ASTRONOMICAL_UNIT), 100.0, 1e-4);
+        assert_float_relative_eq!(tail.length_at(ASTRONOMICAL_UNIT * 2.0), 25.0, 1e-4);
+        assert!(crate::json::from_str::<Tail>(r#"{"length": -1}"#).is_err());
+    }
+}
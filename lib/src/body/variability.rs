@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::float, Float};
+
+/// A sinusoidal change in the light a body gives off, like a pulsating variable star's.
+///
+/// The luminosity swings between `1 - amplitude` and `1 + amplitude` times the body's
+/// [luminosity](super::Body::get_luminosity) once every period, so its apparent magnitude swings by
+/// `2.5 log10((1 + amplitude) / (1 - amplitude))` from faintest to brightest.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "SerializableVariability")]
+pub struct Variability {
+    /// Fraction of the mean luminosity the luminosity rises above and falls below it by
+    amplitude: Float,
+    /// Time between peaks in brightness, in hours
+    period: Float,
+    /// Fraction of a period past its mean brightness on the rise at t=0
+    phase: Float,
+}
+
+/// An unchecked [`Variability`] as read from JSON
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializableVariability {
+    /// See [`Variability::amplitude`]
+    amplitude: Float,
+    /// See [`Variability::period`]
+    period: Float,
+    /// See [`Variability::phase`]
+    #[serde(default)]
+    phase: Float,
+}
+
+impl TryFrom<SerializableVariability> for Variability {
+    type Error = String;
+
+    fn try_from(value: SerializableVariability) -> Result<Self, Self::Error> {
+        crate::json::check_finite("Variability", &[value.amplitude, value.period, value.phase])?;
+        if !(0.0..1.0).contains(&value.amplitude) {
+            return Err(format!(
+                "Variability amplitude must be at least 0 and less than 1, got {}",
+                value.amplitude
+            ));
+        }
+        if value.period <= 0.0 {
+            return Err(format!(
+                "Variability period must be positive, got {}",
+                value.period
+            ));
+        }
+        Ok(Self::new(value.amplitude, value.period).with_phase(value.phase))
+    }
+}
+
+impl Variability {
+    /// Creates a variation of `amplitude` times the mean luminosity (from 0 up to but not
+    /// including 1) either side of it, repeating every `period` hours
+    #[must_use]
+    pub fn new(amplitude: Float, period: Float) -> Self {
+        Self {
+            amplitude,
+            period,
+            phase: 0.0,
+        }
+    }
+
+    /// Starts the variation `phase` of a period past its mean brightness on the rise at t=0,
+    /// e.g. 0.25 to start at its brightest
+    #[must_use]
+    pub fn with_phase(mut self, phase: Float) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Returns the fraction of the mean luminosity the luminosity rises above and falls below it
+    /// by
+    #[must_use]
+    pub fn amplitude(&self) -> Float {
+        self.amplitude
+    }
+
+    /// Returns the time between peaks in brightness, in hours
+    #[must_use]
+    pub fn period(&self) -> Float {
+        self.period
+    }
+
+    /// Returns how many times the mean luminosity the body gives off at the given time
+    #[must_use]
+    pub fn factor_at(&self, time: Float) -> Float {
+        let cycles = (time / self.period + self.phase).rem_euclid(1.0);
+        1.0 + self.amplitude * (cycles * float::TAU).sin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swings_either_side_of_mean() {
+        let variability = Variability::new(0.5, 10.0);
+
+        assert_float_absolute_eq!(variability.factor_at(0.0), 1.0, 1e-5);
+        assert_float_absolute_eq!(variability.factor_at(2.5), 1.5, 1e-5);
+        assert_float_absolute_eq!(variability.factor_at(7.5), 0.5, 1e-5);
+        assert_float_absolute_eq!(variability.factor_at(12.5), 1.5, 1e-5);
+        assert_float_absolute_eq!(variability.with_phase(0.25).factor_at(0.0), 1.5, 1e-5);
+
+        assert!(crate::json::from_str::<Variability>(r#"{"amplitude": 1, "period": 1}"#).is_err());
+        assert!(crate::json::from_str::<Variability>(r#"{"amplitude": 0, "period": 0}"#).is_err());
+        let read: Variability =
+            crate::json::from_str(r#"{"amplitude": 0.5, "period": 10}"#).unwrap();
+        assert_eq!(read, variability);
+    }
+}
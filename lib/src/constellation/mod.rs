@@ -1,13 +1,56 @@
+use std::sync::Arc as StdArc;
+
 use coordinates::three_dimensional::Spherical;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::body::Arc;
 use crate::{Float, LocalObservation};
 
 pub mod weak;
 
-pub type Line = (Spherical<Float>, Spherical<Float>);
+/// Color of constellation lines when the constellation has no name or color
+pub const DEFAULT_COLOR: &str = "#AAA";
+
+/// Width of constellation lines when the constellation has no width, in the same units as charts
+/// which are 2 units across
+pub const DEFAULT_WIDTH: Float = 0.003;
+
+/// A visible edge of a constellation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    /// Location of the body at one end of the edge
+    pub start: Spherical<Float>,
+    /// Location of the body at the other end of the edge
+    pub end: Spherical<Float>,
+    /// CSS color of the constellation the edge belongs to
+    pub color: StdArc<str>,
+    /// Width of the line, see [`DEFAULT_WIDTH`]
+    pub width: Float,
+    /// Sky culture of the constellation the edge belongs to, if it has one
+    pub culture: Option<StdArc<str>>,
+}
+
+impl Line {
+    /// Returns true if the constellation this edge belongs to is in one of the `cultures`, or
+    /// `cultures` is `None` so every culture is selected
+    #[must_use]
+    pub fn is_in(&self, cultures: Option<&[String]>) -> bool {
+        is_in(self.culture.as_deref(), cultures)
+    }
+}
+
+/// How lines of a constellation are drawn, anything missing falls back to the defaults
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Style {
+    /// CSS color to draw the constellation with, if none a color is picked from the name
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub color: Option<String>,
+    /// Width of the lines, if none [`DEFAULT_WIDTH`] is used
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub width: Option<Float>,
+}
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase", into = "weak::Weak")]
@@ -15,11 +58,69 @@ pub struct Constellation {
     /// Lists the edges marked by the bodies that marks the ends of the edges
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     edges: Vec<(crate::body::Arc, crate::body::Arc)>,
-    // TODO: add name field and figure out how to display it.
+    /// Name of the constellation
+    name: Option<String>,
+    /// Sky culture the constellation belongs to, e.g. "Western" or "Navajo"
+    culture: Option<String>,
+    /// What the constellation depicts, or its story
+    description: Option<String>,
+    /// How lines of the constellation are drawn
+    style: Option<Style>,
 }
 
 impl Constellation {
+    /// Returns the color lines of this constellation are drawn with: the given color, or one
+    /// derived from the name so each named constellation is consistently colored, or
+    /// [`DEFAULT_COLOR`].
+    #[must_use]
+    pub fn color(&self) -> StdArc<str> {
+        match (
+            self.style.as_ref().and_then(|style| style.color.as_ref()),
+            &self.name,
+        ) {
+            (Some(color), _) => color.as_str().into(),
+            (None, Some(name)) => color_from_name(name).into(),
+            (None, None) => DEFAULT_COLOR.into(),
+        }
+    }
+
+    /// Returns the width lines of this constellation are drawn with, or [`DEFAULT_WIDTH`]
+    #[must_use]
+    pub fn width(&self) -> Float {
+        self.style
+            .as_ref()
+            .and_then(|style| style.width)
+            .unwrap_or(DEFAULT_WIDTH)
+    }
+
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the sky culture the constellation belongs to
+    #[must_use]
+    pub fn culture(&self) -> Option<&str> {
+        self.culture.as_deref()
+    }
+
+    /// Returns what the constellation depicts
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns true if the constellation is in one of the `cultures`, or `cultures` is `None` so
+    /// every culture is selected
+    #[must_use]
+    pub fn is_in(&self, cultures: Option<&[String]>) -> bool {
+        is_in(self.culture(), cultures)
+    }
+
     pub fn add_edges(&self, observations: &[LocalObservation]) -> Vec<Line> {
+        let color = self.color();
+        let width = self.width();
+        let culture: Option<StdArc<str>> = self.culture().map(Into::into);
         // PERF: is there a O(n) way to do this? currently it is O(n*m) where n is the number of
         // edges and m is the number of observed bodies.
         // It might be quicker if we use a hashmap, or loop through observations first since they are
@@ -36,7 +137,13 @@ impl Constellation {
                     .find(|(x, _)| std::sync::Arc::ptr_eq(x, b))
                 {
                     // Both bodies are visible in the output, so return their locations
-                    return Some((loc_a.to_owned(), loc_b.to_owned()));
+                    return Some(Line {
+                        start: loc_a.to_owned(),
+                        end: loc_b.to_owned(),
+                        color: color.clone(),
+                        width,
+                        culture: culture.clone(),
+                    });
                 }
             }
             None
@@ -51,6 +158,21 @@ impl Constellation {
     }
 }
 
+/// Returns true if `culture` is one of the selected `cultures`, constellations without a culture
+/// are only selected when every culture is
+fn is_in(culture: Option<&str>, cultures: Option<&[String]>) -> bool {
+    cultures.is_none_or(|cultures| culture.is_some_and(|c| cultures.iter().any(|s| s == c)))
+}
+
+/// Picks a light color with a hue from a hash of the name, so it is the same between runs
+fn color_from_name(name: &str) -> String {
+    // FNV-1a, since the standard library doesn't promise a stable hash
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("hsl({}, 70%, 70%)", hash % 360)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -68,6 +190,10 @@ mod test {
                 (body_b.clone(), body_c.clone()),
                 (body_c.clone(), body_a.clone()),
             ],
+            name: None,
+            culture: None,
+            description: None,
+            style: None,
         };
 
         let loc_a = Spherical::UP;
@@ -76,8 +202,30 @@ mod test {
 
         let edges = constellation.add_edges(&[(body_a, loc_a), (body_b, loc_b), (body_c, loc_c)]);
 
-        assert_eq!(edges[0], (loc_a, loc_b));
-        assert_eq!(edges[1], (loc_b, loc_c));
-        assert_eq!(edges[2], (loc_c, loc_a));
+        let ends: Vec<_> = edges.iter().map(|line| (line.start, line.end)).collect();
+        assert_eq!(ends, [(loc_a, loc_b), (loc_b, loc_c), (loc_c, loc_a)]);
+        assert!(edges.iter().all(|line| &*line.color == DEFAULT_COLOR));
+        assert!(edges.iter().all(|line| line.width == DEFAULT_WIDTH));
+    }
+
+    #[test]
+    fn colors() {
+        let named = |name: &str, color: Option<&str>| Constellation {
+            edges: vec![],
+            name: Some(name.into()),
+            culture: None,
+            description: None,
+            style: color.map(|color| Style {
+                color: Some(color.into()),
+                width: None,
+            }),
+        };
+
+        assert_eq!(named("Orion", None).color(), named("Orion", None).color());
+        assert_ne!(
+            named("Orion", None).color(),
+            named("Cassiopeia", None).color()
+        );
+        assert_eq!(&*named("Orion", Some("red")).color(), "red");
     }
 }
@@ -1,13 +1,25 @@
 use serde::{Deserialize, Serialize};
 
-use log::{trace, warn};
+use tracing::{trace, warn};
 
-#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Weak {
     /// List of the edges and the IDs of the bodies that mark their ends
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     edges: Vec<(Vec<usize>, Vec<usize>)>,
+    /// Name of the constellation
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    name: Option<String>,
+    /// Sky culture the constellation belongs to
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    culture: Option<String>,
+    /// What the constellation depicts, or its story
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    description: Option<String>,
+    /// How lines of the constellation are drawn
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    style: Option<super::Style>,
 }
 
 impl Weak {
@@ -22,7 +34,13 @@ impl Weak {
             }
         }
 
-        super::Constellation { edges: new_edges }
+        super::Constellation {
+            edges: new_edges,
+            name: self.name,
+            culture: self.culture,
+            description: self.description,
+            style: self.style,
+        }
     }
 }
 
@@ -62,7 +80,13 @@ impl From<super::Constellation> for Weak {
             })
             .collect();
 
-        Self { edges }
+        Self {
+            edges,
+            name: value.name,
+            culture: value.culture,
+            description: value.description,
+            style: value.style,
+        }
     }
 }
 
@@ -92,9 +116,14 @@ mod test {
                 (body_b.clone(), body_c.clone()),
                 (body_c.clone(), body_a.clone()),
             ],
+            name: Some("Triangle".into()),
+            culture: None,
+            description: None,
+            style: None,
         };
 
         let weak: Weak = constellation.into();
+        assert_eq!(weak.name.as_deref(), Some("Triangle"));
 
         assert_eq!(weak.edges.len(), 3);
         assert_eq!(weak.edges[0], (vec![], vec![0]));
@@ -136,6 +165,33 @@ mod test {
             .eq(&body_a.read().unwrap()));
     }
 
+    #[test]
+    fn metadata_round_trip() {
+        let json = r##"{"edges":[[[0],[1]]],"name":"Orion","culture":"Western","description":"The hunter","style":{"color":"#F80","width":0.01}}"##;
+
+        let weak: Weak = serde_json::from_str(json).unwrap();
+        assert_eq!(weak.culture.as_deref(), Some("Western"));
+        assert_eq!(serde_json::to_string(&weak).unwrap(), json);
+
+        let bare: Weak = serde_json::from_str(r#"{"edges":[[[0],[1]]]}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&bare).unwrap(),
+            r#"{"edges":[[[0],[1]]]}"#
+        );
+
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        Body::new(Some(root.clone()), Fixed::new(Vector3::UP));
+        Body::new(Some(root.clone()), Fixed::new(Vector3::FORWARD));
+        Body::hydrate_all(&root, &None);
+
+        let constellation = weak.clone().upgrade(&root);
+        assert_eq!(constellation.culture(), Some("Western"));
+        assert_eq!(constellation.description(), Some("The hunter"));
+        assert_eq!(&*constellation.color(), "#F80");
+        assert_float_absolute_eq!(constellation.width(), 0.01);
+        assert_eq!(Weak::from(constellation), weak);
+    }
+
     #[test]
     fn get_missing_body() {
         let body_a = Body::new(None, Fixed::new(Vector3::ORIGIN));
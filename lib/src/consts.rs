@@ -3,6 +3,13 @@ use crate::Float;
 /// Gravitational constant in terms of light seconds^3 per jupiter mass per hour^2
 pub const GRAVITATIONAL_CONSTANT: Float = 0.0609_109;
 
+/// Distance from the Earth to the Sun in light seconds
+pub const ASTRONOMICAL_UNIT: Float = 499.004_784;
+
+/// [Apparent magnitude](https://en.wikipedia.org/wiki/Apparent_magnitude) of the Sun seen from
+/// one [`ASTRONOMICAL_UNIT`] away
+pub const SUN_APPARENT_MAGNITUDE: Float = -26.74;
+
 /// Constants for the [`crate::Float`] type alias.
 #[cfg(any(target_arch = "wasm32", not(feature = "f64")))]
 pub mod float {
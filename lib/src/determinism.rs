@@ -0,0 +1,228 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use rand::Rng;
+
+use crate::body::Arc;
+
+/// Hashes of everything a run produced, by the name of each artifact, e.g. a file's path relative
+/// to the output directory
+pub type Artifacts = BTreeMap<String, u64>;
+
+/// Start of an [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// Multiplier of an [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hash
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Returns a hash of `bytes` that is the same on every platform and every version of Rust, unlike
+/// [`std::hash::DefaultHasher`]
+#[must_use]
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Returns a hash of the tree under `root` as it would be written to a universe file
+///
+/// # Errors
+///
+/// If the tree can't be serialized, e.g. a body's lock is poisoned
+pub fn hash_universe(root: &Arc) -> Result<u64, serde_json::Error> {
+    Ok(hash_bytes(&serde_json::to_vec(root)?))
+}
+
+/// Returns a hash of every file under `directory`, by its path relative to `directory` with `/`
+/// between its components, so the names match between runs in different directories and on
+/// different platforms
+///
+/// # Errors
+///
+/// If the directory or any file in it can't be read
+pub fn hash_directory(directory: &Path) -> Result<Artifacts, std::io::Error> {
+    let mut result = Artifacts::new();
+    let mut directories = vec![PathBuf::new()];
+    while let Some(relative) = directories.pop() {
+        for entry in std::fs::read_dir(directory.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                directories.push(path);
+            } else {
+                let name = path
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                result.insert(name, hash_bytes(&std::fs::read(entry.path())?));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The first artifact that differed between two runs, see [`first_divergence`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Name of the artifact
+    pub artifact: String,
+    /// Hash of the artifact from the first run, or `None` if it didn't produce it
+    pub first: Option<u64>,
+    /// Hash of the artifact from the second run, or `None` if it didn't produce it
+    pub second: Option<u64>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.first, self.second) {
+            (Some(first), Some(second)) => write!(
+                f,
+                "{} differed between runs (hash {first:016x} then {second:016x})",
+                self.artifact
+            ),
+            (Some(_), None) => write!(f, "{} was only produced by the first run", self.artifact),
+            (None, _) => write!(f, "{} was only produced by the second run", self.artifact),
+        }
+    }
+}
+
+/// Returns the first artifact, by name, that is missing from one run or has a different hash, or
+/// `None` if both runs produced the same artifacts
+#[must_use]
+pub fn first_divergence(first: &Artifacts, second: &Artifacts) -> Option<Divergence> {
+    let mut names: Vec<_> = first.keys().chain(second.keys()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names.into_iter().find_map(|name| {
+        let (first, second) = (first.get(name).copied(), second.get(name).copied());
+        (first != second).then(|| Divergence {
+            artifact: name.clone(),
+            first,
+            second,
+        })
+    })
+}
+
+/// Calls `run` twice, with the index of the run, and returns where the artifacts it returned
+/// first differ, or `None` if they were the same.
+///
+/// The first run uses the current [rayon] thread pool and the second a pool with a different
+/// number of threads, so results that depend on how work is split between threads are caught as
+/// well as those that depend on random numbers that weren't seeded, or the iteration order of a
+/// [`HashMap`](std::collections::HashMap). `run` should seed a new random number generator each
+/// time it is called.
+///
+/// # Errors
+///
+/// The first error returned by `run`
+pub fn check<E, F>(run: F) -> Result<Option<Divergence>, E>
+where
+    F: Fn(usize) -> Result<Artifacts, E> + Sync,
+    E: Send,
+{
+    let threads = rayon::current_num_threads();
+    let mut other_threads = rand::thread_rng().gen_range(1..=2 * threads);
+    if other_threads == threads {
+        other_threads += 1;
+    }
+    tracing::debug!("Checking determinism with {threads} then {other_threads} threads");
+
+    let first = run(0)?;
+    let second = match rayon::ThreadPoolBuilder::new()
+        .num_threads(other_threads)
+        .build()
+    {
+        Ok(pool) => pool.install(|| run(1))?,
+        Err(e) => {
+            tracing::warn!("Could not change the number of threads for the second run: {e}");
+            run(1)?
+        }
+    };
+    Ok(first_divergence(&first, &second))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use super::*;
+    use crate::{
+        body::Body,
+        generator::{artifexian::ArtifexianBuilder, Generator},
+        output::logger::eclipse,
+        output::svg::Svg,
+        program::ProgramBuilder,
+        projection::StatelessOrthographic,
+        testing::DEFAULT_SEED,
+    };
+
+    /// Builds a small universe from `seed`, simulates it into a directory for the run, and
+    /// returns the hashes of the universe and every file written
+    fn build_and_simulate(name: &str, run: usize, seed: u128) -> Result<Artifacts, std::io::Error> {
+        let (root, observatories) = ArtifexianBuilder::default()
+            .star_count(20)
+            .build()
+            .unwrap()
+            .generate(&mut XorShiftRng::from_seed(seed.to_be_bytes()))
+            .unwrap();
+        Body::hydrate_all(&root, &None);
+
+        let mut output_root = std::env::temp_dir();
+        output_root.push(format!("astrograph-determinism-{name}-{run}"));
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let mut builder = ProgramBuilder::default();
+        builder
+            .root_body(root.clone())
+            .output_file_root(output_root.clone())
+            .add_output(Box::new(Svg::new(StatelessOrthographic())))
+            .add_output(Box::new(eclipse::Logger::default()));
+        for observatory in observatories {
+            builder.add_observatory(observatory);
+        }
+        builder.build().unwrap().make_observations(0, 12, Some(3))?;
+
+        let mut artifacts = hash_directory(&output_root)?;
+        artifacts.insert("universe.json".into(), hash_universe(&root)?);
+        std::fs::remove_dir_all(&output_root)?;
+        Ok(artifacts)
+    }
+
+    #[test]
+    fn seeded_runs_agree() {
+        let divergence = check(|run| build_and_simulate("seeded", run, DEFAULT_SEED)).unwrap();
+        assert_eq!(divergence, None);
+    }
+
+    #[test]
+    fn unseeded_randomness_is_caught() {
+        // As if a generator reached for the thread's generator instead of the seeded one
+        let divergence =
+            check(|run| build_and_simulate("unseeded", run, rand::thread_rng().gen())).unwrap();
+        assert!(divergence.is_some());
+    }
+
+    #[test]
+    fn divergence_names_first_artifact() {
+        let first = Artifacts::from([("a".into(), 1), ("b".into(), 2), ("c".into(), 3)]);
+        let mut second = first.clone();
+        assert_eq!(first_divergence(&first, &second), None);
+
+        second.remove("c");
+        second.insert("b".into(), 4);
+        let divergence = first_divergence(&first, &second).unwrap();
+        assert_eq!(divergence.artifact, "b");
+        assert_eq!((divergence.first, divergence.second), (Some(2), Some(4)));
+
+        second.insert("b".into(), 2);
+        assert_eq!(
+            first_divergence(&first, &second).unwrap().to_string(),
+            "c was only produced by the first run"
+        );
+        assert_ne!(hash_bytes(b"ab"), hash_bytes(b"ba"));
+    }
+}
@@ -0,0 +1,186 @@
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use quaternion::Quaternion;
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+use super::Dynamic;
+
+/// A perfectly circular orbit, cheaper and more explicit than a [`Keplerian`](super::keplerian::Keplerian)
+/// orbit with no eccentricity since no eccentric anomaly has to be solved for
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, DynPartialEq)]
+#[serde(rename_all = "camelCase", try_from = "UncheckedCircular")]
+pub struct Circular {
+    /// Unit: light-seconds
+    ///
+    /// Definition: Distance from the parent body
+    radius: Float,
+    /// Unit: hours
+    ///
+    /// Definition: Time to complete one orbit
+    period: Float,
+    /// Unit: radians
+    ///
+    /// Definition: How far along the orbit this body was at t=0, from the x axis towards the z
+    /// axis before the orbit is inclined
+    phase: Float,
+    /// Rotation from the x-z plane to the plane of the orbit
+    inclination: Quaternion<Float>,
+}
+
+impl Circular {
+    /// Returns a new circular orbit in the x-z plane rotated by `inclination`
+    #[must_use]
+    pub fn new(radius: Float, period: Float, phase: Float, inclination: Quaternion<Float>) -> Self {
+        Self {
+            radius,
+            period,
+            phase,
+            inclination,
+        }
+    }
+
+    /// Returns the distance from the parent body in light seconds
+    #[must_use]
+    pub fn radius(&self) -> Float {
+        self.radius
+    }
+
+    /// Returns how far along the orbit this body was at t=0, in radians
+    #[must_use]
+    pub fn phase(&self) -> Float {
+        self.phase
+    }
+
+    /// Returns the rotation from the x-z plane to the plane of the orbit
+    #[must_use]
+    pub fn inclination(&self) -> Quaternion<Float> {
+        self.inclination
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Circular {
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        let angle = time % self.period / self.period * Float::TAU + self.phase;
+        let (sin, cos) = angle.sin_cos();
+        quaternion::rotate_vector(
+            self.inclination,
+            [self.radius * cos, 0.0, self.radius * sin],
+        )
+        .into()
+    }
+
+    /// Returns the velocity in light seconds per hour, tangent to the orbit
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let angle = time % self.period / self.period * Float::TAU + self.phase;
+        let speed = self.radius * Float::TAU / self.period;
+        let (sin, cos) = angle.sin_cos();
+        quaternion::rotate_vector(self.inclination, [-speed * sin, 0.0, speed * cos]).into()
+    }
+
+    /// Returns the orbital period in hours
+    fn get_period(&self) -> Option<Float> {
+        Some(self.period)
+    }
+
+    /// Rotates the plane of the orbit. Orbits can't be translated since they are always centered
+    /// on their parent.
+    fn transform(
+        &mut self,
+        rotation: Quaternion<Float>,
+        translation: Vector3<Float>,
+    ) -> Result<(), String> {
+        if translation != Vector3::ORIGIN {
+            return Err(format!(
+                "{self:?} can't be moved away from its parent, only rotated"
+            ));
+        }
+        self.inclination = quaternion::mul(rotation, self.inclination);
+        Ok(())
+    }
+}
+
+/// A [`Circular`] orbit as it is read, before it is checked
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UncheckedCircular {
+    /// See [`Circular::radius`]
+    radius: Float,
+    /// Time to complete one orbit in hours
+    period: Float,
+    /// See [`Circular::phase`]
+    #[serde(default)]
+    phase: Float,
+    /// See [`Circular::inclination`]
+    inclination: Quaternion<Float>,
+}
+
+impl TryFrom<UncheckedCircular> for Circular {
+    type Error = String;
+
+    fn try_from(value: UncheckedCircular) -> Result<Self, Self::Error> {
+        let (w, [x, y, z]) = value.inclination;
+        crate::json::check_finite(
+            "Circular orbit",
+            &[value.radius, value.period, value.phase, w, x, y, z],
+        )?;
+        if value.period == 0.0 {
+            return Err("Circular orbit period must not be zero".into());
+        }
+        Ok(Self::new(
+            value.radius,
+            value.period,
+            value.phase,
+            value.inclination,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dynamic::keplerian::Keplerian;
+
+    use super::*;
+
+    #[test]
+    fn matches_circular_keplerian() {
+        let circular = Circular::new(5.0, 12.0, 0.5, quaternion::id());
+        let keplerian = Keplerian::new_with_period(0.0, 5.0, 0.0, 0.0, 0.0, 0.5, 12.0);
+        assert_eq!(circular.get_period(), Some(12.0));
+
+        for t in 0_u8..24 {
+            let time = Float::from(t);
+            let (expected, actual) = (keplerian.get_offset(time), circular.get_offset(time));
+            assert_float_absolute_eq!(actual.x, expected.x, 1e-4);
+            assert_float_absolute_eq!(actual.y, expected.y, 1e-4);
+            assert_float_absolute_eq!(actual.z, expected.z, 1e-4);
+        }
+    }
+
+    #[test]
+    fn inclined_and_round_tripped() {
+        // Tipped onto the x-y plane
+        let inclination = quaternion::axis_angle([1.0, 0.0, 0.0], Float::FRAC_PI_2);
+        let dynamic: Box<dyn Dynamic> = Box::new(Circular::new(2.0, 4.0, 0.0, inclination));
+
+        let quarter = dynamic.get_offset(1.0);
+        assert_float_absolute_eq!(quarter.x, 0.0, 1e-5);
+        assert_float_absolute_eq!(quarter.y.abs(), 2.0, 1e-5);
+        assert_float_absolute_eq!(quarter.z, 0.0, 1e-5);
+
+        // A quarter of the circumference every hour
+        let velocity = dynamic.get_velocity(1.0);
+        assert_float_absolute_eq!(velocity.x, -Float::PI, 1e-5);
+        assert_float_absolute_eq!(velocity.y, 0.0, 1e-5);
+        assert_float_absolute_eq!(velocity.z, 0.0, 1e-5);
+
+        let json = serde_json::to_string(&dynamic).unwrap();
+        let read: Box<dyn Dynamic> = serde_json::from_str(&json).unwrap();
+        assert_eq!(&read, &dynamic);
+
+        let infinite = json.replace("\"radius\":2.0", "\"radius\":1e999");
+        assert!(serde_json::from_str::<Box<dyn Dynamic>>(&infinite).is_err());
+    }
+}
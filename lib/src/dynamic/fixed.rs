@@ -1,4 +1,4 @@
-use coordinates::three_dimensional::Vector3;
+use coordinates::three_dimensional::{ThreeDimensionalConsts, Vector3};
 use dyn_partial_eq::DynPartialEq;
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +9,7 @@ use super::Dynamic;
 /// Returns a struct that returns the same offset for any given time. Especially useful for distant
 /// objects or very slow moving objects
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, DynPartialEq)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", try_from = "Vector3<Float>")]
 pub struct Fixed(pub(crate) Vector3<Float>);
 impl Fixed {
     /// Returns a new fixed dynamic at the given location
@@ -19,11 +19,34 @@ impl Fixed {
     }
 }
 
+impl TryFrom<Vector3<Float>> for Fixed {
+    type Error = String;
+
+    fn try_from(value: Vector3<Float>) -> Result<Self, Self::Error> {
+        crate::json::check_finite("Fixed location", &[value.x, value.y, value.z])?;
+        Ok(Self(value))
+    }
+}
+
 #[typetag::serde]
 impl Dynamic for Fixed {
     fn get_offset(&self, _: crate::Float) -> Vector3<crate::Float> {
         self.0
     }
+
+    /// Fixed bodies never move relative to their parent
+    fn get_velocity(&self, _: Float) -> Vector3<Float> {
+        Vector3::ORIGIN
+    }
+
+    fn transform(
+        &mut self,
+        rotation: quaternion::Quaternion<Float>,
+        translation: Vector3<Float>,
+    ) -> Result<(), String> {
+        self.0 = Vector3::from(quaternion::rotate_vector(rotation, self.0.into())) + translation;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -41,6 +64,7 @@ mod tests {
             assert_eq!(fixed_up.get_offset(Float::from(t)), Vector3::UP);
             assert_eq!(fixed_right.get_offset(Float::from(t)), Vector3::RIGHT);
             assert_eq!(fixed_back.get_offset(Float::from(t)), Vector3::BACK);
+            assert_eq!(fixed_up.get_velocity(Float::from(t)), Vector3::ORIGIN);
         }
     }
 }
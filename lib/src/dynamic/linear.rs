@@ -0,0 +1,116 @@
+use coordinates::three_dimensional::Vector3;
+use dyn_partial_eq::DynPartialEq;
+use quaternion::Quaternion;
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+use super::Dynamic;
+
+/// A body moving in a straight line at a constant velocity, like the
+/// [proper motion](https://en.wikipedia.org/wiki/Proper_motion) of a nearby star. Over long
+/// simulations this lets distant stars drift where a [`Fixed`](super::fixed::Fixed) star never
+/// would, without the cost of an orbit around the galaxy.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, DynPartialEq)]
+#[serde(rename_all = "camelCase", try_from = "UncheckedLinear")]
+pub struct Linear {
+    /// Unit: light-seconds
+    ///
+    /// Definition: Offset from the parent body at t=0
+    position: Vector3<Float>,
+    /// Unit: light-seconds per hour
+    ///
+    /// Definition: Change in offset from the parent body every hour
+    velocity: Vector3<Float>,
+}
+
+impl Linear {
+    /// Returns a body at `position` relative to its parent at t=0, moving `velocity` light seconds
+    /// every hour
+    #[must_use]
+    pub fn new(position: Vector3<Float>, velocity: Vector3<Float>) -> Self {
+        Self { position, velocity }
+    }
+
+    /// Returns the offset from the parent body at t=0 in light seconds
+    #[must_use]
+    pub fn position(&self) -> Vector3<Float> {
+        self.position
+    }
+}
+
+/// A [`Linear`] dynamic as it is read, before it is checked
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UncheckedLinear {
+    /// See [`Linear::position`]
+    position: Vector3<Float>,
+    /// Change in offset every hour in light seconds
+    velocity: Vector3<Float>,
+}
+
+impl TryFrom<UncheckedLinear> for Linear {
+    type Error = String;
+
+    fn try_from(value: UncheckedLinear) -> Result<Self, Self::Error> {
+        let (position, velocity) = (value.position, value.velocity);
+        crate::json::check_finite(
+            "Linear dynamic",
+            &[
+                position.x, position.y, position.z, velocity.x, velocity.y, velocity.z,
+            ],
+        )?;
+        Ok(Self::new(position, velocity))
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Linear {
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        self.position + self.velocity * time
+    }
+
+    /// The velocity never changes
+    fn get_velocity(&self, _: Float) -> Vector3<Float> {
+        self.velocity
+    }
+
+    fn transform(
+        &mut self,
+        rotation: Quaternion<Float>,
+        translation: Vector3<Float>,
+    ) -> Result<(), String> {
+        self.position =
+            Vector3::from(quaternion::rotate_vector(rotation, self.position.into())) + translation;
+        self.velocity = quaternion::rotate_vector(rotation, self.velocity.into()).into();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn moves_at_constant_velocity() {
+        let star = Linear::new(Vector3::UP * 1e4, Vector3::RIGHT * 0.5 + Vector3::UP * 0.2);
+
+        // A century later it has moved the speed times the time
+        let century = 100.0 * 365.25 * 24.0;
+        let moved = star.get_offset(century) - star.get_offset(0.0);
+        assert_float_relative_eq!(moved.magnitude(), (0.29 as Float).sqrt() * century, 1e-6);
+        assert_float_relative_eq!(moved.x, 0.5 * century, 1e-6);
+        assert_eq!(star.get_offset(0.0), Vector3::UP * 1e4);
+        assert_eq!(star.get_velocity(century), star.velocity);
+
+        let json = serde_json::to_string(&(Box::new(star) as Box<dyn Dynamic>)).unwrap();
+        let read: Box<dyn Dynamic> = crate::json::from_str(&json).unwrap();
+        assert_eq!(read.get_offset(10.0), star.get_offset(10.0));
+        assert!(crate::json::from_str::<Linear>(
+            r#"{"position": {"x": 0, "y": 0, "z": 0}, "velocity": {"x": 1e999, "y": 0, "z": 0}}"#
+        )
+        .is_err());
+    }
+}
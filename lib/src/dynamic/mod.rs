@@ -1,24 +1,134 @@
+/// A dynamic that follows a perfectly circular orbit
+pub mod circular;
 /// A dynamic that stays in a constant location
 pub mod fixed;
 /// A dynamic that fits the [keplerian laws for planetary motion](https://en.wikipedia.org/wiki/Kepler%27s_laws_of_planetary_motion).
 pub mod keplerian;
-//mod circular;
+/// A dynamic that moves in a straight line at a constant velocity
+pub mod linear;
 
 use std::fmt::Debug;
 
-use coordinates::three_dimensional::Vector3;
+use coordinates::{
+    prelude::{Cross3D, Magnitude},
+    three_dimensional::Vector3,
+};
 use dyn_clone::DynClone;
 use dyn_partial_eq::dyn_partial_eq;
+use quaternion::Quaternion;
 
-use crate::Float;
+use crate::{consts::GRAVITATIONAL_CONSTANT, Float};
+
+/// Derives the equality every [`Dynamic`] needs, for dynamics defined outside this crate. Import
+/// it alongside [`typetag`], see [`Dynamic`].
+pub use dyn_partial_eq::DynPartialEq;
+/// Registers dynamics defined outside this crate so they can be read and written with the rest of
+/// a universe, see [`Dynamic`]. Re-exported so the version always matches this crate's.
+pub use typetag;
 
 /// Interface to be used by any dynamic.
+///
+/// Dynamics defined outside this crate can be read and written in universe JSON like the built in
+/// ones, named by their type, once their implementation is registered with
+/// [`#[typetag::serde]`](typetag) from the re-exported [`typetag`]:
+///
+/// ```
+/// use astrograph::{
+///     body::Body,
+///     dynamic::{typetag, Dynamic, DynPartialEq},
+///     Float,
+/// };
+/// use coordinates::prelude::Vector3;
+/// use serde::{Deserialize, Serialize};
+///
+/// /// Bobs up and down above its parent once an hour
+/// #[derive(Clone, Debug, PartialEq, DynPartialEq, Serialize, Deserialize)]
+/// struct Bobbing {
+///     height: Float,
+/// }
+///
+/// #[typetag::serde]
+/// impl Dynamic for Bobbing {
+///     fn get_offset(&self, time: Float) -> Vector3<Float> {
+///         Vector3 {
+///             x: 0.0,
+///             y: 0.0,
+///             z: self.height * (time * astrograph::consts::float::TAU).sin(),
+///         }
+///     }
+/// }
+///
+/// let body: Body = astrograph::json::from_str(r#"{"dynamic": {"Bobbing": {"height": 2}}}"#)
+///     .unwrap();
+/// assert_eq!(body.get_dynamic().get_offset(0.25).z, 2.0);
+/// ```
 #[typetag::serde]
 #[dyn_partial_eq]
 pub trait Dynamic: Debug + Send + Sync + DynClone {
     /// Returns the position relative to the parent body at a given time.
     #[must_use]
     fn get_offset(&self, time: Float) -> Vector3<Float>;
+
+    /// Returns the velocity relative to the parent body in light seconds per hour at a given
+    /// time. Unless a dynamic knows it exactly, it is estimated from the offsets a
+    /// ten-thousandth of its period (or an hour if it has none) either side of `time`.
+    #[must_use]
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let step = self.get_period().map_or(1.0, |period| period * 1e-4);
+        (self.get_offset(time + step) - self.get_offset(time - step)) / (2.0 * step)
+    }
+
+    /// Returns the time in hours the body takes to return to the same offset, if its motion
+    /// repeats
+    #[must_use]
+    fn get_period(&self) -> Option<Float> {
+        None
+    }
+
+    /// Rotates the path of the body around its parent, then moves it by `translation`, so every
+    /// offset becomes `rotation * offset + translation`. Used by
+    /// [`Body::transform_subtree`](crate::body::Body::transform_subtree).
+    ///
+    /// # Errors
+    /// Returns an error, leaving the dynamic unchanged, if it can't follow the transformed path.
+    /// Dynamics can't be transformed unless they implement this.
+    fn transform(
+        &mut self,
+        rotation: Quaternion<Float>,
+        translation: Vector3<Float>,
+    ) -> Result<(), String> {
+        let _ = (rotation, translation);
+        Err(format!("{self:?} can't be transformed"))
+    }
 }
 
 dyn_clone::clone_trait_object!(Dynamic);
+
+/// Returns the [specific orbital energy](https://en.wikipedia.org/wiki/Specific_orbital_energy)
+/// in light seconds² per hour² of any dynamic at the given time, around a parent of the given mass
+/// in jupiter masses. Negative if the body is bound to its parent.
+///
+/// The velocity comes from [`Dynamic::get_velocity`], which may be estimated, prefer
+/// [`Keplerian::specific_orbital_energy`](keplerian::Keplerian::specific_orbital_energy) for
+/// orbits.
+#[must_use]
+pub fn specific_orbital_energy(dynamic: &dyn Dynamic, parent_mass: Float, time: Float) -> Float {
+    let speed = dynamic.get_velocity(time).magnitude();
+    let distance = dynamic.get_offset(time).magnitude();
+    speed * speed / 2.0 - parent_mass * GRAVITATIONAL_CONSTANT / distance
+}
+
+/// Returns the magnitude of the [specific relative angular
+/// momentum](https://en.wikipedia.org/wiki/Specific_angular_momentum) in light seconds² per hour of
+/// any dynamic at the given time.
+///
+/// The velocity comes from [`Dynamic::get_velocity`], which may be estimated, prefer
+/// [`Keplerian::specific_angular_momentum`](keplerian::Keplerian::specific_angular_momentum) for
+/// orbits.
+#[must_use]
+pub fn specific_angular_momentum(dynamic: &dyn Dynamic, time: Float) -> Float {
+    dynamic
+        .get_offset(time)
+        .cross(&dynamic.get_velocity(time))
+        .magnitude()
+}
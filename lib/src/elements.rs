@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use crate::{
+    body::Arc,
+    dynamic::keplerian::{Keplerian, OrbitalElements},
+};
+
+/// Orbital elements of one body in a tree, see [`orbital_elements`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElementRow {
+    /// ID of the body, see [`Body::get_id`](crate::body::Body::get_id)
+    pub id: Vec<usize>,
+    /// Name of the body
+    pub name: std::sync::Arc<str>,
+    /// Elements of the body's orbit around its parent
+    pub elements: OrbitalElements,
+}
+
+/// Returns the orbital elements of every body in the tree that follows a [`Keplerian`] orbit, in
+/// order of their IDs. Bodies with other dynamics are left out, but their children are not.
+#[must_use]
+pub fn orbital_elements(root: &Arc) -> Vec<ElementRow> {
+    let mut result = Vec::new();
+    let mut stack = vec![(Vec::new(), root.clone())];
+    while let Some((id, body)) = stack.pop() {
+        let Ok(body) = body.read() else {
+            continue;
+        };
+        // Reversed so children come off the stack in order
+        for (index, child) in body.get_children().iter().enumerate().rev() {
+            let mut child_id = id.clone();
+            child_id.push(index);
+            stack.push((child_id, child.clone()));
+        }
+
+        if let Some(keplerian) = body.get_dynamic().as_any().downcast_ref::<Keplerian>() {
+            result.push(ElementRow {
+                id,
+                name: body.get_name(),
+                elements: keplerian.elements(),
+            });
+        }
+    }
+    result
+}
+
+/// Writes rows from [`orbital_elements`] as CSV with a header, angles in degrees, distances in
+/// light seconds, and periods in hours.
+///
+/// # Errors
+/// Returns any error from writing to `writer`
+pub fn write_csv<W: Write>(rows: &[ElementRow], mut writer: W) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "id,name,eccentricity,semi_major_axis,inclination,longitude_of_ascending_node,\
+         argument_of_periapsis,mean_anomaly_at_epoch,period"
+    )?;
+    for row in rows {
+        let id = row
+            .id
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("-");
+        let elements = &row.elements;
+        writeln!(
+            writer,
+            "{id},{},{},{},{},{},{},{},{}",
+            escape(&row.name, ','),
+            elements.eccentricity,
+            elements.semi_major_axis,
+            elements.inclination.to_degrees(),
+            elements.longitude_of_ascending_node.to_degrees(),
+            elements.argument_of_periapsis.to_degrees(),
+            elements.mean_anomaly_at_epoch.to_degrees(),
+            elements.period
+        )?;
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains the delimiter, a quote, or a line break
+pub(crate) fn escape(field: &str, delimiter: char) -> std::borrow::Cow<'_, str> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+
+    #[test]
+    #[allow(clippy::excessive_precision)] // Tests should pass for f64 builds as well
+    fn earth_elements_from_solar_system() {
+        let root: Arc =
+            serde_json::from_str(include_str!("../../assets/solar-system.json")).unwrap();
+        Body::hydrate_all(&root, &None);
+
+        let rows = orbital_elements(&root);
+        let earth = rows.iter().find(|row| &*row.name == "Earth").unwrap();
+
+        assert_eq!(earth.id, [2]);
+        assert_float_relative_eq!(earth.elements.eccentricity, 0.016_710_22, 1e-5);
+        assert_float_relative_eq!(earth.elements.semi_major_axis, 499.000_054_89, 1e-5);
+        assert_float_relative_eq!(earth.elements.period, 8_766.144, 1e-5);
+        assert_float_absolute_eq!(earth.elements.inclination, 8.726_646e-7, 1e-8);
+        assert!(rows
+            .iter()
+            .any(|row| &*row.name == "Luna" && row.id == [2, 0]));
+        assert!(rows.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        let mut csv = Vec::new();
+        write_csv(&rows, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), rows.len() + 1);
+        assert!(csv
+            .lines()
+            .any(|line| line.starts_with("2,Earth,0.01671022,")));
+    }
+}
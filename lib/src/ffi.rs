@@ -0,0 +1,491 @@
+//! A C compatible interface to the simulator, for calling it from other languages without going
+//! through WebAssembly.
+//!
+//! A C header generated with [cbindgen](https://github.com/mozilla/cbindgen) is in
+//! `lib/include/astrograph.h`, regenerate it with `cbindgen --config cbindgen.toml --output
+//! include/astrograph.h` from the `lib` directory. A shared or static library can be built with
+//! e.g. `cargo rustc -p astrograph --profile release-ffi --features ffi --crate-type cdylib`.
+//!
+//! Every function returns an [`ErrorCode`] and writes its results through pointers supplied by
+//! the caller. Panics are caught and reported as [`ErrorCode::Panic`]. The `release-ffi` profile
+//! unwinds on panic so they can be caught, unlike the workspace `release` profile which aborts.
+
+use std::{
+    collections::HashMap,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use coordinates::prelude::Spherical;
+
+use crate::{
+    body::{self, observatory::Observatory, Body},
+    consts::float,
+    json, Float,
+};
+
+/// Result of a call through the C interface
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was null
+    NullPointer = 1,
+    /// A string was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The JSON was invalid or did not describe a body
+    Parse = 3,
+    /// The JSON was nested too deeply to be read
+    TooDeep = 4,
+    /// No body had the given name
+    BodyNotFound = 5,
+    /// The caller's buffer was too small, and results were truncated
+    BufferTooSmall = 6,
+    /// The library panicked, which is a bug
+    Panic = 7,
+}
+
+impl From<&json::Error> for ErrorCode {
+    fn from(value: &json::Error) -> Self {
+        match value {
+            // Files aren't read through the C interface
+            json::Error::Parse(_) | json::Error::Read(_) => Self::Parse,
+            json::Error::UnknownBody { .. } => Self::BodyNotFound,
+            json::Error::TooDeep { .. } => Self::TooDeep,
+        }
+    }
+}
+
+/// A body that was observed, as written to the caller's buffer by [`astrograph_observe`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Observation {
+    /// Index of the body, its name can be read with [`astrograph_body_name`]
+    pub name_index: usize,
+    /// Compass bearing in radians, from north clockwise through east, between 0 and 2π
+    pub azimuth: f64,
+    /// Angle from the zenith in radians
+    pub polar: f64,
+    /// Distance to the body in light seconds
+    pub distance: f64,
+}
+
+/// A tree of bodies loaded from JSON
+#[derive(Debug)]
+pub struct Universe {
+    /// State shared with any observatories in this universe, so they outlive it if freed first
+    inner: Arc<UniverseInner>,
+}
+
+/// The bodies of a [`Universe`] and their names
+#[derive(Debug)]
+struct UniverseInner {
+    /// Root of the tree, which keeps every body alive
+    root: body::Arc,
+    /// Null terminated names of every body, in the order of their name indexes
+    names: Vec<CString>,
+    /// Name index of each body, keyed by the address of the body
+    indexes: HashMap<usize, usize>,
+}
+
+/// An observatory on a body in a [`Universe`]
+#[derive(Debug)]
+pub struct ObservatoryHandle {
+    /// The observatory itself
+    observatory: Observatory,
+    /// The universe, to find the name indexes of observed bodies
+    universe: Arc<UniverseInner>,
+}
+
+/// Runs a function, converting any panics into [`ErrorCode::Panic`]
+fn guard<F: FnOnce() -> ErrorCode>(f: F) -> ErrorCode {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(ErrorCode::Panic)
+}
+
+/// Returns the key of a body in [`UniverseInner::indexes`]
+fn address(body: &body::Arc) -> usize {
+    Arc::as_ptr(body) as usize
+}
+
+/// Reads a tree of bodies from a JSON buffer (not null terminated), and writes a handle to it
+/// to `universe_out`. The handle must be freed with [`astrograph_universe_free`].
+///
+/// # Safety
+/// `json` must point to `json_length` readable bytes, and `universe_out` must be a valid pointer
+/// to write to.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_universe_from_json(
+    json: *const u8,
+    json_length: usize,
+    universe_out: *mut *mut Universe,
+) -> ErrorCode {
+    guard(|| {
+        if json.is_null() || universe_out.is_null() {
+            return ErrorCode::NullPointer;
+        }
+        // SAFETY: the caller guarantees the buffer is valid
+        let bytes = unsafe { std::slice::from_raw_parts(json, json_length) };
+        let Ok(json) = std::str::from_utf8(bytes) else {
+            return ErrorCode::InvalidUtf8;
+        };
+        let root: body::Arc = match json::from_str::<Body>(json) {
+            Ok(root) => root.into(),
+            Err(e) => return ErrorCode::from(&e),
+        };
+
+        let mut names = Vec::new();
+        let mut indexes = HashMap::new();
+        let mut stack = vec![root.clone()];
+        while let Some(current) = stack.pop() {
+            if let Ok(b) = current.read() {
+                indexes.insert(address(&current), names.len());
+                // Names can't contain null bytes when they come from JSON strings
+                names.push(CString::new(b.get_name().as_bytes()).unwrap_or_default());
+                stack.extend(b.get_children().iter().rev().cloned());
+            }
+        }
+
+        let universe = Universe {
+            inner: Arc::new(UniverseInner {
+                root,
+                names,
+                indexes,
+            }),
+        };
+        // SAFETY: the caller guarantees the pointer is valid
+        unsafe { *universe_out = Box::into_raw(Box::new(universe)) };
+        ErrorCode::Ok
+    })
+}
+
+/// Frees a universe. Observatories in it remain valid until they are freed.
+///
+/// # Safety
+/// `universe` must be null or a handle from [`astrograph_universe_from_json`] that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_universe_free(universe: *mut Universe) {
+    if !universe.is_null() {
+        // SAFETY: the caller guarantees the handle came from Box::into_raw
+        drop(unsafe { Box::from_raw(universe) });
+    }
+}
+
+/// Returns the number of bodies in a universe through `count_out`, name indexes are less than
+/// this.
+///
+/// # Safety
+/// `universe` must be a live handle and `count_out` a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_body_count(
+    universe: *const Universe,
+    count_out: *mut usize,
+) -> ErrorCode {
+    guard(|| {
+        // SAFETY: the caller guarantees the pointers are valid
+        let (Some(universe), Some(count_out)) =
+            (unsafe { universe.as_ref() }, unsafe { count_out.as_mut() })
+        else {
+            return ErrorCode::NullPointer;
+        };
+        *count_out = universe.inner.names.len();
+        ErrorCode::Ok
+    })
+}
+
+/// Returns a pointer to the null terminated name of a body through `name_out`. The name is owned
+/// by the universe and is valid until it is freed.
+///
+/// # Safety
+/// `universe` must be a live handle and `name_out` a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_body_name(
+    universe: *const Universe,
+    name_index: usize,
+    name_out: *mut *const c_char,
+) -> ErrorCode {
+    guard(|| {
+        // SAFETY: the caller guarantees the pointers are valid
+        let (Some(universe), false) = (unsafe { universe.as_ref() }, name_out.is_null()) else {
+            return ErrorCode::NullPointer;
+        };
+        let Some(name) = universe.inner.names.get(name_index) else {
+            return ErrorCode::BodyNotFound;
+        };
+        // SAFETY: checked for null above
+        unsafe { *name_out = name.as_ptr() };
+        ErrorCode::Ok
+    })
+}
+
+/// Creates an observatory at a latitude and longitude (in degrees) on the body with the given
+/// null terminated name, writing a handle to `observatory_out`. The handle must be freed with
+/// [`astrograph_observatory_free`].
+///
+/// # Safety
+/// `universe` must be a live handle, `body_name` a null terminated string, and `observatory_out`
+/// a valid pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_observatory_new(
+    universe: *const Universe,
+    body_name: *const c_char,
+    latitude: f64,
+    longitude: f64,
+    observatory_out: *mut *mut ObservatoryHandle,
+) -> ErrorCode {
+    guard(|| {
+        // SAFETY: the caller guarantees the pointer is valid
+        let Some(universe) = (unsafe { universe.as_ref() }) else {
+            return ErrorCode::NullPointer;
+        };
+        if body_name.is_null() || observatory_out.is_null() {
+            return ErrorCode::NullPointer;
+        }
+        // SAFETY: the caller guarantees the string is null terminated
+        let Ok(body_name) = unsafe { CStr::from_ptr(body_name) }.to_str() else {
+            return ErrorCode::InvalidUtf8;
+        };
+
+        let mut body = None;
+        let mut stack = vec![universe.inner.root.clone()];
+        while let Some(current) = stack.pop() {
+            if let Ok(b) = current.read() {
+                if &*b.get_name() == body_name {
+                    body = Some(current.clone());
+                    break;
+                }
+                stack.extend(b.get_children().iter().cloned());
+            }
+        }
+        let Some(body) = body else {
+            return ErrorCode::BodyNotFound;
+        };
+
+        #[allow(clippy::cast_possible_truncation)] // Precision is chosen at compile time
+        let location = Spherical {
+            radius: 1.0,
+            polar_angle: (90.0 - latitude).to_radians() as Float,
+            azimuthal_angle: longitude.to_radians() as Float,
+        };
+        let observatory = Observatory::new(
+            location,
+            body,
+            Ok(format!("{body_name}@{latitude:.2}N{longitude:.2}E")),
+            vec![],
+        );
+
+        let handle = ObservatoryHandle {
+            observatory,
+            universe: universe.inner.clone(),
+        };
+        // SAFETY: checked for null above
+        unsafe { *observatory_out = Box::into_raw(Box::new(handle)) };
+        ErrorCode::Ok
+    })
+}
+
+/// Frees an observatory.
+///
+/// # Safety
+/// `observatory` must be null or a handle from [`astrograph_observatory_new`] that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_observatory_free(observatory: *mut ObservatoryHandle) {
+    if !observatory.is_null() {
+        // SAFETY: the caller guarantees the handle came from Box::into_raw
+        drop(unsafe { Box::from_raw(observatory) });
+    }
+}
+
+/// Observes the sky from an observatory at a time in hours, writing up to `capacity` bodies above
+/// the horizon to `observations` and the total number visible to `count_out`. If there were more
+/// than `capacity` bodies returns [`ErrorCode::BufferTooSmall`], call again with a buffer of at
+/// least `count_out` to get every body.
+///
+/// # Safety
+/// `observatory` must be a live handle, `observations` must point to `capacity` writable
+/// [`Observation`]s (it may be null if `capacity` is zero), and `count_out` must be a valid
+/// pointer to write to.
+#[no_mangle]
+pub unsafe extern "C" fn astrograph_observe(
+    observatory: *const ObservatoryHandle,
+    time: f64,
+    observations: *mut Observation,
+    capacity: usize,
+    count_out: *mut usize,
+) -> ErrorCode {
+    guard(|| {
+        // SAFETY: the caller guarantees the pointers are valid
+        let (Some(handle), Some(count_out)) = (unsafe { observatory.as_ref() }, unsafe {
+            count_out.as_mut()
+        }) else {
+            return ErrorCode::NullPointer;
+        };
+        if observations.is_null() && capacity > 0 {
+            return ErrorCode::NullPointer;
+        }
+
+        #[allow(clippy::cast_possible_truncation)] // Precision is chosen at compile time
+        let observed = handle.observatory.observe(time as Float);
+        *count_out = observed.len();
+
+        for (i, (body, location)) in observed.iter().take(capacity).enumerate() {
+            #[allow(clippy::useless_conversion)] // Necessary for different length floats
+            let observation = Observation {
+                name_index: handle
+                    .universe
+                    .indexes
+                    .get(&address(body))
+                    .copied()
+                    .unwrap_or(usize::MAX),
+                // Local coordinates measure from +x, which points south
+                azimuth: (location.azimuthal_angle + float::PI)
+                    .rem_euclid(float::TAU)
+                    .into(),
+                polar: location.polar_angle.into(),
+                distance: location.radius.into(),
+            };
+            // SAFETY: the caller guarantees there are `capacity` elements to write to
+            unsafe { observations.add(i).write(observation) };
+        }
+
+        if observed.len() > capacity {
+            ErrorCode::BufferTooSmall
+        } else {
+            ErrorCode::Ok
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use coordinates::prelude::Vector3;
+
+    use super::*;
+
+    /// Loads the solar system through the C interface
+    fn load_solar_system() -> *mut Universe {
+        let json = include_str!("../../assets/solar-system.json");
+        let mut universe = ptr::null_mut();
+        let code =
+            unsafe { astrograph_universe_from_json(json.as_ptr(), json.len(), &mut universe) };
+        assert_eq!(code, ErrorCode::Ok);
+        assert!(!universe.is_null());
+        universe
+    }
+
+    /// Reads the name of a body through the C interface
+    fn body_name(universe: *const Universe, index: usize) -> String {
+        let mut name = ptr::null();
+        let code = unsafe { astrograph_body_name(universe, index, &mut name) };
+        assert_eq!(code, ErrorCode::Ok);
+        unsafe { CStr::from_ptr(name) }.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    #[allow(clippy::useless_conversion)] // Necessary for testing with different length floats
+    fn observe_round_trip() {
+        let universe = load_solar_system();
+
+        let mut count = 0;
+        assert_eq!(
+            unsafe { astrograph_body_count(universe, &mut count) },
+            ErrorCode::Ok
+        );
+        assert_eq!(count, 10);
+        assert_eq!(body_name(universe, 0), "Sol");
+
+        let mut observatory = ptr::null_mut();
+        let code = unsafe {
+            astrograph_observatory_new(universe, c"Earth".as_ptr(), 51.48, 0.0, &mut observatory)
+        };
+        assert_eq!(code, ErrorCode::Ok);
+
+        // Ask how many bodies there are, then get all of them
+        let mut visible = 0;
+        let code =
+            unsafe { astrograph_observe(observatory, 12.0, ptr::null_mut(), 0, &mut visible) };
+        assert!(visible > 0);
+        assert_eq!(code, ErrorCode::BufferTooSmall);
+
+        let mut observations = vec![Observation::default(); visible];
+        let code = unsafe {
+            astrograph_observe(
+                observatory,
+                12.0,
+                observations.as_mut_ptr(),
+                observations.len(),
+                &mut visible,
+            )
+        };
+        assert_eq!(code, ErrorCode::Ok);
+        assert_eq!(visible, observations.len());
+
+        // Matches observing directly
+        let expected = unsafe { &*observatory }.observatory.observe(12.0);
+        for (observation, (body, location)) in observations.iter().zip(&expected) {
+            assert_eq!(
+                body_name(universe, observation.name_index),
+                &*body.read().unwrap().get_name()
+            );
+            assert_eq!(observation.polar, f64::from(location.polar_angle));
+            let bearing = Vector3::from(*location);
+            assert_float_absolute_eq!(
+                observation.azimuth,
+                f64::from(Float::atan2(-bearing.y, -bearing.x).rem_euclid(float::TAU)),
+                1e-5
+            );
+            assert_eq!(observation.distance, f64::from(location.radius));
+            assert!(observation.polar <= std::f64::consts::FRAC_PI_2);
+        }
+
+        // The observatory keeps the universe alive
+        unsafe { astrograph_universe_free(universe) };
+        assert_eq!(
+            unsafe { astrograph_observe(observatory, 13.0, ptr::null_mut(), 0, &mut visible) },
+            ErrorCode::BufferTooSmall
+        );
+        unsafe { astrograph_observatory_free(observatory) };
+    }
+
+    #[test]
+    fn errors() {
+        let mut universe = ptr::null_mut();
+        let invalid = b"{\"dynamic\": 4}";
+        assert_eq!(
+            unsafe {
+                astrograph_universe_from_json(invalid.as_ptr(), invalid.len(), &mut universe)
+            },
+            ErrorCode::Parse
+        );
+        assert_eq!(
+            unsafe { astrograph_universe_from_json(ptr::null(), 0, &mut universe) },
+            ErrorCode::NullPointer
+        );
+
+        let universe = load_solar_system();
+        let mut observatory = ptr::null_mut();
+        assert_eq!(
+            unsafe {
+                astrograph_observatory_new(universe, c"Pluto".as_ptr(), 0.0, 0.0, &mut observatory)
+            },
+            ErrorCode::BodyNotFound
+        );
+        let mut name = ptr::null();
+        assert_eq!(
+            unsafe { astrograph_body_name(universe, 1_000, &mut name) },
+            ErrorCode::BodyNotFound
+        );
+        unsafe { astrograph_universe_free(universe) };
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        assert_eq!(guard(|| panic!("bug in the library")), ErrorCode::Panic);
+        assert_eq!(guard(|| ErrorCode::Ok), ErrorCode::Ok);
+    }
+}
@@ -0,0 +1,80 @@
+use super::{
+    au_to_ls, body, earth_masses_to_jupiter_masses, earth_radii_to_ls, keplerian, random_angle,
+    Arc, Float, MainSequenceStar, Planet,
+};
+
+/// A belt of asteroids on near circular orbits in a wide gap between two planets, like the main
+/// belt between Mars and Jupiter
+#[derive(Debug, Clone)]
+pub(super) struct Belt {
+    /// Semi-major axis of the innermost orbits in the belt in ls
+    inner: Float,
+    /// Semi-major axis of the outermost orbits in the belt in ls
+    outer: Float,
+    /// Number of asteroids in the belt
+    count: usize,
+}
+
+impl Belt {
+    /// Asteroids keep at least this many times further from the planet inside the belt than the
+    /// planet is from the star, and the same fraction closer than the planet outside it
+    const CLEARANCE: Float = 1.15;
+
+    /// Narrowest a belt can be in AU, gaps narrower than this are left empty
+    const MIN_WIDTH_AU: Float = 1.0;
+
+    /// Returns a belt in every gap between neighbouring planets wide enough for one, with
+    /// `density` asteroids per AU of its width. `planets` must be sorted by their distance from
+    /// the star.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation
+    )]
+    pub(super) fn in_gaps(planets: &[Planet], density: Float) -> Vec<Self> {
+        if density <= 0.0 {
+            return vec![];
+        }
+        planets
+            .windows(2)
+            .filter_map(|pair| {
+                let inner = pair[0].semi_major_axis * Self::CLEARANCE;
+                let outer = pair[1].semi_major_axis / Self::CLEARANCE;
+                let width_au = (outer - inner) / au_to_ls(1.0);
+                let count = (density * width_au).round() as usize;
+                (width_au >= Self::MIN_WIDTH_AU && count > 0).then_some(Self {
+                    inner,
+                    outer,
+                    count,
+                })
+            })
+            .collect()
+    }
+
+    /// Adds the asteroids of the belt to `parent`, orbiting close to the plane of its planets
+    pub(super) fn to_bodies<G: rand::Rng>(
+        &self,
+        rng: &mut G,
+        parent_star: &MainSequenceStar,
+        parent: &Arc,
+    ) {
+        for _ in 0..self.count {
+            let inclination =
+                parent_star.north_pole.polar_angle + rng.gen_range(-5.0 as Float..5.0).to_radians();
+            let dynamic = keplerian::Keplerian::new(
+                rng.gen_range(0.0..0.05),
+                rng.gen_range(self.inner..self.outer),
+                inclination,
+                parent_star.north_pole.azimuthal_angle + crate::consts::float::FRAC_PI_2,
+                random_angle(rng),
+                random_angle(rng),
+                parent_star.mass,
+            );
+            let b = body::Body::new(Some(parent.clone()), dynamic);
+            let mut asteroid = b.write().unwrap();
+            // Up to the size of Ceres, small enough to be drawn as the smallest dot
+            asteroid.mass = Some(earth_masses_to_jupiter_masses(rng.gen_range(1e-9..1.6e-4)));
+            asteroid.radius = Some(earth_radii_to_ls(rng.gen_range(0.000_1..0.075)));
+        }
+    }
+}
@@ -2,20 +2,25 @@ use std::ops::Range;
 
 use coordinates::prelude::{Cylindrical, Spherical, ThreeDimensionalConsts, Vector3};
 use derive_builder::Builder;
+use rand::{Rng, SeedableRng};
+use tracing::{debug_span, field, trace_span};
 
 use crate::{
-    body::{self, rotating::Rotating, Arc, Body},
+    body::{self, observatory::Observatory, rotating::Rotating, Arc, Body},
     consts::float,
     dynamic::{self, fixed::Fixed, keplerian},
     Float,
 };
 
 use super::Generator;
+use belt::Belt;
 use moon::Moon;
 use planet::Planet;
 use planet::PlanetType;
 use star::MainSequenceStar;
 
+/// Logic for generating asteroid belts between planets
+mod belt;
 /// Logic for generating bodies that orbit planets
 mod moon;
 /// Logic for generating bodies that orbit stars
@@ -24,31 +29,168 @@ mod planet;
 /// appear fixed over short observational periods <100 years)
 mod star;
 
-#[derive(Clone, Copy, Debug, Builder, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Builder, PartialEq, PartialOrd)]
 pub struct Artifexian {
     /// Number of stars to generate
     #[builder(default = 1_000_000)]
     star_count: usize,
+    /// Give every star, planet, and moon a plausible rotation, not just habitable planets and
+    /// tidally locked moons. Off by default since it adds to the size of the generated universe
+    #[builder(default = false)]
+    rotate_all_bodies: bool,
+    /// Most moons given to any one planet, so planets with very large radii don't get thousands
+    /// of moons
+    #[builder(default = 100)]
+    max_moons_per_planet: usize,
+    /// Age of the galaxy in billions of years. If given, each star forms at a random time in the
+    /// first half of the galaxy's life, and stars that have used up their hydrogen since become
+    /// giants or white dwarfs with no habitable planets. Every star is tagged with its
+    /// [stage](body::stage::StellarStage) and given a radius and luminosity to match. Otherwise
+    /// every star is on the main sequence and isn't tagged.
+    #[builder(default, setter(strip_option))]
+    galaxy_age_gyr: Option<Float>,
+    /// Fastest a star can move through the galaxy in km/s. If given, each star moves in a
+    /// random direction at up to this speed with a [`Linear`](dynamic::linear::Linear) dynamic,
+    /// so over long runs nearby stars drift against distant ones. Otherwise every star is
+    /// [fixed](Fixed) in place.
+    #[builder(default, setter(strip_option))]
+    max_star_speed_km_s: Option<Float>,
+    /// Number of spiral arms, evenly spaced around the galaxy, e.g. 4 for a four armed galaxy.
+    /// Must be at least one
+    #[builder(default = 2)]
+    arm_count: u8,
+    /// Give every star its own random number generator, seeded by [`star_seed`] from the first
+    /// `u64` drawn from the generator given and the star's index, so one star's planets and moons
+    /// can be made again on their own with [`Artifexian::generate_star`]. Off by default so
+    /// seeds give the same universes they always have.
+    #[builder(default = false)]
+    derive_star_seeds: bool,
+    /// Asteroids per AU of width in the belts of stars with planets. Every gap between planets
+    /// wide enough gets a belt of small bodies on near circular orbits, like the main belt
+    /// between Mars and Jupiter. 0 by default, for no belts.
+    #[builder(default = 0.0)]
+    belt_density: Float,
 }
 
-impl Generator for Artifexian {
-    /// Generates bodies based on the star count and random number generator. Generated observatories are on "habitable" worlds.
-    fn generate<G: rand::Rng>(
+/// The shape of the galaxy an [`Artifexian`] generator makes, see
+/// [`Generator::generate_with_params`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArtifexianParams {
+    /// Number of stars to generate
+    pub star_count: usize,
+    /// Number of spiral arms, evenly spaced around the galaxy, at least one
+    pub arm_count: u8,
+    /// Distance of the rim of the galaxy from its center, in light seconds
+    pub galaxy_width: Float,
+    /// Fraction of stars that may have planets, from 0 to 1. Every `1 / habitable_fraction`
+    /// (rounded) stars, starting with the first, one gets a chance to be habitable.
+    pub habitable_fraction: Float,
+}
+
+impl Default for ArtifexianParams {
+    /// A galaxy the size of the milky way, with two arms and a million stars
+    fn default() -> Self {
+        Self {
+            star_count: 1_000_000,
+            arm_count: 2,
+            galaxy_width: WIDTH_OF_MILKY_WAY,
+            habitable_fraction: 0.01,
+        }
+    }
+}
+
+impl ArtifexianParams {
+    /// Returns how many stars there are for each one that gets a chance to be habitable, or
+    /// `None` if none do
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn habitable_every(&self) -> Option<usize> {
+        (self.habitable_fraction > 0.0).then(|| (1.0 / self.habitable_fraction).round() as usize)
+    }
+}
+
+/// Where stars are placed in the galaxy, made from [`ArtifexianParams`]
+#[derive(Clone, Copy, Debug)]
+struct Galaxy {
+    /// Distribution of stars' distances from the center, as a fraction of the width, see
+    /// [`galaxy_distribution`]
+    distribution: rand_distr::Pert<Float>,
+    /// Distance of the rim from the center, in light seconds
+    width: Float,
+    /// Number of spiral arms
+    arm_count: u8,
+}
+
+impl Galaxy {
+    /// Returns the galaxy described by `params`, or an error if they are out of range
+    fn new(params: &ArtifexianParams) -> Result<Self, super::Error> {
+        if params.arm_count == 0 {
+            return Err(super::Error::Params(
+                "a galaxy needs at least one arm".into(),
+            ));
+        }
+        if !(params.galaxy_width.is_finite() && params.galaxy_width > 0.0) {
+            return Err(super::Error::Params(format!(
+                "galaxy width must be positive, got {}",
+                params.galaxy_width
+            )));
+        }
+        if !(0.0..=1.0).contains(&params.habitable_fraction) {
+            return Err(super::Error::Params(format!(
+                "habitable fraction must be from 0 to 1, got {}",
+                params.habitable_fraction
+            )));
+        }
+        Ok(Self {
+            distribution: galaxy_distribution()?,
+            width: params.galaxy_width,
+            arm_count: params.arm_count,
+        })
+    }
+}
+
+impl Artifexian {
+    /// Makes star `index` of a galaxy generated with [derived seeds](ArtifexianBuilder::derive_star_seeds)
+    /// on its own, given the seed [`star_seed`] derives for it. The star is the only child of the
+    /// returned root, with the same planets and moons it has in the whole galaxy, along with the
+    /// observatory on its habitable planet if it has one.
+    ///
+    /// # Errors
+    ///
+    /// If the parameters can't be used, see [`Generator::generate_with_params`]
+    pub fn generate_star(
         &self,
-        rng: &mut G,
-    ) -> (crate::body::Arc, Vec<crate::body::observatory::Observatory>) {
+        params: &ArtifexianParams,
+        index: usize,
+        seed: u64,
+    ) -> Result<(Arc, Option<Observatory>), super::Error> {
+        let galaxy = Galaxy::new(params)?;
         let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(seed);
+        let observer = self.add_star(&mut rng, &galaxy, params.habitable_every(), index, &root);
+        Ok((root, observer))
+    }
 
-        let mut observatories = Vec::with_capacity(self.star_count / 100);
-
-        for i in 0..self.star_count {
-            // At least 1% of stars are habitable
-            let star = if i % 100 != 0 {
-                // Skip planet gen to save memory
-                MainSequenceStar::new(rng)
-            } else {
+    /// Adds star `index` of the galaxy to `root`, with planets if it is one of every
+    /// `habitable_every` stars that may be habitable, returning the observatory on its habitable
+    /// planet if it has one
+    fn add_star<G: Rng>(
+        &self,
+        rng: &mut G,
+        galaxy: &Galaxy,
+        habitable_every: Option<usize>,
+        index: usize,
+        root: &Arc,
+    ) -> Option<Observatory> {
+        let star_span = trace_span!("star", index, planets = field::Empty).entered();
+        // Some stars, 1% by default, may be habitable
+        let star = if habitable_every.is_none_or(|every| !index.is_multiple_of(every)) {
+            // Skip planet gen to save memory
+            MainSequenceStar::new(rng, self.galaxy_age_gyr)
+        } else {
+            let star = MainSequenceStar::new_habitable(rng, self.galaxy_age_gyr);
+            if star.is_habitable {
                 // Habitable star, so generate planets
-                let mut star = MainSequenceStar::new_habitable(rng);
+                let mut star = star;
                 let first_gas_giant = Planet::new_from_frost_line(rng, &star);
                 let mut planets = vec![first_gas_giant.clone()];
 
@@ -99,31 +241,124 @@ impl Generator for Artifexian {
                     }
                 }
 
-                let mut filtered_planets = Vec::with_capacity(planets.len());
+                star.planets = filter_planets(planets);
+                star
+            } else {
+                // Evolved, so any planets it had would no longer be habitable
+                star
+            }
+        };
+
+        star_span.record("planets", star.planets.len());
+        let (star_body, observer) = star.to_body(
+            rng,
+            root,
+            galaxy,
+            self.rotate_all_bodies,
+            self.max_moons_per_planet,
+            self.max_star_speed_km_s,
+        );
+        for belt in Belt::in_gaps(&star.planets, self.belt_density) {
+            belt.to_bodies(rng, &star, &star_body);
+        }
+        observer
+    }
+}
 
-                planets.sort_by_key(|p| p.semi_major_axis.to_bits());
+impl Generator for Artifexian {
+    type Params = ArtifexianParams;
+
+    /// Returns a galaxy the size of the milky way with 1% of stars possibly habitable, with the
+    /// generator's star count and number of arms
+    fn params(&self) -> ArtifexianParams {
+        ArtifexianParams {
+            star_count: self.star_count,
+            arm_count: self.arm_count,
+            ..ArtifexianParams::default()
+        }
+    }
 
-                let mut previous_planet = &planets[0];
+    /// Generates bodies based on the parameters and random number generator. Generated observatories are on "habitable" worlds.
+    fn generate_with_params<G: rand::Rng>(
+        &self,
+        params: &ArtifexianParams,
+        rng: &mut G,
+    ) -> Result<(crate::body::Arc, Vec<crate::body::observatory::Observatory>), super::Error> {
+        let _span = debug_span!("generate", star_count = params.star_count).entered();
+        // Made once up front so bad parameters are found before any work is done
+        let galaxy = Galaxy::new(params)?;
+        let habitable_every = params.habitable_every();
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
 
-                filtered_planets.push(previous_planet.clone());
+        let mut observatories =
+            Vec::with_capacity(habitable_every.map_or(0, |every| params.star_count / every));
 
-                for i in planets.iter().skip(1) {
-                    if previous_planet.semi_major_axis < i.semi_major_axis - au_to_ls(0.15) {
-                        filtered_planets.push(i.clone());
-                        previous_planet = i;
-                    }
-                }
-
-                star.planets = filtered_planets;
-                star
+        let base_seed = self.derive_star_seeds.then(|| rng.gen::<u64>());
+        for index in 0..params.star_count {
+            let observer = if let Some(base_seed) = base_seed {
+                let mut star_rng =
+                    rand_xorshift::XorShiftRng::seed_from_u64(star_seed(base_seed, index));
+                self.add_star(&mut star_rng, &galaxy, habitable_every, index, &root)
+            } else {
+                self.add_star(rng, &galaxy, habitable_every, index, &root)
             };
+            observatories.extend(observer);
+        }
+        return Ok((root, observatories));
+    }
+}
 
-            if let (_, Some(observer)) = star.to_body(rng, &root) {
-                observatories.push(observer);
-            }
+/// Returns the seed of star `index`'s random number generator when a galaxy is generated with
+/// [derived seeds](ArtifexianBuilder::derive_star_seeds), mixing the index into `base_seed` with
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) so neighbouring stars get unrelated seeds
+#[must_use]
+pub fn star_seed(base_seed: u64, index: usize) -> u64 {
+    let mut z = base_seed.wrapping_add(
+        (index as u64)
+            .wrapping_add(1)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Distance of the rim of the milky way from its center, in light seconds
+const WIDTH_OF_MILKY_WAY: Float = 3e12;
+
+/// Returns the distribution stars are spread through the galaxy with, as a fraction of its
+/// [width](ArtifexianParams::galaxy_width) from its center, most often near the center
+fn galaxy_distribution() -> Result<rand_distr::Pert<Float>, rand_distr::PertError> {
+    rand_distr::Pert::new(-1.0, 1.0, 0.0)
+}
+
+/// Sorts planets by their distance from the star, dropping any within 0.15 AU of the planet
+/// before them.
+///
+/// Planets at the same distance are ordered so the same planets survive on every platform and
+/// run: habitable planets first, then heavier planets, then the order they were generated in.
+fn filter_planets(planets: Vec<Planet>) -> Vec<Planet> {
+    let mut planets: Vec<_> = planets.into_iter().enumerate().collect();
+    planets.sort_by(|(a_index, a), (b_index, b)| {
+        a.semi_major_axis
+            .total_cmp(&b.semi_major_axis)
+            .then_with(|| {
+                matches!(b.kind, PlanetType::Habitable)
+                    .cmp(&matches!(a.kind, PlanetType::Habitable))
+            })
+            .then_with(|| b.mass.total_cmp(&a.mass))
+            .then_with(|| a_index.cmp(b_index))
+    });
+
+    let mut filtered_planets: Vec<Planet> = Vec::with_capacity(planets.len());
+    for (_, planet) in planets {
+        if filtered_planets.last().is_none_or(|previous| {
+            previous.semi_major_axis < planet.semi_major_axis - au_to_ls(0.15)
+        }) {
+            filtered_planets.push(planet);
         }
-        return (root, observatories);
     }
+    filtered_planets
 }
 
 /// Convert Astronomical Units (AU) to Light Seconds (ls)
@@ -143,7 +378,7 @@ fn earth_masses_to_jupiter_masses(em: Float) -> Float {
 
 /// Convert Earth Radii to Light Seconds (ls)
 fn earth_radii_to_ls(er: Float) -> Float {
-    er * 0.021_251_398
+    crate::units::Length::EarthRadii.to_light_seconds(er)
 }
 
 /// Generate a random angle between 0 and Tau
@@ -151,6 +386,36 @@ fn random_angle<G: rand::Rng>(rng: &mut G) -> Float {
     rng.gen_range(0.0..float::TAU)
 }
 
+/// Generate a rotation with a period in the given range of hours, and an axis tilted up to
+/// `max_obliquity` degrees from `north_pole`, rotating retrograde with the given probability
+fn random_rotation<G: rand::Rng>(
+    rng: &mut G,
+    period: Range<Float>,
+    north_pole: Spherical<Float>,
+    max_obliquity: Float,
+    retrograde_probability: f64,
+) -> Rotating {
+    let period = rng.gen_range(period);
+    let tilt = quaternion::axis_angle(
+        [1.0, 0.0, 0.0],
+        rng.gen_range(0.0..=max_obliquity).to_radians()
+            + if rng.gen_bool(retrograde_probability) {
+                float::PI
+            } else {
+                0.0
+            },
+    );
+    let spin = quaternion::axis_angle([0.0, 0.0, 1.0], random_angle(rng));
+    let north_pole: Vector3<Float> = north_pole.into();
+    let to_pole = quaternion::rotation_from_to(Vector3::<Float>::UP.into(), north_pole.into());
+
+    let axis = quaternion::rotate_vector(
+        quaternion::mul(to_pole, quaternion::mul(spin, tilt)),
+        Vector3::<Float>::UP.into(),
+    );
+    Rotating::new(period, Vector3::from(axis).into())
+}
+
 #[cfg(test)]
 mod test {
     use rand::SeedableRng;
@@ -170,7 +435,7 @@ mod test {
             .star_count(1_000)
             .build()
             .unwrap();
-        let root = generator.generate(&mut rng);
+        let root = generator.generate(&mut rng).unwrap();
 
         println!("x\ty\tz");
         for p in &root.0.read().unwrap().children {
@@ -180,6 +445,466 @@ mod test {
 
         drop(root);
         let mut rng = rand::rngs::mock::StepRng::new(INC + (INC >> 8) + (INC >> 16), INC);
-        let _ = generator.generate(&mut rng);
+        let _ = generator.generate(&mut rng).unwrap();
+    }
+
+    #[test]
+    fn stars_placed_with_given_distribution() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // Every star half way to the rim, so they can't have made a distribution of their own
+        let halfway = Galaxy {
+            distribution: rand_distr::Pert::new(0.499, 0.501, 0.5).unwrap(),
+            width: WIDTH_OF_MILKY_WAY,
+            arm_count: 2,
+        };
+        for _ in 0..20 {
+            let (star, _) = MainSequenceStar::new(&mut rng, None)
+                .to_body(&mut rng, &root, &halfway, false, 100, None);
+            let distance = coordinates::traits::Magnitude::magnitude(
+                &star.read().unwrap().dynamic.get_offset(0.0),
+            );
+            assert_float_relative_eq!(distance, WIDTH_OF_MILKY_WAY / 2.0, 1e-2);
+        }
+
+        assert!(galaxy_distribution().is_ok());
+        let error: super::super::Error = rand_distr::Pert::new(1.0, -1.0, 0.0).unwrap_err().into();
+        assert!(error.to_string().starts_with("invalid distribution"));
+    }
+
+    #[test]
+    fn close_moons_tidally_locked() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, _) = ArtifexianBuilder::default()
+            .star_count(2)
+            .build()
+            .unwrap()
+            .generate(&mut rng)
+            .unwrap();
+
+        let moons: Vec<_> = root.read().unwrap().children[0]
+            .read()
+            .unwrap()
+            .children
+            .iter()
+            .flat_map(|planet| planet.read().unwrap().children.clone())
+            .collect();
+        let mut locked_moons = 0;
+        for moon in moons {
+            let moon = moon.read().unwrap();
+            let Some(rotation) = &moon.rotation else {
+                continue;
+            };
+            locked_moons += 1;
+            assert_float_relative_eq!(
+                rotation.get_sidereal_period(),
+                moon.dynamic.get_period().unwrap()
+            );
+        }
+        assert!(locked_moons > 0);
+    }
+
+    #[test]
+    fn generated_bodies_have_masses() {
+        /// Checks every body below `body` has a mass lighter than its parent's
+        fn check_masses(body: &Arc, count: &mut usize) {
+            let body = body.read().unwrap();
+            let mass = body.get_mass().unwrap();
+            for child in &body.children {
+                assert!(child.read().unwrap().get_mass().unwrap() < mass);
+                *count += 1;
+                check_masses(child, count);
+            }
+        }
+
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, _) = ArtifexianBuilder::default()
+            .star_count(5)
+            .build()
+            .unwrap()
+            .generate(&mut rng)
+            .unwrap();
+
+        let mut count = 0;
+        for star in &root.read().unwrap().children {
+            check_masses(star, &mut count);
+        }
+        // Planets and moons
+        assert!(count > 5);
+    }
+
+    #[test]
+    fn generated_with_params() {
+        let generator = ArtifexianBuilder::default()
+            .star_count(300)
+            .build()
+            .unwrap();
+        let generate = |params: &ArtifexianParams| {
+            let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+            generator.generate_with_params(params, &mut rng)
+        };
+
+        // The generator's own parameters are the defaults
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, observatories) = generator.generate(&mut rng).unwrap();
+        let (same_root, same_observatories) = generate(&generator.params()).unwrap();
+        assert_eq!(
+            serde_json::to_string(&root).unwrap(),
+            serde_json::to_string(&same_root).unwrap()
+        );
+        assert_eq!(observatories.len(), same_observatories.len());
+
+        // A small, dense, four armed galaxy with no planets
+        let params = ArtifexianParams {
+            star_count: 100,
+            arm_count: 4,
+            galaxy_width: 1e9,
+            habitable_fraction: 0.0,
+        };
+        let (root, observatories) = generate(&params).unwrap();
+        assert!(observatories.is_empty());
+        let root = root.read().unwrap();
+        assert_eq!(root.children.len(), 100);
+        for star in &root.children {
+            let star = star.read().unwrap();
+            assert!(star.children.is_empty());
+            let offset = star.dynamic.get_offset(0.0);
+            assert!(offset.x.hypot(offset.y) <= 1e9);
+        }
+
+        for params in [
+            ArtifexianParams {
+                arm_count: 0,
+                ..params
+            },
+            ArtifexianParams {
+                galaxy_width: -1.0,
+                ..params
+            },
+            ArtifexianParams {
+                habitable_fraction: 2.0,
+                ..params
+            },
+        ] {
+            let error = generate(&params).unwrap_err();
+            assert!(error.to_string().starts_with("invalid parameters"));
+        }
+    }
+
+    #[test]
+    fn arm_count_from_builder() {
+        let four_arms = ArtifexianBuilder::default()
+            .star_count(30)
+            .arm_count(4)
+            .build()
+            .unwrap();
+        assert_eq!(four_arms.params().arm_count, 4);
+        assert!(ArtifexianBuilder::default()
+            .arm_count(0)
+            .build()
+            .unwrap()
+            .generate(&mut rand_xorshift::XorShiftRng::seed_from_u64(1))
+            .is_err());
+
+        // Two arms is the default, so seeds give the same galaxy either way
+        let generate = |generator: Artifexian| {
+            let (root, _) = generator
+                .generate(&mut rand_xorshift::XorShiftRng::seed_from_u64(7))
+                .unwrap();
+            serde_json::to_string(&root).unwrap()
+        };
+        let two_arms = ArtifexianBuilder::default()
+            .star_count(30)
+            .arm_count(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            generate(two_arms),
+            generate(ArtifexianBuilder::default().star_count(30).build().unwrap())
+        );
+        assert_ne!(generate(four_arms), generate(two_arms));
+    }
+
+    #[test]
+    fn regenerate_star_from_derived_seed() {
+        let generator = ArtifexianBuilder::default()
+            .derive_star_seeds(true)
+            .rotate_all_bodies(true)
+            .build()
+            .unwrap();
+        let params = ArtifexianParams {
+            star_count: 20,
+            habitable_fraction: 1.0,
+            ..ArtifexianParams::default()
+        };
+        let (root, observatories) = generator
+            .generate_with_params(&params, &mut rand_xorshift::XorShiftRng::seed_from_u64(3))
+            .unwrap();
+        // The base seed is the first number drawn
+        let base_seed = rand_xorshift::XorShiftRng::seed_from_u64(3).gen::<u64>();
+
+        let root = root.read().unwrap();
+        let with_planets = root
+            .children
+            .iter()
+            .position(|star| !star.read().unwrap().children.is_empty())
+            .unwrap();
+        for index in [0, with_planets, params.star_count - 1] {
+            let (alone, observer) = generator
+                .generate_star(&params, index, star_seed(base_seed, index))
+                .unwrap();
+            let alone = alone.read().unwrap();
+            assert_eq!(alone.children.len(), 1);
+            assert_eq!(
+                serde_json::to_string(&*alone.children[0].read().unwrap()).unwrap(),
+                serde_json::to_string(&*root.children[index].read().unwrap()).unwrap()
+            );
+            if let Some(observer) = observer {
+                assert!(observatories
+                    .iter()
+                    .any(|o| o.surface_offset() == observer.surface_offset()));
+            }
+        }
+        assert_ne!(star_seed(base_seed, 0), star_seed(base_seed, 1));
+    }
+
+    #[test]
+    fn belts_between_planets() {
+        let params = ArtifexianParams {
+            star_count: 50,
+            habitable_fraction: 1.0,
+            ..ArtifexianParams::default()
+        };
+        let generate = |belt_density| {
+            ArtifexianBuilder::default()
+                .belt_density(belt_density)
+                .build()
+                .unwrap()
+                .generate_with_params(&params, &mut rand_xorshift::XorShiftRng::seed_from_u64(11))
+                .unwrap()
+                .0
+        };
+        let is_asteroid = |body: &Arc| {
+            body.read().unwrap().get_radius().is_some_and(|radius| {
+                radius < earth_radii_to_ls(0.08) && body.read().unwrap().children.is_empty()
+            })
+        };
+        let magnitude = |body: &Arc| {
+            coordinates::traits::Magnitude::magnitude(&body.read().unwrap().dynamic.get_offset(0.0))
+        };
+
+        let root = generate(10.0);
+        let mut asteroids = 0;
+        for star in &root.read().unwrap().children {
+            let star = star.read().unwrap();
+            let (belt, planets): (Vec<_>, Vec<_>) =
+                star.children.iter().partition(|b| is_asteroid(b));
+            asteroids += belt.len();
+            for asteroid in belt {
+                // Between the planets, allowing for their eccentricity
+                let distance = magnitude(asteroid);
+                assert!(planets.iter().any(|p| magnitude(p) < distance * 1.3));
+                assert!(planets.iter().any(|p| magnitude(p) > distance / 1.3));
+            }
+        }
+        assert!(asteroids > 0);
+
+        // No belts by default
+        let without = generate(0.0);
+        assert!(without.read().unwrap().children.iter().all(|star| !star
+            .read()
+            .unwrap()
+            .children
+            .iter()
+            .any(is_asteroid)));
+    }
+
+    #[test]
+    fn stars_drift_at_up_to_max_speed() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, _) = ArtifexianBuilder::default()
+            .star_count(50)
+            .max_star_speed_km_s(30.0)
+            .build()
+            .unwrap()
+            .generate(&mut rng)
+            .unwrap();
+
+        let max_speed = 30.0 / crate::units::KILOMETRES_PER_LIGHT_SECOND * 3600.0;
+        let century = 100.0 * 365.25 * 24.0;
+        for star in &root.read().unwrap().children {
+            let star = star.read().unwrap();
+            let moved = coordinates::traits::Magnitude::magnitude(
+                &(star.dynamic.get_offset(century) - star.dynamic.get_offset(0.0)),
+            );
+            assert!(moved > 0.0);
+            assert!(moved <= max_speed * century * 1.000_1);
+        }
+    }
+
+    #[test]
+    fn rotate_all_bodies() {
+        /// Returns the rotation of every body below `body` with its depth in the tree
+        fn rotations(body: &Arc, depth: u8, result: &mut Vec<(u8, bool)>) {
+            let body = body.read().unwrap();
+            result.push((depth, body.rotation.is_some()));
+            for child in &body.children {
+                rotations(child, depth + 1, result);
+            }
+        }
+
+        for rotate_all_bodies in [false, true] {
+            let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+            let (root, _) = ArtifexianBuilder::default()
+                .star_count(2)
+                .rotate_all_bodies(rotate_all_bodies)
+                .build()
+                .unwrap()
+                .generate(&mut rng)
+                .unwrap();
+
+            let mut result = Vec::new();
+            rotations(&root, 0, &mut result);
+            // Stars, planets, and moons
+            for depth in 1..=3 {
+                let rotating = result.iter().filter(|(d, _)| *d == depth);
+                assert!(rotating.clone().count() > 1);
+                if rotate_all_bodies {
+                    assert!(rotating.clone().all(|(_, rotating)| *rotating));
+                } else if depth == 1 {
+                    assert!(rotating.clone().all(|(_, rotating)| !*rotating));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn planets_at_same_distance_filtered_deterministically() {
+        let planet = |semi_major_axis, mass, kind| Planet {
+            semi_major_axis,
+            mass,
+            radius: 0.02,
+            kind,
+            north_pole: Spherical::new(1.0, 0.0, 0.0),
+        };
+        let describe = |planets: Vec<Planet>| -> Vec<_> {
+            planets
+                .into_iter()
+                .map(|p| (p.semi_major_axis, p.mass, format!("{:?}", p.kind)))
+                .collect()
+        };
+        let planets = [
+            planet(500.0, 1.0, PlanetType::Terestrial),
+            planet(500.0, 2.0, PlanetType::Terestrial),
+            planet(500.0, 0.5, PlanetType::Habitable),
+            planet(1000.0, 300.0, PlanetType::GasGiant),
+            planet(1010.0, 400.0, PlanetType::GasGiant),
+        ];
+
+        let forwards = describe(filter_planets(planets.to_vec()));
+        let backwards = describe(filter_planets(planets.iter().rev().cloned().collect()));
+
+        assert_eq!(forwards, backwards);
+        assert_eq!(
+            forwards,
+            [
+                (500.0, 0.5, "Habitable".to_owned()),
+                (1000.0, 300.0, "GasGiant".to_owned())
+            ]
+        );
+
+        // The same seed gives the same planets
+        let planets_of = || {
+            let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+            let (root, _) = ArtifexianBuilder::default()
+                .star_count(300)
+                .build()
+                .unwrap()
+                .generate(&mut rng)
+                .unwrap();
+            let stars = root.read().unwrap().children.clone();
+            stars
+                .iter()
+                .map(|star| {
+                    star.read()
+                        .unwrap()
+                        .children
+                        .iter()
+                        .map(|planet| {
+                            let planet = planet.read().unwrap();
+                            (planet.get_id(), planet.dynamic.get_offset(0.0))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let planets = planets_of();
+        assert!(planets.iter().filter(|planets| !planets.is_empty()).count() >= 3);
+        assert_eq!(planets, planets_of());
+    }
+
+    #[test]
+    fn moons_of_huge_gas_giant_capped() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let star = MainSequenceStar::new_habitable(&mut rng, None);
+        let giant = Planet {
+            semi_major_axis: au_to_ls(50.0),
+            mass: 1000.0,
+            // A thousand times Jupiter's radius
+            radius: 233.0,
+            kind: PlanetType::GasGiant,
+            north_pole: Spherical::new(1.0, 0.0, 0.0),
+        };
+
+        let uncapped = giant.generate_moons(&mut rng, &star, 1e6, usize::MAX).len();
+        assert!(uncapped > 100, "{uncapped}");
+        for cap in [0, 7, 100] {
+            assert_eq!(giant.generate_moons(&mut rng, &star, 1e6, cap).len(), cap);
+        }
+    }
+
+    #[test]
+    fn old_galaxy_has_evolved_stars() {
+        use crate::body::stage::StellarStage;
+
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        for _ in 0..2_000 {
+            let star = MainSequenceStar::new(&mut rng, Some(13.0));
+            if star.mass > solar_masses_to_jupiter_masses(1.5) {
+                assert_ne!(star.stage, Some(StellarStage::MainSequence), "{star:?}");
+            }
+        }
+        assert_eq!(MainSequenceStar::new(&mut rng, None).stage, None);
+
+        let (root, observatories) = ArtifexianBuilder::default()
+            .star_count(3_000)
+            .galaxy_age_gyr(13.0)
+            .build()
+            .unwrap()
+            .generate(&mut rng)
+            .unwrap();
+        let stage_of = |star: &Arc| star.read().unwrap().get_stellar_stage().unwrap();
+        let stars = root.read().unwrap().children.clone();
+        for stage in [
+            StellarStage::MainSequence,
+            StellarStage::Giant,
+            StellarStage::WhiteDwarf,
+        ] {
+            assert!(
+                stars.iter().any(|star| stage_of(star) == stage),
+                "{stage:?}"
+            );
+        }
+        assert!(!observatories.is_empty());
+        for observatory in &observatories {
+            let planet = observatory.body().read().unwrap();
+            let star = planet.parent.as_ref().unwrap().upgrade().unwrap();
+            assert_eq!(stage_of(&star), StellarStage::MainSequence);
+        }
+        // Habitable stars that evolved lost their planets
+        assert!(stars
+            .iter()
+            .filter(|star| stage_of(star) != StellarStage::MainSequence)
+            .all(|star| star.read().unwrap().get_children().is_empty()));
     }
 }
@@ -1,4 +1,7 @@
-use super::{dynamic, float, random_angle, Arc, Body, Float, Planet, PlanetType};
+use super::{
+    dynamic::{self, Dynamic},
+    float, random_angle, random_rotation, Arc, Body, Float, Planet, PlanetType, Rotating,
+};
 
 /// Type of moon
 #[derive(Debug, Clone)]
@@ -13,6 +16,7 @@ enum MoonType {
     MinorIcy,
 }
 
+/// A moon that orbits a planet
 #[derive(Debug, Clone)]
 pub(super) struct Moon {
     /// Radius of the moon in ls
@@ -29,6 +33,15 @@ impl Moon {
     /// Earth's moon's density in jupiter masses per cubic light second
     const LUNA_DENSITY: Float = 47.47;
 
+    /// Moons closer than this many radii of their planet are tidally locked, like Luna at about
+    /// 60 earth radii
+    const TIDAL_LOCK_RADII: Float = 100.0;
+
+    /// Returns the distance in ls from a planet that moons orbiting inside of are tidally locked
+    fn tidal_lock_radius(parent: &Planet) -> Float {
+        parent.radius * Self::TIDAL_LOCK_RADII
+    }
+
     /// # Returns
     /// None if all available orbits are already taken
     pub(super) fn new_moon<G: rand::Rng>(
@@ -118,12 +131,16 @@ impl Moon {
         })
     }
 
-    /// Generate "group a moons" for a gas giant
-    pub(super) fn new_group_a_moons<G: rand::Rng>(rng: &mut G, parent: &Planet) -> Vec<Self> {
+    /// Generate at most `max_moons` "group a moons" for a gas giant
+    pub(super) fn new_group_a_moons<G: rand::Rng>(
+        rng: &mut G,
+        parent: &Planet,
+        max_moons: usize,
+    ) -> Vec<Self> {
         let mut result = Vec::new();
 
         let mut semi_major_axis = (1.97 + rng.gen_range(-0.2..0.2)) * parent.radius;
-        while semi_major_axis - 2.0 * 0.01861 < 2.44 * parent.radius {
+        while semi_major_axis - 2.0 * 0.01861 < 2.44 * parent.radius && result.len() < max_moons {
             let radius: Float = rng.gen_range(20.0..200.0) * 3.336e-6;
             let mass = radius.powi(3) * float::FRAC_2_PI * 2.0 / 3.0
                 * Self::LUNA_DENSITY
@@ -142,13 +159,17 @@ impl Moon {
         result
     }
 
-    /// Generate "group b moons" for a gas giant
-    pub(super) fn new_group_b_moons<G: rand::Rng>(rng: &mut G, parent: &Planet) -> Vec<Self> {
+    /// Generate at most `max_moons` "group b moons" for a gas giant
+    pub(super) fn new_group_b_moons<G: rand::Rng>(
+        rng: &mut G,
+        parent: &Planet,
+        max_moons: usize,
+    ) -> Vec<Self> {
         let mut result = Vec::new();
 
         let mut semi_major_axis = 3.0 * parent.radius;
 
-        while semi_major_axis <= 15.0 * parent.radius {
+        while semi_major_axis <= 15.0 * parent.radius && result.len() < max_moons {
             let is_icy = rng.gen_bool(0.333);
             let min_mass = (0.001_001 as Float).powi(3) * float::FRAC_2_PI * 2.0 / 3.0
                 * if is_icy { 21.3 } else { Self::LUNA_DENSITY };
@@ -170,13 +191,15 @@ impl Moon {
         result
     }
 
-    /// Convert this moon to a body
+    /// Convert this moon to a body. Moons inside the [tidal lock radius](Self::tidal_lock_radius)
+    /// always turn once per orbit, others only get a rotation if `rotate_all_bodies` is set
     pub(super) fn to_body<G: rand::Rng>(
         &self,
         rng: &mut G,
         parent: &Planet,
         parent_body: &Arc,
         hill_sphere_limit: Float,
+        rotate_all_bodies: bool,
     ) -> Arc {
         let roche_limit = self.radius * (2.0 * parent.mass / self.mass).cbrt();
         let (inclination, eccentricity) = match self.kind {
@@ -204,19 +227,42 @@ impl Moon {
                 )
             }
         };
-        Body::new(
-            Some(parent_body.clone()),
-            dynamic::keplerian::Keplerian::new(
-                eccentricity,
-                self.semi_major_axis,
-                inclination + parent.north_pole.polar_angle,
-                parent.north_pole.azimuthal_angle
-                    + float::FRAC_PI_2
-                    + rng.gen_range(-10.0 as Float..10.0).to_radians(),
-                random_angle(rng),
-                random_angle(rng),
-                parent.mass,
-            ),
-        )
+        let orbit = dynamic::keplerian::Keplerian::new(
+            eccentricity,
+            self.semi_major_axis,
+            inclination + parent.north_pole.polar_angle,
+            parent.north_pole.azimuthal_angle
+                + float::FRAC_PI_2
+                + rng.gen_range(-10.0 as Float..10.0).to_radians(),
+            random_angle(rng),
+            random_angle(rng),
+            parent.mass,
+        );
+
+        let rotation = if self.semi_major_axis < Self::tidal_lock_radius(parent) {
+            // Keep the same face towards the planet
+            Some(Rotating::new(
+                orbit.get_period().expect("orbits have a period"),
+                orbit.get_orbit_normal().into(),
+            ))
+        } else if rotate_all_bodies {
+            Some(random_rotation(
+                rng,
+                5.0..50.0,
+                orbit.get_orbit_normal().into(),
+                90.0,
+                0.1,
+            ))
+        } else {
+            None
+        };
+
+        let b = Body::new(Some(parent_body.clone()), orbit);
+        {
+            let mut body = b.write().unwrap();
+            body.rotation = rotation;
+            body.mass = Some(self.mass);
+        }
+        b
     }
 }
@@ -1,7 +1,7 @@
 use super::{
     au_to_ls, body, earth_masses_to_jupiter_masses, earth_radii_to_ls, float, keplerian,
-    random_angle, Arc, Float, MainSequenceStar, Moon, Rotating, Spherical, ThreeDimensionalConsts,
-    Vector3,
+    random_angle, random_rotation, Arc, Float, MainSequenceStar, Moon, Rotating, Spherical,
+    ThreeDimensionalConsts, Vector3,
 };
 
 /// A planet that orbits a star
@@ -163,12 +163,16 @@ impl Planet {
         )
     }
 
-    /// Converts a planet to a body that can be added to the tree
+    /// Converts a planet to a body that can be added to the tree, giving it and its moons a
+    /// rotation if `rotate_all_bodies` is set (habitable planets always rotate), and at most
+    /// `max_moons` moons
     pub(super) fn to_body<G: rand::Rng>(
         &self,
         rng: &mut G,
         parent_star: &MainSequenceStar,
         parent: &Arc,
+        rotate_all_bodies: bool,
+        max_moons: usize,
     ) -> Arc {
         let longitude_of_ascending_node = parent_star.north_pole.azimuthal_angle
             + float::FRAC_PI_2
@@ -225,12 +229,12 @@ impl Planet {
             }
         };
 
-        let hill_sphere_limit = dynamic.semi_major_axis
-            * (1.0 - &dynamic.eccentricity)
-            * (self.mass / (3.0 * (self.mass + parent_star.mass))).cbrt();
+        let hill_sphere_limit =
+            dynamic.periapsis() * (self.mass / (3.0 * (self.mass + parent_star.mass))).cbrt();
         let b = body::Body::new(Some(parent.clone()), dynamic);
-        for m in self.generate_moons(rng, parent_star, hill_sphere_limit) {
-            m.to_body(rng, self, &b, hill_sphere_limit);
+        b.write().unwrap().mass = Some(self.mass);
+        for m in self.generate_moons(rng, parent_star, hill_sphere_limit, max_moons) {
+            m.to_body(rng, self, &b, hill_sphere_limit, rotate_all_bodies);
         }
 
         if let PlanetType::Habitable = self.kind {
@@ -247,23 +251,37 @@ impl Planet {
                     azimuthal_angle: random_angle(rng),
                 },
             ));
+        } else if rotate_all_bodies {
+            let rotation = match self.kind {
+                // Gas giants spin quickly, like Jupiter's 10 hour day
+                PlanetType::GasGiant => random_rotation(rng, 9.0..20.0, self.north_pole, 30.0, 0.0),
+                PlanetType::Habitable | PlanetType::Terestrial => {
+                    random_rotation(rng, 12.0..100.0, self.north_pole, 80.0, 0.2)
+                }
+            };
+            b.write().unwrap().rotation = Some(rotation);
         }
         b
     }
 
-    /// Generates the moons around this planet
-    fn generate_moons<G: rand::Rng>(
+    /// Generates at most `max_moons` moons around this planet
+    pub(super) fn generate_moons<G: rand::Rng>(
         &self,
         rng: &mut G,
         star: &MainSequenceStar,
         hill_sphere_limit: Float,
+        max_moons: usize,
     ) -> Vec<Moon> {
         let mut moons = Vec::new();
 
         match self.kind {
             PlanetType::GasGiant => {
-                moons.extend(Moon::new_group_a_moons(rng, self));
-                moons.extend(Moon::new_group_b_moons(rng, self));
+                moons.extend(Moon::new_group_a_moons(rng, self, max_moons));
+                moons.extend(Moon::new_group_b_moons(
+                    rng,
+                    self,
+                    max_moons.saturating_sub(moons.len()),
+                ));
             }
             PlanetType::Terestrial => {
                 let (max_minor_moons, max_major_moons) =
@@ -319,6 +337,7 @@ impl Planet {
                 }
             }
         }
+        moons.truncate(max_moons);
         moons
     }
 }
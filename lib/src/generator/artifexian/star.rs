@@ -1,6 +1,10 @@
 use super::{
-    au_to_ls, dynamic, float, random_angle, solar_masses_to_jupiter_masses, Arc, Body, Cylindrical,
-    Float, Planet, Range, Spherical,
+    au_to_ls, dynamic, float, random_angle, random_rotation, solar_masses_to_jupiter_masses, Arc,
+    Body, Cylindrical, Float, Planet, Range, Spherical, Vector3,
+};
+use crate::{
+    body::stage::StellarStage,
+    units::{Length, KILOMETRES_PER_LIGHT_SECOND},
 };
 
 /// A star that can have bodies that orbit it
@@ -8,9 +12,9 @@ use super::{
 pub(super) struct MainSequenceStar {
     /// Mass of the star in jupiter masses
     pub(super) mass: Float,
-    //luminosity: Float,
-    //diameter: Float,
     //surface_temp: Float,
+    /// Stage in the star's life, if the age of the galaxy is known
+    pub(super) stage: Option<StellarStage>,
     /// Area where habitable planets can exist in ls (light seconds)
     pub(super) habitable_zone: Range<Float>,
     /// Area where planets can exist
@@ -26,37 +30,66 @@ pub(super) struct MainSequenceStar {
 }
 
 impl MainSequenceStar {
-    /// Generate a star that may or may not be habitable
-    pub(super) fn new<G: rand::Rng>(rng: &mut G) -> Self {
+    /// Generate a star that may or may not be habitable, in a galaxy `galaxy_age_gyr` billion
+    /// years old if it is known
+    pub(super) fn new<G: rand::Rng>(rng: &mut G, galaxy_age_gyr: Option<Float>) -> Self {
         let mass: Float = rng.gen_range(0.02..16.0);
-        Self::new_from_mass(rng, mass)
+        Self::new_from_mass(rng, mass, galaxy_age_gyr)
     }
 
-    /// Generate a habitable star
-    pub(super) fn new_habitable<G: rand::Rng>(rng: &mut G) -> Self {
+    /// Generate a star with a mass that would be habitable on the main sequence, it is only
+    /// habitable if it hasn't evolved by the time the galaxy is `galaxy_age_gyr` years old
+    pub(super) fn new_habitable<G: rand::Rng>(rng: &mut G, galaxy_age_gyr: Option<Float>) -> Self {
         let mass: Float = rng.gen_range(0.6..1.4);
-        Self::new_from_mass(rng, mass)
+        Self::new_from_mass(rng, mass, galaxy_age_gyr)
     }
 
+    /// Generate a star in a galaxy `galaxy_age_gyr` billion years old, which formed at a random
+    /// time in the first half of the galaxy's life. Its stage isn't known if the age of the
+    /// galaxy isn't.
+    ///
     /// # Note
     /// Mass is in solar masses, not jupiter masses as used throughout the rest of this library
-    fn new_from_mass<G: rand::Rng>(rng: &mut G, mass: Float) -> Self {
+    fn new_from_mass<G: rand::Rng>(
+        rng: &mut G,
+        mass: Float,
+        galaxy_age_gyr: Option<Float>,
+    ) -> Self {
         let luminosity = mass.powi(3);
         let sqrt_luminosity = luminosity.sqrt();
+        let north_pole = Spherical::new(1.0, random_angle(rng), random_angle(rng));
+        let stage = galaxy_age_gyr
+            .filter(|age| *age > 0.0)
+            .map(|age| StellarStage::at_age(mass, rng.gen_range(age / 2.0..=age)));
         Self {
             mass: solar_masses_to_jupiter_masses(mass),
-            //luminosity,
-            //diameter: mass.powf(0.74),
             //surface_temp: mass.powf(0.505),
+            stage,
             habitable_zone: au_to_ls(sqrt_luminosity * 0.95)..au_to_ls(sqrt_luminosity * 1.37),
             planetary_zone: au_to_ls(0.1 * mass)..au_to_ls(40.0 * mass),
             frost_line: au_to_ls(4.85 * sqrt_luminosity),
-            is_habitable: (0.6..1.4).contains(&mass),
-            north_pole: Spherical::new(1.0, random_angle(rng), random_angle(rng)),
+            is_habitable: (0.6..1.4).contains(&mass)
+                && stage.is_none_or(|stage| stage == StellarStage::MainSequence),
+            north_pole,
             planets: Vec::new(),
         }
     }
 
+    /// Returns the radius in light seconds and luminosity in solar luminosities of the star at
+    /// `stage` in its life
+    fn radius_and_luminosity(&self, stage: StellarStage) -> (Float, Float) {
+        let mass = self.mass / solar_masses_to_jupiter_masses(1.0);
+        let (radius, luminosity) = match stage {
+            StellarStage::MainSequence => (mass.powf(0.74), mass.powi(3)),
+            // Like a red giant, around a hundred times its former size and a thousand times as
+            // bright
+            StellarStage::Giant => (100.0 * mass.powf(0.74), 1000.0 * mass.powi(3)),
+            // About the size of the Earth
+            StellarStage::WhiteDwarf => (0.01, 0.001),
+        };
+        (Length::SolarRadii.to_light_seconds(radius), luminosity)
+    }
+
     /// Gets the allowed deviation above or below the universal reference plane
     #[allow(clippy::excessive_precision)] // Needs to work for f32 and f64 versions
     fn allowed_height(radius: Float) -> Float {
@@ -68,56 +101,86 @@ impl MainSequenceStar {
         maximum * 1.029e8
     }
 
-    /// Convert this star to a body to add to the body tree
+    /// Convert this star to a body to add to the body tree, placed in the galaxy using `d` (see
+    /// [`super::galaxy_distribution`]), giving every body a rotation if `rotate_all_bodies` is
+    /// set, each planet at most `max_moons` moons, and the star a random velocity of up to
+    /// `max_speed_km_s` if given
     pub(super) fn to_body<G: rand::Rng>(
         &self,
         rng: &mut G,
         root: &Arc,
+        galaxy: &super::Galaxy,
+        rotate_all_bodies: bool,
+        max_moons: usize,
+        max_speed_km_s: Option<Float>,
     ) -> (Arc, Option<crate::body::observatory::Observatory>) {
-        const WIDTH_OF_MILKY_WAY: Float = 3e12;
-
-        let d = rand_distr::Pert::new(-1.0, 1.0, 0.0).unwrap();
+        let d = galaxy.distribution;
 
-        let radius = (rng.sample(d) * WIDTH_OF_MILKY_WAY).abs();
+        let radius = (rng.sample(d) * galaxy.width).abs();
         let height = rng.sample(d) * Self::allowed_height(radius);
-        let theta = if radius > 5e11 {
+        // Stars in the core aren't in an arm
+        let theta = if radius > galaxy.width / 6.0 {
+            // Arms are evenly spaced, with the last one at no offset so a two armed galaxy places
+            // stars the same way for the same random numbers as it always has
+            let arm = rng.gen_range(0..galaxy.arm_count);
+            let offset = Float::from((arm + 1) % galaxy.arm_count) / Float::from(galaxy.arm_count);
             float::TAU // Convert revs to radians
-            * (if rng.gen() {
-                // The primary arm
-                rng.sample(d) * 0.25
-            } else {
-                // Make a second arm, half a turn from the primary
-                rng.sample(d) * 0.25 + 0.5
-            } + 1.0 + radius * 1.352 / (WIDTH_OF_MILKY_WAY)) // Make theta map out one and a half turns on
-                                                             // the way from the center to the outer rim
+            * (rng.sample(d) * 0.25 + offset
+                + 1.0 + radius * 1.352 / galaxy.width) // Make theta map out one and a half turns on
+                                                       // the way from the center to the outer rim
         } else {
             random_angle(rng)
         };
-        // Use fixed as a performance saver since their periods would be on the order of millions
-        // of years
-        let b = Body::new(
-            Some(root.clone()),
-            dynamic::fixed::Fixed(Cylindrical::new(radius, height, theta).into()),
-        );
+        // Use fixed (or linear) as a performance saver since their periods would be on the order
+        // of millions of years
+        let position = Cylindrical::new(radius, height, theta).into();
+        let b = if let Some(max_speed_km_s) = max_speed_km_s {
+            let direction: Vector3<Float> = Spherical::new(
+                1.0,
+                rng.gen_range(-1.0..=1.0 as Float).acos(),
+                random_angle(rng),
+            )
+            .into();
+            let speed = rng.gen_range(0.0..=max_speed_km_s) / KILOMETRES_PER_LIGHT_SECOND * 3600.0;
+            Body::new(
+                Some(root.clone()),
+                dynamic::linear::Linear::new(position, direction * speed),
+            )
+        } else {
+            Body::new(Some(root.clone()), dynamic::fixed::Fixed(position))
+        };
+        b.write().unwrap().mass = Some(self.mass);
+
+        if let Some(stage) = self.stage {
+            let (radius, luminosity) = self.radius_and_luminosity(stage);
+            let mut body = b.write().unwrap();
+            body.stellar_stage = Some(stage);
+            body.radius = Some(radius);
+            body.luminosity = Some(luminosity);
+        }
+
+        if rotate_all_bodies {
+            // Heavier stars spin faster, e.g. the sun takes about 25 days while Vega takes 16 hours
+            let period = if self.mass > solar_masses_to_jupiter_masses(1.4) {
+                12.0..240.0
+            } else {
+                240.0..960.0
+            };
+            b.write().unwrap().rotation =
+                Some(random_rotation(rng, period, self.north_pole, 10.0, 0.0));
+        }
 
         let mut observatory = None;
         // Add planets to this body
         for p in &self.planets {
-            let arc = p.to_body(rng, self, &b);
+            let arc = p.to_body(rng, self, &b, rotate_all_bodies, max_moons);
 
             match p.kind {
                 super::planet::PlanetType::Habitable => {
                     use coordinates::prelude::*;
-                    let name = match arc.read() {
-                        Ok(b) => Err(b.get_id()),
-                        Err(_) => Ok("Unnamed".to_string()),
-                    };
-
-                    observatory = Some(crate::body::observatory::Observatory::new(
+                    observatory = Some(crate::generator::observatories::observatory_on(
+                        &arc,
                         Spherical::FORWARD,
-                        arc,
-                        name,
-                        vec![],
                     ));
                 }
                 super::planet::PlanetType::Terestrial | super::planet::PlanetType::GasGiant => (),
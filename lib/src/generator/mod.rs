@@ -4,10 +4,70 @@ use crate::body::observatory::Observatory;
 use crate::body::Arc;
 
 pub mod artifexian;
+/// Placing observatories on the planets of an existing universe
+pub mod observatories;
+
+/// An error while generating a universe
+#[derive(Debug)]
+pub enum Error {
+    /// A distribution bodies are placed with couldn't be made from its parameters
+    Distribution(rand_distr::PertError),
+    /// A [parameter](Generator::Params) was out of range
+    Params(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Distribution(e) => write!(f, "invalid distribution: {e}"),
+            Self::Params(e) => write!(f, "invalid parameters: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Distribution(e) => Some(e),
+            Self::Params(_) => None,
+        }
+    }
+}
+
+impl From<rand_distr::PertError> for Error {
+    fn from(value: rand_distr::PertError) -> Self {
+        Self::Distribution(value)
+    }
+}
 
 pub trait Generator {
+    /// The shape of what is generated, e.g. how many stars there are and how they are spread out,
+    /// that can be tuned without changing the rest of the generator's settings
+    type Params;
+
+    /// Returns the parameters [`Self::generate`] uses
+    fn params(&self) -> Self::Params;
+
     /// Generates stars, planets, and moons based on settings made to self --- e.g. number of stars ---
     /// and the random number generator given. As well as a liist of observatories that were
     /// generated on planets
-    fn generate<G: Rng>(&self, rng: &mut G) -> (Arc, Vec<Observatory>);
+    ///
+    /// # Errors
+    ///
+    /// If the generator's settings can't be used, found before any bodies are generated
+    fn generate<G: Rng>(&self, rng: &mut G) -> Result<(Arc, Vec<Observatory>), Error> {
+        self.generate_with_params(&self.params(), rng)
+    }
+
+    /// Generates like [`Self::generate`], but with `params` in place of [its own](Self::params)
+    ///
+    /// # Errors
+    ///
+    /// If the parameters or the generator's settings can't be used, found before any bodies are
+    /// generated
+    fn generate_with_params<G: Rng>(
+        &self,
+        params: &Self::Params,
+        rng: &mut G,
+    ) -> Result<(Arc, Vec<Observatory>), Error>;
 }
@@ -0,0 +1,146 @@
+use std::ops::Range;
+
+use coordinates::prelude::Spherical;
+use derive_builder::Builder;
+use rand::Rng;
+
+use crate::{
+    body::{observatory::Observatory, Arc},
+    consts::float,
+    Float,
+};
+
+/// Settings for [`place`]
+#[derive(Clone, Debug, Builder, PartialEq)]
+pub struct PlacementOptions {
+    /// Number of observatories placed on each planet
+    #[builder(default = 1)]
+    per_planet: usize,
+    /// Observatories are placed no further than this many degrees north or south of the equator
+    #[builder(default = 90.0)]
+    max_latitude: Float,
+    /// Planets with a radius in light seconds outside of this range are skipped, the default
+    /// covers the terrestrial planets the generator makes. Planets without a radius are not
+    /// skipped, since generated universes don't record them.
+    #[builder(default = "0.0106..0.0319")]
+    radius: Range<Float>,
+}
+
+impl Default for PlacementOptions {
+    fn default() -> Self {
+        PlacementOptionsBuilder::default().build().unwrap()
+    }
+}
+
+/// Returns true if observatories can be placed on the body: it rotates, has a radius in range if
+/// it has one at all, and orbits a body that doesn't orbit anything, like a star
+fn is_candidate(body: &crate::body::Body, options: &PlacementOptions) -> bool {
+    body.rotation.is_some()
+        && body
+            .radius
+            .is_none_or(|radius| options.radius.contains(&radius))
+        && body
+            .parent
+            .as_ref()
+            .and_then(std::sync::Weak::upgrade)
+            .is_some_and(|parent| {
+                parent
+                    .read()
+                    .is_ok_and(|parent| parent.get_dynamic().get_period().is_none())
+            })
+}
+
+/// Makes an observatory at `location` on `planet`, named after the planet's ID and the location
+pub fn observatory_on(planet: &Arc, location: Spherical<Float>) -> Observatory {
+    let name = match planet.read() {
+        Ok(b) => Err(b.get_id()),
+        Err(_) => Ok("Unnamed".to_string()),
+    };
+
+    Observatory::new(location, planet.clone(), name, vec![])
+}
+
+/// Places [`PlacementOptions::per_planet`] observatories at random locations on every planet in
+/// the tree that could be lived on, in order of the planets' IDs so the same random number
+/// generator always gives the same observatories.
+///
+/// There's no record of which planets the generator made habitable, so any planet that rotates,
+/// orbits a star, and has a suitable radius is used. Planets in universes generated without
+/// [rotating every body](super::artifexian::ArtifexianBuilder::rotate_all_bodies) only rotate if
+/// they are habitable.
+pub fn place<G: Rng>(root: &Arc, rng: &mut G, options: &PlacementOptions) -> Vec<Observatory> {
+    // Uniform in the sine of the latitude so observatories are evenly spread over the surface
+    let max_height = options.max_latitude.clamp(0.0, 90.0).to_radians().sin();
+
+    let mut result = Vec::new();
+    let mut stack = vec![root.clone()];
+    while let Some(arc) = stack.pop() {
+        let Ok(body) = arc.read() else {
+            continue;
+        };
+        // Reversed so children come off the stack in order
+        stack.extend(body.get_children().iter().rev().cloned());
+
+        if is_candidate(&body, options) {
+            for _ in 0..options.per_planet {
+                let height = if max_height > 0.0 {
+                    rng.gen_range(-max_height..=max_height)
+                } else {
+                    0.0
+                };
+                let location = Spherical::new(1.0, height.acos(), rng.gen_range(0.0..float::TAU));
+                result.push(observatory_on(&arc, location));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use super::*;
+    use crate::{
+        body::observatory::WeakObservatory,
+        generator::{artifexian::ArtifexianBuilder, Generator},
+        testing::DEFAULT_SEED,
+    };
+
+    /// Serializes observatories the way they are written to files
+    fn to_json(observatories: Vec<Observatory>) -> String {
+        let weak: Vec<WeakObservatory> = observatories.into_iter().map(Into::into).collect();
+        serde_json::to_string(&weak).unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_observatories() {
+        let (root, generated) = ArtifexianBuilder::default()
+            .star_count(500)
+            .build()
+            .unwrap()
+            .generate(&mut XorShiftRng::from_seed(DEFAULT_SEED.to_be_bytes()))
+            .unwrap();
+        let place_with = |seed: u128, per_planet| {
+            let options = PlacementOptionsBuilder::default()
+                .per_planet(per_planet)
+                .max_latitude(60.0)
+                .build()
+                .unwrap();
+            place(
+                &root,
+                &mut XorShiftRng::from_seed(seed.to_be_bytes()),
+                &options,
+            )
+        };
+
+        let once = place_with(1, 1);
+        assert_eq!(once.len(), generated.len());
+        assert!(!once.is_empty());
+        assert_eq!(to_json(place_with(1, 3)), to_json(place_with(1, 3)));
+        assert_ne!(to_json(place_with(1, 3)), to_json(place_with(2, 3)));
+        assert_eq!(place_with(1, 3).len(), 3 * once.len());
+        assert!(place_with(1, 0).is_empty());
+    }
+}
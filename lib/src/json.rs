@@ -0,0 +1,271 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::Float;
+
+/// The deepest nesting of JSON objects and arrays `serde_json` will read by default. Each body in
+/// a tree is two levels deep, one for the body and one for its `children` array.
+pub const RECURSION_LIMIT: usize = 128;
+
+/// An error while reading JSON
+#[derive(Debug)]
+pub enum Error {
+    /// The JSON was invalid, or did not describe the requested type
+    Parse(serde_json::Error),
+    /// The JSON was nested more deeply than [`RECURSION_LIMIT`]. Enabling the `deep-trees` feature
+    /// removes this limit.
+    TooDeep {
+        /// Deepest nesting of objects and arrays in the JSON
+        depth: usize,
+    },
+    /// An observatory or constellation referred to a body ID that isn't in the tree
+    UnknownBody {
+        /// The ID that couldn't be found
+        id: Vec<usize>,
+    },
+    /// A file couldn't be read, see [`Program::from_file`](crate::program::Program::from_file)
+    Read(std::io::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::TooDeep { depth } => write!(
+                f,
+                "JSON is nested {depth} levels deep (about {} bodies), but at most {RECURSION_LIMIT} levels can be read without the `deep-trees` feature",
+                depth / 2
+            ),
+            Self::UnknownBody { id } => write!(f, "there is no body with the ID {id:?}"),
+            Self::Read(e) => write!(f, "could not read the file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            Self::Read(e) => Some(e),
+            Self::TooDeep { .. } | Self::UnknownBody { .. } => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Parse(value)
+    }
+}
+
+/// Reads a value, such as a [`Body`](crate::body::Body) tree or a
+/// [`Program`](crate::program::Program), from a JSON string.
+///
+/// With the `deep-trees` feature, trees of any depth can be read, growing the stack as needed.
+/// Without it, trees nested deeper than [`RECURSION_LIMIT`] return [`Error::TooDeep`].
+///
+/// # Errors
+/// Returns an error if the JSON is invalid, too deep, or does not describe a `T`.
+#[cfg(feature = "deep-trees")]
+pub fn from_str<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    deserializer.disable_recursion_limit();
+    let value = T::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Reads a value, such as a [`Body`](crate::body::Body) tree or a
+/// [`Program`](crate::program::Program), from a JSON string.
+///
+/// With the `deep-trees` feature, trees of any depth can be read, growing the stack as needed.
+/// Without it, trees nested deeper than [`RECURSION_LIMIT`] return [`Error::TooDeep`].
+///
+/// # Errors
+/// Returns an error if the JSON is invalid, too deep, or does not describe a `T`.
+#[cfg(not(feature = "deep-trees"))]
+pub fn from_str<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json).map_err(|e| {
+        // serde_json doesn't categorise this error, so we have to check the message
+        if e.to_string().starts_with("recursion limit exceeded") {
+            Error::TooDeep {
+                depth: nesting_depth(json),
+            }
+        } else {
+            Error::Parse(e)
+        }
+    })
+}
+
+/// Reads an optional number that must be finite and not negative, such as a radius or mass.
+/// Numbers too large for [`Float`] are read as infinity, so they are rejected here.
+///
+/// # Errors
+/// Returns an error if the number is negative, infinite, or not a number
+pub(crate) fn non_negative<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Float>, D::Error> {
+    let value = Option::<Float>::deserialize(deserializer)?;
+    match value {
+        Some(x) if !x.is_finite() || x < 0.0 => Err(serde::de::Error::custom(format!(
+            "expected a finite, non-negative number, found {x}"
+        ))),
+        _ => Ok(value),
+    }
+}
+
+/// Reads the optional inner and outer radius of a body's rings, which must be finite, not
+/// negative, and with the inner radius no larger than the outer one.
+///
+/// # Errors
+/// Returns an error if either radius is negative, infinite, or not a number, or the inner radius
+/// is larger than the outer one
+pub(crate) fn ring_radii<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<(Float, Float)>, D::Error> {
+    let value = Option::<(Float, Float)>::deserialize(deserializer)?;
+    match value {
+        Some((inner, outer))
+            if !(inner.is_finite() && outer.is_finite() && (0.0..=outer).contains(&inner)) =>
+        {
+            Err(serde::de::Error::custom(format!(
+                "expected finite ring radii from 0 with the inner no larger than the outer, found \
+                 {inner} and {outer}"
+            )))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Returns true for numbers that are zero, so they can be left out when serializing optional
+/// offsets
+#[allow(clippy::trivially_copy_pass_by_ref)] // Signature required by serde
+pub(crate) fn is_zero(value: &Float) -> bool {
+    *value == 0.0
+}
+
+/// Checks that every number in a deserialized value is finite, returning a message naming the
+/// value if not. Numbers too large for [`Float`] are read as infinity, which would otherwise turn
+/// into NaNs throughout the simulation.
+///
+/// # Errors
+/// Returns an error if any of the numbers are infinite or not a number
+pub(crate) fn check_finite(name: &str, values: &[Float]) -> Result<(), String> {
+    match values.iter().find(|x| !x.is_finite()) {
+        Some(x) => Err(format!("{name} must be finite, found {x}")),
+        None => Ok(()),
+    }
+}
+
+/// Returns the deepest nesting of objects and arrays in some JSON, without recursing.
+#[must_use]
+pub fn nesting_depth(json: &str) -> usize {
+    let mut depth: usize = 0;
+    let mut deepest = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for byte in json.bytes() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    deepest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{Arc, Body};
+
+    /// A JSON body with no children
+    const LEAF: &str = r#"{"dynamic":{"Fixed":{"x":1.0,"y":0.0,"z":0.0}}}"#;
+
+    /// Builds a chain of bodies, each the only child of the last
+    fn deep_chain(depth: usize) -> String {
+        let mut json = String::new();
+        for _ in 1..depth {
+            json.push_str(r#"{"dynamic":{"Fixed":{"x":1.0,"y":0.0,"z":0.0}},"children":["#);
+        }
+        json.push_str(LEAF);
+        for _ in 1..depth {
+            json.push_str("]}");
+        }
+        json
+    }
+
+    #[test]
+    fn depth_ignores_strings() {
+        assert_eq!(nesting_depth(r#"{"a":["{[\"{"]}"#), 2);
+        assert_eq!(nesting_depth(&deep_chain(3)), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "deep-trees")]
+    fn deep_chain_is_read() {
+        const DEPTH: usize = 5000;
+        let root: Arc = from_str::<Body>(&deep_chain(DEPTH)).unwrap().into();
+
+        let mut depth = 1;
+        let mut current = root.clone();
+        loop {
+            let next = current.read().unwrap().get_children().first().cloned();
+            match next {
+                Some(child) => {
+                    // Hydrating should have linked every child to its parent
+                    assert!(child.read().unwrap().parent.is_some());
+                    current = child;
+                    depth += 1;
+                }
+                None => break,
+            }
+        }
+        assert_eq!(depth, DEPTH);
+    }
+
+    #[test]
+    #[cfg(not(feature = "deep-trees"))]
+    fn deep_chain_names_depth() {
+        match from_str::<Body>(&deep_chain(5000)) {
+            Err(Error::TooDeep { depth }) => assert_eq!(depth, 2 * 4999 + 3),
+            other => panic!("Expected the depth to be too deep, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn wide_tree_is_read() {
+        const WIDTH: usize = 1_000_000;
+        let mut json =
+            String::from(r#"{"dynamic":{"Fixed":{"x":0.0,"y":0.0,"z":0.0}},"children":["#);
+        for i in 0..WIDTH {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(LEAF);
+        }
+        json.push_str("]}");
+
+        let root: Arc = from_str::<Body>(&json).unwrap().into();
+
+        let root = root.read().unwrap();
+        assert_eq!(root.get_children().len(), WIDTH);
+        let last = root.get_children()[WIDTH - 1].read().unwrap().get_name();
+        assert_eq!(&*last, "999999");
+    }
+}
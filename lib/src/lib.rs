@@ -4,6 +4,8 @@
 #![allow(clippy::needless_return)]
 #![warn(clippy::missing_docs_in_private_items)]
 
+/// Rough estimates for planning scenes, e.g. travel times between planets.
+pub mod almanac;
 /// Structures that model discrete objects in the simulation, e.g. planets, stars, and
 /// observatories.
 pub mod body;
@@ -11,17 +13,36 @@ pub mod body;
 /// Physical constants for the simulation, e.g. The Gravitational Constant, Pi, and Tau.
 #[allow(clippy::excessive_precision)] // Constants should work with up to f128 precision
 pub mod consts;
+/// Checks that runs with the same seed produce the same results, by hashing what they produce.
+pub mod determinism;
 /// Structs that model the orbits that bodies can follow.
 pub mod dynamic;
+/// Tables of the orbital elements of every body in a tree, e.g. for documentation.
+pub mod elements;
+/// A C interface for loading bodies and making observations from other languages.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// Reading bodies and programs from JSON, including trees too deep for `serde_json`'s defaults.
+pub mod json;
 /// Objects that assist in outputting data to various types, e.g. HTML canvas, SVG, etc.
 pub mod output;
 /// A helper [facade](https://en.wikipedia.org/wiki/Facade_pattern) that takes simulation times and
 /// converts them to outputs, such as SVG files.
 pub mod program;
 
+/// Answers requests for single charts and observations on demand, e.g. to serve them over HTTP.
+pub mod serve;
+
 /// Projections that map 3D space to a 2D plane
 pub mod projection;
 
+/// Units that quantities can be given in, and their conversions to the units used internally
+#[allow(clippy::excessive_precision)] // Constants should work with up to f128 precision
+pub mod units;
+
+/// Checks on body trees that warn about results that may not be what they seem
+pub mod validate;
+
 pub mod generator;
 
 pub mod constellation;
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    output::{
+        logger::{eclipse, parallactic, radial_velocity},
+        svg::{
+            comet_tails, compass_rose, effective_horizon, meridian, planetary_rings, MultiRes, Svg,
+            Trail,
+        },
+        Output,
+    },
+    projection::{
+        Equirectangular, Gnomonic, Mollweide, Orthographic, Projection, StatelessGnomonic,
+        StatelessOrthographic, DEFAULT_CLIP_TOLERANCE,
+    },
+    Float,
+};
+
+/// A [projection](crate::projection) as it is written in a program file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ProjectionConfig {
+    /// [`StatelessOrthographic`], the default
+    #[default]
+    StatelessOrthographic,
+    /// [`Orthographic`] centered on a latitude and longitude in radians
+    #[serde(rename_all = "camelCase")]
+    Orthographic {
+        /// Latitude of the center of projection
+        latitude: Float,
+        /// Longitude of the center of projection
+        longitude: Float,
+        /// See [`Orthographic::with_clip_tolerance`]
+        #[serde(default = "default_clip_tolerance")]
+        clip_tolerance: Float,
+    },
+    /// [`StatelessGnomonic`]
+    StatelessGnomonic,
+    /// [`Gnomonic`], only drawing points up to an angle in radians from its center
+    #[serde(rename_all = "camelCase")]
+    Gnomonic {
+        /// See [`Gnomonic::new`]
+        max_half_angle: Float,
+        /// Latitude of the center of projection, the zenith by default
+        #[serde(default = "default_center_latitude")]
+        latitude: Float,
+        /// Longitude of the center of projection
+        #[serde(default)]
+        longitude: Float,
+    },
+    /// [`Equirectangular`]
+    Equirectangular,
+    /// [`Mollweide`]
+    Mollweide,
+}
+
+/// Returns [`DEFAULT_CLIP_TOLERANCE`], for serde
+fn default_clip_tolerance() -> Float {
+    DEFAULT_CLIP_TOLERANCE
+}
+
+/// Returns the latitude of the zenith, for serde
+fn default_center_latitude() -> Float {
+    crate::consts::float::FRAC_PI_2
+}
+
+/// Calls `$body` with `$projector` bound to the projection described by a [`ProjectionConfig`],
+/// since each projection is a different type
+macro_rules! with_projector {
+    ($config:expr, $projector:ident => $body:expr) => {
+        match $config {
+            ProjectionConfig::StatelessOrthographic => {
+                let $projector = StatelessOrthographic();
+                $body
+            }
+            ProjectionConfig::Orthographic {
+                latitude,
+                longitude,
+                clip_tolerance,
+            } => {
+                let $projector =
+                    Orthographic::new(latitude, longitude).with_clip_tolerance(clip_tolerance);
+                $body
+            }
+            ProjectionConfig::StatelessGnomonic => {
+                let $projector = StatelessGnomonic();
+                $body
+            }
+            ProjectionConfig::Gnomonic {
+                max_half_angle,
+                latitude,
+                longitude,
+            } => {
+                let $projector = Gnomonic::new(max_half_angle).with_center(latitude, longitude);
+                $body
+            }
+            ProjectionConfig::Equirectangular => {
+                let $projector = Equirectangular();
+                $body
+            }
+            ProjectionConfig::Mollweide => {
+                let $projector = Mollweide();
+                $body
+            }
+        }
+    };
+}
+
+/// A [layer](crate::output::svg::Layer) drawn on top of SVG charts, as it is written in a program
+/// file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LayerConfig {
+    /// [`compass_rose`]
+    CompassRose,
+    /// [`effective_horizon`] of the program's observatories
+    EffectiveHorizon,
+    /// [`comet_tails`] seen from the program's observatories
+    CometTails,
+    /// [`meridian`] of the program's observatories
+    Meridian,
+    /// [`planetary_rings`] seen from the program's observatories
+    PlanetaryRings,
+}
+
+/// An [output](Output) as it is written in a program file, so a program can be read and run
+/// without setting up its outputs in code. See
+/// [`Program::add_output_config`](crate::program::Program::add_output_config).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum OutputConfig {
+    /// [`Svg`] charts of every frame, along with thumbnails from [`MultiRes`] if a minimum
+    /// angular radius is given
+    #[serde(rename_all = "camelCase")]
+    Svg {
+        /// Projection the charts are drawn with
+        #[serde(default)]
+        projection: ProjectionConfig,
+        /// Layers drawn on top of each chart, in order
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        layers: Vec<LayerConfig>,
+        /// Smallest angular radius in degrees a body can have and still be drawn in thumbnails,
+        /// or `None` to not draw thumbnails
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thumbnails_deg: Option<Float>,
+        /// See [`Svg::set_subtitle`]
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        subtitle: bool,
+    },
+    /// A [`Trail`] of the body with this name
+    #[serde(rename_all = "camelCase")]
+    Trail {
+        /// Name of the body that is followed
+        body: String,
+        /// Projection the trail is drawn with
+        #[serde(default)]
+        projection: ProjectionConfig,
+    },
+    /// An [eclipse log](eclipse::Logger)
+    EclipseLog,
+    /// A [parallactic angle log](parallactic::Logger) of the bodies with these names
+    ParallacticAngle {
+        /// Names of the bodies to track
+        targets: Vec<String>,
+    },
+    /// A [radial velocity log](radial_velocity::Logger) of the star with this name
+    RadialVelocity {
+        /// Name of the star whose reflex motion is measured
+        target: String,
+    },
+}
+
+impl OutputConfig {
+    /// Builds the output this describes
+    #[must_use]
+    pub fn build(&self) -> Box<dyn Output> {
+        match self {
+            Self::Svg {
+                projection,
+                layers,
+                thumbnails_deg,
+                subtitle,
+            } => with_projector!(*projection, projector => {
+                let svg = svg_with_layers(projector, layers, *subtitle);
+                match thumbnails_deg {
+                    Some(degrees) => Box::new(MultiRes::new(svg, degrees.to_radians())),
+                    None => Box::new(svg),
+                }
+            }),
+            Self::Trail { body, projection } => {
+                with_projector!(*projection, projector => Box::new(Trail::new(projector, body.clone())))
+            }
+            Self::EclipseLog => Box::new(eclipse::Logger::default()),
+            Self::ParallacticAngle { targets } => {
+                Box::new(parallactic::Logger::new(targets.iter().map(String::as_str)))
+            }
+            Self::RadialVelocity { target } => {
+                Box::new(radial_velocity::Logger::new(target.as_str()))
+            }
+        }
+    }
+}
+
+/// Creates an [`Svg`] output drawing the given layers on top of each chart
+fn svg_with_layers<T: Projection>(projector: T, layers: &[LayerConfig], subtitle: bool) -> Svg<T> {
+    let mut svg = Svg::new(projector);
+    svg.set_subtitle(subtitle);
+    for layer in layers {
+        match layer {
+            LayerConfig::CompassRose => svg.add_layer(Box::new(compass_rose)),
+            LayerConfig::EffectiveHorizon => svg.add_layer(Box::new(effective_horizon)),
+            LayerConfig::CometTails => svg.add_layer(Box::new(comet_tails)),
+            LayerConfig::Meridian => svg.add_layer(Box::new(meridian)),
+            LayerConfig::PlanetaryRings => svg.add_layer(Box::new(planetary_rings)),
+        }
+    }
+    svg
+}
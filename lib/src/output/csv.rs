@@ -0,0 +1,346 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use crate::{consts::float, elements::escape, LocalObservation};
+
+use super::{FileNameTemplate, FrameInfo, Output};
+
+/// Name of the file each observatory's rows are written to when they are
+/// [combined](Csv::set_combined)
+pub const COMBINED_FILE_NAME: &str = "observations";
+
+/// Rows with the time they were observed at, by the file they are written to
+type RowMap = Arc<RwLock<BTreeMap<Arc<Path>, Vec<(i128, String)>>>>;
+
+/// Names of the columns, in order
+const COLUMNS: [&str; 7] = [
+    "time",
+    "observatory",
+    "body",
+    "azimuth_deg",
+    "altitude_deg",
+    "distance_ls",
+    "angular_radius_rad",
+];
+
+/// An output that writes tables of observations for analysis in spreadsheets or dataframes, with
+/// a row for each body observed in each frame:
+/// - `time` in hours
+/// - `observatory`, the name of the observatory
+/// - `body`, the name of the body
+/// - `azimuth_deg`, the compass bearing in degrees clockwise from north, so east is 90°
+/// - `altitude_deg` above the horizon in degrees
+/// - `distance_ls` in light seconds
+/// - `angular_radius_rad` in radians, empty for bodies without a radius
+///
+/// Rows are kept in memory and written when the output is [flushed](Output::flush), so frames
+/// observed in parallel never interleave their rows. Each frame is written to
+/// `<observatory>/<time>.csv` by default, or every frame of an observatory to one file sorted by
+/// time if [combined](Self::set_combined).
+#[derive(Clone, Debug)]
+pub struct Csv {
+    /// Rows waiting to be written. Sorted so files are written in the same order every run
+    rows: RowMap,
+    /// Whether each observatory's rows are written to one file
+    combined: bool,
+    /// Separator between columns
+    delimiter: char,
+    /// Template for the names of the files rows are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Csv {
+    /// Creates an output that writes comma separated files, one for each frame
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            rows: Arc::default(),
+            combined: false,
+            delimiter: ',',
+            file_name_template: FileNameTemplate::default(),
+        }
+    }
+
+    /// Writes every frame observed by an observatory to one file, `<observatory>/observations.csv`
+    /// by default, sorted by time, instead of a file for each frame if true. Off by default.
+    pub fn set_combined(&mut self, combined: bool) {
+        self.combined = combined;
+    }
+
+    /// Separates columns with `delimiter` instead of a comma, e.g. `'\t'` for tab separated
+    /// files. Files are still named `.csv`.
+    pub fn set_delimiter(&mut self, delimiter: char) {
+        self.delimiter = delimiter;
+    }
+
+    /// Names the files rows are written to with a [template](super::to_templated_path), e.g.
+    /// `"{time}-{observatory}{ext}"`, or [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if
+    /// `None`. When [combined](Self::set_combined), `{time}` is filled in with
+    /// [`COMBINED_FILE_NAME`] instead.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Returns the file rows for a frame are written to, see [`Self::set_file_name_template`]
+    fn path(&self, output_path_root: &Path, observatory_name: &str, time: i128) -> PathBuf {
+        if self.combined {
+            self.file_name_template.observatory_path(
+                output_path_root,
+                observatory_name,
+                COMBINED_FILE_NAME,
+                ".csv",
+            )
+        } else {
+            self.file_name_template
+                .frame_path(output_path_root, observatory_name, time, ".csv")
+        }
+    }
+}
+
+impl Output for Csv {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        _constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let delimiter = self.delimiter.to_string();
+        let observatory = escape(frame.directory, self.delimiter);
+        let rows = observations.iter().map(|(body, location)| {
+            let (name, angular_radius) = body.read().map_or_else(
+                |_| ("Poisoned Body".into(), None),
+                |body| {
+                    (
+                        body.get_name().to_string(),
+                        body.radius
+                            .map(|_| body.get_angular_radius(location.radius)),
+                    )
+                },
+            );
+            let altitude = float::FRAC_PI_2 - location.polar_angle;
+            let row = [
+                frame.time.to_string(),
+                observatory.to_string(),
+                escape(&name, self.delimiter).into_owned(),
+                // Local coordinates measure from +x, which points south
+                (location.azimuthal_angle + float::PI)
+                    .rem_euclid(float::TAU)
+                    .to_degrees()
+                    .to_string(),
+                altitude.to_degrees().to_string(),
+                location.radius.to_string(),
+                angular_radius.map_or_else(String::new, |radius| radius.to_string()),
+            ];
+            (frame.time, row.join(&delimiter))
+        });
+
+        let path = self.path(output_path_root, frame.directory, frame.time);
+        if let Ok(mut map) = self.rows.write() {
+            map.entry(path.into()).or_default().extend(rows);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), std::io::Error> {
+        if let Ok(map) = self.rows.read() {
+            for (path, rows) in map.iter() {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                // Each frame's rows were added together, so a stable sort keeps them in order
+                let mut rows: Vec<_> = rows.iter().collect();
+                rows.sort_by_key(|(time, _)| *time);
+                let mut contents = COLUMNS.join(&self.delimiter.to_string());
+                for (_, row) in rows {
+                    contents.push('\n');
+                    contents.push_str(row);
+                }
+                contents.push('\n');
+                std::fs::write(path, contents)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+    use crate::{
+        body::{observatory::Observatory, Body},
+        dynamic::fixed::Fixed,
+        Float,
+    };
+
+    use super::*;
+
+    /// Returns an observatory on a planet with a star straight overhead and a moon 45° up
+    fn fixed_system() -> Observatory {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(Some(planet.clone()), Fixed::new(Vector3::UP * 10.0));
+        star.write().unwrap().radius = Some(1.0);
+        star.write().unwrap().name = crate::body::Name::Named("Sol".into());
+        let moon = Body::new(
+            Some(planet.clone()),
+            Fixed::new((Vector3::UP + Vector3::RIGHT) * 3.0),
+        );
+        moon.write().unwrap().name = crate::body::Name::Named("Moon, the".into());
+        Body::hydrate_all(&planet, &None);
+        Observatory::new(Spherical::UP, planet, Ok("Base".into()), vec![])
+    }
+
+    /// Reads back every row of a written file, checking its header
+    fn read_rows(path: &Path, delimiter: &str) -> Vec<Vec<String>> {
+        let contents = std::fs::read_to_string(path).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next().unwrap(), COLUMNS.join(delimiter));
+        lines
+            .map(|line| {
+                // Only the moon's name is quoted, and it doesn't contain quotes
+                let mut fields = Vec::new();
+                let mut rest = line;
+                loop {
+                    let (field, next) = match rest.strip_prefix('"') {
+                        Some(quoted) => {
+                            let end = quoted.find('"').unwrap();
+                            (&quoted[..end], &quoted[end + 1..])
+                        }
+                        None => rest.split_at(rest.find(delimiter).unwrap_or(rest.len())),
+                    };
+                    fields.push(field.to_string());
+                    match next.strip_prefix(delimiter) {
+                        Some(next) => rest = next,
+                        None => break,
+                    }
+                }
+                fields
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_written_per_frame() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-csv-rows-written-per-frame");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let observatory = fixed_system();
+        let output = Csv::new();
+        for time in 0..3 {
+            output
+                .write_observations(
+                    &observatory.observe(0.0),
+                    &[],
+                    FrameInfo::new("Base", time),
+                    &output_root,
+                )
+                .unwrap();
+        }
+        output.flush().unwrap();
+
+        for time in 0..3 {
+            let rows = read_rows(
+                &crate::output::to_default_path(&output_root, "Base", time, ".csv"),
+                ",",
+            );
+            assert_eq!(rows.len(), 2);
+
+            let star = rows.iter().find(|row| row[2] == "Sol").unwrap();
+            assert_eq!(star[0], time.to_string());
+            assert_eq!(star[1], "Base");
+            let field = |row: &[String], i: usize| row[i].parse::<Float>().unwrap();
+            assert_float_absolute_eq!(field(star, 4), 90.0, 1e-3);
+            assert_float_absolute_eq!(field(star, 5), 10.0, 1e-4);
+            assert_float_absolute_eq!(field(star, 6), (0.1 as Float).asin(), 1e-4);
+
+            let moon = rows.iter().find(|row| row[2] == "Moon, the").unwrap();
+            assert_float_absolute_eq!(field(moon, 4), 45.0, 1e-3);
+            // Towards +x in local coordinates, which is south
+            assert_float_absolute_eq!(field(moon, 3), 180.0, 1e-3);
+            assert_eq!(moon[6], "");
+        }
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn combined_rows_sorted_by_time() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-csv-combined-rows-sorted-by-time");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let observatory = fixed_system();
+        let mut output = Csv::new();
+        output.set_combined(true);
+        output.set_delimiter('\t');
+        // Written out of order, like frames observed in parallel
+        for time in [2, 0, 1] {
+            output
+                .write_observations(
+                    &observatory.observe(0.0),
+                    &[],
+                    FrameInfo::new("Base", time),
+                    &output_root,
+                )
+                .unwrap();
+        }
+        output.flush().unwrap();
+
+        let rows = read_rows(
+            &output_root
+                .join("Base")
+                .join(format!("{COMBINED_FILE_NAME}.csv")),
+            "\t",
+        );
+        assert_eq!(rows.len(), 6);
+        let times: Vec<_> = rows.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(times, ["0", "0", "1", "1", "2", "2"]);
+        assert!(rows.iter().any(|row| row[2] == "Moon, the"));
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn files_follow_template() {
+        let output_root =
+            std::env::temp_dir().join(format!("astrograph-csv-template-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let observatory = fixed_system();
+        let mut per_frame = Csv::new();
+        per_frame.set_file_name_template(Some("{time}-{observatory}{ext}".into()));
+        let mut combined = per_frame.clone();
+        combined.set_combined(true);
+        for output in [&per_frame, &combined] {
+            output
+                .write_observations(
+                    &observatory.observe(0.0),
+                    &[],
+                    FrameInfo::new("Base", 4),
+                    &output_root,
+                )
+                .unwrap();
+            output.flush().unwrap();
+        }
+
+        assert!(output_root.join("0000000004-Base.csv").is_file());
+        assert!(output_root
+            .join(format!("{COMBINED_FILE_NAME}-Base.csv"))
+            .is_file());
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+}
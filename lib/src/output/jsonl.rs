@@ -0,0 +1,257 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use coordinates::prelude::Spherical;
+use serde::{Deserialize, Serialize};
+
+use crate::{Float, LocalObservation};
+
+use super::{FileNameTemplate, FrameInfo, Output};
+
+/// Name of the file frames are written to in the output directory by default
+pub const FILE_NAME: &str = "observations.jsonl";
+
+/// A direction in local coordinates, see [`Observatory::observe`](crate::body::observatory::Observatory::observe)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Direction {
+    /// Angle from the zenith in radians
+    pub polar: Float,
+    /// Angle around the horizon in radians
+    pub azimuthal: Float,
+}
+
+impl From<&Spherical<Float>> for Direction {
+    fn from(value: &Spherical<Float>) -> Self {
+        Self {
+            polar: value.polar_angle,
+            azimuthal: value.azimuthal_angle,
+        }
+    }
+}
+
+/// A body seen in a [`Frame`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BodyRecord {
+    /// Name of the body, see [`Body::get_name`](crate::body::Body::get_name)
+    pub name: String,
+    /// Angle from the zenith in radians
+    pub polar: Float,
+    /// Angle around the horizon in radians
+    pub azimuthal: Float,
+    /// Distance from the observatory in light seconds
+    pub distance: Float,
+}
+
+/// An edge of a constellation seen in a [`Frame`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LineRecord {
+    /// Direction of the body at one end of the edge
+    pub start: Direction,
+    /// Direction of the body at the other end of the edge
+    pub end: Direction,
+    /// CSS color of the constellation
+    pub color: String,
+    /// Width of the line, see [`DEFAULT_WIDTH`](crate::constellation::DEFAULT_WIDTH)
+    pub width: Float,
+}
+
+/// Everything an observatory saw at one time, written as one line of [`JsonLines`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Time of the frame, in hours
+    pub time: i128,
+    /// Time the observatory actually observed the sky at, see
+    /// [`FrameInfo::observation_time`]
+    pub observation_time: Float,
+    /// Name of the observatory that made the observations
+    pub observatory: String,
+    /// Bodies above the horizon
+    pub bodies: Vec<BodyRecord>,
+    /// Edges of constellations
+    pub constellations: Vec<LineRecord>,
+}
+
+/// An output that writes every frame as a line of JSON (a [`Frame`]) to [`FILE_NAME`] in the output
+/// directory by default, for pipelines that would rather not parse SVG files.
+///
+/// Lines are kept in memory and written when the output is [flushed](Output::flush), sorted by time
+/// then observatory, so frames observed in parallel never interleave.
+#[derive(Clone, Debug, Default)]
+pub struct JsonLines {
+    /// Frames waiting to be written with the directory they are written to, in the order they
+    /// were observed
+    frames: Arc<RwLock<Vec<(PathBuf, Frame)>>>,
+    /// Template of the path frames are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl JsonLines {
+    /// Creates an output that writes frames as JSON lines
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the file frames are written to with a [template](super::to_templated_path), or
+    /// [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if `None`. Every observatory is written
+    /// to the same file, so `{time}` is filled in with `observations`, `{ext}` with `.jsonl`, and
+    /// `{observatory}` is left empty, see [`FileNameTemplate::run_path`].
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+}
+
+impl Output for JsonLines {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let bodies = observations
+            .iter()
+            .map(|(body, location)| BodyRecord {
+                name: body.read().map_or_else(
+                    |b| b.into_inner().get_name().to_string(),
+                    |b| b.get_name().to_string(),
+                ),
+                polar: location.polar_angle,
+                azimuthal: location.azimuthal_angle,
+                distance: location.radius,
+            })
+            .collect();
+        let constellations = constellations
+            .iter()
+            .map(|line| LineRecord {
+                start: (&line.start).into(),
+                end: (&line.end).into(),
+                color: line.color.to_string(),
+                width: line.width,
+            })
+            .collect();
+        let record = Frame {
+            time: frame.time,
+            observation_time: frame.observation_time,
+            observatory: frame.directory.to_string(),
+            bodies,
+            constellations,
+        };
+
+        if let Ok(mut frames) = self.frames.write() {
+            frames.push((output_path_root.to_owned(), record));
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), std::io::Error> {
+        let Ok(mut frames) = self.frames.write() else {
+            return Ok(());
+        };
+        frames.sort_by(|(a_root, a), (b_root, b)| {
+            a_root
+                .cmp(b_root)
+                .then(a.time.cmp(&b.time))
+                .then_with(|| a.observatory.cmp(&b.observatory))
+        });
+
+        for group in frames.chunk_by(|(a, _), (b, _)| a == b) {
+            let path = self
+                .file_name_template
+                .run_path(&group[0].0, "observations", ".jsonl");
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut contents = String::new();
+            for (_, frame) in group {
+                contents.push_str(&serde_json::to_string(frame)?);
+                contents.push('\n');
+            }
+            std::fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Lines are written with each frame, for programs that draw them
+    fn wants_constellations(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::ThreeDimensionalConsts;
+
+    use crate::{
+        body::{observatory::Observatory, Body},
+        testing::{make_toy_example, DEFAULT_SEED},
+    };
+
+    use super::*;
+
+    #[test]
+    fn frames_read_back() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-jsonl-frames-read-back");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new(Spherical::UP, observer, Ok("Lines".into()), vec![]);
+        let constellations = [crate::constellation::Line {
+            start: Spherical::UP,
+            end: Spherical::LEFT,
+            color: "#F00".into(),
+            width: crate::constellation::DEFAULT_WIDTH,
+            culture: None,
+        }];
+
+        let output = JsonLines::new();
+        let mut counts = Vec::new();
+        // Written out of order, like frames observed in parallel
+        for time in [3, 0, 2, 1] {
+            #[allow(clippy::cast_precision_loss)]
+            let observations = observatory.observe(time as Float);
+            counts.push((time, observations.len()));
+            output
+                .write_observations(
+                    &observations,
+                    &constellations,
+                    FrameInfo::new("Lines", time),
+                    &output_root,
+                )
+                .unwrap();
+        }
+        output.flush().unwrap();
+        counts.sort_unstable();
+
+        let contents = std::fs::read_to_string(output_root.join(FILE_NAME)).unwrap();
+        let frames: Vec<Frame> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(frames.len(), 4);
+        for (frame, (time, count)) in frames.iter().zip(counts) {
+            assert_eq!(frame.time, time);
+            assert_eq!(frame.observatory, "Lines");
+            assert_eq!(frame.bodies.len(), count);
+            assert!(count > 0);
+            assert_eq!(frame.constellations.len(), 1);
+            assert_eq!(frame.constellations[0].color, "#F00");
+        }
+
+        // Names come from the hydrated tree
+        let observations = observatory.observe(0.0);
+        let (body, location) = &observations[0];
+        let record = frames[0]
+            .bodies
+            .iter()
+            .find(|record| record.name == body.read().unwrap().get_name().to_string())
+            .unwrap();
+        assert_float_absolute_eq!(record.distance, location.radius);
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+}
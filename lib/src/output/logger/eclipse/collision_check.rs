@@ -1,6 +1,4 @@
-use std::ops::Div;
-
-use coordinates::{prelude::Spherical, traits::Positional};
+use coordinates::prelude::Spherical;
 
 use crate::{body::Arc, consts::float, Float, LocalObservation};
 
@@ -8,6 +6,9 @@ use crate::{body::Arc, consts::float, Float, LocalObservation};
 pub struct CollisionGrid {
     /// Grid of observations
     body_grid: [std::sync::Arc<[LocalObservation]>; Self::NUMBER_OF_CELLS],
+    /// Observations too large to fit in a cell, e.g. a nearby planet or the ground, which are
+    /// checked against every other observation
+    large_bodies: Vec<LocalObservation>,
 }
 
 impl CollisionGrid {
@@ -17,6 +18,9 @@ impl CollisionGrid {
     const ROWS_PER_SPHERE: usize = 8;
     /// Total number of cells
     const NUMBER_OF_CELLS: usize = Self::CELLS_PER_ROW * Self::ROWS_PER_SPHERE;
+    /// Bodies with a larger angular radius than this may overlap cells that aren't adjacent to
+    /// their own
+    const LARGE_ANGULAR_RADIUS: Float = float::FRAC_PI_8;
 
     /// Generate a new collision grid
     pub(super) fn new(observed_bodies: &[LocalObservation]) -> Self {
@@ -29,8 +33,13 @@ impl CollisionGrid {
             Err(_) => unreachable!(),
         };
 
+        let mut large_bodies = Vec::new();
         for (b, loc) in observed_bodies {
-            body_grid[Self::get_face_id(loc)].push((b.clone(), *loc));
+            if Self::is_large(b, loc) {
+                large_bodies.push((b.clone(), *loc));
+            } else {
+                body_grid[Self::get_face_id(loc)].push((b.clone(), *loc));
+            }
         }
 
         let body_grid: [std::sync::Arc<_>; Self::NUMBER_OF_CELLS] = match body_grid
@@ -43,7 +52,16 @@ impl CollisionGrid {
             Err(_) => unreachable!(),
         };
 
-        Self { body_grid }
+        Self {
+            body_grid,
+            large_bodies,
+        }
+    }
+
+    /// Returns true if the body is too large to only be checked against adjacent cells
+    fn is_large(body: &Arc, location: &Spherical<Float>) -> bool {
+        body.read()
+            .is_ok_and(|b| b.get_angular_radius(location.radius) > Self::LARGE_ANGULAR_RADIUS)
     }
 
     /// Returns the magnitude of any eclipses if there is any
@@ -52,13 +70,19 @@ impl CollisionGrid {
         // len = 18 when the face is on the north or south pole region and many edges join on the z
         // axis
         // Otherwise regions are laid out in a honeycome like pattern
-        let faces = Self::get_adjacent_faces(Self::get_face_id(&near_point.1));
+        // Large points could reach any cell
+        let faces = if Self::is_large(&near_point.0, &near_point.1) {
+            (0..Self::NUMBER_OF_CELLS).collect()
+        } else {
+            Self::get_adjacent_faces(Self::get_face_id(&near_point.1))
+        };
 
         // List of points that this point could eclipse i.e. are further away from the observer than this
-        // point and are in a neighboring cell
+        // point and are in a neighboring cell, or are large enough to reach this cell
         let points: Vec<(Float, &Spherical<Float>, &Arc)> = faces
             .into_iter()
             .flat_map(|face_id| self.body_grid[face_id].iter())
+            .chain(&self.large_bodies)
             .filter_map(|(b, loc)| {
                 if loc.radius > near_point.1.radius {
                     Some((b.read().ok()?.get_angular_radius(loc.radius), loc, b))
@@ -78,7 +102,7 @@ impl CollisionGrid {
             points
                 .into_iter()
                 .filter_map(|(angular_radius, loc, b)| {
-                    Self::check_collision(&near_point_diameter, &(angular_radius, *loc))
+                    super::eclipse_magnitude(near_point_diameter, (angular_radius, *loc))
                         .map(|mag| (b.clone(), mag))
                 })
                 .collect()
@@ -87,38 +111,13 @@ impl CollisionGrid {
         }
     }
 
-    /// See if the near body is in front of the far body
-    fn check_collision(
-        near_point: &(Float, Spherical<Float>),
-        far_point: &(Float, Spherical<Float>),
-    ) -> Option<Float> {
-        let angle_to = near_point.1.angle_to(&far_point.1);
-        if angle_to < near_point.0 + far_point.0 {
-            // There is an eclipse happening.
-            // Angle to must be positive so we can ignore the upper
-            // limit `clamp(0.0, near_point.0 + far_point.0)`
-            let radius_sum = near_point.0 + far_point.0;
-            let fully_occluded_magnitude = near_point.0 / far_point.0 * (1.0 - angle_to);
-            if fully_occluded_magnitude >= 1.0 {
-                // Other body is fully occluded
-                Some(fully_occluded_magnitude)
-            } else {
-                // If the near object is partially (but not fully) occluding the far object
-                Some((radius_sum - angle_to).div(2.0).clamp(0.0, near_point.0) / far_point.0)
-            }
-        } else {
-            // No eclipse has occurred
-            None
-        }
-    }
-
     /// Get the index that this body is inside
     #[allow(clippy::cast_sign_loss)] // abs is called before conversion
     #[allow(clippy::cast_possible_truncation)] // floor is called before conversion
     fn get_face_id(loc: &Spherical<Float>) -> usize {
         let layer_count = (loc.polar_angle / float::FRAC_PI_8).floor() as usize;
         let x_count = ((loc.azimuthal_angle
-            - if layer_count % 2 != 0 {
+            - if !layer_count.is_multiple_of(2) {
                 float::FRAC_PI_8 / 2.0
             } else {
                 0.0
@@ -150,7 +149,7 @@ impl CollisionGrid {
         } else if row_number == Self::ROWS_PER_SPHERE - 1 {
             // South pole adjacency rules
 
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 [id - Self::CELLS_PER_ROW, id - 1]
             } else {
                 // Previous row
@@ -162,9 +161,9 @@ impl CollisionGrid {
                 (Self::ROWS_PER_SPHERE - 1) * Self::CELLS_PER_ROW..(Self::NUMBER_OF_CELLS),
             )
             .collect()
-        } else if row_number % 2 != 0 {
+        } else if !row_number.is_multiple_of(2) {
             // Middle latitude adjacency rules (odd row)
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 vec![
                     // Previous row
                     id - Self::CELLS_PER_ROW,
@@ -206,7 +205,7 @@ impl CollisionGrid {
             }
         } else {
             // Middle latitude adjacency rules (even row)
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 vec![
                     // Previous row
                     id - Self::CELLS_PER_ROW,
@@ -252,8 +251,6 @@ impl CollisionGrid {
 
 #[cfg(test)]
 mod test {
-    use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
-
     use super::CollisionGrid;
 
     #[test]
@@ -321,28 +318,4 @@ mod test {
             }
         }
     }
-
-    #[test]
-    fn collision_checks() {
-        let close = (0.1, Spherical::UP);
-        let far = (0.1, Spherical::UP);
-
-        assert_eq!(CollisionGrid::check_collision(&close, &far), Some(1.0));
-
-        let close = (0.01, Spherical::UP);
-
-        assert_float_absolute_eq!(CollisionGrid::check_collision(&close, &far).unwrap(), 0.1);
-
-        let close = (0.01, Spherical::new(1.0, 0.09, 0.0));
-
-        assert_float_absolute_eq!(CollisionGrid::check_collision(&close, &far).unwrap(), 0.1);
-
-        let close = (1.0, Spherical::UP);
-
-        assert_float_absolute_eq!(CollisionGrid::check_collision(&close, &far).unwrap(), 10.0);
-
-        let close = (1.0, Spherical::RIGHT);
-
-        assert_eq!(CollisionGrid::check_collision(&close, &far), None);
-    }
 }
@@ -1,30 +1,162 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     fs::OpenOptions,
     io::Write,
     sync::{Arc, RwLock},
 };
 
 use collision_check::CollisionGrid;
-use coordinates::prelude::Spherical;
+use coordinates::{
+    prelude::{Dot, Magnitude, Spherical, Vector3},
+    traits::Positional,
+};
 
-use crate::{output::Output, Float};
+use crate::{
+    output::{FrameInfo, Output},
+    Float,
+};
 
 /// Provides a struct that speeds up eclipse checks
 mod collision_check;
 
 #[derive(Clone, Debug, Default)]
 pub struct Logger {
-    /// List of eclipses that have been observed
-    eclipse_log: Arc<RwLock<HashMap<Arc<std::path::Path>, Vec<String>>>>,
+    /// List of eclipses that have been observed, by the file they are written to. Sorted so
+    /// files are written in the same order every run
+    eclipse_log: Arc<RwLock<BTreeMap<Arc<std::path::Path>, Vec<String>>>>,
+    /// Template of the paths logs are written to
+    file_name_template: crate::output::FileNameTemplate,
 }
 
-/// Gets a list of eclipses that have been observed at this time
-fn get_eclipses_on_frame(
+impl Logger {
+    /// Names the files eclipses are logged to with a [template](crate::output::to_templated_path),
+    /// e.g. `"eclipses/{observatory}{ext}"` to log each observatory's eclipses to one file, or
+    /// [the default](crate::output::DEFAULT_FILE_NAME_TEMPLATE) if `None`. The extension is
+    /// `-eclipses.txt`.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = crate::output::FileNameTemplate::new(template);
+    }
+}
+
+/// Returns the [magnitude](https://en.wikipedia.org/wiki/Magnitude_of_eclipse) of an eclipse of
+/// the `far` body by the `near` body, each given as its angular radius and direction from the
+/// observer, or `None` if their discs don't overlap.
+///
+/// The magnitude is the fraction of the far body's diameter that is covered, measured along the
+/// line through both centers. It is at least 1 when the far body is completely covered, growing
+/// as the near body covers more sky past its edges, and less than 1 for partial and annular
+/// eclipses.
+#[must_use]
+pub fn eclipse_magnitude(
+    near: (Float, Spherical<Float>),
+    far: (Float, Spherical<Float>),
+) -> Option<Float> {
+    let (near_radius, near_direction) = near;
+    let (far_radius, far_direction) = far;
+    let separation = near_direction.angle_to(&far_direction);
+    if separation >= near_radius + far_radius || far_radius <= 0.0 {
+        // No eclipse has occurred
+        return None;
+    }
+
+    let covered = if separation + far_radius <= near_radius {
+        // Total, the near body's disc reaches past the far body's disc on both sides
+        near_radius + far_radius - separation
+    } else {
+        // Partial or annular, the overlap of the discs along the line through their centers
+        (separation + near_radius).min(far_radius) - (separation - near_radius).max(-far_radius)
+    };
+    Some(covered / (2.0 * far_radius))
+}
+
+/// Which part of a body's shadow another body is in, see [`shadow_magnitude`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shadow {
+    /// Only part of the light source is hidden, so the body is only dimmed a little
+    Penumbral,
+    /// All of the light source is hidden from some of the body
+    Umbral,
+}
+
+impl std::fmt::Display for Shadow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Penumbral => "penumbral",
+            Self::Umbral => "umbral",
+        })
+    }
+}
+
+/// Returns how deep the `far` body is in the shadow the `near` body casts away from the `light`
+/// source, like the Moon in the Earth's shadow during a lunar eclipse, or `None` if it isn't in
+/// it. Each body is given as its radius and position, in the same units and frame.
+///
+/// The magnitude is the fraction of the far body's diameter inside the shadow, measured across
+/// the shadow through the far body's center, like [`eclipse_magnitude`]. It is the magnitude in
+/// the umbra if any of the far body is in it, otherwise the magnitude in the penumbra.
+#[must_use]
+pub fn shadow_magnitude(
+    light: (Float, Vector3<Float>),
+    near: (Float, Vector3<Float>),
+    far: (Float, Vector3<Float>),
+) -> Option<(Shadow, Float)> {
+    let (light_radius, light_position) = light;
+    let (near_radius, near_position) = near;
+    let (far_radius, far_position) = far;
+
+    let axis = near_position - light_position;
+    let light_distance = axis.magnitude();
+    if light_distance <= 0.0 || far_radius <= 0.0 {
+        return None;
+    }
+    let axis = axis / light_distance;
+
+    // How far behind the near body the far body is along the shadow, and how far from its axis
+    let relative = far_position - near_position;
+    let behind = relative.dot(&axis);
+    if behind <= 0.0 {
+        // Between the light and the near body, or beside it
+        return None;
+    }
+    let from_axis = (relative - axis * behind).magnitude();
+
+    // Radii of the shadows at the far body, the umbra narrows and the penumbra widens with
+    // distance from the near body
+    let umbra = near_radius - behind * (light_radius - near_radius) / light_distance;
+    let penumbra = near_radius + behind * (light_radius + near_radius) / light_distance;
+
+    let magnitude =
+        |shadow_radius: Float| (shadow_radius + far_radius - from_axis) / (2.0 * far_radius);
+    if umbra > 0.0 && magnitude(umbra) > 0.0 {
+        Some((Shadow::Umbral, magnitude(umbra)))
+    } else if magnitude(penumbra) > 0.0 {
+        Some((Shadow::Penumbral, magnitude(penumbra)))
+    } else {
+        None
+    }
+}
+
+/// An eclipse seen from an observatory, see [`Program::eclipse_report`](crate::program::Program::eclipse_report)
+#[derive(Clone, Debug, PartialEq)]
+pub struct EclipseEvent {
+    /// Time of the frame the eclipse was seen in, in hours
+    pub time: i128,
+    /// [Directory](crate::program::Program::observatory_directories) of the observatory that saw
+    /// the eclipse
+    pub observatory: String,
+    /// Name of the body in front
+    pub near: String,
+    /// Name of the body being covered
+    pub far: String,
+    /// [Magnitude](eclipse_magnitude) of the eclipse
+    pub magnitude: Float,
+}
+
+/// Returns the names of the near and far bodies and the magnitude of every eclipse in the
+/// observations, sorted by the names so they are listed in the same order every run
+pub(crate) fn eclipses_in(
     observations: &[(crate::body::Arc, Spherical<Float>)],
-    time: &str,
-) -> Vec<String> {
-    // TODO: Handle lunar eclipses
+) -> Vec<(String, String, Float)> {
     // Create an object to speed up searches similar to a hashgrid or oct-tree
     let grid = CollisionGrid::new(observations);
 
@@ -34,37 +166,96 @@ fn get_eclipses_on_frame(
         // Get name of the near body
         let name =
             p.0.read()
-                .map(|p| p.get_name())
+                .map(|p| p.get_name().to_string())
                 .unwrap_or("Poisoned Body".into());
 
         for (other, magnitude) in grid.collisions(p) {
             // For each body this body has eclipsed, get the name of the far body
             let other_name = other
                 .read()
-                .map(|b| b.get_name())
+                .map(|b| b.get_name().to_string())
                 .unwrap_or("Poisoned Body".into());
 
-            results.push(format!("Time={time}, There was an eclipse between {name} and {other_name} with magnitude {magnitude:.2}"));
+            results.push((name.clone(), other_name, magnitude));
         }
     }
 
+    results.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    results
+}
+
+/// Returns the names of the parents and the bodies in their shadows, the shadow they are in, and
+/// its [magnitude](shadow_magnitude) for every observed body in the shadow of its parent, sorted
+/// by the names so they are listed in the same order every run. The light source is the body's
+/// [light source](crate::body::Body::get_light_source), and positions are found at `time`.
+pub(crate) fn lunar_eclipses_in(
+    observations: &[(crate::body::Arc, Spherical<Float>)],
+    time: Float,
+) -> Vec<(String, String, Shadow, Float)> {
+    /// Radius and position of a body, if it has a radius and isn't poisoned
+    fn extent(body: &crate::body::Body, time: Float) -> Option<(Float, Vector3<Float>)> {
+        Some((body.radius?, body.get_absolute_position(time)))
+    }
+
+    let mut results: Vec<_> = observations
+        .iter()
+        .filter_map(|(body, _)| {
+            let far = body.read().ok()?;
+            let light = far.get_light_source()?;
+            let near = far.parent.as_ref()?.upgrade()?;
+            if std::sync::Arc::ptr_eq(&light, &near) {
+                // Lit directly by its parent, so nothing can cast a shadow between them
+                return None;
+            }
+            let light = light.read().ok()?;
+            let near = near.read().ok()?;
+
+            let (shadow, magnitude) = shadow_magnitude(
+                extent(&light, time)?,
+                extent(&near, time)?,
+                extent(&far, time)?,
+            )?;
+            Some((
+                near.get_name().to_string(),
+                far.get_name().to_string(),
+                shadow,
+                magnitude,
+            ))
+        })
+        .collect();
+
+    results.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
     results
 }
 
+/// Gets a list of eclipses that have been observed in the frame at `time`, followed by the lunar
+/// eclipses, whose shadows are found at the frame's `observation_time`
+fn get_eclipses_on_frame(
+    observations: &[(crate::body::Arc, Spherical<Float>)],
+    time: i128,
+    observation_time: Float,
+) -> Vec<String> {
+    let lunar_eclipses = lunar_eclipses_in(observations, observation_time);
+    eclipses_in(observations)
+        .into_iter()
+        .map(|(name, other_name, magnitude)| format!("Time={time}, There was an eclipse between {name} and {other_name} with magnitude {magnitude:.2}"))
+        .chain(lunar_eclipses.into_iter().map(|(name, other_name, shadow, magnitude)| format!("Time={time}, There was a lunar eclipse of {other_name} in the {shadow} shadow of {name} with magnitude {magnitude:.2}")))
+        .collect()
+}
+
 impl Output for Logger {
     fn write_observations(
         &self,
         observations: &[(crate::body::Arc, Spherical<Float>)],
         _constellations: &[crate::constellation::Line],
-        observatory_name: &str,
-        time: i128,
+        frame: FrameInfo<'_>,
         output_path_root: &std::path::Path,
     ) -> Result<(), std::io::Error> {
-        let log = get_eclipses_on_frame(observations, &time.to_string());
-        let path = super::super::to_default_path(
+        let log = get_eclipses_on_frame(observations, frame.time, frame.observation_time);
+        let path = self.file_name_template.frame_path(
             output_path_root,
-            observatory_name,
-            time,
+            frame.directory,
+            frame.time,
             "-eclipses.txt",
         );
         if let Ok(mut hash_map) = self.eclipse_log.write() {
@@ -101,7 +292,121 @@ mod tests {
     use super::*;
     use coordinates::prelude::Spherical;
 
-    use crate::{body::Body, dynamic::fixed::Fixed};
+    use crate::{
+        body::{observatory::Observatory, Body},
+        consts::float,
+        dynamic::{fixed::Fixed, keplerian::Keplerian},
+    };
+
+    #[test]
+    fn magnitudes() {
+        use coordinates::prelude::ThreeDimensionalConsts;
+
+        let far = (0.1, Spherical::UP);
+
+        // Discs the same size exactly overlapping, just total
+        assert_float_absolute_eq!(eclipse_magnitude((0.1, Spherical::UP), far).unwrap(), 1.0);
+        // Centered annular eclipse, the ratio of the diameters: 0.02 / 0.2
+        assert_float_absolute_eq!(eclipse_magnitude((0.01, Spherical::UP), far).unwrap(), 0.1);
+        // Small disc inside the far disc touching its edge, still the ratio of the diameters
+        let near = (0.01, Spherical::new(1.0, 0.09, 0.0));
+        assert_float_absolute_eq!(eclipse_magnitude(near, far).unwrap(), 0.1, 1e-5);
+        // Partial, covering from 0.05 to the edge at 0.1: 0.05 / 0.2
+        let near = (0.1, Spherical::new(1.0, 0.15, 0.0));
+        assert_float_absolute_eq!(eclipse_magnitude(near, far).unwrap(), 0.25, 1e-5);
+        // Centered total eclipse by a much larger disc: (1.0 + 0.1) / 0.2
+        assert_float_absolute_eq!(eclipse_magnitude((1.0, Spherical::UP), far).unwrap(), 5.5);
+        // Off center total eclipse, covering 0.7 past the near edge: (1.0 + 0.1 - 0.3) / 0.2
+        let near = (1.0, Spherical::new(1.0, 0.3, 0.0));
+        assert_float_absolute_eq!(eclipse_magnitude(near, far).unwrap(), 4.0, 1e-5);
+
+        assert_eq!(eclipse_magnitude((1.0, Spherical::RIGHT), far), None);
+        assert_eq!(
+            eclipse_magnitude((0.1, Spherical::new(1.0, 0.21, 0.0)), far),
+            None
+        );
+    }
+
+    #[test]
+    fn shadow_magnitudes() {
+        // A light with twice the radius of the near body ten times its radius away, so the
+        // umbra shrinks by 0.1 and the penumbra grows by 0.3 for each unit behind the near body
+        let light = (2.0, Vector3::from([0.0, 0.0, 0.0]));
+        let near = (1.0, Vector3::from([10.0, 0.0, 0.0]));
+        let far = |y: Float| (0.25, Vector3::from([12.0, y, 0.0]));
+
+        // Centered in the umbra, 0.8 wide there: (0.8 + 0.25) / 0.5
+        let (shadow, magnitude) = shadow_magnitude(light, near, far(0.0)).unwrap();
+        assert_eq!(shadow, Shadow::Umbral);
+        assert_float_absolute_eq!(magnitude, 2.1, 1e-5);
+        // Partly in the umbra: (0.8 + 0.25 - 0.9) / 0.5
+        let (shadow, magnitude) = shadow_magnitude(light, near, far(0.9)).unwrap();
+        assert_eq!(shadow, Shadow::Umbral);
+        assert_float_absolute_eq!(magnitude, 0.3, 1e-5);
+        // Outside the umbra but in the penumbra, 1.6 wide there: (1.6 + 0.25 - 1.2) / 0.5
+        let (shadow, magnitude) = shadow_magnitude(light, near, far(1.2)).unwrap();
+        assert_eq!(shadow, Shadow::Penumbral);
+        assert_float_absolute_eq!(magnitude, 1.3, 1e-5);
+
+        // Outside both shadows
+        assert_eq!(shadow_magnitude(light, near, far(2.0)), None);
+        // Between the light and the near body
+        assert_eq!(
+            shadow_magnitude(light, near, (0.25, Vector3::from([8.0, 0.0, 0.0]))),
+            None
+        );
+    }
+
+    #[test]
+    fn lunar_eclipse_is_logged() {
+        let sun = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        let earth = Body::new(Some(sun.clone()), Fixed::new([10.0, 0.0, 0.0].into()));
+        let moon = Body::new(Some(earth.clone()), Fixed::new([2.0, 0.0, 0.0].into()));
+        {
+            let mut sun = sun.write().unwrap();
+            sun.radius = Some(2.0);
+            sun.luminosity = Some(1.0);
+        }
+        earth.write().unwrap().radius = Some(1.0);
+        moon.write().unwrap().radius = Some(0.25);
+        Body::hydrate_all(&sun, &None);
+
+        // At midnight, with the full moon overhead and the sun below the horizon
+        let observatory = Observatory::new(
+            Spherical::from(coordinates::prelude::Vector3::<Float>::from([
+                1.0, 0.0, 0.0,
+            ])),
+            earth.clone(),
+            Ok("Midnight".into()),
+            vec![],
+        );
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 1);
+
+        let name = |body: &crate::body::Arc| body.read().unwrap().get_name();
+        assert_eq!(
+            get_eclipses_on_frame(&observations, 0, 0.0),
+            [format!(
+                "Time=0, There was a lunar eclipse of {} in the umbral shadow of {} with magnitude 2.10",
+                name(&moon),
+                name(&earth)
+            )]
+        );
+
+        // Half an orbit later the moon is between the earth and the sun, so a frame observed then
+        // has no lunar eclipse whatever its nominal time
+        moon.write().unwrap().dynamic = Box::new(Keplerian::new_with_period(
+            0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 24.0,
+        ));
+        assert_eq!(get_eclipses_on_frame(&observations, 0, 0.0).len(), 1);
+        assert!(get_eclipses_on_frame(&observations, 0, 12.0).is_empty());
+
+        // Moved out of the umbra into the penumbra
+        moon.write().unwrap().dynamic = Box::new(Fixed::new([2.0, 1.2, 0.0].into()));
+        let lunar_eclipses = lunar_eclipses_in(&observatory.observe(0.0), 0.0);
+        assert_eq!(lunar_eclipses.len(), 1);
+        assert_eq!(lunar_eclipses[0].2, Shadow::Penumbral);
+    }
 
     #[test]
     fn eclipse_is_logged_in_correct_format() {
@@ -110,12 +415,12 @@ mod tests {
         let _moon = Body::new(Some(earth.clone()), Fixed::new([-1.0, 0.0, 0.0].into()));
 
         Body::hydrate_all(&sun, &None);
-        let time = 0.0;
+        let time = 0;
 
         let observations: Vec<_> = earth
             .read()
             .unwrap()
-            .get_observations_from_here(time)
+            .get_observations_from_here(0.0)
             .into_iter()
             .map(|(b, loc)| {
                 let loc = Spherical::from(loc);
@@ -124,7 +429,7 @@ mod tests {
             })
             .collect();
 
-        let log = get_eclipses_on_frame(&observations, &time.to_string());
+        let log = get_eclipses_on_frame(&observations, time, 0.0);
 
         assert_eq!(
             log[0],
@@ -134,4 +439,84 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn planet_and_ground_occlude_stars_from_moon() {
+        const PLANET_RADIUS: Float = 0.2;
+        const MOON_DISTANCE: Float = 1.0;
+
+        let planet = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        planet.write().unwrap().radius = Some(PLANET_RADIUS);
+        let moon = Body::new(
+            Some(planet.clone()),
+            Fixed::new([MOON_DISTANCE, 0.0, 0.0].into()),
+        );
+        moon.write().unwrap().radius = Some(0.01);
+        // Behind the planet, and behind the moon as seen from the side facing the planet
+        let star_behind_planet =
+            Body::new(Some(planet.clone()), Fixed::new([-1e6, 0.0, 0.0].into()));
+        let star_behind_moon = Body::new(Some(planet.clone()), Fixed::new([1e6, 0.0, 0.0].into()));
+        Body::hydrate_all(&planet, &None);
+
+        let observatory = Observatory::new(
+            Spherical::from(coordinates::prelude::Vector3::<Float>::from([
+                -1.0, 0.0, 0.0,
+            ])),
+            moon.clone(),
+            Ok("Near side".into()),
+            vec![],
+        )
+        .with_include_host(true);
+        let mut observations = observatory.observe(0.0);
+        let location_of = |body: &crate::body::Arc| {
+            observations
+                .iter()
+                .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+                .map(|(_, location)| *location)
+        };
+
+        // The planet is overhead, filling the expected part of the sky
+        let planet_location = location_of(&planet).unwrap();
+        assert_float_absolute_eq!(planet_location.polar_angle, 0.0, 1e-4);
+        assert_float_relative_eq!(
+            planet
+                .read()
+                .unwrap()
+                .get_angular_radius(planet_location.radius),
+            // Seen from the surface of the moon, a little closer than its center
+            (PLANET_RADIUS / (MOON_DISTANCE - 0.01)).asin(),
+            1e-4
+        );
+        // The ground is below, filling everything below the horizon
+        let ground = location_of(&moon).unwrap();
+        assert_float_absolute_eq!(ground.polar_angle, float::PI);
+        assert_float_absolute_eq!(
+            moon.read().unwrap().get_angular_radius(ground.radius),
+            float::FRAC_PI_2,
+            1e-4
+        );
+        // Only hidden by the ground, so observe it anyway to see it is occluded
+        assert!(location_of(&star_behind_moon).is_none());
+        observations.push((
+            star_behind_moon.clone(),
+            Spherical {
+                radius: 1e6,
+                polar_angle: float::PI,
+                azimuthal_angle: 0.0,
+            },
+        ));
+
+        let log = get_eclipses_on_frame(&observations, 0, 0.0);
+        let name = |body: &crate::body::Arc| body.read().unwrap().get_name();
+        for (near, far) in [(&planet, &star_behind_planet), (&moon, &star_behind_moon)] {
+            let eclipse = format!("between {} and {} ", name(near), name(far));
+            assert!(
+                log.iter().any(|line| line.contains(&eclipse)),
+                "{eclipse} not in {log:#?}"
+            );
+        }
+        // Listed in the same order whatever order the bodies were observed in
+        observations.reverse();
+        assert_eq!(get_eclipses_on_frame(&observations, 0, 0.0), log);
+    }
 }
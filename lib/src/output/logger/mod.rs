@@ -1 +1,3 @@
 pub mod eclipse;
+pub mod parallactic;
+pub mod radial_velocity;
@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    output::{FileNameTemplate, FrameInfo, Output},
+    Float, LocalObservation,
+};
+
+/// Values keyed by the output directory and name of the observatory that recorded them
+type ObservatoryMap<T> = Arc<RwLock<HashMap<(Arc<Path>, Arc<str>), Vec<T>>>>;
+
+/// Nights of each target, keyed by the name of the target
+type TargetNights = HashMap<Arc<str>, Vec<Night>>;
+
+/// Tracks the [parallactic angle](crate::body::observatory::Observatory::parallactic_angle) of target bodies, i.e. how much
+/// the field of view of an alt-azimuth mounted camera rotates while following them. Frames
+/// without an [observatory](crate::output::FrameInfo::observatory) are ignored.
+///
+/// Observations are summarised per night, where a night is an unbroken run of frames with the
+/// target above the horizon.
+///
+/// # Outputs
+///
+/// When flushed, writes `parallactic-angle.csv` (time in hours, angle in degrees) and
+/// `parallactic-angle-nights.txt` to the directory of each observatory by default, see
+/// [`Logger::set_file_name_template`].
+#[derive(Clone, Debug)]
+pub struct Logger {
+    /// Names of the bodies to track
+    targets: Vec<Arc<str>>,
+    /// Times of every frame, whether or not a target was visible
+    frames: ObservatoryMap<i128>,
+    /// Time, target name, and parallactic angle of each visible target
+    angles: ObservatoryMap<(i128, Arc<str>, Float)>,
+    /// Template of the paths logs are written to
+    file_name_template: FileNameTemplate,
+}
+
+/// A night of observations of a single target
+#[derive(Clone, Debug, PartialEq)]
+pub struct Night {
+    /// Time of the first frame the target was visible
+    pub start: i128,
+    /// Time of the last frame the target was visible
+    pub end: i128,
+    /// Smallest parallactic angle in radians
+    pub min: Float,
+    /// Largest parallactic angle in radians
+    pub max: Float,
+    /// Time of the first frame after the target crossed the meridian, if it did
+    pub meridian_crossing: Option<i128>,
+}
+
+impl Logger {
+    /// Creates a logger that tracks the bodies with the given names
+    #[must_use]
+    pub fn new<T: Into<Arc<str>>>(targets: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            targets: targets.into_iter().map(Into::into).collect(),
+            frames: Arc::default(),
+            angles: Arc::default(),
+            file_name_template: FileNameTemplate::default(),
+        }
+    }
+
+    /// Names the files angles are logged to with a [template](crate::output::to_templated_path),
+    /// e.g. `"{observatory}-{time}{ext}"`, or [the default](crate::output::DEFAULT_FILE_NAME_TEMPLATE)
+    /// if `None`. `{time}` is filled in with `parallactic-angle` or `parallactic-angle-nights`,
+    /// since each log covers every frame.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Returns the nights that each target was observed, keyed by the directory of the observatory
+    /// and then the name of the target.
+    #[must_use]
+    pub fn nights(&self) -> HashMap<Arc<Path>, TargetNights> {
+        self.nights_by_observatory()
+            .into_iter()
+            .map(|((root, name), nights)| (root.join(&*name).into(), nights))
+            .collect()
+    }
+
+    /// Returns the nights that each target was observed, keyed by the output directory and name
+    /// of the observatory and then the name of the target
+    fn nights_by_observatory(&self) -> HashMap<(Arc<Path>, Arc<str>), TargetNights> {
+        let (Ok(frames), Ok(angles)) = (self.frames.read(), self.angles.read()) else {
+            return HashMap::new();
+        };
+
+        let mut result = HashMap::new();
+        for (observatory, times) in frames.iter() {
+            let mut times = times.clone();
+            times.sort_unstable();
+
+            let angles = angles.get(observatory).map_or(&[][..], Vec::as_slice);
+            let nights = self
+                .targets
+                .iter()
+                .map(|target| {
+                    let target_angles: HashMap<i128, Float> = angles
+                        .iter()
+                        .filter(|(_, name, _)| name == target)
+                        .map(|&(time, _, angle)| (time, angle))
+                        .collect();
+                    (target.clone(), split_nights(&times, &target_angles))
+                })
+                .collect();
+
+            result.insert(observatory.clone(), nights);
+        }
+
+        result
+    }
+}
+
+/// Groups the angles into runs of consecutive frames
+fn split_nights(times: &[i128], angles: &HashMap<i128, Float>) -> Vec<Night> {
+    let mut nights = Vec::new();
+    let mut current: Option<(Night, Float)> = None;
+
+    for time in times {
+        match (angles.get(time), current.as_mut()) {
+            (Some(&angle), None) => {
+                current = Some((
+                    Night {
+                        start: *time,
+                        end: *time,
+                        min: angle,
+                        max: angle,
+                        meridian_crossing: None,
+                    },
+                    angle,
+                ));
+            }
+            (Some(&angle), Some((night, previous))) => {
+                night.end = *time;
+                night.min = night.min.min(angle);
+                night.max = night.max.max(angle);
+                if night.meridian_crossing.is_none() && *previous < 0.0 && angle >= 0.0 {
+                    night.meridian_crossing = Some(*time);
+                }
+                *previous = angle;
+            }
+            (None, _) => nights.extend(current.take().map(|(night, _)| night)),
+        }
+    }
+    nights.extend(current.map(|(night, _)| night));
+
+    nights
+}
+
+impl Output for Logger {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        _constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let Some(observatory) = frame.observatory else {
+            return Ok(());
+        };
+        let key: (Arc<Path>, Arc<str>) = (output_path_root.into(), frame.directory.into());
+        let time = frame.time;
+        let angles: Vec<_> = observations
+            .iter()
+            .filter_map(|(body, location)| {
+                let name = body.read().ok()?.get_name();
+                if !self.targets.contains(&name) {
+                    return None;
+                }
+                observatory
+                    .parallactic_angle(*location, frame.observation_time)
+                    .map(|angle| (time, name, angle))
+            })
+            .collect();
+
+        if let Ok(mut hash_map) = self.frames.write() {
+            hash_map.entry(key.clone()).or_default().push(time);
+        }
+        if let Ok(mut hash_map) = self.angles.write() {
+            hash_map.entry(key).or_default().extend(angles);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), std::io::Error> {
+        if let Ok(angles) = self.angles.read() {
+            for ((root, observatory_name), angles) in angles.iter() {
+                let mut angles = angles.clone();
+                angles.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+                let mut contents = String::from("time,target,parallacticAngle");
+                for (time, name, angle) in angles {
+                    contents.push_str(&format!("\n{time},{name},{}", angle.to_degrees()));
+                }
+
+                let path = self.file_name_template.observatory_path(
+                    root,
+                    observatory_name,
+                    "parallactic-angle",
+                    ".csv",
+                );
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, contents)?;
+            }
+        }
+
+        for ((root, observatory_name), targets) in self.nights_by_observatory() {
+            let mut lines = Vec::new();
+            for target in &self.targets {
+                for (i, night) in targets.get(target).into_iter().flatten().enumerate() {
+                    let mut line = format!(
+                        "{target} night {}: t={}..{}, parallactic angle {:.1}° to {:.1}°, field rotation {:.1}°",
+                        i + 1,
+                        night.start,
+                        night.end,
+                        night.min.to_degrees(),
+                        night.max.to_degrees(),
+                        (night.max - night.min).to_degrees()
+                    );
+                    if let Some(crossing) = night.meridian_crossing {
+                        line.push_str(&format!(", crossed the meridian by t={crossing}"));
+                    }
+                    lines.push(line);
+                }
+            }
+
+            let path = self.file_name_template.observatory_path(
+                &root,
+                &observatory_name,
+                "parallactic-angle-nights",
+                ".txt",
+            );
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, lines.join("\n"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+    use super::*;
+    use crate::{
+        body::{observatory::Observatory, rotating::Rotating, Body},
+        consts::float,
+        dynamic::fixed::Fixed,
+    };
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn nights_cross_meridian() {
+        // A star at declination 20° seen from latitude 40°N, crossing the meridian at t=12
+        let earth = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        earth.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        let star = Body::new(
+            Some(earth.clone()),
+            Fixed::new(
+                Spherical {
+                    radius: 1e3,
+                    polar_angle: Float::to_radians(70.0),
+                    azimuthal_angle: float::PI,
+                }
+                .into(),
+            ),
+        );
+        star.write().unwrap().name = Some("Star").into();
+        Body::hydrate_all(&earth, &None);
+        let observatory = Observatory::new(
+            Spherical {
+                radius: 1.0,
+                polar_angle: Float::to_radians(50.0),
+                azimuthal_angle: 0.0,
+            },
+            earth,
+            Ok("Earth-like".into()),
+            vec![],
+        );
+
+        let logger = Logger::new(["Star"]);
+        for time in 0..48 {
+            logger
+                .write_observations(
+                    &observatory.observe(time as Float),
+                    &[],
+                    FrameInfo::new("Earth-like", time).with_observatory(&observatory),
+                    Path::new(""),
+                )
+                .unwrap();
+        }
+
+        let nights = logger.nights();
+        let nights = &nights.values().next().unwrap()[&Arc::from("Star")];
+        assert_eq!(nights.len(), 2);
+        // The angle is zero at the crossing, so rounding decides which frame it lands on
+        assert!(matches!(nights[0].meridian_crossing, Some(12 | 13)));
+        assert!(matches!(nights[1].meridian_crossing, Some(36 | 37)));
+        for night in nights {
+            assert!(night.min < 0.0 && night.max > 0.0);
+        }
+    }
+}
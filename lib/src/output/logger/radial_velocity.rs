@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use coordinates::prelude::{
+    Dot, Magnitude, Positional, Spherical, ThreeDimensionalConsts, Vector3,
+};
+
+use crate::{
+    output::{FileNameTemplate, FrameInfo, Output},
+    Float, LocalObservation,
+};
+
+/// The output directory and name of an observatory
+type ObservatoryKey = (Arc<Path>, Arc<str>);
+
+/// Series of `(time, value)` pairs keyed by the observatory that made them
+type SeriesMap<T> = Arc<RwLock<HashMap<ObservatoryKey, Vec<(i128, T)>>>>;
+
+/// Records the radial velocity of a target star, induced by the bodies orbiting it, along with any
+/// transits of those bodies across the star's disc.
+///
+/// The reflex motion of the star is approximated by weighting the position of each companion by
+/// its mass relative to the star, so only a star with a [mass](crate::body::Body::get_mass) is
+/// measured, and only companions that have a mass and appear in the observations contribute. Velocities are the time derivative of the
+/// line of sight displacement between frames, so step sizes should be small compared to the
+/// orbital periods of the companions.
+///
+/// # Outputs
+///
+/// When flushed, writes `radial-velocity.csv` (time in hours, velocity in light-seconds per hour,
+/// positive when receding) and `transits.txt` to the directory of each observatory by default, see
+/// [`Logger::set_file_name_template`].
+#[derive(Clone, Debug)]
+pub struct Logger {
+    /// Name of the star whose reflex motion is measured
+    target: Arc<str>,
+    /// Line of sight displacement of the target star at each observed time
+    displacements: SeriesMap<Float>,
+    /// Names of companions that were in front of the target star at each observed time
+    transits: SeriesMap<Arc<str>>,
+    /// Template of the paths logs are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl Logger {
+    /// Creates a logger that measures the star with the given name
+    #[must_use]
+    pub fn new<T: Into<Arc<str>>>(target: T) -> Self {
+        Self {
+            target: target.into(),
+            displacements: Arc::default(),
+            transits: Arc::default(),
+            file_name_template: FileNameTemplate::default(),
+        }
+    }
+
+    /// Names the files velocities and transits are logged to with a
+    /// [template](crate::output::to_templated_path), e.g. `"{observatory}-{time}{ext}"`, or
+    /// [the default](crate::output::DEFAULT_FILE_NAME_TEMPLATE) if `None`. `{time}` is filled in
+    /// with `radial-velocity` or `transits`, since each log covers every frame.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Returns the radial velocity series (sorted by time) recorded by each observatory, keyed by
+    /// the directory of that observatory.
+    #[must_use]
+    pub fn radial_velocities(&self) -> HashMap<Arc<Path>, Vec<(i128, Float)>> {
+        by_directory(self.radial_velocities_by_observatory())
+    }
+
+    /// Returns the radial velocity series (sorted by time) recorded by each observatory
+    fn radial_velocities_by_observatory(&self) -> HashMap<ObservatoryKey, Vec<(i128, Float)>> {
+        self.displacements
+            .read()
+            .map(|map| {
+                map.iter()
+                    .map(|(key, series)| (key.clone(), differentiate(series)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the times (sorted) that each companion was seen transiting the target star, keyed
+    /// by the directory of the observatory.
+    #[must_use]
+    pub fn transits(&self) -> HashMap<Arc<Path>, Vec<(i128, Arc<str>)>> {
+        by_directory(self.transits_by_observatory())
+    }
+
+    /// Returns the times (sorted) that each companion was seen transiting the target star by each
+    /// observatory
+    fn transits_by_observatory(&self) -> HashMap<ObservatoryKey, Vec<(i128, Arc<str>)>> {
+        self.transits
+            .read()
+            .map(|map| {
+                map.iter()
+                    .map(|(key, transits)| {
+                        let mut transits = transits.clone();
+                        transits.sort_by_key(|(time, _)| *time);
+                        (key.clone(), transits)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Finds the target star in the observations, returning the component of its reflex
+    /// displacement along the line of sight and the names of any companions transiting it.
+    fn measure(&self, observations: &[LocalObservation]) -> Option<(Float, Vec<Arc<str>>)> {
+        let (star, star_location) = observations
+            .iter()
+            .find(|(b, _)| b.read().is_ok_and(|b| b.get_name() == self.target))?;
+        let star = star.read().ok()?;
+        let star_mass = star.get_mass()?;
+        let star_angular_radius = star.get_angular_radius(star_location.radius);
+        let star_vector = Vector3::from(star_location);
+
+        let mut displacement = Vector3::ORIGIN;
+        let mut transits = Vec::new();
+        for companion in star.get_children() {
+            let Some((_, location)) = observations.iter().find(|(b, _)| Arc::ptr_eq(b, companion))
+            else {
+                // Companion is below the horizon
+                continue;
+            };
+            if let Ok(companion) = companion.read() {
+                if let Some(mass) = companion.get_mass() {
+                    // The star moves opposite to its companions around their barycenter
+                    displacement =
+                        displacement - (Vector3::from(location) - star_vector) * (mass / star_mass);
+                }
+
+                if is_transiting(location, star_location, star_angular_radius) {
+                    transits.push(companion.get_name());
+                }
+            }
+        }
+
+        let line_of_sight = star_vector / star_vector.magnitude();
+        Some((line_of_sight.dot(&displacement), transits))
+    }
+}
+
+/// Keys values by the directory of each observatory instead
+fn by_directory<T>(map: HashMap<ObservatoryKey, T>) -> HashMap<Arc<Path>, T> {
+    map.into_iter()
+        .map(|((root, name), value)| (root.join(&*name).into(), value))
+        .collect()
+}
+
+/// Returns true if the companion is closer than the star and overlapping its disc
+fn is_transiting(
+    companion: &Spherical<Float>,
+    star: &Spherical<Float>,
+    star_angular_radius: Float,
+) -> bool {
+    companion.radius < star.radius && companion.angle_to(star) < star_angular_radius
+}
+
+/// Converts a series of displacements into velocities using central differences (one sided
+/// differences at either end of the series)
+#[allow(clippy::cast_precision_loss)] // Differences between times are small
+fn differentiate(series: &[(i128, Float)]) -> Vec<(i128, Float)> {
+    let mut series = series.to_vec();
+    series.sort_by_key(|(time, _)| *time);
+
+    if series.len() < 2 {
+        return Vec::new();
+    }
+
+    let last = series.len() - 1;
+    return (0..=last)
+        .map(|i| {
+            let (previous_time, previous) = series[i.saturating_sub(1)];
+            let (next_time, next) = series[(i + 1).min(last)];
+            (
+                series[i].0,
+                (next - previous) / (next_time - previous_time) as Float,
+            )
+        })
+        .collect();
+}
+
+impl Output for Logger {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        _constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        if let Some((displacement, transits)) = self.measure(observations) {
+            let key: ObservatoryKey = (output_path_root.into(), frame.directory.into());
+
+            if let Ok(mut hash_map) = self.displacements.write() {
+                hash_map
+                    .entry(key.clone())
+                    .or_default()
+                    .push((frame.time, displacement));
+            }
+
+            if let Ok(mut hash_map) = self.transits.write() {
+                hash_map
+                    .entry(key)
+                    .or_default()
+                    .extend(transits.into_iter().map(|name| (frame.time, name)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), std::io::Error> {
+        for ((root, observatory_name), series) in self.radial_velocities_by_observatory() {
+            let path = self.file_name_template.observatory_path(
+                &root,
+                &observatory_name,
+                "radial-velocity",
+                ".csv",
+            );
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let mut contents = String::from("time,radialVelocity");
+            for (time, velocity) in series {
+                contents.push_str(&format!("\n{time},{velocity}"));
+            }
+            std::fs::write(path, contents)?;
+        }
+
+        for ((root, observatory_name), transits) in self.transits_by_observatory() {
+            let path = self.file_name_template.observatory_path(
+                &root,
+                &observatory_name,
+                "transits",
+                ".txt",
+            );
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let contents: Vec<_> = transits
+                .iter()
+                .map(|(time, name)| format!("Time={time}, {name} transited {}", self.target))
+                .collect();
+            std::fs::write(path, contents.join("\n"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+    use super::*;
+    use crate::{
+        body::{observatory::Observatory, Body},
+        consts::float,
+        dynamic::{fixed::Fixed, keplerian::Keplerian},
+    };
+
+    const PERIOD: Float = 48.0;
+    const SEMI_MAJOR_AXIS: Float = 10.0;
+    const STAR_MASS: Float = 1048.0;
+
+    /// A hot jupiter on a circular, edge on orbit, observed from far away
+    fn hot_jupiter(planet_mass: Float) -> (crate::body::Arc, Observatory) {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let planet = Body::new(
+            Some(star.clone()),
+            Keplerian::new_with_period(0.0, SEMI_MAJOR_AXIS, 0.0, 0.0, 0.0, 0.0, PERIOD),
+        );
+        // An observer at rest relative to the system, far enough away to see the whole orbit
+        let observer = Body::new(
+            Some(star.clone()),
+            Fixed::new(Vector3 {
+                x: 1e3,
+                y: 0.0,
+                z: 0.0,
+            }),
+        );
+
+        if let Ok(mut star) = star.write() {
+            star.name = Some("Star").into();
+            star.mass = Some(STAR_MASS);
+            star.radius = Some(2.321);
+        }
+        planet.write().unwrap().mass = Some(planet_mass);
+        Body::hydrate_all(&root, &None);
+
+        // Look back towards the star
+        let observatory = Observatory::new(Spherical::LEFT, observer, Ok("Distant".into()), vec![]);
+        (root, observatory)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn measure_radial_velocity(planet_mass: Float) -> Vec<(i128, Float)> {
+        let (_root, observatory) = hot_jupiter(planet_mass);
+        let logger = Logger::new("Star");
+
+        for time in 0..96 {
+            logger
+                .write_observations(
+                    &observatory.observe(time as Float),
+                    &[],
+                    FrameInfo::new(&observatory.get_name(), time),
+                    Path::new(""),
+                )
+                .unwrap();
+        }
+
+        logger.radial_velocities().into_values().next().unwrap()
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn sinusoid_with_planet_period() {
+        const PLANET_MASS: Float = 10.0;
+        let series = measure_radial_velocity(PLANET_MASS);
+        assert_eq!(series.len(), 96);
+
+        let expected_amplitude = PLANET_MASS / STAR_MASS * float::TAU * SEMI_MAJOR_AXIS / PERIOD;
+        // Skip the ends where one sided differences are used
+        for &(time, velocity) in &series[1..95] {
+            let phase = time as Float / PERIOD * float::TAU;
+            // The planet starts on the near side, moving away from the observer, so the star moves
+            // towards the observer
+            let expected = -expected_amplitude * phase.sin();
+            assert_float_absolute_eq!(velocity, expected, expected_amplitude * 0.02);
+        }
+
+        // Repeats every period
+        for (&(_, a), &(_, b)) in series[1..47].iter().zip(&series[49..95]) {
+            assert_float_absolute_eq!(a, b, expected_amplitude * 0.02);
+        }
+    }
+
+    #[test]
+    fn amplitude_scales_with_mass() {
+        let light = measure_radial_velocity(5.0);
+        let heavy = measure_radial_velocity(10.0);
+
+        let amplitude = |series: &[(i128, Float)]| {
+            series
+                .iter()
+                .map(|(_, v)| v.abs())
+                .fold(0.0 as Float, Float::max)
+        };
+
+        assert_float_relative_eq!(amplitude(&heavy), 2.0 * amplitude(&light), 1e-2);
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn transits_once_per_period() {
+        let (_root, observatory) = hot_jupiter(1.0);
+        let logger = Logger::new("Star");
+
+        for time in 0..96 {
+            logger
+                .write_observations(
+                    &observatory.observe(time as Float),
+                    &[],
+                    FrameInfo::new(&observatory.get_name(), time),
+                    Path::new(""),
+                )
+                .unwrap();
+        }
+
+        let transits = logger.transits().into_values().next().unwrap();
+        let times: Vec<_> = transits.iter().map(|(time, _)| *time).collect();
+        assert!(times.contains(&0));
+        assert!(times.contains(&48));
+        assert!(!times.contains(&24));
+    }
+}
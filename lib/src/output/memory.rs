@@ -0,0 +1,143 @@
+use std::{
+    io::Error,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use super::{recording::Frame, FrameInfo, Output};
+use crate::{constellation::Line, LocalObservation};
+
+/// An output that keeps every frame in memory instead of writing files, so they can be inspected
+/// after a run with [`Memory::frames`].
+///
+/// Clones share the same frames, so keep a clone of the output given to a
+/// [`Program`](crate::program::Program) to read them back.
+#[derive(Clone, Debug, Default)]
+pub struct Memory {
+    /// Frames that have been observed, in the order they arrived
+    frames: Arc<RwLock<Vec<Frame>>>,
+    /// Whether frames are kept without constellation lines, see [`Memory::without_constellations`]
+    skip_constellations: bool,
+}
+
+impl Memory {
+    /// Creates an output with no frames
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an output with no frames that doesn't
+    /// [want constellation lines](Output::wants_constellations), for when only the bodies matter
+    #[must_use]
+    pub fn without_constellations() -> Self {
+        Self {
+            skip_constellations: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the frames observed so far, ascending by time and then observatory name
+    #[must_use]
+    pub fn frames(&self) -> Vec<Frame> {
+        let mut frames = self
+            .frames
+            .read()
+            .map(|frames| frames.clone())
+            .unwrap_or_default();
+        // Frames arrive out of order from parallel observations
+        frames.sort_by(|a, b| {
+            a.time
+                .cmp(&b.time)
+                .then_with(|| a.directory.cmp(&b.directory))
+        });
+        frames
+    }
+
+    /// Forgets every frame observed so far
+    pub fn clear(&self) {
+        if let Ok(mut frames) = self.frames.write() {
+            frames.clear();
+        }
+    }
+}
+
+impl Output for Memory {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[Line],
+        frame: FrameInfo<'_>,
+        _output_path_root: &Path,
+    ) -> Result<(), Error> {
+        if let Ok(mut frames) = self.frames.write() {
+            frames.push(Frame {
+                directory: frame.directory.to_owned(),
+                time: frame.time,
+                observation_time: frame.observation_time,
+                observations: observations.to_vec(),
+                constellations: constellations.to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Lines are kept unless the output was made [without them](Self::without_constellations)
+    fn wants_constellations(&self) -> bool {
+        !self.skip_constellations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        output::{svg::Svg, to_default_path},
+        program::Program,
+        projection::StatelessOrthographic,
+    };
+
+    #[test]
+    fn matches_svg_files() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-memory-matches-svg-files");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let memory = Memory::new();
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../../assets/solar-system.program.json"))
+                .unwrap();
+        program.add_output(Box::new(memory.clone()));
+        program.add_output(Box::new(Svg::new(StatelessOrthographic())));
+        program.set_output_path(&output_root);
+
+        program.make_observations(0, 24, Some(6)).unwrap();
+
+        let frames = memory.frames();
+        let files: usize = std::fs::read_dir(&output_root)
+            .unwrap()
+            .map(|observatory| {
+                std::fs::read_dir(observatory.unwrap().path())
+                    .unwrap()
+                    .count()
+            })
+            .sum();
+        assert_eq!(frames.len(), 4 * program.observatories().len());
+        assert_eq!(frames.len(), files);
+        for frame in &frames {
+            let path = to_default_path(&output_root, &frame.directory, frame.time, ".svg");
+            let document = std::fs::read_to_string(&path).unwrap();
+            // Every body is a circle, on top of the circle around the edge of the chart
+            assert_eq!(
+                document.matches("<circle").count() - 1,
+                frame.observations.len(),
+                "{}",
+                path.display()
+            );
+        }
+
+        memory.clear();
+        assert!(memory.frames().is_empty());
+    }
+}
@@ -5,13 +5,79 @@ use std::{
 
 use dyn_clone::DynClone;
 
-use crate::LocalObservation;
+use crate::{body::observatory::Observatory, Float, LocalObservation};
 
+/// Outputs as they are written in program files
+pub mod config;
+/// An output for tables of observations, for analysis in spreadsheets or dataframes
+pub mod csv;
+/// An output that writes each frame as a line of JSON, for other programs to read
+pub mod jsonl;
+/// An output for PNG images, for charts too dense to draw as SVG files
+pub mod png;
 /// An output for SVG files
 pub mod svg;
+/// Structural comparison of SVG documents, tolerant of element order and float rounding
+pub mod svg_diff;
 
 pub mod logger;
 
+/// Keep observations in memory to inspect after a run
+pub mod memory;
+
+/// Record observations to a file, and replay them into other outputs
+pub mod recording;
+
+/// Which observatory made the observations given to [`Output::write_observations`], and when
+#[derive(Clone, Copy, Debug)]
+pub struct FrameInfo<'a> {
+    /// The observatory that made the observations, or `None` if it isn't known, e.g. for
+    /// [replayed](recording::replay) frames
+    pub observatory: Option<&'a Observatory>,
+    /// Name of the directory the observatory's files are written to, see
+    /// [`Program::observatory_directories`](crate::program::Program::observatory_directories)
+    pub directory: &'a str,
+    /// Time of the frame, in hours
+    pub time: i128,
+    /// Time the observatory actually observed the sky at, in hours. This includes the program's
+    /// [epoch](crate::program::Program::set_epoch) and the observatory's time offset, so
+    /// positions an output computes itself should be evaluated at this time rather than `time`
+    pub observation_time: Float,
+}
+
+impl<'a> FrameInfo<'a> {
+    /// Describes a frame at `time` hours whose files are written to `directory`, without an
+    /// observatory, observed at `time` hours
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(directory: &'a str, time: i128) -> Self {
+        Self {
+            observatory: None,
+            directory,
+            time,
+            observation_time: time as Float,
+        }
+    }
+
+    /// Describes the same frame as observed at `observation_time` hours
+    #[must_use]
+    pub fn observed_at(self, observation_time: Float) -> Self {
+        Self {
+            observation_time,
+            ..self
+        }
+    }
+
+    /// Describes the same frame as made by `observatory`
+    #[must_use]
+    pub fn with_observatory(self, observatory: &'a Observatory) -> Self {
+        Self {
+            observatory: Some(observatory),
+            ..self
+        }
+    }
+}
+
 /// The trait for structs that output to a file. It may be made more general in future to better
 /// accommodate non-file outputs e.g. console loggers, or outputs to screen or streams
 pub trait Output: DynClone + Debug + Sync {
@@ -23,8 +89,7 @@ pub trait Output: DynClone + Debug + Sync {
         &self,
         observations: &[LocalObservation],
         constellations: &[crate::constellation::Line],
-        observatory_name: &str,
-        time: i128,
+        frame: FrameInfo<'_>,
         output_path_root: &Path,
     ) -> Result<(), std::io::Error>;
 
@@ -34,9 +99,33 @@ pub trait Output: DynClone + Debug + Sync {
     fn flush(&self) -> Result<(), std::io::Error> {
         Ok(())
     }
+
+    /// Called once every observatory's observations at `time` have been given to
+    /// [`Self::write_observations`], e.g. to write a frame of an animation made from all of them.
+    /// With [ordered delivery](crate::program::Program::set_ordered_delivery) this is called in
+    /// ascending order of time, otherwise frames may finish in any order.
+    ///
+    /// # Errors
+    /// Implementations may return an error if there is an error in the filesystem, as in
+    /// [`Self::write_observations`]
+    fn finalize_frame(&self, _time: i128, _output_path_root: &Path) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    /// Returns true if the output uses the constellation lines it is given. Working out the
+    /// lines takes time, so a [`Program`](crate::program::Program) only does it when at least one
+    /// of its outputs wants them, otherwise outputs are given no lines.
+    fn wants_constellations(&self) -> bool {
+        false
+    }
 }
 dyn_clone::clone_trait_object!(Output);
 
+/// Template of the paths files are written to by default, `<observatory>/<time>.<ext>`, see
+/// [`to_templated_path`]
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "{observatory}/{time}{ext}";
+
+/// Returns the path of the file written for a frame, following [`DEFAULT_FILE_NAME_TEMPLATE`]
 #[must_use]
 pub fn to_default_path(
     output_path_root: &Path,
@@ -44,12 +133,229 @@ pub fn to_default_path(
     time: i128,
     extension: &str,
 ) -> PathBuf {
-    let mut path = output_path_root.to_owned();
-    path.push(observatory_name);
-    path.push(format!("{time:010}{extension}"));
+    to_templated_path(
+        output_path_root,
+        DEFAULT_FILE_NAME_TEMPLATE,
+        observatory_name,
+        time,
+        extension,
+    )
+}
+
+/// Returns the path of the file written for a frame, relative to `output_path_root`, by filling
+/// in the placeholders of `template`:
+/// - `{observatory}` with the name of the observatory
+/// - `{time}` with the time in hours, padded with zeros to 10 digits so files sort in order
+/// - `{ext}` with the extension of the file, including its leading dot
+///
+/// Slashes in the template separate directories, e.g. `"{time}-{observatory}{ext}"` writes every
+/// file directly in the root. Observatory names are passed through [`to_file_name`], so they
+/// can't add directories of their own.
+#[must_use]
+pub fn to_templated_path(
+    output_path_root: &Path,
+    template: &str,
+    observatory_name: &str,
+    time: i128,
+    extension: &str,
+) -> PathBuf {
+    output_path_root.join(fill_template(
+        template,
+        &to_file_name(observatory_name),
+        &format!("{time:010}"),
+        extension,
+    ))
+}
+
+/// Fills in the placeholders of a [template](to_templated_path) with the given values as they are
+fn fill_template(template: &str, observatory_name: &str, time: &str, extension: &str) -> String {
+    // Filled in one pass, so placeholders in observatory names aren't filled in too
+    let mut file_name = String::with_capacity(template.len() + observatory_name.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        file_name.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let placeholder = ["{observatory}", "{time}", "{ext}"]
+            .into_iter()
+            .find(|placeholder| rest.starts_with(placeholder));
+        match placeholder {
+            Some("{observatory}") => file_name.push_str(observatory_name),
+            Some("{time}") => file_name.push_str(time),
+            // `{ext}`
+            Some(_) => file_name.push_str(extension),
+            None => {
+                file_name.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        }
+        rest = &rest[placeholder.map_or(0, str::len)..];
+    }
+    file_name.push_str(rest);
 
-    path
+    file_name
+}
+
+/// The [template](to_templated_path) an output names the files it writes with, shared by every
+/// output that writes files so they can all be named the same way. Defaults to
+/// [`DEFAULT_FILE_NAME_TEMPLATE`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileNameTemplate(Option<String>);
+
+impl FileNameTemplate {
+    /// Creates a template, or the default one if `None`
+    #[must_use]
+    pub fn new(template: Option<String>) -> Self {
+        Self(template)
+    }
+
+    /// Returns the template, with the placeholders still in it
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.0.as_deref().unwrap_or(DEFAULT_FILE_NAME_TEMPLATE)
+    }
+
+    /// Returns the path of the file written for the frame at `time`
+    #[must_use]
+    pub fn frame_path(
+        &self,
+        output_path_root: &Path,
+        observatory_name: &str,
+        time: i128,
+        extension: &str,
+    ) -> PathBuf {
+        to_templated_path(
+            output_path_root,
+            self.as_str(),
+            observatory_name,
+            time,
+            extension,
+        )
+    }
+
+    /// Returns the path of a file that covers every frame of an observatory, with `{time}`
+    /// filled in with `name` instead, e.g. `<observatory>/<name><ext>` by default
+    #[must_use]
+    pub fn observatory_path(
+        &self,
+        output_path_root: &Path,
+        observatory_name: &str,
+        name: &str,
+        extension: &str,
+    ) -> PathBuf {
+        output_path_root.join(fill_template(
+            self.as_str(),
+            &to_file_name(observatory_name),
+            name,
+            extension,
+        ))
+    }
+
+    /// Returns the path of a file that covers every frame of every observatory, with `{time}`
+    /// filled in with `name` and `{observatory}` left empty. Directories left with no name are
+    /// skipped, so it is `<name><ext>` in the root by default.
+    #[must_use]
+    pub fn run_path(&self, output_path_root: &Path, name: &str, extension: &str) -> PathBuf {
+        let file_name = fill_template(self.as_str(), "", name, extension);
+        output_path_root.join(
+            file_name
+                .split('/')
+                .filter(|directory| !directory.is_empty())
+                .collect::<PathBuf>(),
+        )
+    }
+}
+
+/// Returns `name` made safe to use as a single file or directory name: path separators and
+/// characters that Windows doesn't allow in file names are replaced with `_`, as are names that
+/// would refer to the current or parent directory.
+#[must_use]
+pub fn to_file_name(name: &str) -> String {
+    if name.is_empty() || name == "." || name == ".." {
+        return "_".repeat(name.len().max(1));
+    }
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            // NUL is left for the file system to reject
+            c if c.is_control() && c != '\0' => '_',
+            c => c,
+        })
+        .collect()
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_follow_templates() {
+        let root = Path::new("out");
+        assert_eq!(
+            to_default_path(root, "Earth", 42, ".svg"),
+            Path::new("out/Earth/0000000042.svg")
+        );
+        assert_eq!(
+            to_templated_path(root, "{time}-{observatory}{ext}", "Earth", 42, ".png"),
+            Path::new("out/0000000042-Earth.png")
+        );
+        assert_eq!(
+            to_templated_path(
+                root,
+                "charts/{observatory}/t{time}{ext}",
+                "{time}",
+                -1,
+                ".svg"
+            ),
+            Path::new("out/charts/{time}/t-000000001.svg")
+        );
+        assert_eq!(
+            to_templated_path(root, "{unknown}{ext}", "Earth", 0, ".txt"),
+            Path::new("out/{unknown}.txt")
+        );
+    }
+
+    #[test]
+    fn observatory_names_stay_in_one_directory() {
+        let root = Path::new("out");
+        assert_eq!(
+            to_default_path(root, "../Earth/North", 0, ".svg"),
+            Path::new("out/.._Earth_North/0000000000.svg")
+        );
+        assert_eq!(
+            to_default_path(root, "..", 0, ".svg"),
+            Path::new("out/__/0000000000.svg")
+        );
+        assert_eq!(to_file_name(r#"a\b:c*d?e"f<g>h|i"#), "a_b_c_d_e_f_g_h_i");
+        assert_eq!(to_file_name("North Pole (Summer)"), "North Pole (Summer)");
+        assert_eq!(to_file_name(""), "_");
+    }
+
+    #[test]
+    fn summary_files_follow_templates() {
+        let root = Path::new("out");
+        let default = FileNameTemplate::default();
+        assert_eq!(
+            default.observatory_path(root, "Earth", "trail-Moon", ".svg"),
+            Path::new("out/Earth/trail-Moon.svg")
+        );
+        assert_eq!(
+            default.run_path(root, "recording", ".tsv"),
+            Path::new("out/recording.tsv")
+        );
+
+        let flat = FileNameTemplate::new(Some("{observatory}-{time}{ext}".into()));
+        assert_eq!(
+            flat.frame_path(root, "Earth", 7, ".svg"),
+            Path::new("out/Earth-0000000007.svg")
+        );
+        assert_eq!(
+            flat.observatory_path(root, "Earth", "{time}", ".csv"),
+            Path::new("out/Earth-{time}.csv")
+        );
+        assert_eq!(
+            flat.run_path(root, "recording", ".tsv"),
+            Path::new("out/-recording.tsv")
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use std::{fmt::Debug, fs::File, io::BufWriter, path::Path};
+
+use coordinates::prelude::{Polar, Vector2};
+
+use crate::{
+    constellation::DEFAULT_COLOR, consts::float, projection::Projection, Float, LocalObservation,
+};
+
+use super::{
+    svg::{bounds, drawn_radius_of, ProjectedFrame, BOTTOM_RIGHT, MARGIN, TOP_LEFT},
+    FileNameTemplate, FrameInfo, Output,
+};
+
+/// Color of bodies, the same as in [`Svg`](super::svg::Svg) charts
+const BODY_COLOR: [u8; 3] = [0xFF, 0xFF, 0xFF];
+
+/// Color of the outline of the sky
+const OUTLINE_COLOR: [u8; 3] = [0x55, 0x55, 0x55];
+
+/// Width of the outline of the sky, in the same units as the chart
+const OUTLINE_WIDTH: Float = 0.01;
+
+/// Color of the ground and anything else below the horizon drawn along its edge
+const HORIZON_COLOR: [u8; 3] = [0x33, 0xAA, 0x33];
+
+/// Width of the line along the horizon, in the same units as the chart
+const HORIZON_WIDTH: Float = 0.02;
+
+/// Number of straight lines the outline of the sky is drawn with, when the projection doesn't
+/// give its own
+const OUTLINE_SEGMENTS: u8 = 128;
+
+/// A struct that outputs PNG images from observations, drawing bodies, constellations, the ground
+/// and the outline of the sky the same way as [`Svg`](super::svg::Svg) charts, but without any
+/// text. Images stay the same size however many bodies are drawn, so they suit dense star fields
+/// far better than SVGs.
+#[derive(Clone, Debug)]
+pub struct Png<T: Projection> {
+    /// Projection used to draw observations
+    projector: T,
+    /// Width of each image in pixels
+    width: u32,
+    /// Height of each image in pixels
+    height: u32,
+    /// Sky cultures whose constellations are drawn, or `None` to draw every constellation
+    cultures: Option<Vec<String>>,
+    /// Template of the paths images are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl<T: Projection> Png<T> {
+    /// Generates a new Png that draws images `width` by `height` pixels with the given projector.
+    /// The chart is scaled to fit inside the image and centered in it.
+    #[must_use]
+    pub fn new(projector: T, width: u32, height: u32) -> Self {
+        Self {
+            projector,
+            width,
+            height,
+            cultures: None,
+            file_name_template: FileNameTemplate::default(),
+        }
+    }
+
+    /// Returns the width of each image in pixels
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of each image in pixels
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Only draws constellations from these sky cultures, or every constellation if `None` (the
+    /// default). See [`Svg::select_cultures`](super::svg::Svg::select_cultures).
+    pub fn select_cultures(&mut self, cultures: Option<Vec<String>>) {
+        self.cultures = cultures;
+    }
+
+    /// Names the files images are written to with a [template](super::to_templated_path), e.g.
+    /// `"{time}-{observatory}{ext}"`, or [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if
+    /// `None`.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Rasterizes observations to rows of RGB pixels, from the top left of the image
+    #[must_use]
+    pub fn consume_observation(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+    ) -> Vec<u8> {
+        let constellations: Vec<_> = constellations
+            .iter()
+            .filter(|line| line.is_in(self.cultures.as_deref()))
+            .cloned()
+            .collect();
+        // Overlapping bodies are drawn over each other anyway, merging them saves nothing
+        let frame = ProjectedFrame::new(observations, &constellations, &self.projector, None);
+        let mut canvas = Canvas::new(self.width, self.height, frame.boundary.as_deref());
+
+        match &frame.boundary {
+            Some(boundary) => canvas.polyline(boundary, true, OUTLINE_WIDTH, OUTLINE_COLOR),
+            None => {
+                let circle: Vec<Vector2<Float>> = (0..OUTLINE_SEGMENTS)
+                    .map(|i| {
+                        Polar {
+                            radius: 1.0,
+                            theta: float::TAU * Float::from(i) / Float::from(OUTLINE_SEGMENTS),
+                        }
+                        .into()
+                    })
+                    .collect();
+                canvas.polyline(&circle, true, OUTLINE_WIDTH, OUTLINE_COLOR);
+            }
+        }
+
+        // Display constellations behind bodies
+        for (start, end, color, width) in &frame.constellations {
+            canvas.line(*start, *end, *width, parse_color(color));
+        }
+
+        for (_, points) in &frame.horizon_arcs {
+            canvas.polyline(points, false, HORIZON_WIDTH, HORIZON_COLOR);
+        }
+
+        // Display the bodies on top of everything else, furthest first
+        for body in &frame.bodies {
+            canvas.disc(
+                body.location,
+                drawn_radius_of(body.angular_radius),
+                BODY_COLOR,
+            );
+        }
+
+        canvas.pixels
+    }
+}
+
+impl<T> Output for Png<T>
+where
+    T: Projection,
+    T: Clone,
+    T: Debug,
+    T: Send,
+    T: Sync,
+{
+    /// Outputs [`Self::consume_observation`] to a PNG file.
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let path = self.file_name_template.frame_path(
+            output_path_root,
+            frame.directory,
+            frame.time,
+            ".png",
+        );
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()?
+            .write_image_data(&self.consume_observation(observations, constellations))?;
+        Ok(())
+    }
+
+    /// Lines are drawn behind the bodies, like in SVG charts
+    fn wants_constellations(&self) -> bool {
+        true
+    }
+}
+
+/// An image being drawn, with the transformation from chart units to pixels
+struct Canvas {
+    /// Width of the image in pixels
+    width: u32,
+    /// Height of the image in pixels
+    height: u32,
+    /// Rows of RGB pixels from the top left, initially black like the sky
+    pixels: Vec<u8>,
+    /// Pixels per chart unit
+    scale: Float,
+    /// Location in pixels of the chart's origin
+    origin: (Float, Float),
+}
+
+impl Canvas {
+    /// Creates a black image that fits the same view as an [`Svg`](super::svg::Svg) chart with
+    /// the given outline, see [`Projection::boundary_path`]
+    fn new(width: u32, height: u32, boundary: Option<&[Vector2<Float>]>) -> Self {
+        let (left, top, view_width, view_height) = match boundary {
+            Some(boundary) => {
+                let (left, top, right, bottom) = bounds(boundary);
+                (
+                    left - MARGIN,
+                    top - MARGIN,
+                    right - left + 2.0 * MARGIN,
+                    bottom - top + 2.0 * MARGIN,
+                )
+            }
+            None => (TOP_LEFT, TOP_LEFT, BOTTOM_RIGHT, BOTTOM_RIGHT),
+        };
+        #[allow(clippy::cast_precision_loss)] // Images are far smaller than floats count exactly
+        let (pixel_width, pixel_height) = (width as Float, height as Float);
+        let scale = (pixel_width / view_width).min(pixel_height / view_height);
+        // Centered in the image, like `xMidYMid meet`
+        let origin = (
+            (pixel_width - view_width * scale) / 2.0 - left * scale,
+            (pixel_height - view_height * scale) / 2.0 - top * scale,
+        );
+
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 3],
+            scale,
+            origin,
+        }
+    }
+
+    /// Returns the location in pixels of a point on the chart
+    fn to_pixels(&self, point: Vector2<Float>) -> (Float, Float) {
+        (
+            self.origin.0 + point.x * self.scale,
+            self.origin.1 + point.y * self.scale,
+        )
+    }
+
+    /// Blends `color` into the pixel at `x`, `y` by `coverage` from 0 to 1
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 3], coverage: Float) {
+        let index = (y * self.width as usize + x) * 3;
+        for (pixel, channel) in self.pixels[index..index + 3].iter_mut().zip(color) {
+            let blended =
+                Float::from(*pixel) + (Float::from(channel) - Float::from(*pixel)) * coverage;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Between 0 and 255
+            {
+                *pixel = blended.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    /// Calls `coverage` with the center of each pixel from `min` to `max` in pixels, blending in
+    /// `color` by how much of the pixel it says is covered
+    fn fill(
+        &mut self,
+        min: (Float, Float),
+        max: (Float, Float),
+        color: [u8; 3],
+        coverage: impl Fn(Float, Float) -> Float,
+    ) {
+        #[allow(clippy::cast_precision_loss)] // Images are far smaller than floats count exactly
+        let clamp = |value: Float, size: u32| value.floor().clamp(0.0, size as Float);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Clamped to the image
+        let (left, top, right, bottom) = (
+            clamp(min.0, self.width) as usize,
+            clamp(min.1, self.height) as usize,
+            clamp(max.0 + 1.0, self.width) as usize,
+            clamp(max.1 + 1.0, self.height) as usize,
+        );
+        for y in top..bottom {
+            for x in left..right {
+                #[allow(clippy::cast_precision_loss)]
+                let covered = coverage(x as Float + 0.5, y as Float + 0.5).clamp(0.0, 1.0);
+                if covered > 0.0 {
+                    self.blend(x, y, color, covered);
+                }
+            }
+        }
+    }
+
+    /// Draws a filled circle, `radius` in chart units
+    fn disc(&mut self, center: Vector2<Float>, radius: Float, color: [u8; 3]) {
+        let (x, y) = self.to_pixels(center);
+        // At least two pixels across, so the smallest bodies still show up clearly
+        let radius = (radius * self.scale).max(1.0);
+        self.fill(
+            (x - radius, y - radius),
+            (x + radius, y + radius),
+            color,
+            |px, py| radius + 0.5 - (px - x).hypot(py - y),
+        );
+    }
+
+    /// Draws a straight line `width` chart units wide
+    fn line(&mut self, start: Vector2<Float>, end: Vector2<Float>, width: Float, color: [u8; 3]) {
+        let (start, end) = (self.to_pixels(start), self.to_pixels(end));
+        // At least a pixel wide, so thin lines don't disappear
+        let half_width = (width * self.scale).max(1.0) / 2.0;
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length_squared = dx * dx + dy * dy;
+        self.fill(
+            (
+                start.0.min(end.0) - half_width,
+                start.1.min(end.1) - half_width,
+            ),
+            (
+                start.0.max(end.0) + half_width,
+                start.1.max(end.1) + half_width,
+            ),
+            color,
+            |px, py| {
+                // Distance to the nearest point on the segment
+                let along = if length_squared > 0.0 {
+                    (((px - start.0) * dx + (py - start.1) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let distance = (px - start.0 - along * dx).hypot(py - start.1 - along * dy);
+                half_width + 0.5 - distance
+            },
+        );
+    }
+
+    /// Draws straight lines between consecutive points, and back to the first if `closed`
+    fn polyline(&mut self, points: &[Vector2<Float>], closed: bool, width: Float, color: [u8; 3]) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], width, color);
+        }
+        if let (true, Some(first), Some(last)) = (closed, points.first(), points.last()) {
+            self.line(*last, *first, width, color);
+        }
+    }
+}
+
+/// Reads a CSS hex color like `#F80` or `#FF8800`, falling back to
+/// [`DEFAULT_COLOR`](crate::constellation::DEFAULT_COLOR) for anything else, e.g. named colors
+fn parse_color(color: &str) -> [u8; 3] {
+    let hex = |color: &str| -> Option<[u8; 3]> {
+        let digits = color.strip_prefix('#')?;
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+        match digits.len() {
+            3 => {
+                let mut result = [0; 3];
+                for (i, digit) in digits.char_indices() {
+                    result[i] = channel(&digit.to_string().repeat(2))?;
+                }
+                Some(result)
+            }
+            6 => Some([
+                channel(digits.get(0..2)?)?,
+                channel(digits.get(2..4)?)?,
+                channel(digits.get(4..6)?)?,
+            ]),
+            _ => None,
+        }
+    };
+    hex(color)
+        .or_else(|| hex(DEFAULT_COLOR))
+        .unwrap_or([0xAA; 3])
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+    use crate::{
+        body::{observatory::Observatory, Body},
+        projection::StatelessOrthographic,
+        testing::{make_toy_example, DEFAULT_SEED},
+    };
+
+    use super::*;
+
+    #[test]
+    fn toy_example_rendered() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-png-toy-example-rendered");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new(Spherical::UP, observer, Ok("Png".into()), vec![]);
+        let constellations = [crate::constellation::Line {
+            start: Spherical::UP,
+            end: Spherical::LEFT,
+            color: "#F00".into(),
+            width: crate::constellation::DEFAULT_WIDTH,
+            culture: None,
+        }];
+
+        let output = Png::new(StatelessOrthographic(), 320, 200);
+        let observations = observatory.observe(0.0);
+        output
+            .write_observations(
+                &observations,
+                &constellations,
+                FrameInfo::new("Png", 0),
+                &output_root,
+            )
+            .unwrap();
+
+        let path = crate::output::to_default_path(&output_root, "Png", 0, ".png");
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        let mut reader = png::Decoder::new(File::open(&path).unwrap())
+            .read_info()
+            .unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (320, 200));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(
+            &pixels[..info.buffer_size()],
+            output.consume_observation(&observations, &constellations)
+        );
+
+        // The corners are outside the sky, but bodies and the constellation are drawn
+        assert_eq!(pixels[..3], [0, 0, 0]);
+        assert!(pixels.chunks(3).any(|pixel| pixel == BODY_COLOR));
+        assert!(pixels
+            .chunks(3)
+            .any(|pixel| pixel[0] > 0x40 && pixel[1] == 0 && pixel[2] == 0));
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn css_colors_parsed() {
+        assert_eq!(parse_color("#F80"), [0xFF, 0x88, 0x00]);
+        assert_eq!(parse_color("#12abEF"), [0x12, 0xAB, 0xEF]);
+        assert_eq!(parse_color("red"), [0xAA; 3]);
+    }
+}
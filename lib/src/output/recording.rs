@@ -0,0 +1,583 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Error, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use coordinates::prelude::Spherical;
+
+use super::{FileNameTemplate, FrameInfo, Output};
+use crate::{body, constellation::Line, Float, LocalObservation};
+
+/// Everything an [`Output`] is given for one observatory at one time, apart from the
+/// observatory itself.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    /// Name of the directory the files of the observatory that made the observations are written
+    /// to, see [`FrameInfo::directory`]
+    pub directory: String,
+    /// Time of the frame, in hours
+    pub time: i128,
+    /// Time the observatory actually observed the sky at, see [`FrameInfo::observation_time`]
+    pub observation_time: Float,
+    /// Bodies that were observed and their locations
+    pub observations: Vec<LocalObservation>,
+    /// Constellation lines that were visible
+    pub constellations: Vec<Line>,
+}
+
+impl Frame {
+    /// Passes this frame to an output, as if it had come from a
+    /// [`Program`](crate::program::Program)
+    ///
+    /// # Errors
+    /// Returns any error the output returns while writing
+    pub fn write_to(&self, output: &dyn Output, output_path_root: &Path) -> Result<(), Error> {
+        output.write_observations(
+            &self.observations,
+            &self.constellations,
+            self.info(),
+            output_path_root,
+        )
+    }
+
+    /// Returns where and when this frame was observed, without the observatory
+    #[must_use]
+    pub fn info(&self) -> FrameInfo<'_> {
+        FrameInfo::new(&self.directory, self.time).observed_at(self.observation_time)
+    }
+
+    /// Appends this frame in the recording format, identifying bodies by their IDs in `ids`
+    fn record(&self, buffer: &mut String, ids: &HashMap<usize, String>) {
+        use std::fmt::Write;
+
+        // Infallible writes to a string
+        let _ = writeln!(
+            buffer,
+            "frame\t{}\t{}\t{}",
+            self.time,
+            escape(&self.directory),
+            self.observation_time
+        );
+        for (body, location) in &self.observations {
+            let name = body
+                .read()
+                .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name());
+            // Bodies from a different tree to the first frame's aren't in the map
+            let id = ids.get(&address(body)).cloned().unwrap_or_else(|| {
+                body.read()
+                    .map(|b| crate::body::observatory::to_name(&b.get_id()))
+                    .unwrap_or_default()
+            });
+            let _ = writeln!(
+                buffer,
+                "body\t{}\t{id}\t{}",
+                write_location(location),
+                escape(&name)
+            );
+        }
+        for line in &self.constellations {
+            let _ = writeln!(
+                buffer,
+                "line\t{}\t{}\t{}\t{}\t{}",
+                write_location(&line.start),
+                write_location(&line.end),
+                escape(&line.color),
+                line.width,
+                escape(line.culture.as_deref().unwrap_or_default())
+            );
+        }
+    }
+}
+
+/// An output that records every frame to a single tab separated file, `recording.tsv` in the
+/// output directory by default, so that it can be [replayed](replay) into other outputs later
+/// without recomputing the simulation.
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    /// Frames that have been observed, and the directory their recording goes in
+    frames: Arc<RwLock<HashMap<PathBuf, Vec<Frame>>>>,
+    /// Template of the path frames are recorded to
+    file_name_template: FileNameTemplate,
+}
+
+impl Recording {
+    /// Name of the file that frames are recorded to by default
+    pub const FILE_NAME: &'static str = "recording.tsv";
+
+    /// Creates an empty recording
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the file frames are recorded to with a [template](super::to_templated_path), or
+    /// [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if `None`. Every observatory is recorded
+    /// to the same file, so `{time}` is filled in with `recording`, `{ext}` with `.tsv`, and
+    /// `{observatory}` is left empty, see [`FileNameTemplate::run_path`].
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+}
+
+impl Output for Recording {
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), Error> {
+        if let Ok(mut hash_map) = self.frames.write() {
+            hash_map
+                .entry(output_path_root.to_owned())
+                .or_default()
+                .push(Frame {
+                    directory: frame.directory.to_owned(),
+                    time: frame.time,
+                    observation_time: frame.observation_time,
+                    observations: observations.to_vec(),
+                    constellations: constellations.to_vec(),
+                });
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        if let Ok(mut hash_map) = self.frames.write() {
+            for (directory, frames) in hash_map.iter_mut() {
+                // Frames arrive out of order from parallel observations
+                frames.sort_by(|a, b| {
+                    a.time
+                        .cmp(&b.time)
+                        .then_with(|| a.directory.cmp(&b.directory))
+                });
+
+                let ids = frames
+                    .iter()
+                    .flat_map(|frame| frame.observations.first())
+                    .next()
+                    .map(|(body, _)| ids_by_address(&root_of(body)))
+                    .unwrap_or_default();
+                let mut buffer = String::new();
+                for frame in frames.iter() {
+                    frame.record(&mut buffer, &ids);
+                }
+
+                let path = self
+                    .file_name_template
+                    .run_path(directory, "recording", ".tsv");
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, buffer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lines are recorded so they can be replayed into outputs that want them
+    fn wants_constellations(&self) -> bool {
+        true
+    }
+}
+
+/// Reads a file written by [`Recording`], resolving the recorded bodies by their
+/// [IDs](body::Body::get_id) in the tree at `root`, or by name for recordings made before IDs were
+/// recorded. Frames are returned in the order they were recorded, ascending by time and then
+/// observatory name.
+///
+/// # Errors
+/// Returns an error if the file cannot be read, is malformed, or refers to a body that does not
+/// exist in the tree.
+pub fn replay<R: BufRead>(reader: R, root: &body::Arc) -> Result<Vec<Frame>, Error> {
+    let bodies = bodies_by_id(root);
+    // Only needed for old recordings
+    let mut bodies_by_name = None;
+
+    let mut frames: Vec<Frame> = Vec::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let malformed = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("malformed recording on line {}: '{line}'", line_number + 1),
+            )
+        };
+        let mut fields = line.split('\t');
+
+        match fields.next() {
+            Some("frame") => {
+                let time = fields
+                    .next()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(malformed)?;
+                let directory = unescape(fields.next().ok_or_else(malformed)?);
+                // Recordings made before observation times were recorded end at the directory
+                #[allow(clippy::cast_precision_loss)]
+                let observation_time = match fields.next() {
+                    Some(observation_time) => observation_time.parse().map_err(|_| malformed())?,
+                    None => time as Float,
+                };
+                frames.push(Frame {
+                    directory,
+                    time,
+                    observation_time,
+                    observations: Vec::new(),
+                    constellations: Vec::new(),
+                });
+            }
+            Some("body") => {
+                let location = read_location(&mut fields).ok_or_else(malformed)?;
+                let first = fields.next().ok_or_else(malformed)?;
+                let body = match fields.next() {
+                    Some(name) => bodies.get(first).ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::NotFound,
+                            format!(
+                                "recorded body '{}' with ID '{first}' is not in the tree",
+                                unescape(name)
+                            ),
+                        )
+                    })?,
+                    // Recordings made before IDs were recorded end at the name
+                    None => bodies_by_name
+                        .get_or_insert_with(|| self::bodies_by_name(root))
+                        .get(first)
+                        .ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::NotFound,
+                                format!("recorded body '{first}' is not in the tree"),
+                            )
+                        })?,
+                };
+                frames
+                    .last_mut()
+                    .ok_or_else(malformed)?
+                    .observations
+                    .push((body.clone(), location));
+            }
+            Some("line") => {
+                let start = read_location(&mut fields).ok_or_else(malformed)?;
+                let end = read_location(&mut fields).ok_or_else(malformed)?;
+                let color = unescape(fields.next().ok_or_else(malformed)?).into();
+                // Recordings made before lines had widths and cultures end at the color
+                let width = match fields.next() {
+                    Some(width) => width.parse().map_err(|_| malformed())?,
+                    None => crate::constellation::DEFAULT_WIDTH,
+                };
+                let culture = fields
+                    .next()
+                    .filter(|c| !c.is_empty())
+                    .map(|c| unescape(c).into());
+                frames
+                    .last_mut()
+                    .ok_or_else(malformed)?
+                    .constellations
+                    .push(Line {
+                        start,
+                        end,
+                        color,
+                        width,
+                        culture,
+                    });
+            }
+            Some("") | None => (),
+            Some(_) => return Err(malformed()),
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Formats a location as its radius, polar angle, and azimuthal angle separated by tabs
+fn write_location(location: &Spherical<Float>) -> String {
+    format!(
+        "{}\t{}\t{}",
+        location.radius, location.polar_angle, location.azimuthal_angle
+    )
+}
+
+/// Reads a location written by [`write_location`]
+fn read_location<'a, I: Iterator<Item = &'a str>>(fields: &mut I) -> Option<Spherical<Float>> {
+    let mut next = || fields.next().and_then(|f| f.parse::<Float>().ok());
+    Some(Spherical {
+        radius: next()?,
+        polar_angle: next()?,
+        azimuthal_angle: next()?,
+    })
+}
+
+/// Escapes backslashes, tabs, and line breaks so a field can't split a line of a recording
+fn escape(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains(['\\', '\t', '\n', '\r']) {
+        field
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+            .into()
+    } else {
+        field.into()
+    }
+}
+
+/// Reverses [`escape`]
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Returns the key of a body in the map returned by [`ids_by_address`]
+fn address(body: &body::Arc) -> usize {
+    Arc::as_ptr(body) as usize
+}
+
+/// Returns the root of the tree a body is in
+fn root_of(body: &body::Arc) -> body::Arc {
+    let mut current = body.clone();
+    loop {
+        let parent = current
+            .read()
+            .ok()
+            .and_then(|b| b.parent.as_ref().and_then(std::sync::Weak::upgrade));
+        match parent {
+            Some(parent) => current = parent,
+            None => return current,
+        }
+    }
+}
+
+/// Returns every body in the tree at `root` with its [ID](body::Body::get_id), written like the
+/// IDs of [unnamed bodies](body::Name::from_id). IDs are passed down the tree so very wide trees
+/// don't have to search their siblings for each body.
+fn with_ids(root: &body::Arc) -> Vec<(body::Arc, String)> {
+    let mut result = Vec::new();
+    let mut stack = vec![(root.clone(), Vec::new())];
+    while let Some((current, id)) = stack.pop() {
+        if let Ok(b) = current.read() {
+            for (index, child) in b.get_children().iter().enumerate() {
+                let mut child_id = id.clone();
+                child_id.push(index);
+                stack.push((child.clone(), child_id));
+            }
+        }
+        result.push((current, crate::body::observatory::to_name(&id)));
+    }
+    result
+}
+
+/// Maps the address of every body in the tree to its ID, see [`with_ids`]
+fn ids_by_address(root: &body::Arc) -> HashMap<usize, String> {
+    with_ids(root)
+        .into_iter()
+        .map(|(body, id)| (address(&body), id))
+        .collect()
+}
+
+/// Maps the ID of every body in the tree to that body, see [`with_ids`]
+fn bodies_by_id(root: &body::Arc) -> HashMap<String, body::Arc> {
+    with_ids(root)
+        .into_iter()
+        .map(|(body, id)| (id, body))
+        .collect()
+}
+
+/// Maps the names of every body in the tree to that body
+fn bodies_by_name(root: &body::Arc) -> HashMap<Arc<str>, body::Arc> {
+    let mut result = HashMap::new();
+    let mut stack = vec![root.clone()];
+    while let Some(current) = stack.pop() {
+        if let Ok(b) = current.read() {
+            stack.extend(b.get_children().iter().cloned());
+            result.insert(b.get_name(), current.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+    use super::*;
+    use crate::{
+        body::{observatory::Observatory, Body},
+        program::ProgramBuilder,
+        testing::{make_toy_example, DEFAULT_SEED},
+    };
+
+    #[test]
+    fn recording_follows_template() {
+        let output_root = std::env::temp_dir().join(format!(
+            "astrograph-recording-template-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new(Spherical::UP, observer, Ok("Up".into()), vec![]);
+
+        let mut recording = Recording::new();
+        recording.set_file_name_template(Some("runs/{observatory}{time}{ext}".into()));
+        let program = ProgramBuilder::default()
+            .root_body(root.clone())
+            .observatories(vec![observatory])
+            .add_output(Box::new(recording))
+            .output_file_root(output_root.clone())
+            .build()
+            .unwrap();
+        program.make_observations(0, 3, Some(1)).unwrap();
+
+        let file =
+            std::fs::File::open(output_root.join("runs").join(Recording::FILE_NAME)).unwrap();
+        assert_eq!(
+            replay(std::io::BufReader::new(file), &root).unwrap().len(),
+            3
+        );
+        assert!(!output_root.join(Recording::FILE_NAME).exists());
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn record_then_replay() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-record-then-replay");
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatories: Vec<_> = [(Spherical::UP, "Up"), (Spherical::LEFT, "Left")]
+            .into_iter()
+            .map(|(location, name)| {
+                Observatory::new(location, observer.clone(), Ok(name.into()), vec![])
+            })
+            .collect();
+
+        let recording = Recording::new();
+        let program = ProgramBuilder::default()
+            .root_body(root.clone())
+            .observatories(observatories)
+            .add_output(Box::new(recording.clone()))
+            .output_file_root(output_root.clone())
+            .build()
+            .unwrap();
+        program.make_observations(0, 20, Some(3)).unwrap();
+
+        let file = std::fs::File::open(output_root.join(Recording::FILE_NAME)).unwrap();
+        let replayed = replay(std::io::BufReader::new(file), &root).unwrap();
+
+        let recorded = recording.frames.read().unwrap();
+        let recorded = &recorded[&output_root];
+        assert_eq!(replayed.len(), 14);
+        assert_eq!(replayed.len(), recorded.len());
+
+        for (original, copy) in recorded.iter().zip(&replayed) {
+            assert_eq!(original.time, copy.time);
+            assert_eq!(
+                original.observation_time.to_bits(),
+                copy.observation_time.to_bits()
+            );
+            assert_eq!(original.directory, copy.directory);
+            assert_eq!(original.observations.len(), copy.observations.len());
+            for ((body_a, loc_a), (body_b, loc_b)) in
+                original.observations.iter().zip(&copy.observations)
+            {
+                assert!(std::sync::Arc::ptr_eq(body_a, body_b));
+                assert_eq!(loc_a.radius.to_bits(), loc_b.radius.to_bits());
+                assert_eq!(loc_a.polar_angle.to_bits(), loc_b.polar_angle.to_bits());
+                assert_eq!(
+                    loc_a.azimuthal_angle.to_bits(),
+                    loc_b.azimuthal_angle.to_bits()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bodies_replayed_by_id() {
+        use crate::dynamic::fixed::Fixed;
+        use coordinates::prelude::Vector3;
+
+        let output_root =
+            std::env::temp_dir().join(format!("astrograph-replay-by-id-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        // Two bodies share a name, and one has a name that would split the line
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let names = ["Twin", "Twin", "Tab\tand\nnewline \\t"];
+        let bodies: Vec<_> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                #[allow(clippy::cast_precision_loss)]
+                let body = Body::new(
+                    Some(root.clone()),
+                    Fixed::new(Vector3 {
+                        x: 1.0,
+                        y: i as Float,
+                        z: 10.0,
+                    }),
+                );
+                body.write().unwrap().name = crate::body::Name::Named((*name).into());
+                body
+            })
+            .collect();
+        Body::hydrate_all(&root, &None);
+        let observatory =
+            Observatory::new(Spherical::UP, root.clone(), Ok("Odd\tnames".into()), vec![]);
+
+        let recording = Recording::new();
+        recording
+            .write_observations(
+                &observatory.observe(0.0),
+                &[],
+                FrameInfo::new("Odd\tnames", 0),
+                &output_root,
+            )
+            .unwrap();
+        recording.flush().unwrap();
+
+        let file = std::fs::File::open(output_root.join(Recording::FILE_NAME)).unwrap();
+        let replayed = replay(std::io::BufReader::new(file), &root).unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].directory, "Odd\tnames");
+        let recorded = &recording.frames.read().unwrap()[&output_root][0];
+        assert_eq!(recorded.observations.len(), bodies.len());
+        for ((original, _), (copy, _)) in
+            recorded.observations.iter().zip(&replayed[0].observations)
+        {
+            assert!(std::sync::Arc::ptr_eq(original, copy));
+        }
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn unknown_body_is_an_error() {
+        let (root, _) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+
+        let recording = "frame\t0\tobservatory\nbody\t1\t0\t0\tNot a body\n";
+        let error = replay(recording.as_bytes(), &root).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
@@ -1,32 +1,343 @@
-use std::{fmt::Debug, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
 
-use crate::{consts::float, projection::Projection, Float, LocalObservation};
+use crate::{
+    body::observatory::Observatory, consts::float, projection::Projection, Float, LocalObservation,
+};
 
 use coordinates::three_dimensional::Spherical;
 
-use super::Output;
-use coordinates::prelude::{Polar, Vector2};
+use super::{to_file_name, FileNameTemplate, FrameInfo, Output};
+use coordinates::prelude::{Polar, ThreeDimensionalConsts, Vector2, Vector3};
 use svg::{
     self,
-    node::element::{Circle, Line, Rectangle, Style, Text},
+    node::element::{Circle, Group, Line, Polygon, Polyline, Rectangle, Style, Text},
     Document, Node,
 };
 
+/// Draws a chart of the observations with the default settings of [`Svg`].
+///
+/// Bodies are drawn from the furthest to the nearest, so where two bodies overlap the nearer one
+/// is always on top, whatever order they were observed in.
 pub fn new_document<P: Projection>(
     time: &str,
     observations: &[LocalObservation],
-    constellations: &[(Spherical<Float>, Spherical<Float>)],
+    constellations: &[crate::constellation::Line],
     projector: &P,
 ) -> svg::node::element::SVG {
-    // TODO: remove some magic values (like "0.005", "-0.95", etc.)
+    new_document_with_subtitle(time, None, observations, constellations, projector)
+}
+
+/// Like [`new_document`], but draws `subtitle` under the time if there is one, e.g. the
+/// [subtitle of an observatory](observatory_subtitle)
+pub fn new_document_with_subtitle<P: Projection>(
+    time: &str,
+    subtitle: Option<&str>,
+    observations: &[LocalObservation],
+    constellations: &[crate::constellation::Line],
+    projector: &P,
+) -> svg::node::element::SVG {
+    draw_document(
+        time,
+        subtitle,
+        &ProjectedFrame::new(
+            observations,
+            constellations,
+            projector,
+            Some(DEFAULT_MERGE_RESOLUTION),
+        ),
+        DEFAULT_BISECTIONS,
+    )
+}
+
+/// Returns the name of an observatory followed by its latitude and longitude, e.g.
+/// `Siding Spring, 31.27°S 149.06°E`, or just the name if there is no observatory
+#[must_use]
+pub fn observatory_subtitle(name: &str, observatory: Option<&Observatory>) -> String {
+    let Some(observatory) = observatory else {
+        return name.to_owned();
+    };
+    let (lat, long) = observatory.lat_long_deg();
+    format!(
+        "{name}, {:.2}°{} {:.2}°{}",
+        lat.abs(),
+        if lat < 0.0 { 'S' } else { 'N' },
+        long.abs(),
+        if long < 0.0 { 'W' } else { 'E' }
+    )
+}
+
+/// Smallest radius bodies are drawn with, so bodies too small or far away to see still show up
+const MIN_DRAWN_RADIUS: Float = 0.005;
+
+/// Width of the bins that bodies drawn at the smallest radius are merged in by default, see
+/// [`Svg::set_merge_resolution`]. Bodies in the same bin overlap almost entirely.
+pub const DEFAULT_MERGE_RESOLUTION: Float = MIN_DRAWN_RADIUS;
+
+/// A visible body mapped to "screen space"
+pub(super) struct ProjectedBody<'a> {
+    /// The body, or the largest of the bodies merged into this one
+    pub(super) body: &'a crate::body::Arc,
+    /// Projected location of the body
+    pub(super) location: Vector2<Float>,
+    /// Angular radius of the body in radians, read once so drawing doesn't lock it again
+    pub(super) angular_radius: Float,
+    /// Distance to the body from the observatory in light seconds
+    pub(super) distance: Float,
+    /// Number of bodies drawn as this one, more than one if bodies were merged
+    pub(super) count: usize,
+}
+
+/// Observations and constellation lines of a frame mapped to "screen space", so every document
+/// drawn from the frame shares the projection work
+pub(super) struct ProjectedFrame<'a> {
+    /// Visible bodies, in the order they are drawn, see [`sort_far_to_near`]
+    pub(super) bodies: Vec<ProjectedBody<'a>>,
+    /// Visible constellation lines as their projected start, end, color, and width
+    pub(super) constellations: Vec<(Vector2<Float>, Vector2<Float>, &'a str, Float)>,
+    /// Bodies below the horizon whose discs reach above it, like the ground, with the projected
+    /// points along the part of the horizon they cover
+    pub(super) horizon_arcs: Vec<(&'a crate::body::Arc, Vec<Vector2<Float>>)>,
+    /// Outline of the projection, see [`Projection::boundary_path`]
+    pub(super) boundary: Option<Vec<Vector2<Float>>>,
+}
+
+impl<'a> ProjectedFrame<'a> {
+    /// Projects everything in a frame that can be drawn, merging the smallest bodies into bins
+    /// `merge_resolution` wide if it is given
+    pub(super) fn new<P: Projection>(
+        observations: &'a [LocalObservation],
+        constellations: &'a [crate::constellation::Line],
+        projector: &P,
+        merge_resolution: Option<Float>,
+    ) -> Self {
+        let constellations = constellations
+            .iter()
+            .filter_map(|line| {
+                projector
+                    .project_with_state(&line.start)
+                    .and_then(|projected_a| {
+                        projector
+                            .project_with_state(&line.end)
+                            .map(|projected_b| (projected_a, projected_b, &*line.color, line.width))
+                    })
+            })
+            .collect();
+
+        // Nothing is hidden below the horizon of the whole sky
+        let horizon_arcs = if projector.is_full_sky() {
+            Vec::new()
+        } else {
+            observations
+                .iter()
+                .filter(|(_, loc)| projector.project_with_state(loc).is_none())
+                .filter_map(|(body, loc)| Some((body, horizon_arc(body, loc, projector)?)))
+                .collect()
+        };
+
+        let bodies = observations
+            .iter()
+            // Map from world space to "screen space" (we still require some uniform
+            // transformations to map to a true screen space)
+            .filter_map(|(body, loc)| {
+                projector
+                    .project_with_state(loc)
+                    .map(|location| ProjectedBody {
+                        body,
+                        location,
+                        angular_radius: body
+                            .read()
+                            .map_or(0.0, |b| b.get_angular_radius(loc.radius)),
+                        distance: loc.radius,
+                        count: 1,
+                    })
+            })
+            .collect();
+        let mut bodies = sort_far_to_near(bodies);
+        if let Some(resolution) = merge_resolution.filter(|resolution| *resolution > 0.0) {
+            // A merged body can be nearer than the furthest body in its bin, whose place it took
+            bodies = sort_far_to_near(merge_bodies(bodies, resolution));
+        }
+
+        Self {
+            bodies,
+            constellations,
+            horizon_arcs,
+            boundary: projector.boundary_path(),
+        }
+    }
+}
 
-    // Create lines of longitude through the circle to more easily read it.
-    const NUMBER_OF_BISECTIONS: u8 = 4;
+/// Sorts bodies from the furthest to the nearest, so nearer bodies are drawn on top of the
+/// bodies they are in front of. Bodies at the same distance are sorted by name, so the order
+/// doesn't depend on the order they were observed in.
+fn sort_far_to_near(mut bodies: Vec<ProjectedBody>) -> Vec<ProjectedBody> {
+    let name = |body: &ProjectedBody| {
+        body.body
+            .read()
+            .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name())
+    };
+    bodies.sort_by(|a, b| {
+        b.distance
+            .total_cmp(&a.distance)
+            .then_with(|| name(a).cmp(&name(b)))
+    });
+    bodies
+}
+
+/// Merges bodies drawn at the [smallest radius](MIN_DRAWN_RADIUS) that fall in the same
+/// `resolution` wide square into the largest of them, since they'd be drawn as the same dot.
+/// Bodies drawn any larger are never merged.
+fn merge_bodies(bodies: Vec<ProjectedBody>, resolution: Float) -> Vec<ProjectedBody> {
+    let mut result: Vec<ProjectedBody> = Vec::with_capacity(bodies.len());
+    // Index in the result of the body drawn for each bin
+    let mut bins: HashMap<(i64, i64), usize> = HashMap::new();
+    for body in bodies {
+        if drawn_radius_of(body.angular_radius) > MIN_DRAWN_RADIUS {
+            result.push(body);
+            continue;
+        }
+
+        #[allow(clippy::cast_possible_truncation)] // Locations are within a few units of 0
+        let bin = (
+            (body.location.x / resolution).floor() as i64,
+            (body.location.y / resolution).floor() as i64,
+        );
+        match bins.entry(bin) {
+            Entry::Occupied(entry) => {
+                let drawn = &mut result[*entry.get()];
+                let count = drawn.count + body.count;
+                if body.angular_radius > drawn.angular_radius {
+                    *drawn = body;
+                }
+                drawn.count = count;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(result.len());
+                result.push(body);
+            }
+        }
+    }
+    result
+}
+
+/// Returns the projected points along the horizon covered by the disc of a body below it, or
+/// `None` if the disc doesn't reach the horizon
+fn horizon_arc<P: Projection>(
+    body: &crate::body::Arc,
+    location: &Spherical<Float>,
+    projector: &P,
+) -> Option<Vec<Vector2<Float>>> {
+    /// Number of line segments used to draw the arc
+    const SEGMENTS: u8 = 64;
+    /// Angle in radians that discs can miss the horizon by and still be drawn along it, so
+    /// rounding doesn't hide the ground which touches the horizon exactly
+    const TOLERANCE: Float = 1e-4;
+
+    let angular_radius = body.read().ok()?.get_angular_radius(location.radius);
+    let depression = location.polar_angle - float::FRAC_PI_2;
+    if depression <= 0.0 || depression > angular_radius + TOLERANCE {
+        // Above the horizon, or too far below it to reach it
+        return None;
+    }
+
+    // Angle either side of the body's azimuth where its disc meets the horizon, discs that reach
+    // the opposite side of the horizon (like the ground) cover all of it
+    let half_width = if depression + angular_radius >= float::PI - TOLERANCE {
+        float::PI
+    } else {
+        (angular_radius.cos() / depression.cos())
+            .clamp(-1.0, 1.0)
+            .acos()
+    };
 
-    const TOP_LEFT: Float = -1.02;
-    const BOTTOM_RIGHT: Float = 2.0 * 1.02;
+    let points: Vec<_> = (0..=SEGMENTS)
+        .filter_map(|i| {
+            let azimuthal_angle = location.azimuthal_angle - half_width
+                + 2.0 * half_width * Float::from(i) / Float::from(SEGMENTS);
+            projector.project_with_state(&Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2,
+                azimuthal_angle,
+            })
+        })
+        .collect();
+    (points.len() > 1).then_some(points)
+}
+
+/// Lower left corner of the document's view box
+pub(super) const TOP_LEFT: Float = -1.02;
+/// Width and height of the document's view box
+pub(super) const BOTTOM_RIGHT: Float = 2.0 * 1.02;
+
+/// Space left around an outline given by [`Projection::boundary_path`] in the document's view box
+pub(super) const MARGIN: Float = 0.02;
+
+/// Returns the left, top, right, and bottom edges of the smallest box around `points`
+pub(super) fn bounds(points: &[Vector2<Float>]) -> (Float, Float, Float, Float) {
+    points.iter().fold(
+        (
+            Float::INFINITY,
+            Float::INFINITY,
+            -Float::INFINITY,
+            -Float::INFINITY,
+        ),
+        |(left, top, right, bottom), point| {
+            (
+                left.min(point.x),
+                top.min(point.y),
+                right.max(point.x),
+                bottom.max(point.y),
+            )
+        },
+    )
+}
+
+/// Creates a document with a background and the outline of the sky, but nothing in it. The sky is
+/// the unit circle unless the projection gives another `boundary`, in which case the document is
+/// fitted around it.
+fn empty_document(boundary: Option<&[Vector2<Float>]>) -> svg::node::element::SVG {
+    if let Some(boundary) = boundary {
+        let (left, top, right, bottom) = bounds(boundary);
+        return Document::new()
+            .set("preserveAspectRatio", "xMidYMid meet")
+            .set(
+                "viewBox",
+                format!(
+                    "{} {} {} {}",
+                    left - MARGIN,
+                    top - MARGIN,
+                    right - left + 2.0 * MARGIN,
+                    bottom - top + 2.0 * MARGIN
+                ),
+            )
+            .add(
+                Rectangle::new()
+                    .set("width", "100%")
+                    .set("height", "100%")
+                    .set("x", left - MARGIN)
+                    .set("y", top - MARGIN),
+            )
+            .add(
+                Polygon::new()
+                    .set(
+                        "points",
+                        boundary
+                            .iter()
+                            .map(|point| format!("{},{}", point.x, point.y))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    )
+                    .set("class", "outer"),
+            );
+    }
 
-    let mut result = Document::new()
+    Document::new()
         .set("preserveAspectRatio", "xMidYMid meet")
         .set(
             "viewBox",
@@ -46,16 +357,50 @@ pub fn new_document<P: Projection>(
                 .set("cx", "0")
                 .set("class", "outer"),
         )
-        .add(
-            Text::new(format!("t={time}"))
-                .set("class", "heading")
-                .set("y", format!("{}", -0.95))
-                .set("x", format!("{}", -0.98)),
+}
+
+/// Number of lines through the middle of each chart, see [`Svg::set_bisections`]
+pub const DEFAULT_BISECTIONS: u8 = 4;
+
+/// Draws a full chart of a projected frame, with a heading, an optional subtitle below it, a grid
+/// of `bisections` lines through the middle, constellations, and bodies
+fn draw_document(
+    time: &str,
+    subtitle: Option<&str>,
+    frame: &ProjectedFrame,
+    bisections: u8,
+) -> svg::node::element::SVG {
+    // TODO: remove some magic values (like "0.005", "-0.95", etc.)
+
+    // Top left of the heading, inside the top left corner of the outline
+    let (left, top) = frame.boundary.as_deref().map_or((-1.0, -1.0), |boundary| {
+        let (left, top, _, _) = bounds(boundary);
+        (left, top)
+    });
+    let mut result = empty_document(frame.boundary.as_deref()).add(
+        Text::new(format!("t={time}"))
+            .set("class", "heading")
+            .set("y", format!("{}", top + 0.05))
+            .set("x", format!("{}", left + 0.02)),
+    );
+    if let Some(subtitle) = subtitle {
+        result.append(
+            Text::new(subtitle)
+                .set("class", "subtitle")
+                .set("y", format!("{}", top + 0.09))
+                .set("x", format!("{}", left + 0.02)),
         );
+    }
 
-    // Create lines that run north-south east-west etc.
-    for i in 0..NUMBER_OF_BISECTIONS {
-        let theta = float::PI * (i as Float / NUMBER_OF_BISECTIONS as Float);
+    // Create lines that run north-south east-west etc. through the middle of the circle, which
+    // other outlines don't have
+    let bisections = if frame.boundary.is_some() {
+        0
+    } else {
+        bisections
+    };
+    for i in 0..bisections {
+        let theta = float::PI * (Float::from(i) / Float::from(bisections));
         let starting_point: Vector2<Float> = Polar { radius: 1.0, theta }.into();
 
         let ending_point: Vector2<Float> = Polar {
@@ -74,79 +419,615 @@ pub fn new_document<P: Projection>(
     }
 
     // Display constellations behind bodies
-    for (start, end) in constellations.iter().filter_map(|(a, b)| {
-        projector.project_with_state(a).and_then(|projected_a| {
-            projector
-                .project_with_state(b)
-                .map(|projected_b| (projected_a, projected_b))
-        })
-    }) {
+    for (start, end, color, width) in &frame.constellations {
         let line = Line::new()
             .set("x1", start.x)
             .set("y1", start.y)
             .set("x2", end.x)
             .set("y2", end.y)
-            .set("style", "stroke-width: 0.003;stroke:#AAA")
+            .set("style", format!("stroke-width: {width};stroke:{color}"))
             .set("class", "constellation");
 
         result.append(line);
     }
 
+    // Display the ground and anything else below the horizon along its edge
+    for (body, points) in &frame.horizon_arcs {
+        result.append(
+            Polyline::new()
+                .set(
+                    "points",
+                    points
+                        .iter()
+                        .map(|point| format!("{},{}", point.x, point.y))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+                .set(
+                    "class",
+                    format!(
+                        "horizon {}",
+                        body.read()
+                            .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name())
+                    ),
+                ),
+        );
+    }
+
     // Display the bodies on top of everything else
-    for (body, projected_location, distance) in observations
+    for body in &frame.bodies {
+        result.append(body_circle(body));
+    }
+
+    return result;
+}
+
+/// Draws a simplified chart of a projected frame with only the bodies that appear at least
+/// `min_angular_radius` radians in radius
+fn draw_thumbnail(frame: &ProjectedFrame, min_angular_radius: Float) -> svg::node::element::SVG {
+    frame
+        .bodies
         .iter()
-        // Map from world space to "screen space" (we still require some uniform
-        // transformations to map to a true screen space)
-        .filter_map(|(body, loc)| {
-            projector
-                .project_with_state(loc)
-                .map(|projection| (body, projection, loc.radius))
+        .filter(|body| body.angular_radius >= min_angular_radius)
+        .fold(empty_document(frame.boundary.as_deref()), |result, body| {
+            result.add(body_circle(body))
         })
-    {
-        let circle = Circle::new()
-            .set(
-                "r",
-                body.read()
-                    // Set radius to a small but still visible value if angular diameter is too small
-                    .map(|b| (b.get_angular_radius(distance) * float::FRAC_1_PI).max(0.005))
-                    // or we don't have the information for it
-                    .unwrap_or(0.005),
-            )
-            .set("cx", projected_location.x)
-            .set("cy", projected_location.y)
-            .set("fill", "#FFF")
-            .set(
-                "class",
-                body.read()
-                    .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name())
-                    .to_string(),
+}
+
+/// Draws a body as a circle at its projected location, noting how many bodies it stands for if
+/// others were merged into it
+fn body_circle(body: &ProjectedBody) -> Circle {
+    let circle = Circle::new()
+        .set("r", drawn_radius_of(body.angular_radius))
+        .set("cx", body.location.x)
+        .set("cy", body.location.y)
+        .set("fill", "#FFF")
+        .set(
+            "class",
+            body.body
+                .read()
+                .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name())
+                .to_string(),
+        );
+    if body.count > 1 {
+        circle.set("data-count", body.count)
+    } else {
+        circle
+    }
+}
+
+/// Returns the radius a body is drawn with in the document when seen from the given distance
+fn drawn_radius(body: &crate::body::Arc, distance: Float) -> Float {
+    // Or the smallest radius if we don't have the information for it
+    drawn_radius_of(body.read().map_or(0.0, |b| b.get_angular_radius(distance)))
+}
+
+/// Returns the radius a body with the given angular radius is drawn with in the document
+pub(super) fn drawn_radius_of(angular_radius: Float) -> Float {
+    // Set radius to a small but still visible value if angular diameter is too small
+    (angular_radius * float::FRAC_1_PI).max(MIN_DRAWN_RADIUS)
+}
+
+/// Information about the frame being drawn, passed to each [layer](Svg::add_layer)
+pub struct FrameContext<'a> {
+    /// Time of the frame, in hours
+    pub time: i128,
+    /// Time the observatory actually observed the sky at, which anything a layer computes from
+    /// the observatory should use. See [`FrameInfo::observation_time`]
+    pub observation_time: Float,
+    /// Name of the directory the observatory's files are written to
+    pub directory: &'a str,
+    /// The observatory that made the observations, if it is known. See [`FrameInfo::observatory`]
+    pub observatory: Option<&'a Observatory>,
+    /// Constellation lines that are visible in this frame
+    pub constellations: &'a [crate::constellation::Line],
+    /// Projects from local coordinates to the same 2D space as the rest of the document
+    projector: &'a dyn Fn(&Spherical<Float>) -> Option<Vector2<Float>>,
+}
+
+impl FrameContext<'_> {
+    /// Projects a location in local coordinates with the same projection as the rest of the
+    /// document, returning `None` if it can't be drawn.
+    #[must_use]
+    pub fn project(&self, location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        (self.projector)(location)
+    }
+}
+
+/// Extra content drawn on top of every frame of an [`Svg`]
+pub type Layer = dyn Fn(&FrameContext, &[LocalObservation]) -> Group + Send + Sync;
+
+/// A layer that labels the cardinal directions around the horizon.
+#[must_use]
+pub fn compass_rose(context: &FrameContext, _observations: &[LocalObservation]) -> Group {
+    /// How far from the center of the projection the labels are, relative to the horizon
+    const LABEL_RADIUS: Float = 0.92;
+
+    let mut result = Group::new().set("class", "compass-rose");
+    for (label, direction) in [
+        ("N", Spherical::LEFT),
+        ("E", Spherical::BACK),
+        ("S", Spherical::RIGHT),
+        ("W", Spherical::FORWARD),
+    ] {
+        if let Some(location) = context.project(&direction) {
+            result.append(
+                Text::new(label)
+                    .set("class", "compass")
+                    .set("x", location.x * LABEL_RADIUS)
+                    .set("y", location.y * LABEL_RADIUS)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "middle"),
             );
+        }
+    }
+
+    result
+}
 
-        result.append(circle);
+/// A layer that draws the [effective horizon](Observatory::with_horizon_altitude_deg) of the
+/// observatory that made each frame, as a ring inside the true horizon. Nothing is drawn for
+/// observatories with a cutoff at or below the true horizon, or frames without an observatory.
+#[must_use]
+pub fn effective_horizon(context: &FrameContext, _observations: &[LocalObservation]) -> Group {
+    /// Number of line segments used to draw the ring
+    const SEGMENTS: u8 = 64;
+
+    let mut result = Group::new().set("class", "effective-horizons");
+    let Some(observatory) = context.observatory else {
+        return result;
+    };
+    let altitude = observatory.horizon_altitude_deg().to_radians();
+    if altitude <= 0.0 {
+        return result;
     }
 
-    return result;
+    let points: Vec<_> = (0..=SEGMENTS)
+        .filter_map(|i| {
+            context.project(&Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2 - altitude,
+                azimuthal_angle: float::TAU * Float::from(i) / Float::from(SEGMENTS),
+            })
+        })
+        .map(|point| format!("{},{}", point.x, point.y))
+        .collect();
+    if points.len() > 1 {
+        result.append(
+            Polyline::new()
+                .set("points", points.join(" "))
+                .set("class", "effective-horizon"),
+        );
+    }
+    result
+}
+
+/// A layer that draws the meridian of the observatory that made each frame, the great circle through the zenith and the [celestial
+/// pole](Observatory::celestial_pole), from the horizon in the north to the horizon in the south.
+/// Nothing is drawn for observatories on bodies that don't rotate, or at a geographic pole where
+/// every great circle through the zenith passes through the celestial pole.
+#[must_use]
+pub fn meridian(context: &FrameContext, _observations: &[LocalObservation]) -> Group {
+    /// Number of line segments used to draw the meridian
+    const SEGMENTS: u8 = 64;
+
+    let mut result = Group::new().set("class", "meridians");
+    let Some(observatory) = context.observatory else {
+        return result;
+    };
+    let Some(pole) = observatory.celestial_pole(context.observation_time) else {
+        return result;
+    };
+    // Along the horizon towards the pole
+    let north = Vector3 { z: 0.0, ..pole };
+    let length = north.x.hypot(north.y);
+    if length < Float::EPSILON.sqrt() {
+        return result;
+    }
+    let north = north / length;
+
+    let points: Vec<_> = (0..=SEGMENTS)
+        .filter_map(|i| {
+            // From the northern horizon, through the zenith, to the southern horizon
+            let angle = float::PI * Float::from(i) / Float::from(SEGMENTS);
+            let direction = Vector3::UP * angle.sin() + north * angle.cos();
+            context.project(&direction.into())
+        })
+        .map(|point| format!("{},{}", point.x, point.y))
+        .collect();
+    if points.len() > 1 {
+        result.append(
+            Polyline::new()
+                .set("points", points.join(" "))
+                .set("class", "meridian"),
+        );
+    }
+    result
+}
+
+/// A layer that draws the [tails](crate::body::tail::Tail) of bodies seen from the observatory
+/// that made each frame, tapering from the body to the end of the tail. Tails too short to reach
+/// past the body they come from aren't drawn.
+#[must_use]
+pub fn comet_tails(context: &FrameContext, observations: &[LocalObservation]) -> Group {
+    let mut result = Group::new().set("class", "tails");
+    let Some(observatory) = context.observatory else {
+        return result;
+    };
+
+    for (body, location) in observations {
+        let Some(end) = observatory.tail_end(body, context.observation_time) else {
+            continue;
+        };
+        let (Some(head), Some(end)) = (context.project(location), context.project(&end)) else {
+            continue;
+        };
+
+        let width = drawn_radius(body, location.radius);
+        let (dx, dy) = (end.x - head.x, end.y - head.y);
+        let length = dx.hypot(dy);
+        if length <= width {
+            continue;
+        }
+        // Perpendicular to the tail, as wide as the body
+        let (px, py) = (-dy / length * width, dx / length * width);
+
+        result.append(
+            Polygon::new()
+                .set(
+                    "points",
+                    format!(
+                        "{},{} {},{} {},{}",
+                        head.x + px,
+                        head.y + py,
+                        end.x,
+                        end.y,
+                        head.x - px,
+                        head.y - py
+                    ),
+                )
+                .set("class", "tail"),
+        );
+    }
+
+    result
+}
+
+/// A layer that outlines the inner and outer edges of the [rings](crate::body::Body::get_rings)
+/// of bodies seen from the observatory that made each frame, tilted with the body's equator.
+///
+/// Each edge is drawn as a polyline through points around the ring, projected one by one, rather
+/// than as an ellipse: a circle on the sky is only an ellipse under an orthographic projection,
+/// and the other projections bend it. Parts of the rings below the horizon aren't drawn, and the
+/// body is not drawn over the far side of its rings.
+#[must_use]
+pub fn planetary_rings(context: &FrameContext, observations: &[LocalObservation]) -> Group {
+    /// Number of points around each edge of the rings
+    const SEGMENTS: u8 = 64;
+
+    let mut result = Group::new().set("class", "rings");
+    let Some(observatory) = context.observatory else {
+        return result;
+    };
+
+    for (body, _) in observations {
+        let Some(edges) = observatory.ring_edges(body, context.observation_time, SEGMENTS) else {
+            continue;
+        };
+        for edge in edges {
+            // Closed, and broken wherever the edge dips below the horizon
+            let points: Vec<_> = edge
+                .iter()
+                .chain(edge.first())
+                .map(|point| context.project(&(*point).into()))
+                .collect();
+            for run in points.split(Option::is_none) {
+                if run.len() < 2 {
+                    continue;
+                }
+                let run: Vec<_> = run
+                    .iter()
+                    .flatten()
+                    .map(|point| format!("{},{}", point.x, point.y))
+                    .collect();
+                result.append(
+                    Polyline::new()
+                        .set("points", run.join(" "))
+                        .set("class", "ring"),
+                );
+            }
+        }
+    }
+
+    result
+}
+
+/// The field of view of a finder chart, centered on one body, see [`Svg::set_finder`].
+///
+/// Each frame is turned so the target is at the center of the chart, then angles from it are
+/// stretched so the edge of the field of view lies on the horizon of the projection. Bodies and
+/// constellation lines outside the field of view are left out. [Layers](Svg::add_layer) are drawn
+/// over the turned frame, so those that mark directions, like the [`compass_rose`], no longer
+/// line up with the sky.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finder {
+    /// Name of the body at the center of the chart
+    pub target: String,
+    /// Angle in radians from the target to the edge of the chart
+    pub half_angle: Float,
+}
+
+impl Finder {
+    /// Creates a field of view `half_angle` radians in radius around the body named `target`
+    #[must_use]
+    pub fn new(target: impl Into<String>, half_angle: Float) -> Self {
+        Self {
+            target: target.into(),
+            half_angle,
+        }
+    }
+
+    /// Returns the observations and constellation lines in the field of view, turned and
+    /// stretched to fill the chart, or none of them if the target wasn't observed
+    fn view<'a>(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+    ) -> (
+        Cow<'a, [LocalObservation]>,
+        Cow<'a, [crate::constellation::Line]>,
+    ) {
+        let Some((_, target)) = observations
+            .iter()
+            .find(|(body, _)| body.read().is_ok_and(|b| *b.get_name() == *self.target))
+        else {
+            return (Cow::Owned(Vec::new()), Cow::Owned(Vec::new()));
+        };
+        let target: Vector3<Float> = (*target).into();
+        let rotation = quaternion::rotation_from_to(target.into(), Vector3::UP.into());
+        let zoom = |location: &Spherical<Float>| {
+            let turned = Vector3::from(quaternion::rotate_vector(
+                rotation,
+                Vector3::from(*location).into(),
+            ));
+            let mut turned = Spherical::from(turned);
+            (turned.polar_angle <= self.half_angle).then(|| {
+                turned.polar_angle *= float::FRAC_PI_2 / self.half_angle;
+                turned
+            })
+        };
+
+        let observations = observations
+            .iter()
+            .filter_map(|(body, location)| Some((body.clone(), zoom(location)?)))
+            .collect();
+        let constellations = constellations
+            .iter()
+            .filter_map(|line| {
+                Some(crate::constellation::Line {
+                    start: zoom(&line.start)?,
+                    end: zoom(&line.end)?,
+                    ..line.clone()
+                })
+            })
+            .collect();
+        (Cow::Owned(observations), Cow::Owned(constellations))
+    }
+}
+
+/// Labels every drawn body with its name, beside its circle
+fn labels(frame: &ProjectedFrame) -> Group {
+    frame
+        .bodies
+        .iter()
+        .fold(Group::new().set("class", "labels"), |group, body| {
+            let name = body
+                .body
+                .read()
+                .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name());
+            group.add(
+                Text::new(name.to_string())
+                    .set("class", "label")
+                    .set(
+                        "x",
+                        body.location.x + drawn_radius_of(body.angular_radius) + 0.01,
+                    )
+                    .set("y", body.location.y)
+                    .set("dominant-baseline", "middle"),
+            )
+        })
 }
 
 /// A struct that outputs SVG files from observations.
-#[derive(Debug, Clone)]
-pub struct Svg<T: Projection>(T);
+#[derive(Clone)]
+pub struct Svg<T: Projection> {
+    /// Projection used to draw observations
+    projector: T,
+    /// Extra content drawn on top of each frame, in the order it was added
+    layers: Vec<Arc<Layer>>,
+    /// Sky cultures whose constellations are drawn, or `None` to draw every constellation
+    cultures: Option<Vec<String>>,
+    /// Number of lines of azimuth drawn through the middle of each chart
+    bisections: u8,
+    /// Width of the bins the smallest bodies are merged in, or `None` to draw every body
+    merge_resolution: Option<Float>,
+    /// Whether the observatory name is drawn under the heading
+    subtitle: bool,
+    /// Field of view charts are zoomed in to, or `None` to draw the whole sky
+    finder: Option<Finder>,
+    /// Template of the paths charts are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl<T: Projection + Debug> Debug for Svg<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Svg")
+            .field("projector", &self.projector)
+            .field("layers", &self.layers.len())
+            .field("cultures", &self.cultures)
+            .field("bisections", &self.bisections)
+            .field("merge_resolution", &self.merge_resolution)
+            .field("subtitle", &self.subtitle)
+            .field("finder", &self.finder)
+            .field("file_name_template", &self.file_name_template)
+            .finish()
+    }
+}
 
 impl<T: Projection> Svg<T> {
     /// Generates a new Svg with the given projector
     #[must_use]
     pub fn new(projector: T) -> Self {
-        Self(projector)
+        Self {
+            projector,
+            layers: Vec::new(),
+            cultures: None,
+            bisections: DEFAULT_BISECTIONS,
+            merge_resolution: Some(DEFAULT_MERGE_RESOLUTION),
+            subtitle: false,
+            finder: None,
+            file_name_template: FileNameTemplate::default(),
+        }
     }
 
-    /// Converts observations to a SVG document
+    /// Draws the name, latitude, and longitude of the observatory under the time at the top of
+    /// each chart if true, to tell apart charts from different observatories, see
+    /// [`observatory_subtitle`]. Frames without an [observatory](FrameInfo::observatory) only
+    /// show its name. Off by default.
+    pub fn set_subtitle(&mut self, subtitle: bool) {
+        self.subtitle = subtitle;
+    }
+
+    /// Merges bodies drawn at the smallest radius into one circle when they fall in the same
+    /// square `resolution` units wide, keeping the body with the largest angular radius and
+    /// recording how many bodies it stands for in a `data-count` attribute. Bodies drawn any
+    /// larger are never merged. This keeps frames from large universes, with thousands of
+    /// overlapping dots, quick to draw and small to store.
+    ///
+    /// Defaults to [`DEFAULT_MERGE_RESOLUTION`], `None` draws every body.
+    pub fn set_merge_resolution(&mut self, resolution: Option<Float>) {
+        self.merge_resolution = resolution;
+    }
+
+    /// Sets the number of lines drawn through the middle of each chart to help read off
+    /// azimuths, evenly spaced around the horizon. Defaults to [`DEFAULT_BISECTIONS`], which
+    /// draws north-south, east-west, and the two diagonals between them.
+    pub fn set_bisections(&mut self, bisections: u8) {
+        self.bisections = bisections;
+    }
+
+    /// Only draws constellations from these sky cultures, or every constellation if `None` (the
+    /// default). Constellations without a culture are only drawn when every culture is.
+    pub fn select_cultures(&mut self, cultures: Option<Vec<String>>) {
+        self.cultures = cultures;
+    }
+
+    /// Draws finder charts of the field of view around a body instead of the whole sky, labelling
+    /// every body in it, or the whole sky if `None` (the default). See [`Finder`].
+    pub fn set_finder(&mut self, finder: Option<Finder>) {
+        self.finder = finder;
+    }
+
+    /// Names the files charts are written to with a [template](super::to_templated_path), e.g.
+    /// `"{time}-{observatory}{ext}"`, or [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if
+    /// `None`.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Returns the observations and constellation lines in the [finder's](Self::set_finder) field
+    /// of view, or all of them if there is no finder
+    fn in_view<'a>(
+        &self,
+        observations: &'a [LocalObservation],
+        constellations: Cow<'a, [crate::constellation::Line]>,
+    ) -> (
+        Cow<'a, [LocalObservation]>,
+        Cow<'a, [crate::constellation::Line]>,
+    ) {
+        match &self.finder {
+            None => (Cow::Borrowed(observations), constellations),
+            Some(finder) => finder.view(observations, &constellations),
+        }
+    }
+
+    /// Returns the lines of constellations in the [selected cultures](Self::select_cultures)
+    fn selected<'a>(
+        &self,
+        constellations: &'a [crate::constellation::Line],
+    ) -> Cow<'a, [crate::constellation::Line]> {
+        match &self.cultures {
+            None => Cow::Borrowed(constellations),
+            Some(cultures) => constellations
+                .iter()
+                .filter(|line| line.is_in(Some(cultures)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Adds a layer that is drawn on top of the built in content of every frame, e.g.
+    /// [`compass_rose`] or mission specific markers.
+    pub fn add_layer(&mut self, layer: Box<Layer>) {
+        self.layers.push(layer.into());
+    }
+
+    /// Converts observations to a SVG document, drawing nearer bodies on top of further ones like
+    /// [`new_document`]
     pub fn consume_observation(
         &self,
-        time: &str,
+        frame: FrameInfo<'_>,
         observations: &[LocalObservation],
-        constellations: &[(Spherical<Float>, Spherical<Float>)],
+        constellations: &[crate::constellation::Line],
     ) -> svg::Document {
-        new_document(time, observations, constellations, &self.0)
+        let (observations, constellations) =
+            self.in_view(observations, self.selected(constellations));
+        self.draw_frame(
+            frame,
+            &observations,
+            &constellations,
+            &ProjectedFrame::new(
+                &observations,
+                &constellations,
+                &self.projector,
+                self.merge_resolution,
+            ),
+        )
+    }
+
+    /// Draws the full document for an already projected frame, including layers
+    fn draw_frame(
+        &self,
+        info: FrameInfo<'_>,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        frame: &ProjectedFrame,
+    ) -> svg::Document {
+        let mut result = draw_document(
+            &format!("{:010}", info.time),
+            self.subtitle
+                .then(|| observatory_subtitle(info.directory, info.observatory))
+                .as_deref(),
+            frame,
+            self.bisections,
+        );
+
+        let context = FrameContext {
+            time: info.time,
+            observation_time: info.observation_time,
+            directory: info.directory,
+            observatory: info.observatory,
+            constellations,
+            projector: &|location| self.projector.project_with_state(location),
+        };
+        for layer in &self.layers {
+            result.append(layer(&context, observations));
+        }
+        if self.finder.is_some() {
+            result.append(labels(frame));
+        }
+
+        result
             .set("style", "background-color: #000")
             .add(Style::new(include_str!("svgStyle.css")))
     }
@@ -157,6 +1038,7 @@ where
     T: Projection,
     T: Clone,
     T: Debug,
+    T: Send,
     T: Sync,
 {
     /// Outputs [`Self::consume_observation`] to a given file.
@@ -164,18 +1046,1048 @@ where
         &self,
         observations: &[LocalObservation],
         constellations: &[crate::constellation::Line],
-        observatory_name: &str,
-        time: i128,
+        frame: FrameInfo<'_>,
         output_path_root: &Path,
     ) -> Result<(), std::io::Error> {
-        let path = super::to_default_path(output_path_root, observatory_name, time, ".svg");
+        let path = self.file_name_template.frame_path(
+            output_path_root,
+            frame.directory,
+            frame.time,
+            ".svg",
+        );
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         svg::save(
             path,
-            &self.consume_observation(&format!("{time:010}"), observations, constellations),
+            &self.consume_observation(frame, observations, constellations),
         )
     }
+
+    /// Lines are drawn behind the bodies
+    fn wants_constellations(&self) -> bool {
+        true
+    }
+}
+
+/// An output that writes a full [`Svg`] chart of every frame along with a simplified thumbnail to
+/// `<observatory>/thumbs/<time>.svg`, both drawn from the same projected observations.
+///
+/// Thumbnails only contain the bodies, without constellations, labels, the grid, or any
+/// [layers](Svg::add_layer), and skip bodies smaller than a minimum angular radius.
+#[derive(Clone, Debug)]
+pub struct MultiRes<T: Projection> {
+    /// Output used for the full size charts
+    full: Svg<T>,
+    /// Smallest angular radius in radians a body can have and still be drawn in thumbnails
+    min_angular_radius: Float,
+}
+
+impl<T: Projection> MultiRes<T> {
+    /// Name of the directory thumbnails are written to in each observatory's directory
+    pub const THUMBNAIL_DIRECTORY: &str = "thumbs";
+
+    /// Creates an output that draws full size charts with `full`, and thumbnails with only the
+    /// bodies whose angular radius is at least `min_angular_radius` radians
+    #[must_use]
+    pub fn new(full: Svg<T>, min_angular_radius: Float) -> Self {
+        Self {
+            full,
+            min_angular_radius,
+        }
+    }
+
+    /// Converts observations to a full SVG document and a thumbnail, projecting them only once
+    pub fn consume_observation(
+        &self,
+        frame: FrameInfo<'_>,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+    ) -> (svg::Document, svg::Document) {
+        let (observations, constellations) = self
+            .full
+            .in_view(observations, self.full.selected(constellations));
+        let projected = ProjectedFrame::new(
+            &observations,
+            &constellations,
+            &self.full.projector,
+            self.full.merge_resolution,
+        );
+
+        let thumbnail = draw_thumbnail(&projected, self.min_angular_radius)
+            .set("style", "background-color: #000")
+            .add(Style::new(include_str!("svgStyle.css")));
+
+        (
+            self.full
+                .draw_frame(frame, &observations, &constellations, &projected),
+            thumbnail,
+        )
+    }
+}
+
+impl<T> Output for MultiRes<T>
+where
+    T: Projection,
+    T: Clone,
+    T: Debug,
+    T: Send,
+    T: Sync,
+{
+    /// Outputs both documents from [`Self::consume_observation`]
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let path = self.full.file_name_template.frame_path(
+            output_path_root,
+            frame.directory,
+            frame.time,
+            ".svg",
+        );
+        let mut thumbnail_path = path.clone();
+        thumbnail_path.pop();
+        thumbnail_path.push(Self::THUMBNAIL_DIRECTORY);
+        if let Some(file_name) = path.file_name() {
+            thumbnail_path.push(file_name);
+        }
+        if let Some(parent) = thumbnail_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let (full, thumbnail) = self.consume_observation(frame, observations, constellations);
+        svg::save(path, &full)?;
+        svg::save(thumbnail_path, &thumbnail)
+    }
+
+    /// Lines are drawn on both the full size chart and its thumbnail
+    fn wants_constellations(&self) -> bool {
+        true
+    }
+}
+
+/// Projected positions of a [`Trail`]'s body at each time, or `None` when it couldn't be seen,
+/// for each output directory and observatory name
+type TrailPoints = HashMap<(PathBuf, String), Vec<(i128, Option<Vector2<Float>>)>>;
+
+/// An output that follows one body across every frame and draws all of its positions on a single
+/// chart per observatory, like an [analemma](https://en.wikipedia.org/wiki/Analemma) or a star
+/// trail. Each sample is drawn as a dot, and samples in a row that are all visible are joined
+/// with a line.
+///
+/// Charts are written when the output is [flushed](Output::flush), to
+/// `<observatory>/trail-<body>.svg` by default, see [`Trail::path`].
+#[derive(Clone, Debug)]
+pub struct Trail<T: Projection> {
+    /// Projection used to draw the body's positions
+    projector: T,
+    /// Name of the body that is followed
+    body: String,
+    /// Positions of the body so far
+    points: Arc<RwLock<TrailPoints>>,
+    /// Template of the paths charts are written to
+    file_name_template: FileNameTemplate,
+}
+
+impl<T: Projection> Trail<T> {
+    /// Creates an output that follows the body named `body`
+    #[must_use]
+    pub fn new(projector: T, body: impl Into<String>) -> Self {
+        Self {
+            projector,
+            body: body.into(),
+            points: Arc::default(),
+            file_name_template: FileNameTemplate::default(),
+        }
+    }
+
+    /// Names the files trails are written to with a [template](super::to_templated_path), e.g.
+    /// `"{observatory}-{time}{ext}"`, or [the default](super::DEFAULT_FILE_NAME_TEMPLATE) if
+    /// `None`. `{time}` is filled in with `trail-<body>`, since a trail covers every frame.
+    pub fn set_file_name_template(&mut self, template: Option<String>) {
+        self.file_name_template = FileNameTemplate::new(template);
+    }
+
+    /// Returns the path an observatory's trail is written to, with the body's name made safe to
+    /// use in a file name by [`to_file_name`]
+    #[must_use]
+    pub fn path(&self, output_path_root: &Path, observatory_name: &str) -> PathBuf {
+        self.file_name_template.observatory_path(
+            output_path_root,
+            observatory_name,
+            &format!("trail-{}", to_file_name(&self.body)),
+            ".svg",
+        )
+    }
+}
+
+/// Draws a trail through the given points, which are in order of time, inside the `boundary` of
+/// the projection
+fn draw_trail(
+    points: &[(i128, Option<Vector2<Float>>)],
+    boundary: Option<&[Vector2<Float>]>,
+) -> svg::Document {
+    let mut result = empty_document(boundary);
+
+    // Join runs of visible points
+    for run in points.split(|(_, point)| point.is_none()) {
+        if run.len() > 1 {
+            result.append(
+                Polyline::new()
+                    .set(
+                        "points",
+                        run.iter()
+                            .filter_map(|(_, point)| point.as_ref())
+                            .map(|point| format!("{},{}", point.x, point.y))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    )
+                    .set("class", "trail"),
+            );
+        }
+    }
+    for (time, point) in points {
+        if let Some(point) = point {
+            result.append(
+                Circle::new()
+                    .set("r", MIN_DRAWN_RADIUS)
+                    .set("cx", point.x)
+                    .set("cy", point.y)
+                    .set("class", "trail")
+                    .set("data-time", time.to_string()),
+            );
+        }
+    }
+
+    result
+        .set("style", "background-color: #000")
+        .add(Style::new(include_str!("svgStyle.css")))
+}
+
+impl<T> Output for Trail<T>
+where
+    T: Projection,
+    T: Clone,
+    T: Debug,
+    T: Send,
+    T: Sync,
+{
+    fn write_observations(
+        &self,
+        observations: &[LocalObservation],
+        _constellations: &[crate::constellation::Line],
+        frame: FrameInfo<'_>,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let point = observations
+            .iter()
+            .find(|(body, _)| body.read().is_ok_and(|b| *b.get_name() == *self.body))
+            .and_then(|(_, location)| self.projector.project_with_state(location));
+
+        if let Ok(mut points) = self.points.write() {
+            points
+                .entry((output_path_root.to_owned(), frame.directory.to_owned()))
+                .or_default()
+                .push((frame.time, point));
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), std::io::Error> {
+        if let Ok(mut points) = self.points.write() {
+            for ((root, observatory_name), points) in points.iter_mut() {
+                // Frames arrive out of order from parallel observations
+                points.sort_by_key(|(time, _)| *time);
+
+                let path = self.path(root, observatory_name);
+                if let Some(directory) = path.parent() {
+                    std::fs::create_dir_all(directory)?;
+                }
+                svg::save(
+                    path,
+                    &draw_trail(points, self.projector.boundary_path().as_deref()),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::Vector3;
+    use svg::node::element::Rectangle;
+
+    use super::*;
+    use crate::{
+        body::Body,
+        projection::StatelessOrthographic,
+        testing::{make_toy_example, DEFAULT_SEED},
+    };
+
+    #[test]
+    fn nearer_bodies_drawn_on_top() {
+        let root = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        let body = || {
+            let body = Body::new(
+                Some(root.clone()),
+                crate::dynamic::fixed::Fixed::new(Vector3::UP),
+            );
+            body.write().unwrap().radius = Some(1.0);
+            body
+        };
+        let (near, far) = (body(), body());
+        Body::hydrate_all(&root, &None);
+        let name = |body: &crate::body::Arc| body.read().unwrap().get_name();
+        let near_observation = (near.clone(), Spherical::UP * 10.0);
+        let far_observation = (
+            far.clone(),
+            Spherical {
+                radius: 20.0,
+                polar_angle: 0.05,
+                azimuthal_angle: 0.0,
+            },
+        );
+
+        for _ in 0..10 {
+            for observations in [
+                [near_observation.clone(), far_observation.clone()],
+                [far_observation.clone(), near_observation.clone()],
+            ] {
+                let document =
+                    new_document("0", &observations, &[], &StatelessOrthographic()).to_string();
+                let near_at = document
+                    .find(&format!("class=\"{}\"", name(&near)))
+                    .unwrap();
+                let far_at = document.find(&format!("class=\"{}\"", name(&far))).unwrap();
+                assert!(far_at < near_at, "{document}");
+            }
+        }
+    }
+
+    #[test]
+    fn small_bodies_merged_per_bin() {
+        let root = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        let hero = Body::new(
+            Some(root.clone()),
+            crate::dynamic::fixed::Fixed::new(Vector3::UP),
+        );
+        hero.write().unwrap().radius = Some(1.0);
+        let mut observations = vec![(hero.clone(), Spherical::UP * 10.0)];
+        // A crowd of dots around the hero, and a few others spread across the sky
+        for i in 0_u8..100 {
+            let body = Body::new(
+                Some(root.clone()),
+                crate::dynamic::fixed::Fixed::new(Vector3::UP),
+            );
+            let polar_angle = if i % 10 == 0 {
+                Float::from(i) / 100.0 + 0.1
+            } else {
+                0.0025
+            };
+            observations.push((
+                body,
+                Spherical {
+                    radius: 1e6,
+                    polar_angle,
+                    azimuthal_angle: 0.0,
+                },
+            ));
+        }
+        Body::hydrate_all(&root, &None);
+
+        let mut output = Svg::new(StatelessOrthographic());
+        let circles = |output: &Svg<StatelessOrthographic>| {
+            output
+                .consume_observation(FrameInfo::new("Crowded", 0), &observations, &[])
+                .to_string()
+                .matches("<circle")
+                .count()
+                // The edge of the chart
+                - 1
+        };
+
+        let document = output
+            .consume_observation(FrameInfo::new("Crowded", 0), &observations, &[])
+            .to_string();
+        assert!(document.contains(&format!("class=\"{}\"", hero.read().unwrap().get_name())));
+        // The hero, one dot for the crowd by the zenith, and ten spread out dots
+        assert_eq!(circles(&output), 12);
+        assert!(document.contains("data-count=\"90\""));
+
+        output.set_merge_resolution(None);
+        assert_eq!(circles(&output), 101);
+        assert!(!output
+            .consume_observation(FrameInfo::new("Crowded", 0), &observations, &[])
+            .to_string()
+            .contains("data-count"));
+    }
+
+    #[test]
+    fn subtitle_names_observatory() {
+        let mut output = Svg::new(StatelessOrthographic());
+        let document = |output: &Svg<StatelessOrthographic>| {
+            output
+                .consume_observation(FrameInfo::new("Siding Spring", 0), &[], &[])
+                .to_string()
+        };
+
+        assert!(!document(&output).contains("Siding Spring"));
+        output.set_subtitle(true);
+        let document = document(&output);
+        assert!(document.contains("class=\"subtitle\""));
+        assert!(document.contains("\nSiding Spring\n</text>"));
+
+        let (_, observer) = make_toy_example(DEFAULT_SEED);
+        let observatory = Observatory::from_lat_long(
+            -31.27,
+            149.06,
+            observer,
+            Ok("Siding Spring".into()),
+            vec![],
+        );
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Siding Spring", 0).with_observatory(&observatory),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert!(document.contains("\nSiding Spring, 31.27°S 149.06°E\n</text>"));
+    }
+
+    #[test]
+    fn circumpolar_star_trail_closes_after_a_day() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-circumpolar-star-trail-closes-after-a-day");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let root = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        root.write().unwrap().rotation =
+            Some(crate::body::rotating::Rotating::new(24.0, Spherical::UP));
+        // Close to the north pole, so it never sets from high northern latitudes
+        let star = Body::new(
+            Some(root.clone()),
+            crate::dynamic::fixed::Fixed::new(Vector3 {
+                x: 1e5,
+                y: 0.0,
+                z: 1e6,
+            }),
+        );
+        star.write().unwrap().name = crate::body::Name::Named("Polaris".into());
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::from_lat_long(60.0, 0.0, root, Ok("Trails".into()), vec![]);
+
+        let output = Trail::new(StatelessOrthographic(), "Polaris");
+        for time in 0..=24 {
+            #[allow(clippy::cast_precision_loss)]
+            let observations = observatory.observe(time as Float);
+            output
+                .write_observations(
+                    &observations,
+                    &[],
+                    FrameInfo::new("Trails", time),
+                    &output_root,
+                )
+                .unwrap();
+        }
+        output.flush().unwrap();
+
+        let document =
+            std::fs::read_to_string(output_root.join("Trails").join("trail-Polaris.svg")).unwrap();
+        assert_eq!(document.matches("<polyline").count(), 1);
+        assert_eq!(document.matches("<circle class=\"trail\"").count(), 25);
+
+        let start = document.find("points=\"").unwrap() + "points=\"".len();
+        let points: Vec<Vec<Float>> = document[start..]
+            .split('"')
+            .next()
+            .unwrap()
+            .split(' ')
+            .map(|point| point.split(',').map(|x| x.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(points.len(), 25);
+        // Back where it started after a full turn, having gone somewhere in between
+        assert_float_absolute_eq!(points[0][0], points[24][0], 1e-4);
+        assert_float_absolute_eq!(points[0][1], points[24][1], 1e-4);
+        let halfway = (points[12][0] - points[0][0]).hypot(points[12][1] - points[0][1]);
+        assert!(halfway > 0.01, "{halfway}");
+    }
+
+    #[test]
+    fn trail_paths_follow_template() {
+        let mut output = Trail::new(StatelessOrthographic(), "../Alpha/Centauri");
+        let root = Path::new("out");
+        assert_eq!(
+            output.path(root, "Trails"),
+            Path::new("out/Trails/trail-.._Alpha_Centauri.svg")
+        );
+
+        output.set_file_name_template(Some("{observatory}-{time}{ext}".into()));
+        assert_eq!(
+            output.path(root, "Trails"),
+            Path::new("out/Trails-trail-.._Alpha_Centauri.svg")
+        );
+    }
+
+    #[test]
+    fn effective_horizon_inside_true_horizon() {
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let valley = Observatory::new(Spherical::UP, observer.clone(), Ok("Valley".into()), vec![])
+            .with_horizon_altitude_deg(10.0);
+        let plain = Observatory::new(Spherical::UP, observer, Ok("Plain".into()), vec![]);
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(effective_horizon));
+
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Valley", 0).with_observatory(&valley),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert_eq!(document.matches("class=\"effective-horizon\"").count(), 1);
+        let start = document.find("points=\"").unwrap() + "points=\"".len();
+        let (x, y) = document[start..]
+            .split(' ')
+            .next()
+            .unwrap()
+            .split_once(',')
+            .unwrap();
+        let radius = x.parse::<Float>().unwrap().hypot(y.parse().unwrap());
+        // Orthographic projections draw the ring at the cosine of its altitude
+        assert_float_absolute_eq!(radius, Float::to_radians(10.0).cos(), 1e-4);
+
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Plain", 0).with_observatory(&plain),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert!(!document.contains("class=\"effective-horizon\""));
+    }
+
+    #[test]
+    fn meridian_through_zenith_and_pole() {
+        let earth = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        earth.write().unwrap().rotation =
+            Some(crate::body::rotating::Rotating::new(24.0, Spherical::UP));
+        Body::hydrate_all(&earth, &None);
+        // 40°N
+        let location = Spherical {
+            radius: 1.0,
+            polar_angle: Float::to_radians(50.0),
+            azimuthal_angle: 0.0,
+        };
+        let observatory = Observatory::new(location, earth, Ok("Temperate".into()), vec![]);
+        let pole = observatory.celestial_pole(3.0).unwrap();
+        assert_float_absolute_eq!(pole.z, Float::to_radians(40.0).sin(), 1e-4);
+        let projected_pole = StatelessOrthographic()
+            .project_with_state(&pole.into())
+            .unwrap();
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(meridian));
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Temperate", 3).with_observatory(&observatory),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert_eq!(document.matches("class=\"meridian\"").count(), 1);
+
+        let start = document.find("points=\"").unwrap() + "points=\"".len();
+        let end = start + document[start..].find('"').unwrap();
+        let points: Vec<(Float, Float)> = document[start..end]
+            .split(' ')
+            .map(|point| {
+                let (x, y) = point.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect();
+        // Through the center of the chart, where the zenith is drawn
+        assert!(points.iter().any(|(x, y)| x.abs() < 1e-4 && y.abs() < 1e-4));
+        // And along the line from it through the pole
+        for (x, y) in points {
+            assert_float_absolute_eq!(x * projected_pole.y - y * projected_pole.x, 0.0, 1e-4);
+        }
+
+        // The observatory is given with the frame, so it is found whatever its directory is
+        // called, e.g. when directories are named by ID, and at whatever time it observed at
+        let document = output
+            .consume_observation(
+                FrameInfo::new("7f3a2c", 3)
+                    .with_observatory(&observatory)
+                    .observed_at(9.5),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert_eq!(document.matches("class=\"meridian\"").count(), 1);
+
+        // Bodies that don't rotate have no meridian
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let still = Observatory::new(Spherical::UP, observer, Ok("Still".into()), vec![]);
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(meridian));
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Still", 0).with_observatory(&still),
+                &[],
+                &[],
+            )
+            .to_string();
+        assert!(!document.contains("class=\"meridian\""));
+    }
+
+    #[test]
+    fn configurable_bisections() {
+        let mut output = Svg::new(StatelessOrthographic());
+        let count_lines = |output: &Svg<StatelessOrthographic>| {
+            output
+                .consume_observation(FrameInfo::new("Grid", 0), &[], &[])
+                .to_string()
+                .matches("<line")
+                .count()
+        };
+
+        assert_eq!(count_lines(&output), usize::from(DEFAULT_BISECTIONS));
+        output.set_bisections(8);
+        assert_eq!(count_lines(&output), 8);
+        output.set_bisections(0);
+        assert_eq!(count_lines(&output), 0);
+    }
+
+    #[test]
+    fn layer_drawn_once_per_frame() {
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = crate::body::observatory::Observatory::new(
+            Spherical::UP,
+            observer,
+            Ok("Layered".into()),
+            vec![],
+        );
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(|context, _| {
+            Group::new().add(
+                Rectangle::new()
+                    .set("class", "test-layer")
+                    .set("data-time", context.time.to_string()),
+            )
+        }));
+
+        for time in 0..5 {
+            #[allow(clippy::cast_precision_loss)]
+            let observations = observatory.observe(time as Float);
+            let document = output
+                .consume_observation(FrameInfo::new("Layered", time), &observations, &[])
+                .to_string();
+
+            assert_eq!(document.matches("class=\"test-layer\"").count(), 1);
+            assert!(document.contains(&format!("data-time=\"{time}\"")));
+            // Drawn on top of the built in content
+            let layer = document.find("test-layer").unwrap();
+            assert!(document
+                .rfind("<circle")
+                .is_none_or(|circle| circle < layer));
+        }
+    }
+
+    #[test]
+    fn constellations_have_own_colors() {
+        let line = |color: &str| crate::constellation::Line {
+            start: Spherical::UP,
+            end: Spherical::LEFT,
+            color: color.into(),
+            width: crate::constellation::DEFAULT_WIDTH,
+            culture: None,
+        };
+        let output = Svg::new(StatelessOrthographic());
+
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Colorful", 0),
+                &[],
+                &[line("#F00"), line("#0F0")],
+            )
+            .to_string();
+
+        assert!(document.contains("stroke:#F00"));
+        assert!(document.contains("stroke:#0F0"));
+    }
+
+    #[test]
+    fn only_selected_cultures_drawn() {
+        let line = |color: &str, culture: Option<&str>| crate::constellation::Line {
+            start: Spherical::UP,
+            end: Spherical::LEFT,
+            color: color.into(),
+            width: 0.01,
+            culture: culture.map(Into::into),
+        };
+        let lines = [
+            line("#F00", Some("Western")),
+            line("#0F0", Some("Navajo")),
+            line("#00F", None),
+        ];
+        let mut output = Svg::new(StatelessOrthographic());
+
+        let everything = output
+            .consume_observation(FrameInfo::new("Cultured", 0), &[], &lines)
+            .to_string();
+        assert_eq!(everything.matches("class=\"constellation\"").count(), 3);
+        assert!(everything.contains("stroke-width: 0.01;"));
+
+        output.select_cultures(Some(vec!["Navajo".into()]));
+        let document = output
+            .consume_observation(FrameInfo::new("Cultured", 0), &[], &lines)
+            .to_string();
+
+        assert_eq!(document.matches("class=\"constellation\"").count(), 1);
+        assert!(document.contains("stroke:#0F0"));
+        assert!(!document.contains("stroke:#F00"));
+        assert!(!document.contains("stroke:#00F"));
+    }
+
+    #[test]
+    fn tails_only_drawn_near_star() {
+        use crate::{body::tail::Tail, consts::ASTRONOMICAL_UNIT, dynamic::fixed::Fixed};
+        use coordinates::prelude::Vector3;
+
+        let sun = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let observer = Body::new(Some(sun.clone()), Fixed::new(Vector3::DOWN * 1_000.0));
+        let near = Body::new(Some(sun.clone()), Fixed::new(Vector3::RIGHT * 50.0));
+        let far = Body::new(
+            Some(sun.clone()),
+            Fixed::new(Vector3::RIGHT * (ASTRONOMICAL_UNIT * 30.0)),
+        );
+        sun.write().unwrap().luminosity = Some(1.0);
+        for comet in [&near, &far] {
+            comet.write().unwrap().tail = Some(Tail::new(10.0));
+        }
+        Body::hydrate_all(&sun, &None);
+        let observatory = crate::body::observatory::Observatory::new(
+            Spherical::UP,
+            observer,
+            Ok("Comets".into()),
+            vec![],
+        );
+
+        // The near tail points away from the sun overhead, towards the horizon
+        let near_location = observatory
+            .observe(0.0)
+            .into_iter()
+            .find(|(b, _)| std::sync::Arc::ptr_eq(b, &near))
+            .unwrap()
+            .1;
+        let near_end = observatory.tail_end(&near, 0.0).unwrap();
+        assert!(near_end.polar_angle > near_location.polar_angle + 0.1);
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(comet_tails));
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Comets", 0).with_observatory(&observatory),
+                &observatory.observe(0.0),
+                &[],
+            )
+            .to_string();
+
+        assert_eq!(document.matches("class=\"tail\"").count(), 1);
+    }
+
+    #[test]
+    fn rings_tilt_with_equator() {
+        use crate::{body::rotating::Rotating, dynamic::fixed::Fixed};
+        use coordinates::prelude::Vector3;
+
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let planet = Body::new(Some(root.clone()), Fixed::new(Vector3::UP * 100.0));
+        planet.write().unwrap().rings = Some((2.0, 5.0));
+        Body::hydrate_all(&root, &None);
+        let observatory = crate::body::observatory::Observatory::new(
+            Spherical::UP,
+            root.clone(),
+            Ok("Rings".into()),
+            vec![],
+        );
+
+        // Overhead with its pole towards us, so the rings are circles around it
+        let [inner, outer] = observatory.ring_edges(&planet, 0.0, 16).unwrap();
+        for (edge, radius) in [(&inner, 2.0), (&outer, 5.0)] {
+            assert_eq!(edge.len(), 16);
+            for point in edge {
+                let polar_angle = Spherical::from(*point).polar_angle;
+                assert_float_relative_eq!(polar_angle, (radius / 100.0 as Float).atan(), 1e-4);
+            }
+        }
+
+        // Edge on, with its pole along the horizon
+        planet.write().unwrap().rotation = Some(Rotating::new(10.0, Spherical::RIGHT));
+        let [_, outer] = observatory.ring_edges(&planet, 0.0, 16).unwrap();
+        for point in &outer {
+            assert_float_absolute_eq!(point.x, 0.0, 1e-4);
+        }
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(planetary_rings));
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Rings", 0).with_observatory(&observatory),
+                &observatory.observe(0.0),
+                &[],
+            )
+            .to_string();
+        assert_eq!(document.matches("class=\"ring\"").count(), 2);
+
+        // Bodies without rings have none drawn
+        planet.write().unwrap().rings = None;
+        assert!(observatory.ring_edges(&planet, 0.0, 16).is_none());
+    }
+
+    #[test]
+    fn finder_centered_on_target() {
+        let root = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        let bodies: Vec<_> = ["Target", "Neighbour", "Distant"]
+            .into_iter()
+            .map(|name| {
+                let body = Body::new(
+                    Some(root.clone()),
+                    crate::dynamic::fixed::Fixed::new(Vector3::UP),
+                );
+                body.write().unwrap().name = crate::body::Name::Named(name.into());
+                body
+            })
+            .collect();
+        Body::hydrate_all(&root, &None);
+        let at = |polar_angle: Float| Spherical {
+            radius: 100.0,
+            polar_angle,
+            azimuthal_angle: 1.0,
+        };
+        // The neighbour is 2° from the target and the distant body 20°
+        let observations = vec![
+            (bodies[0].clone(), at(0.8)),
+            (bodies[1].clone(), at(0.8 + 2_f32.to_radians() as Float)),
+            (bodies[2].clone(), at(0.8 + 20_f32.to_radians() as Float)),
+        ];
+
+        let mut output = Svg::new(StatelessOrthographic());
+        output.set_finder(Some(Finder::new("Target", 5_f32.to_radians() as Float)));
+        let (view, _) = output.in_view(&observations, Cow::Borrowed(&[]));
+        assert_eq!(view.len(), 2);
+        assert_float_absolute_eq!(view[0].1.polar_angle, 0.0, 1e-3);
+        // Stretched so the edge of the field of view is at the horizon
+        assert_float_absolute_eq!(view[1].1.polar_angle, float::FRAC_PI_2 * 0.4, 1e-3);
+
+        let document = output
+            .consume_observation(FrameInfo::new("Finder", 0), &observations, &[])
+            .to_string();
+        let target = StatelessOrthographic::project(&view[0].1).unwrap();
+        assert_float_absolute_eq!(target.x, 0.0, 1e-3);
+        assert_float_absolute_eq!(target.y, 0.0, 1e-3);
+        assert!(document.contains("class=\"Target\""));
+        assert!(document.contains("class=\"Neighbour\""));
+        assert!(!document.contains("Distant"));
+        assert_eq!(document.matches("class=\"label\"").count(), 2);
+
+        // Nothing is drawn when the target isn't observed
+        output.set_finder(Some(Finder::new("Missing", 0.1)));
+        let document = output
+            .consume_observation(FrameInfo::new("Finder", 0), &observations, &[])
+            .to_string();
+        assert!(!document.contains("class=\"Target\""));
+    }
+
+    #[test]
+    fn full_sky_outline_drawn() {
+        let root = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        let below = Body::new(
+            Some(root.clone()),
+            crate::dynamic::fixed::Fixed::new(Vector3::UP),
+        );
+        Body::hydrate_all(&root, &None);
+        let name = below.read().unwrap().get_name();
+        let observations = [(below, Spherical::DOWN * 10.0)];
+
+        let document = Svg::new(crate::projection::Mollweide())
+            .consume_observation(FrameInfo::new("Whole sky", 0), &observations, &[])
+            .to_string();
+        // Fitted around the ellipse instead of the unit circle
+        assert!(
+            document.contains("viewBox=\"-2.02 -1.02 4.04 2.04\""),
+            "{document}"
+        );
+        assert!(document.contains("<polygon class=\"outer\""));
+        assert!(!document.contains("class=\"outer\" cx"));
+        assert!(!document.contains("<line"));
+        // Bodies below the horizon are drawn like any other
+        assert!(document.contains(&format!("class=\"{name}\"")));
+        assert!(!document.contains("class=\"horizon"));
+
+        let document = Svg::new(StatelessOrthographic())
+            .consume_observation(FrameInfo::new("Hemisphere", 0), &observations, &[])
+            .to_string();
+        assert!(document.contains("viewBox=\"-1.02 -1.02 2.04 2.04\""));
+        assert!(!document.contains("<polygon class=\"outer\""));
+    }
+
+    #[test]
+    fn compass_rose_labels_horizon() {
+        let mut output = Svg::new(StatelessOrthographic());
+        output.add_layer(Box::new(compass_rose));
+
+        let document = output
+            .consume_observation(FrameInfo::new("Compass", 0), &[], &[])
+            .to_string();
+
+        assert_eq!(document.matches("class=\"compass\"").count(), 4);
+        for label in ["N", "E", "S", "W"] {
+            assert!(document.contains(&format!("\n{label}\n</text>")));
+        }
+    }
+
+    #[test]
+    fn thumbnails_written_beside_charts() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-thumbnails-written-beside-charts");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new(Spherical::UP, observer, Ok("Thumbs".into()), vec![]);
+        let constellations = [crate::constellation::Line {
+            start: Spherical::UP,
+            end: Spherical::LEFT,
+            color: "#F00".into(),
+            width: crate::constellation::DEFAULT_WIDTH,
+            culture: None,
+        }];
+
+        let mut full = Svg::new(StatelessOrthographic());
+        full.add_layer(Box::new(compass_rose));
+        let output = MultiRes::new(full.clone(), 0.01);
+
+        for time in 0..5 {
+            #[allow(clippy::cast_precision_loss)]
+            let observations = observatory.observe(time as Float);
+            output
+                .write_observations(
+                    &observations,
+                    &constellations,
+                    FrameInfo::new("Thumbs", time),
+                    &output_root,
+                )
+                .unwrap();
+
+            let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+            let chart = read(&crate::output::to_default_path(
+                &output_root,
+                "Thumbs",
+                time,
+                ".svg",
+            ));
+            let thumbnail = read(&crate::output::to_default_path(
+                &output_root.join("Thumbs"),
+                "thumbs",
+                time,
+                ".svg",
+            ));
+
+            // The full chart is unchanged from a single output
+            assert_eq!(
+                chart,
+                full.consume_observation(
+                    FrameInfo::new("Thumbs", time),
+                    &observations,
+                    &constellations
+                )
+                .to_string()
+            );
+            assert!(thumbnail.matches('<').count() < chart.matches('<').count());
+            assert!(!thumbnail.contains("<line"));
+            assert!(!thumbnail.contains("<text"));
+        }
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn charts_named_by_template() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-charts-named-by-template");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let (root, observer) = make_toy_example(DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new(Spherical::UP, observer, Ok("Flat".into()), vec![]);
+
+        let mut full = Svg::new(StatelessOrthographic());
+        full.set_file_name_template(Some("{time}-{observatory}{ext}".into()));
+        let output = MultiRes::new(full, 0.01);
+        output
+            .write_observations(
+                &observatory.observe(0.0),
+                &[],
+                FrameInfo::new("Flat", 12),
+                &output_root,
+            )
+            .unwrap();
+
+        assert!(output_root.join("0000000012-Flat.svg").is_file());
+        assert!(output_root
+            .join(MultiRes::<StatelessOrthographic>::THUMBNAIL_DIRECTORY)
+            .join("0000000012-Flat.svg")
+            .is_file());
+        assert!(!output_root.join("Flat").exists());
+
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn ground_drawn_along_horizon() {
+        let planet = Body::new(None, crate::dynamic::fixed::Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().radius = Some(0.02);
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::new(Spherical::UP, planet, Ok("Grounded".into()), vec![]);
+        let output = Svg::new(StatelessOrthographic());
+
+        let without_ground = output
+            .consume_observation(
+                FrameInfo::new("Grounded", 0),
+                &observatory.observe(0.0),
+                &[],
+            )
+            .to_string();
+        let document = output
+            .consume_observation(
+                FrameInfo::new("Grounded", 0),
+                &observatory.with_include_host(true).observe(0.0),
+                &[],
+            )
+            .to_string();
+
+        assert!(!without_ground.contains("<polyline"));
+        assert_eq!(document.matches("<polyline").count(), 1);
+        // The whole way around the horizon
+        let points = document.split("points=\"").nth(1).unwrap();
+        let points = &points[..points.find('"').unwrap()];
+        for point in points.split(' ') {
+            let (x, y) = point.split_once(',').unwrap();
+            let radius = x.parse::<Float>().unwrap().hypot(y.parse().unwrap());
+            assert_float_absolute_eq!(radius, 1.0, 1e-4);
+        }
+        assert_eq!(points.split(' ').count(), 65);
+    }
 }
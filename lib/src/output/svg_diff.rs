@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use svg::{
+    node::element::tag::Type,
+    parser::{Event, Parser},
+};
+
+use crate::Float;
+
+/// An element of an SVG document, with its attributes and any text directly inside it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Element {
+    /// Name of the tag, e.g. `circle`
+    pub name: String,
+    /// Attributes of the element, sorted by name
+    pub attributes: BTreeMap<String, String>,
+    /// Text inside the element, with surrounding whitespace removed
+    pub text: String,
+}
+
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}", self.name)?;
+        for (name, value) in &self.attributes {
+            write!(f, " {name}=\"{value}\"")?;
+        }
+        write!(f, ">{}", self.text)
+    }
+}
+
+/// A way two documents differ, see [`compare`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    /// The element was expected but not found
+    Missing(Element),
+    /// The element was found but not expected
+    Unexpected(Element),
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(element) => write!(f, "- {element}"),
+            Self::Unexpected(element) => write!(f, "+ {element}"),
+        }
+    }
+}
+
+/// Reads every element of an SVG document, in document order.
+///
+/// # Errors
+/// Returns an error if the document isn't valid SVG
+pub fn parse(document: &str) -> Result<Vec<Element>, svg::parser::Error> {
+    let mut elements: Vec<Element> = Vec::new();
+    // Indexes of elements that haven't been closed yet, so text goes in the right one
+    let mut open = Vec::new();
+
+    for event in Parser::new(document) {
+        match event {
+            Event::Error(e) => return Err(e),
+            Event::Tag(name, tag_type, attributes) => match tag_type {
+                Type::Start | Type::Empty => {
+                    if tag_type == Type::Start {
+                        open.push(elements.len());
+                    }
+                    elements.push(Element {
+                        name: name.to_owned(),
+                        attributes: attributes
+                            .into_iter()
+                            .map(|(name, value)| (name, value.to_string()))
+                            .collect(),
+                        text: String::new(),
+                    });
+                }
+                Type::End => {
+                    open.pop();
+                }
+            },
+            Event::Text(text) => {
+                if let Some(element) = open.last().and_then(|i| elements.get_mut(*i)) {
+                    element.text.push_str(text.trim());
+                }
+            }
+            Event::Comment(_) | Event::Declaration(_) | Event::Instruction(_) => (),
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Compares two documents by their elements, ignoring the order the elements appear in. Numeric
+/// attributes match if they are within `relative_tolerance` of each other (relative to the larger
+/// of the two), all other attributes and text must match exactly.
+///
+/// Returns every element that couldn't be paired with a matching element in the other document,
+/// so an empty list means the documents are equivalent.
+#[must_use]
+pub fn compare(
+    expected: &[Element],
+    actual: &[Element],
+    relative_tolerance: Float,
+) -> Vec<Difference> {
+    let mut unmatched: Vec<Option<&Element>> = actual.iter().map(Some).collect();
+    let mut result = Vec::new();
+
+    for element in expected {
+        let found = unmatched.iter_mut().find(|candidate| {
+            candidate.is_some_and(|candidate| is_match(element, candidate, relative_tolerance))
+        });
+        match found {
+            Some(candidate) => *candidate = None,
+            None => result.push(Difference::Missing(element.clone())),
+        }
+    }
+
+    result.extend(
+        unmatched
+            .into_iter()
+            .flatten()
+            .map(|element| Difference::Unexpected(element.clone())),
+    );
+    result
+}
+
+/// Returns true if two elements are the same, within a tolerance for numeric attributes
+fn is_match(a: &Element, b: &Element, relative_tolerance: Float) -> bool {
+    a.name == b.name
+        && a.text == b.text
+        && a.attributes.len() == b.attributes.len()
+        && a.attributes.iter().all(|(name, value_a)| {
+            b.attributes.get(name).is_some_and(|value_b| {
+                match (value_a.parse::<Float>(), value_b.parse::<Float>()) {
+                    (Ok(x), Ok(y)) => {
+                        // Allow for rounding errors around zero
+                        (x - y).abs() <= relative_tolerance * x.abs().max(y.abs()) + 1e-9
+                    }
+                    _ => value_a == value_b,
+                }
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A document with two bodies drawn in opposite orders
+    const DOCUMENT: &str = r#"<svg viewBox="-1 -1 2 2">
+<circle class="a" cx="0.5" cy="0" r="0.01"/>
+<circle class="b" cx="0" cy="-0.25" r="0.005"/>
+<text class="heading" x="-0.98">
+t=0000000001
+</text>
+</svg>"#;
+
+    #[test]
+    fn order_is_ignored() {
+        let expected = parse(DOCUMENT).unwrap();
+        let mut actual = expected.clone();
+        actual.swap(1, 2);
+
+        assert_eq!(expected.len(), 4);
+        assert_eq!(expected[3].text, "t=0000000001");
+        assert!(compare(&expected, &actual, 1e-6).is_empty());
+    }
+
+    #[test]
+    fn small_changes_are_found() {
+        let expected = parse(DOCUMENT).unwrap();
+        let actual = parse(&DOCUMENT.replace("r=\"0.005\"", "r=\"0.00505\"")).unwrap();
+
+        let differences = compare(&expected, &actual, 1e-4);
+        assert_eq!(differences.len(), 2);
+        assert!(matches!(&differences[0], Difference::Missing(e) if e.attributes["class"] == "b"));
+        assert!(
+            matches!(&differences[1], Difference::Unexpected(e) if e.attributes["r"] == "0.00505")
+        );
+
+        // Within tolerance
+        assert!(compare(&expected, &actual, 2e-2).is_empty());
+    }
+}
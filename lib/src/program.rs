@@ -1,23 +1,38 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
 
 use derive_builder::Builder;
-use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use tracing::{debug_span, field, info, info_span, warn, Span};
 
 use rayon::prelude::*;
 
 use crate::{
     body::{
         observatory::{to_observatory, Observatory, WeakObservatory},
-        Arc,
+        Arc, Body,
+    },
+    output::{
+        config::OutputConfig,
+        logger::eclipse::{eclipses_in, EclipseEvent},
+        recording::Frame,
+        svg::Svg,
+        FrameInfo, Output,
     },
-    output::Output,
-    Float,
+    projection::StatelessOrthographic,
+    Float, LocalObservation,
 };
 
 /// A facade that takes values from [`crate::body::observatory::Observatory`] in the tree defined at the root of [`Self::_root_body`] that outputs using the given [outputs](crate::output::Output) provided with a [path](Self::output_file_root)
 #[derive(Builder, Clone, Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase", from = "DeserializedProgram")]
+#[serde(
+    rename_all = "camelCase",
+    try_from = "DeserializedProgram",
+    into = "SerializedProgram"
+)]
 pub struct Program {
     /// The root of the tree, we need to reference it here to prevent the reference counter from
     /// reaching zero prematurely.
@@ -30,10 +45,248 @@ pub struct Program {
     #[builder(setter(each(name = "add_output")))]
     #[serde(skip)]
     outputs: Vec<Box<dyn crate::output::Output>>,
+    /// Configs of the outputs added with [`Program::add_output_config`], so they can be written
+    /// to program files
+    #[builder(setter(skip), default)]
+    #[serde(skip)]
+    output_configs: Vec<OutputConfig>,
     /// Location where output files will be stored, typically under a subdirectory for which
     /// observatory made that observation.
     #[builder(default)]
     output_file_root: PathBuf,
+    /// The output file root as it was written in the program file it was
+    /// [read from](Program::from_file), if it was relative to the file. It is written back in
+    /// this form so the program still works wherever it is moved to.
+    #[builder(setter(skip), default)]
+    #[serde(skip)]
+    written_output_file_root: Option<PathBuf>,
+    /// Name the output directory of each observatory after its [ID](Observatory::get_id) instead
+    /// of its name, see [`Program::observatory_directories`]
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    directories_by_id: bool,
+    /// Sky cultures whose constellations are given to outputs, or `None` for every constellation.
+    /// See [`crate::constellation::Constellation::is_in`]
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cultures: Option<Vec<String>>,
+    /// Time in hours that frame zero is observed at, see [`Program::set_epoch`]
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "crate::json::is_zero")]
+    epoch: Float,
+    /// Give outputs frames in ascending order of time, see [`Program::set_ordered_delivery`]
+    #[builder(default)]
+    #[serde(skip)]
+    ordered_delivery: bool,
+}
+
+/// Every observatory's frame at one time in the same order as [`Program::observatories`], each
+/// with the span it was observed in, along with the span of the whole frame
+type ObservedFrames = (Span, Vec<(Frame, Span)>);
+
+/// How many frames each thread observes ahead of the frames being delivered with
+/// [ordered delivery](Program::set_ordered_delivery)
+const ORDERED_FRAMES_PER_THREAD: usize = 4;
+
+/// An error writing the outputs of a run. Runs carry on past errors writing frames, so every
+/// other frame is still written.
+#[derive(Debug)]
+pub enum ObservationError {
+    /// The output directory couldn't be created, so nothing was written
+    OutputRoot(std::io::Error),
+    /// An output couldn't write a frame
+    Write {
+        /// Time of the frame
+        time: i128,
+        /// [Directory name](Program::observatory_directories) of the observatory that observed it
+        observatory: String,
+        /// What went wrong
+        error: std::io::Error,
+    },
+    /// An output couldn't [finalize](Output::finalize_frame) the frames at a time
+    Finalize {
+        /// Time of the frames
+        time: i128,
+        /// What went wrong
+        error: std::io::Error,
+    },
+    /// An output couldn't be [flushed](Output::flush) at the end of the run
+    Flush(std::io::Error),
+    /// The checkpoint of a [resumable run](Program::make_observations_resumable) couldn't be
+    /// read or written
+    Checkpoint(std::io::Error),
+    /// More than one thing went wrong, in the order the frames were observed
+    Multiple(Vec<Self>),
+}
+
+impl ObservationError {
+    /// Returns `Ok` if nothing went wrong, otherwise the only error or all of them
+    fn from_errors(mut errors: Vec<Self>) -> Result<(), Self> {
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Self::Multiple(errors)),
+        }
+    }
+
+    /// Returns the first underlying I/O error
+    fn first_io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::OutputRoot(error)
+            | Self::Write { error, .. }
+            | Self::Finalize { error, .. }
+            | Self::Flush(error)
+            | Self::Checkpoint(error) => Some(error),
+            Self::Multiple(errors) => errors.iter().find_map(Self::first_io_error),
+        }
+    }
+}
+
+impl std::fmt::Display for ObservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutputRoot(e) => write!(f, "could not create the output directory: {e}"),
+            Self::Write {
+                time,
+                observatory,
+                error,
+            } => write!(f, "could not write t={time} from {observatory}: {error}"),
+            Self::Finalize { time, error } => write!(f, "could not finalize t={time}: {error}"),
+            Self::Flush(e) => write!(f, "could not flush an output: {e}"),
+            Self::Checkpoint(e) => write!(f, "could not use the checkpoint: {e}"),
+            Self::Multiple(errors) => {
+                write!(f, "{} errors", errors.len())?;
+                for (i, e) in errors.iter().enumerate() {
+                    write!(f, "{} {e}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObservationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Multiple(_) => None,
+            _ => self
+                .first_io_error()
+                .map(|e| e as &(dyn std::error::Error + 'static)),
+        }
+    }
+}
+
+impl From<ObservationError> for std::io::Error {
+    fn from(value: ObservationError) -> Self {
+        let kind = value
+            .first_io_error()
+            .map_or(std::io::ErrorKind::Other, std::io::Error::kind);
+        Self::new(kind, value)
+    }
+}
+
+/// A body that will look frozen (or barely move) in every frame, because frames are close to a
+/// whole number of its periods apart. See [`Program::aliasing_warnings`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aliasing {
+    /// Name of the body
+    pub body: std::sync::Arc<str>,
+    /// True if the body's rotation aliases, false if its orbit does
+    pub rotation: bool,
+    /// The period in hours that aliases
+    pub period: Float,
+    /// How many periods pass between frames
+    pub multiple: Float,
+}
+
+impl std::fmt::Display for Aliasing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} will look frozen, each step is {} of its {} hour {}",
+            self.body,
+            self.multiple,
+            self.period,
+            if self.rotation { "days" } else { "orbits" }
+        )
+    }
+}
+
+/// One observation of a body in a [`BodySeries`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesPoint {
+    /// Time of the frame in hours, not including the epoch or the observatory's time offset
+    pub time: i128,
+    /// Angle above the horizon in radians
+    pub altitude: Float,
+    /// Angle around the horizon in radians
+    pub azimuth: Float,
+    /// Distance to the body in light seconds
+    pub distance: Float,
+    /// Apparent magnitude, see [`Observatory::apparent_magnitude`]
+    pub magnitude: Option<Float>,
+}
+
+/// Every observation of one body from one observatory, see [`Program::body_series`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodySeries {
+    /// Name of the observatory, as used for its output directory
+    pub observatory: String,
+    /// Name of the body
+    pub body: std::sync::Arc<str>,
+    /// Observations of the body, ascending by time
+    pub points: Vec<SeriesPoint>,
+}
+
+/// What a [`Program`] contains, see [`Program::summary`], and what to look out for in a run of
+/// it, see [`Program::run_summary`]
+///
+/// This is only [`PartialEq`], as the warnings hold floating point periods and errors.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ProgramSummary {
+    /// Number of bodies in the tree, including the root
+    pub body_count: usize,
+    /// Names of the observatories, in the order they are observed from
+    pub observatories: Vec<String>,
+    /// Number of configured outputs
+    pub outputs: usize,
+    /// Location where output files will be stored
+    pub output_file_root: PathBuf,
+    /// Bodies that will look frozen between the frames of the run, see
+    /// [`Program::aliasing_warnings`]
+    pub aliasing: Vec<Aliasing>,
+    /// Bodies that may be visibly out of place during the run, see
+    /// [`crate::validate::precision_report`]
+    pub imprecise: Vec<crate::validate::PrecisionWarning>,
+}
+
+impl ProgramSummary {
+    /// Returns the warnings about the run, aliasing first, formatted as they are displayed
+    pub fn warnings(&self) -> impl Iterator<Item = String> + '_ {
+        self.aliasing
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.imprecise.iter().map(ToString::to_string))
+    }
+}
+
+impl std::fmt::Display for ProgramSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} bodies, {} observatories ({}), {} outputs writing to {}",
+            self.body_count,
+            self.observatories.len(),
+            self.observatories.join(", "),
+            self.outputs,
+            self.output_file_root.display()
+        )?;
+        for warning in self.warnings() {
+            write!(f, "\n{warning}")?;
+        }
+        Ok(())
+    }
 }
 
 impl Program {
@@ -44,93 +297,867 @@ impl Program {
     ///
     /// Outputs depend on the implementations of [`crate::output::Output`] used, but generally they
     /// will be files in the directory [`Self::output_file_root`]`/[OBSERVATORY NAME]/`
+    ///
+    /// # Errors
+    /// Returns every error the outputs had writing frames, after writing all the frames they
+    /// could. Returns an error without writing anything if the output directory can't be created.
+    pub fn make_observations(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> Result<(), ObservationError> {
+        self.warn_before_run(start_time, end_time, step_size);
+        let times: Vec<_> = (start_time..end_time)
+            .step_by(step_size.unwrap_or(1))
+            .collect();
+
+        self.make_observations_at(&times)
+    }
+
+    /// Makes the same observations as [`Self::make_observations`] but returns them instead of
+    /// passing them to the outputs, as `(time, observatory, observations)` where the observatory
+    /// is named by its [directory name](Self::observatory_directories).
+    ///
+    /// The result is sorted by time and then observatory, whichever order the frames were
+    /// observed in.
+    #[must_use]
+    pub fn collect_observations(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> Vec<(i128, String, Vec<LocalObservation>)> {
+        let directories = self.observatory_directories();
+        let times: Vec<_> = (start_time..end_time)
+            .step_by(step_size.unwrap_or(1))
+            .collect();
+
+        let mut result: Vec<_> = times
+            .par_iter()
+            .flat_map_iter(|&time| {
+                self.observatories
+                    .iter()
+                    .zip(&directories)
+                    .map(move |(observatory, directory)| {
+                        (
+                            time,
+                            directory.clone(),
+                            observatory.observe(self.observation_time(observatory, time)),
+                        )
+                    })
+            })
+            .collect();
+        result.sort_by(|(a_time, a_name, _), (b_time, b_name, _)| {
+            a_time.cmp(b_time).then_with(|| a_name.cmp(b_name))
+        });
+        result
+    }
+
+    /// Generate observations at each of the given times, in hours.
+    ///
+    /// # Outputs
+    ///
+    /// See [`Self::make_observations`]
+    ///
+    /// # Errors
+    /// See [`Self::make_observations`]
     // Precision loss is inevitable since we are going from an integer to a (compile-time) variable length float
     #[allow(clippy::cast_precision_loss)]
-    #[allow(clippy::missing_panics_doc)] // Should only panic in unit tests
-    pub fn make_observations(&self, start_time: i128, end_time: i128, step_size: Option<usize>) {
-        if let Err(e) = std::fs::create_dir_all(&self.output_file_root) {
-            let message = format!(
-                "ERROR WRITING FILE/DIRECTORY {}, message: {e}",
-                &self
-                    .output_file_root
-                    .to_str()
-                    .unwrap_or("[could not display path]")
-            );
-            if cfg!(test) {
-                // Panic can only occur in internal testing mode when panics are expected
-                panic!("{message}");
-            } else {
-                warn!("{message}");
-            }
-            // We cannot write any outputs, so return without doing anything
-            return;
-        }
+    pub fn make_observations_at(&self, times: &[i128]) -> Result<(), ObservationError> {
+        ObservationError::from_errors(self.make_observations_then(times, |_| ()))
+    }
+
+    /// Observes one frame from the observatory with this [directory
+    /// name](Self::observatory_directories) at `time` hours, as it would be given to the outputs,
+    /// or `None` if there is no such observatory. Constellation lines are always included.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn observe_frame(&self, observatory_name: &str, time: i128) -> Option<Frame> {
+        let observatory = self.observatory_in(observatory_name)?;
+
+        let observation_time = self.observation_time(observatory, time);
+        let observations = observatory.observe(observation_time);
+        let constellations =
+            observatory.add_constellations_in(&observations, self.cultures.as_deref());
+        Some(Frame {
+            directory: observatory_name.to_owned(),
+            time,
+            observation_time,
+            observations,
+            constellations,
+        })
+    }
+
+    /// Returns every eclipse seen by any observatory every `step_size` hours from `start_time`
+    /// until `end_time`, ascending by time and then observatory. Observatories are named by their
+    /// [directory](Self::observatory_directories).
+    ///
+    /// Unlike the eclipse [`Logger`](crate::output::logger::eclipse::Logger) this doesn't use the
+    /// outputs or write any files.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn eclipse_report(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> Vec<EclipseEvent> {
+        let times: Vec<_> = (start_time..end_time)
+            .step_by(step_size.unwrap_or(1))
+            .collect();
+
+        let directories = self.observatory_directories();
+        let mut events: Vec<_> = times
+            .par_iter()
+            .flat_map_iter(|&time| {
+                self.observatories.iter().zip(&directories).flat_map(
+                    move |(observatory, directory)| {
+                        let observations =
+                            observatory.observe(self.observation_time(observatory, time));
+                        eclipses_in(&observations)
+                            .into_iter()
+                            .map(move |(near, far, magnitude)| EclipseEvent {
+                                time,
+                                observatory: directory.clone(),
+                                near,
+                                far,
+                                magnitude,
+                            })
+                    },
+                )
+            })
+            .collect();
+        events.sort_by(|a, b| {
+            a.time
+                .cmp(&b.time)
+                .then_with(|| a.observatory.cmp(&b.observatory))
+        });
+        events
+    }
 
+    /// Returns how many bodies are visible from all of the observatories combined every
+    /// `step_size` hours from `start_time` until `end_time`, as `(time, count)` ascending by
+    /// time. See [`Observatory::visible_count`].
+    ///
+    /// Like [`Self::eclipse_report`] this doesn't use the outputs or write any files.
+    #[must_use]
+    pub fn visible_counts(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> Vec<(i128, usize)> {
         let times: Vec<_> = (start_time..end_time)
             .step_by(step_size.unwrap_or(1))
             .collect();
 
-        let _: Vec<()> = times
+        times
             .par_iter()
-            .map(|time| self.make_observation(*time))
+            .map(|&time| {
+                let count = self
+                    .observatories
+                    .iter()
+                    .map(|observatory| {
+                        observatory.visible_count(self.observation_time(observatory, time))
+                    })
+                    .sum();
+                (time, count)
+            })
+            .collect()
+    }
+
+    /// Returns the observations of each body from each observatory every `step_size` hours from
+    /// `start_time` until `end_time`, grouped by body rather than by frame, e.g. to plot a
+    /// body's altitude over a night. Series are in the order of the observatories then by body
+    /// name, and each series is ascending by time. Only times when the body is visible are in its
+    /// series, like the frames given to the outputs.
+    ///
+    /// Like [`Self::eclipse_report`] this doesn't use the outputs or write any files.
+    #[must_use]
+    pub fn body_series(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> Vec<BodySeries> {
+        let times: Vec<_> = (start_time..end_time)
+            .step_by(step_size.unwrap_or(1))
+            .collect();
+
+        self.observatories
+            .iter()
+            .zip(self.observatory_directories())
+            .flat_map(|(observatory, directory)| {
+                let frames: Vec<_> = times
+                    .par_iter()
+                    .map(|&time| {
+                        let observation_time = self.observation_time(observatory, time);
+                        (
+                            time,
+                            observation_time,
+                            observatory.observe(observation_time),
+                        )
+                    })
+                    .collect();
+
+                let mut by_body: BTreeMap<std::sync::Arc<str>, Vec<SeriesPoint>> = BTreeMap::new();
+                for (time, observation_time, observations) in frames {
+                    for (body, direction) in observations {
+                        let Ok(name) = body.read().map(|body| body.get_name()) else {
+                            continue;
+                        };
+                        by_body.entry(name).or_default().push(SeriesPoint {
+                            time,
+                            altitude: crate::consts::float::FRAC_PI_2 - direction.polar_angle,
+                            azimuth: direction.azimuthal_angle,
+                            distance: direction.radius,
+                            magnitude: observatory.apparent_magnitude(
+                                &body,
+                                direction.radius,
+                                observation_time,
+                            ),
+                        });
+                    }
+                }
+
+                by_body.into_iter().map(move |(body, points)| BodySeries {
+                    observatory: directory.clone(),
+                    body,
+                    points,
+                })
+            })
+            .collect()
+    }
+
+    /// Like [`Self::make_observations`], but records progress in a checkpoint file so that a run
+    /// that is interrupted (e.g. by a crash) can be resumed by calling this again with the same
+    /// arguments. Frames at or before the time in the checkpoint are skipped, and the checkpoint
+    /// is updated as frames complete.
+    ///
+    /// The checkpoint holds the last time that it and every frame before it were written, so
+    /// frames that were written after it when the run was interrupted, or that had errors, are
+    /// written again. Outputs that buffer frames until they are flushed only see the frames from
+    /// the resumed run.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint can't be read or written, frames may still have been
+    /// written if it can't be written. Errors writing frames are returned as in
+    /// [`Self::make_observations`].
+    pub fn make_observations_resumable(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+        checkpoint_path: &Path,
+    ) -> Result<(), ObservationError> {
+        self.warn_before_run(start_time, end_time, step_size);
+        let resume_after = match std::fs::read_to_string(checkpoint_path) {
+            Ok(checkpoint) => Some(checkpoint.trim().parse::<i128>().map_err(|e| {
+                ObservationError::Checkpoint(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid checkpoint {}: {e}", checkpoint_path.display()),
+                ))
+            })?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(ObservationError::Checkpoint(e)),
+        };
+        let times: Vec<_> = (start_time..end_time)
+            .step_by(step_size.unwrap_or(1))
+            .filter(|time| resume_after.is_none_or(|last| *time > last))
             .collect();
+        if let Some(last) = resume_after {
+            info!("Resuming after t={last}, {} frames left", times.len());
+        }
+
+        // Which frames are complete, and how many at the start are complete without gaps
+        let progress = Mutex::new((vec![false; times.len()], 0));
+        let error = Mutex::new(None);
+        let mut errors = self.make_observations_then(&times, |index| {
+            let Ok(mut progress) = progress.lock() else {
+                return;
+            };
+            let (complete, frontier) = &mut *progress;
+            complete[index] = true;
+            let previous_frontier = *frontier;
+            while complete.get(*frontier).copied().unwrap_or(false) {
+                *frontier += 1;
+            }
+
+            if *frontier > previous_frontier {
+                // Write to a temporary file first so the checkpoint is never left half written
+                let temporary = checkpoint_path.with_extension("tmp");
+                let result = std::fs::write(&temporary, times[*frontier - 1].to_string())
+                    .and_then(|()| std::fs::rename(&temporary, checkpoint_path));
+                if let (Err(e), Ok(mut error)) = (result, error.lock()) {
+                    error.get_or_insert(e);
+                }
+            }
+        });
+
+        if let Ok(Some(e)) = error.into_inner() {
+            errors.push(ObservationError::Checkpoint(e));
+        }
+        ObservationError::from_errors(errors)
+    }
+
+    /// Generate observations at each of the given times, calling `on_complete` with the index of
+    /// each time once all of its frames are written without errors. Returns every error writing
+    /// the outputs.
+    fn make_observations_then<F: Fn(usize) + Sync>(
+        &self,
+        times: &[i128],
+        on_complete: F,
+    ) -> Vec<ObservationError> {
+        if let Err(e) = std::fs::create_dir_all(&self.output_file_root) {
+            // We cannot write any outputs, so return without doing anything
+            return vec![ObservationError::OutputRoot(e)];
+        }
+
+        let mut errors = Vec::new();
+
+        if self.ordered_delivery {
+            // Observe a few frames for each thread at once, then deliver them in order, so only
+            // that many frames are held in memory while they wait their turn
+            let mut order: Vec<_> = (0..times.len()).collect();
+            order.sort_by_key(|index| times[*index]);
+            let chunk_size = rayon::current_num_threads() * ORDERED_FRAMES_PER_THREAD;
+            for chunk in order.chunks(chunk_size) {
+                let frames: Vec<_> = chunk
+                    .par_iter()
+                    .map(|index| self.observe_frames(times[*index]))
+                    .collect();
+                for (index, frames) in chunk.iter().zip(frames) {
+                    let frame_errors = self.deliver_frames(times[*index], frames);
+                    if frame_errors.is_empty() {
+                        on_complete(*index);
+                    }
+                    errors.extend(frame_errors);
+                }
+            }
+        } else {
+            errors = times
+                .par_iter()
+                .enumerate()
+                .flat_map_iter(|(index, time)| {
+                    let frame_errors = self.make_observation(*time);
+                    if frame_errors.is_empty() {
+                        on_complete(index);
+                    }
+                    frame_errors
+                })
+                .collect();
+        }
 
         for output in &self.outputs {
-            match output.flush() {
-                Ok(()) => (),
-                Err(e) => warn!("{e}"),
+            if let Err(e) = output.flush() {
+                warn!("{e}");
+                errors.push(ObservationError::Flush(e));
+            }
+        }
+        errors
+    }
+
+    /// Returns the bodies whose orbital or rotation periods are within 1% of dividing the step
+    /// size (in hours) a whole number of times, so they will barely move between frames.
+    #[must_use]
+    pub fn aliasing_warnings(&self, step_size: Option<usize>) -> Vec<Aliasing> {
+        /// How close to a whole number of periods a step can be before it aliases
+        const TOLERANCE: Float = 0.01;
+
+        #[allow(clippy::cast_precision_loss)]
+        let step = step_size.unwrap_or(1).max(1) as Float;
+        let aliases = |body: &std::sync::Arc<str>, rotation, period: Float| {
+            let multiple = (step / period).round();
+            (period > 0.0
+                && multiple >= 1.0
+                && (step - multiple * period).abs() <= TOLERANCE * period)
+                .then(|| Aliasing {
+                    body: body.clone(),
+                    rotation,
+                    period,
+                    multiple,
+                })
+        };
+
+        let mut result = Vec::new();
+        let mut stack = vec![self._root_body.clone()];
+        while let Some(body) = stack.pop() {
+            let Ok(body) = body.read() else {
+                continue;
+            };
+            let name = body.get_name();
+            if let Some(period) = body.get_dynamic().get_period() {
+                result.extend(aliases(&name, false, period));
+            }
+            if let Some(rotation) = &body.rotation {
+                result.extend(aliases(&name, true, rotation.get_sidereal_period()));
             }
+            stack.extend(body.get_children().iter().cloned());
         }
+        result
+    }
+
+    /// Logs a warning for each of the [`Self::aliasing_warnings`], and each body that is
+    /// [too imprecise](crate::validate::precision_report) between the start and end times
+    fn warn_before_run(&self, start_time: i128, end_time: i128, step_size: Option<usize>) {
+        for warning in self.run_summary(start_time, end_time, step_size).warnings() {
+            warn!("{warning}");
+        }
+    }
+
+    /// Returns the times in `[start_time, end_time)` (to the nearest hour) when the observatory
+    /// [in the given directory](Self::observatory_in) is at the given
+    /// [phase of its solar day](Observatory::solar_phase), e.g. `0.5` for every local midnight.
+    /// Pass the result to [`Self::make_observations_at`].
+    ///
+    /// Times are those of the frames passed to outputs, so they allow for the observatory's
+    /// [time offset](Observatory::with_time_offset_hours).
+    ///
+    /// Returns no times if there is no observatory in that directory, or its solar phase is
+    /// unknown.
+    #[must_use]
+    pub fn times_at_local_phase(
+        &self,
+        directory: &str,
+        phase: Float,
+        start_time: i128,
+        end_time: i128,
+    ) -> Vec<i128> {
+        let Some(observatory) = self.observatory_in(directory) else {
+            warn!("No observatory in the directory {directory}");
+            return Vec::new();
+        };
+
+        // Signed distance from the wanted phase, in the range [-0.5, 0.5)
+        #[allow(clippy::cast_precision_loss)]
+        let phase_difference = |time: i128| {
+            observatory
+                .solar_phase(self.observation_time(observatory, time))
+                .map(|p| (p - phase + 0.5).rem_euclid(1.0) - 0.5)
+        };
+
+        let mut times = Vec::new();
+        let mut previous = (start_time, phase_difference(start_time));
+        for time in start_time + 1..=end_time {
+            let current = (time, phase_difference(time));
+            if let ((before_time, Some(before)), (after_time, Some(after))) = (previous, current) {
+                // Phases increase over time, so look for the difference changing sign without
+                // wrapping around
+                if before <= 0.0 && after > 0.0 && after - before < 0.5 {
+                    let closest = if -before <= after {
+                        before_time
+                    } else {
+                        after_time
+                    };
+                    if closest < end_time {
+                        times.push(closest);
+                    }
+                }
+            }
+            previous = current;
+        }
+
+        if times.is_empty() && phase_difference(start_time).is_none() {
+            warn!("The solar phase of {directory} is unknown, it must be on a rotating body that orbits a star");
+        }
+        return times;
+    }
+
+    /// Estimates how many bytes of SVG files [`Self::make_observations`] would write with the same
+    /// arguments, by rendering a frame from each observatory at `start_time` and assuming every
+    /// other frame from that observatory is about the same size.
+    ///
+    /// This is a heuristic, the number of bodies above the horizon changes over time, so it
+    /// should only be relied on to within an order of magnitude.
+    #[must_use]
+    pub fn estimate_output_bytes(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> u64 {
+        let step = step_size.unwrap_or(1).max(1) as u128;
+        let frames = if end_time > start_time {
+            (end_time.abs_diff(start_time) - 1) / step + 1
+        } else {
+            0
+        };
+
+        let svg = Svg::new(StatelessOrthographic());
+        let bytes_per_step: u128 = self
+            .observatories
+            .iter()
+            .zip(self.observatory_directories())
+            .map(|(observatory, directory)| {
+                let observations =
+                    observatory.observe(self.observation_time(observatory, start_time));
+                let constellations =
+                    observatory.add_constellations_in(&observations, self.cultures.as_deref());
+                svg.consume_observation(
+                    FrameInfo::new(&directory, start_time).with_observatory(observatory),
+                    &observations,
+                    &constellations,
+                )
+                .to_string()
+                .len() as u128
+            })
+            .sum();
+
+        u64::try_from(frames.saturating_mul(bytes_per_step)).unwrap_or(u64::MAX)
+    }
+
+    /// Makes a single observation to help with parallel computation, returning every error
+    /// writing it
+    fn make_observation(&self, time: i128) -> Vec<ObservationError> {
+        self.deliver_frames(time, self.observe_frames(time))
     }
 
-    /// Makes a single observation to help with parallel computation
-    fn make_observation(&self, time: i128) {
+    /// Observes a frame at `time` from every observatory, ready to be delivered to the outputs,
+    /// along with the spans they were observed in so they can be delivered in the same spans
+    fn observe_frames(&self, time: i128) -> ObservedFrames {
+        let frame_span = info_span!("frame", time).entered();
         info!("Calculating observations for t={time}");
-        for observatory in &self.observatories {
+        let wants_constellations = self
+            .outputs
+            .iter()
+            .any(|output| output.wants_constellations());
+
+        // Observatories on the same body observing at the same time share one traversal of the
+        // tree of bodies. Each group is observed in turn so only one traversal is kept at a time
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of = HashMap::new();
+        for (index, observatory) in self.observatories.iter().enumerate() {
+            let key = (
+                std::sync::Arc::as_ptr(observatory.body()),
+                self.observation_time(observatory, time).to_bits(),
+            );
+            let group = *group_of.entry(key).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(index);
+        }
+
+        let mut directories = self.observatory_directories();
+        let mut frames: Vec<Option<(Frame, Span)>> =
+            self.observatories.iter().map(|_| None).collect();
+        for group in groups {
+            let first = &self.observatories[group[0]];
+            let observation_time = self.observation_time(first, time);
+            let raw_observations = first.body().read().map_or_else(
+                |_| Vec::new(),
+                |body| body.get_observations_from_here(observation_time),
+            );
+
+            for index in group {
+                let observatory = &self.observatories[index];
+                let observatory_span = info_span!(
+                    "observatory",
+                    observatory = %observatory.get_name(),
+                    bodies = field::Empty,
+                    constellation_lines = field::Empty,
+                )
+                .entered();
+                let observations =
+                    observatory.observe_precomputed(observation_time, &raw_observations);
+                let constellations = if wants_constellations {
+                    observatory.add_constellations_in(&observations, self.cultures.as_deref())
+                } else {
+                    Vec::new()
+                };
+                observatory_span.record("bodies", observations.len());
+                observatory_span.record("constellation_lines", constellations.len());
+                let frame = Frame {
+                    directory: std::mem::take(&mut directories[index]),
+                    time,
+                    observation_time,
+                    observations,
+                    constellations,
+                };
+                frames[index] = Some((frame, observatory_span.exit()));
+            }
+        }
+        let frames = frames.into_iter().flatten().collect();
+        (frame_span.exit(), frames)
+    }
+
+    /// Gives every output the frames observed at `time`, then
+    /// [finalizes](Output::finalize_frame) it, returning every error writing them
+    fn deliver_frames(
+        &self,
+        time: i128,
+        (frame_span, frames): ObservedFrames,
+    ) -> Vec<ObservationError> {
+        let _frame = frame_span.entered();
+        let mut errors = Vec::new();
+        for (observatory, (frame, span)) in self.observatories.iter().zip(frames) {
+            let _observatory_span = span.entered();
             let path = self
                 .output_file_root
-                .join(format!("{}/{time:010}", observatory.get_name()));
-            let observations = observatory.observe(time as Float);
-            let constellations = observatory.add_constelatations(&observations);
-            for output in &self.outputs {
-                // Write the observations to file, recovering on errors
-                // HACK: Should remove this match statement and return an error on writing
+                .join(format!("{}/{time:010}", frame.directory));
+            for (index, output) in self.outputs.iter().enumerate() {
+                let _output_span = debug_span!("write_observations", output = index).entered();
+                // Write the observations to file, carrying on with the other outputs on errors
                 match output.write_observations(
-                    &observations,
-                    &constellations,
-                    &observatory.get_name(),
-                    time,
+                    &frame.observations,
+                    &frame.constellations,
+                    frame.info().with_observatory(observatory),
                     &self.output_file_root,
                 ) {
                     Ok(()) => info!(
                         "File {} was written successfully",
                         &path.to_str().unwrap_or("[could not display path]")
                     ),
-                    Err(e) => {
-                        let message = format!(
-                            "ERROR WRITING FILE/DIRECTORY {}, message: {e}",
+                    Err(error) => {
+                        warn!(
+                            "ERROR WRITING FILE/DIRECTORY {}, message: {error}",
                             &path.to_str().unwrap_or("[could not display path]")
                         );
-                        if cfg!(test) {
-                            // Panic can only occur in internal testing mode when panics are expected
-                            panic!("{message}");
-                        } else {
-                            warn!("{message}");
-                        }
+                        errors.push(ObservationError::Write {
+                            time,
+                            observatory: frame.directory.clone(),
+                            error,
+                        });
                     }
                 }
             }
         }
+        for output in &self.outputs {
+            if let Err(error) = output.finalize_frame(time, &self.output_file_root) {
+                warn!("Could not finalize t={time}, message: {error}");
+                errors.push(ObservationError::Finalize { time, error });
+            }
+        }
+        errors
+    }
+
+    /// Gives outputs every observatory's frames in ascending order of time if true, rather than
+    /// in whatever order they finish being observed (the default). Frames are still observed in
+    /// parallel a few at a time, but outputs are given them one at a time, so outputs that are
+    /// slow to write frames will run slower. See [`Output::finalize_frame`].
+    pub fn set_ordered_delivery(&mut self, ordered_delivery: bool) {
+        self.ordered_delivery = ordered_delivery;
     }
 
     /// Set the output root
     pub fn set_output_path<T: Into<PathBuf>>(&mut self, output: T) {
         self.output_file_root = output.into();
+        self.written_output_file_root = None;
+    }
+
+    /// Reads a program from a JSON file. A relative output file root is resolved from the
+    /// directory the file is in, rather than the working directory, so the program works
+    /// wherever it is run from. It is still written back relative to the file.
+    ///
+    /// # Errors
+    /// Returns [`crate::json::Error::Read`] if the file or its directory can't be read, or any
+    /// error [`crate::json::from_str`] returns for its contents.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, crate::json::Error> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(crate::json::Error::Read)?;
+        let mut program: Self = crate::json::from_str(&json)?;
+
+        if program.output_file_root.is_relative() {
+            let directory = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let mut resolved = directory.canonicalize().map_err(crate::json::Error::Read)?;
+            // The output root may not have been made yet so it can't be canonicalized, but going
+            // up from a canonical directory gives the same result
+            for component in program.output_file_root.components() {
+                match component {
+                    Component::ParentDir => {
+                        resolved.pop();
+                    }
+                    Component::CurDir => {}
+                    component => resolved.push(component),
+                }
+            }
+            program.written_output_file_root =
+                Some(std::mem::replace(&mut program.output_file_root, resolved));
+        }
+        Ok(program)
+    }
+
+    /// Names the output directory of each observatory after its [ID](Observatory::get_id) if
+    /// true, or its [name](Observatory::get_name) if false (the default). IDs stay the same when
+    /// observatories are renamed, or the way unnamed observatories are named changes.
+    pub fn set_directories_by_id(&mut self, directories_by_id: bool) {
+        self.directories_by_id = directories_by_id;
+    }
+
+    /// Only gives outputs the constellations from these sky cultures, or every constellation if
+    /// `None` (the default)
+    pub fn select_cultures(&mut self, cultures: Option<Vec<String>>) {
+        self.cultures = cultures;
+    }
+
+    /// Returns the name of the directory under [`Self::output_file_root`] that each observatory's
+    /// output is written to, in the same order as [`Self::observatories`]. Outputs are given these
+    /// as the [directory](crate::output::FrameInfo::directory) of each frame.
+    #[must_use]
+    pub fn observatory_directories(&self) -> Vec<String> {
+        self.observatories
+            .iter()
+            .map(|observatory| {
+                if self.directories_by_id {
+                    observatory.get_id()
+                } else {
+                    observatory.get_name()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the observatory whose output is written to the directory with this
+    /// [name](Self::observatory_directories), if there is one
+    #[must_use]
+    pub fn observatory_in(&self, directory: &str) -> Option<&Observatory> {
+        self.observatories
+            .iter()
+            .zip(self.observatory_directories())
+            .find_map(|(observatory, name)| (name == directory).then_some(observatory))
     }
 
     pub fn add_output(&mut self, output_method: Box<dyn Output>) {
         self.outputs.push(output_method);
     }
+
+    /// Builds the output described by `config` and adds it. Unlike [`Self::add_output`], the
+    /// output is written along with the rest of the program, so the program can be run straight
+    /// from its file.
+    pub fn add_output_config(&mut self, config: OutputConfig) {
+        self.outputs.push(config.build());
+        self.output_configs.push(config);
+    }
+
+    /// Returns the configs of the outputs added with [`Self::add_output_config`]
+    #[must_use]
+    pub fn output_configs(&self) -> &[OutputConfig] {
+        &self.output_configs
+    }
+
+    /// Observes frame `time` at `epoch + time` hours (before each observatory's
+    /// [time offset](Observatory::with_time_offset_hours)), so frames and file names can count
+    /// from zero however long the universe has been running. Defaults to zero.
+    pub fn set_epoch(&mut self, epoch: Float) {
+        self.epoch = epoch;
+    }
+
+    /// Returns the time in hours that frame zero is observed at, see [`Self::set_epoch`]
+    #[must_use]
+    pub fn epoch(&self) -> Float {
+        self.epoch
+    }
+
+    /// Returns the time `observatory` is observed at for frame `time`, allowing for the epoch
+    /// and its time offset
+    #[allow(clippy::cast_precision_loss)]
+    fn observation_time(&self, observatory: &Observatory, time: i128) -> Float {
+        self.epoch + time as Float + observatory.time_offset_hours()
+    }
+
+    /// Returns the observatories that observations are made from
+    #[must_use]
+    pub fn observatories(&self) -> &[Observatory] {
+        &self.observatories
+    }
+
+    /// Returns the location where output files will be stored
+    #[must_use]
+    pub fn output_file_root(&self) -> &Path {
+        &self.output_file_root
+    }
+
+    /// Returns the number of outputs observations are written to
+    #[must_use]
+    pub fn outputs_len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Returns the root of the tree of bodies
+    #[must_use]
+    pub fn root_body(&self) -> &Arc {
+        &self._root_body
+    }
+
+    /// Summarises what the program contains: how many bodies and outputs, and the observatories
+    #[must_use]
+    pub fn summary(&self) -> ProgramSummary {
+        ProgramSummary {
+            body_count: Body::iter_tree(&self._root_body).count(),
+            observatories: self
+                .observatories
+                .iter()
+                .map(Observatory::get_name)
+                .collect(),
+            outputs: self.outputs.len(),
+            output_file_root: self.output_file_root.clone(),
+            aliasing: Vec::new(),
+            imprecise: Vec::new(),
+        }
+    }
+
+    /// Like [`Self::summary`], but also warns about bodies that will look frozen or be too
+    /// imprecise in a run with the same arguments as [`Self::make_observations`]. These are the
+    /// warnings that are logged before the run starts.
+    #[must_use]
+    pub fn run_summary(
+        &self,
+        start_time: i128,
+        end_time: i128,
+        step_size: Option<usize>,
+    ) -> ProgramSummary {
+        #[allow(clippy::cast_precision_loss)]
+        let max_time = start_time.abs().max(end_time.abs()) as Float;
+        ProgramSummary {
+            aliasing: self.aliasing_warnings(step_size),
+            imprecise: crate::validate::precision_report(&self._root_body, max_time),
+            ..self.summary()
+        }
+    }
+}
+
+/// A [`Program`] in the form it is written to files, the same form [`DeserializedProgram`] reads
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedProgram {
+    /// The root body
+    root_body: Arc,
+    /// The observatories, referring to their bodies by ID
+    observatories: Vec<WeakObservatory>,
+    /// The output path, as it was written if the program was read from a file
+    output_file_root: PathBuf,
+    /// Whether output directories are named after observatory IDs
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    directories_by_id: bool,
+    /// The selected sky cultures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cultures: Option<Vec<String>>,
+    /// The outputs that were added from configs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<OutputConfig>,
+    /// Time in hours of frame zero
+    #[serde(skip_serializing_if = "crate::json::is_zero")]
+    epoch: Float,
+}
+
+impl From<Program> for SerializedProgram {
+    fn from(value: Program) -> Self {
+        Self {
+            root_body: value._root_body,
+            observatories: value.observatories.into_iter().map(Into::into).collect(),
+            output_file_root: value
+                .written_output_file_root
+                .unwrap_or(value.output_file_root),
+            directories_by_id: value.directories_by_id,
+            cultures: value.cultures,
+            outputs: value.output_configs,
+            epoch: value.epoch,
+        }
+    }
 }
 
 /// Intermediate type to allow deserializing programs and maintaining validity of the data
@@ -143,30 +1170,54 @@ struct DeserializedProgram {
     observatories: Vec<WeakObservatory>,
     /// The output path
     output_file_root: PathBuf,
+    /// Whether output directories are named after observatory IDs
+    #[serde(default)]
+    directories_by_id: bool,
+    /// The selected sky cultures
+    #[serde(default)]
+    cultures: Option<Vec<String>>,
+    /// Outputs that will be built once the observatories are linked
+    #[serde(default)]
+    outputs: Vec<OutputConfig>,
+    /// Time in hours of frame zero
+    #[serde(default)]
+    epoch: Float,
 }
 
-impl From<DeserializedProgram> for Program {
-    fn from(value: DeserializedProgram) -> Self {
+impl TryFrom<DeserializedProgram> for Program {
+    type Error = crate::json::Error;
+
+    fn try_from(value: DeserializedProgram) -> Result<Self, Self::Error> {
         let mut observatories = Vec::with_capacity(value.observatories.len());
 
         for o in value.observatories {
-            observatories.push(to_observatory(o, &value.root_body));
+            observatories.push(to_observatory(o, &value.root_body)?);
         }
 
         crate::body::Body::hydrate_all(&value.root_body, &None);
 
-        Program {
+        let mut program = Program {
             _root_body: value.root_body,
             observatories,
             output_file_root: value.output_file_root,
+            written_output_file_root: None,
             outputs: Vec::new(),
+            output_configs: Vec::new(),
+            directories_by_id: value.directories_by_id,
+            cultures: value.cultures,
+            epoch: value.epoch,
+            ordered_delivery: false,
+        };
+        for config in value.outputs {
+            program.add_output_config(config);
         }
+        Ok(program)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{output::svg::Svg, projection};
+    use crate::{consts::float, projection};
 
     use super::*;
 
@@ -182,13 +1233,79 @@ mod tests {
             root_body: root.clone(),
             observatories,
             output_file_root: PathBuf::default(),
+            directories_by_id: false,
+            cultures: None,
+            outputs: Vec::new(),
+            epoch: 0.0,
         };
 
-        let program: Program = dp.into();
+        let program: Program = dp.try_into().unwrap();
 
         assert_eq!(6, program.observatories.len());
     }
 
+    #[test]
+    fn summary_of_solar_system() {
+        let program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+
+        let summary = program.summary();
+
+        // Sol, eight planets, and Luna
+        assert_eq!(summary.body_count, 10);
+        assert_eq!(summary.observatories.len(), 6);
+        assert_eq!(summary.observatories.len(), program.observatories().len());
+        assert_eq!(summary.outputs, program.outputs_len());
+        assert_eq!(summary.output_file_root, program.output_file_root());
+        assert!(!program
+            .root_body()
+            .read()
+            .unwrap()
+            .get_children()
+            .is_empty());
+    }
+
+    #[test]
+    fn output_root_relative_to_program_file() {
+        let mut directory = std::env::temp_dir();
+        directory.push("astrograph-output-root-relative-to-program-file");
+        let _ = std::fs::remove_dir_all(&directory);
+        directory.push("programs");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        json["observatories"].as_array_mut().unwrap().truncate(1);
+        json["outputFileRoot"] = "../charts".into();
+        let path = directory.join("program.json");
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let mut program = Program::from_file(&path).unwrap();
+        program.add_output(Box::new(Svg::new(projection::StatelessOrthographic())));
+        program.make_observations(0, 1, None).unwrap();
+
+        let charts = directory.parent().unwrap().join("charts");
+        assert_eq!(program.output_file_root(), charts.canonicalize().unwrap());
+        assert!(charts
+            .join(&program.observatory_directories()[0])
+            .join("0000000000.svg")
+            .exists());
+
+        // Written back as it was, and read again the same way
+        let written = serde_json::to_string(&program).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap()["outputFileRoot"],
+            "../charts"
+        );
+        std::fs::write(&path, written).unwrap();
+        let reread = Program::from_file(&path).unwrap();
+        assert_eq!(reread.output_file_root(), program.output_file_root());
+        assert_eq!(
+            reread.summary().observatories,
+            program.summary().observatories
+        );
+    }
+
     #[test]
     fn deserialize() {
         let program = include_str!("../../assets/solar-system.program.json");
@@ -198,15 +1315,770 @@ mod tests {
         assert_eq!(6, program.observatories.len());
     }
 
+    /// An output that records the order frames are given to it in
+    #[derive(Clone, Debug, Default)]
+    struct OrderRecorder {
+        /// Observatory and time of each frame written, in order
+        written: std::sync::Arc<Mutex<Vec<(String, i128)>>>,
+        /// Time of each frame finalized, in order
+        finalized: std::sync::Arc<Mutex<Vec<i128>>>,
+    }
+
+    impl Output for OrderRecorder {
+        fn write_observations(
+            &self,
+            _observations: &[LocalObservation],
+            _constellations: &[crate::constellation::Line],
+            frame: FrameInfo<'_>,
+            _output_path_root: &Path,
+        ) -> Result<(), std::io::Error> {
+            self.written
+                .lock()
+                .unwrap()
+                .push((frame.directory.to_owned(), frame.time));
+            Ok(())
+        }
+
+        fn finalize_frame(
+            &self,
+            time: i128,
+            _output_path_root: &Path,
+        ) -> Result<(), std::io::Error> {
+            self.finalized.lock().unwrap().push(time);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn body_series_match_frames() {
+        let program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+
+        let series = program.body_series(0, 40, Some(7));
+        assert!(!series.is_empty());
+
+        // Gather each body from the frames the outputs would be given
+        let mut expected = Vec::new();
+        for (observatory, directory) in program
+            .observatories()
+            .iter()
+            .zip(program.observatory_directories())
+        {
+            let mut by_body: BTreeMap<std::sync::Arc<str>, Vec<SeriesPoint>> = BTreeMap::new();
+            for time in (0..40).step_by(7) {
+                let observation_time = program.observation_time(observatory, time);
+                for (body, direction) in observatory.observe(observation_time) {
+                    let name = body.read().unwrap().get_name();
+                    by_body.entry(name).or_default().push(SeriesPoint {
+                        time,
+                        altitude: float::FRAC_PI_2 - direction.polar_angle,
+                        azimuth: direction.azimuthal_angle,
+                        distance: direction.radius,
+                        magnitude: observatory.apparent_magnitude(
+                            &body,
+                            direction.radius,
+                            observation_time,
+                        ),
+                    });
+                }
+            }
+            expected.extend(by_body.into_iter().map(|(body, points)| BodySeries {
+                observatory: directory.clone(),
+                body,
+                points,
+            }));
+        }
+
+        assert_eq!(series, expected);
+        for body_series in &series {
+            assert!(body_series
+                .points
+                .windows(2)
+                .all(|pair| pair[0].time < pair[1].time));
+        }
+    }
+
+    #[test]
+    fn visible_counts_match_observations() {
+        let program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+
+        let counts = program.visible_counts(0, 40, Some(7));
+        assert_eq!(
+            counts.iter().map(|(time, _)| *time).collect::<Vec<_>>(),
+            [0, 7, 14, 21, 28, 35]
+        );
+        for (time, count) in counts {
+            let expected: usize = program
+                .observatories()
+                .iter()
+                .map(|observatory| {
+                    observatory
+                        .observe(program.observation_time(observatory, time))
+                        .len()
+                })
+                .sum();
+            assert_eq!(count, expected);
+            assert!(count > 0);
+        }
+    }
+
+    #[test]
+    fn shared_traversal_matches_observe() {
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        // Several stations on the first observatory's body, one of them on its own schedule
+        let body = program.observatories()[0].body().clone();
+        for (index, (lat, long, offset)) in
+            [(10.0, 20.0, 0.0), (-45.0, 170.0, 0.0), (60.0, -30.0, 5.5)]
+                .into_iter()
+                .enumerate()
+        {
+            program.observatories.push(
+                Observatory::from_lat_long(
+                    lat,
+                    long,
+                    body.clone(),
+                    Ok(format!("Station {index}")),
+                    vec![],
+                )
+                .with_time_offset_hours(offset),
+            );
+        }
+
+        for time in [0, 13, 4_000] {
+            let (_, frames) = program.observe_frames(time);
+            assert_eq!(frames.len(), program.observatories().len());
+            for ((frame, _), observatory) in frames.iter().zip(program.observatories()) {
+                let expected = observatory.observe(program.observation_time(observatory, time));
+                assert!(!expected.is_empty());
+                assert_eq!(frame.observations.len(), expected.len());
+                for ((body, location), (expected_body, expected_location)) in
+                    frame.observations.iter().zip(&expected)
+                {
+                    assert!(std::sync::Arc::ptr_eq(body, expected_body));
+                    assert_eq!(
+                        (
+                            location.radius,
+                            location.polar_angle,
+                            location.azimuthal_angle
+                        ),
+                        (
+                            expected_location.radius,
+                            expected_location.polar_angle,
+                            expected_location.azimuthal_angle
+                        )
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ordered_delivery_ascends_in_time() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-program-ordered-delivery");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let recorder = OrderRecorder::default();
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        program.add_output(Box::new(recorder.clone()));
+        program.set_output_path(&output_root);
+        program.set_ordered_delivery(true);
+
+        // Given out of order, with more frames than are observed at once
+        let times: Vec<i128> = (0..100).map(|i| (i * 37) % 100).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(16)
+            .build()
+            .unwrap();
+        pool.install(|| program.make_observations_at(&times))
+            .unwrap();
+
+        let finalized = recorder.finalized.lock().unwrap();
+        assert_eq!(*finalized, (0..100).collect::<Vec<_>>());
+        let written = recorder.written.lock().unwrap();
+        assert_eq!(written.len(), 100 * program.observatories().len());
+        for directory in program.observatory_directories() {
+            let times: Vec<_> = written
+                .iter()
+                .filter(|(observatory, _)| *observatory == directory)
+                .map(|(_, time)| *time)
+                .collect();
+            assert_eq!(times.len(), 100);
+            assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        let _ = std::fs::remove_dir_all(&output_root);
+    }
+
+    #[test]
+    fn estimate_output_bytes_matches_run() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-estimate-output-bytes");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let program = include_str!("../../assets/solar-system.program.json");
+        let mut program: Program = serde_json::from_str(program).unwrap();
+        program.add_output(Box::new(Svg::new(projection::StatelessOrthographic())));
+        program.set_output_path(&output_root);
+
+        let estimate = program.estimate_output_bytes(0, 48, Some(5));
+        program.make_observations(0, 48, Some(5)).unwrap();
+
+        let mut actual = 0;
+        for observatory in std::fs::read_dir(&output_root).unwrap() {
+            for file in std::fs::read_dir(observatory.unwrap().path()).unwrap() {
+                actual += file.unwrap().metadata().unwrap().len();
+            }
+        }
+
+        assert!(actual > 0);
+        assert!(
+            estimate > actual / 10 && estimate < actual * 10,
+            "Estimated {estimate} bytes but {actual} bytes were written"
+        );
+    }
+
+    #[test]
+    fn outputs_given_observatory_with_directories_by_id() {
+        use crate::output::logger::parallactic;
+
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        program.set_directories_by_id(true);
+        let logger = parallactic::Logger::new(["Sol"]);
+        program.add_output(Box::new(logger.clone()));
+        program.set_output_path(std::env::temp_dir().join(format!(
+            "astrograph-outputs-given-observatory-{}",
+            std::process::id()
+        )));
+
+        program.make_observations_then(&(0..48).collect::<Vec<_>>(), |_| ());
+
+        // Every observatory's frames were logged, rather than dropped for not matching a name
+        let nights = logger.nights();
+        assert_eq!(nights.len(), program.observatories().len());
+        for directory in program.observatory_directories() {
+            assert!(nights.contains_key(program.output_file_root().join(directory).as_path()));
+        }
+        assert!(nights
+            .values()
+            .any(|targets| !targets[&std::sync::Arc::from("Sol")].is_empty()));
+    }
+
+    #[test]
+    fn directories_by_id_survive_renames() {
+        let mut directories = Vec::new();
+        for name in ["Mauna Kea Observatory", "Mauna Kea"] {
+            let mut output_root = std::env::temp_dir();
+            output_root.push(format!("astrograph-directories-by-id-{}", name.len()));
+            let _ = std::fs::remove_dir_all(&output_root);
+
+            let mut program: serde_json::Value =
+                serde_json::from_str(include_str!("../../assets/solar-system.program.json"))
+                    .unwrap();
+            program["observatories"].as_array_mut().unwrap().truncate(1);
+            program["observatories"][0]["name"] = name.into();
+            let mut program: Program = serde_json::from_value(program).unwrap();
+            program.add_output(Box::new(Svg::new(projection::StatelessOrthographic())));
+            program.set_output_path(&output_root);
+            program.set_directories_by_id(true);
+
+            program.make_observations(0, 1, None).unwrap();
+
+            directories.push(
+                std::fs::read_dir(&output_root)
+                    .unwrap()
+                    .map(|entry| entry.unwrap().file_name())
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        assert_eq!(directories[0].len(), 1);
+        assert_eq!(directories[0], directories[1]);
+        assert!(directories[0][0].to_string_lossy().starts_with("2@"));
+    }
+
+    #[test]
+    fn local_midnights_are_a_day_apart() {
+        let program = include_str!("../../assets/solar-system.program.json");
+        let program: Program = serde_json::from_str(program).unwrap();
+        let observatory_name = "Royal Observatory Grenwich";
+
+        let times = program.times_at_local_phase(observatory_name, 0.5, 0, 24 * 30);
+
+        assert!((29..=31).contains(&times.len()), "{times:?}");
+        for pair in times.windows(2) {
+            assert!((23..=25).contains(&(pair[1] - pair[0])), "{times:?}");
+        }
+
+        let observatory = program.observatory_in(observatory_name).unwrap();
+        for time in times {
+            #[allow(clippy::cast_precision_loss)]
+            let sun_is_up = observatory
+                .observe(time as Float)
+                .iter()
+                .any(|(b, _)| &*b.read().unwrap().get_name() == "Sol");
+            assert!(!sun_is_up, "The sun was up at t={time}");
+        }
+    }
+
+    #[test]
+    fn time_offsets_rotate_sky() {
+        use crate::{
+            body::{rotating::Rotating, Body},
+            dynamic::fixed::Fixed,
+            output::recording::{replay, Recording},
+        };
+        use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-time-offsets");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let planet = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        Body::new(
+            Some(root.clone()),
+            Fixed::new(Vector3 {
+                x: 1_000.0,
+                y: 0.0,
+                z: 500.0,
+            }),
+        );
+        Body::hydrate_all(&root, &None);
+
+        let observatory =
+            |name: &str| Observatory::new(Spherical::UP, planet.clone(), Ok(name.into()), vec![]);
+        let mut program = ProgramBuilder::default()
+            .root_body(root.clone())
+            .add_observatory(observatory("Early"))
+            .add_observatory(observatory("Late").with_time_offset_hours(6.0))
+            .add_output(Box::new(Recording::new()))
+            .output_file_root(output_root.clone())
+            .build()
+            .unwrap();
+        program.set_output_path(&output_root);
+        program.make_observations_at(&[3]).unwrap();
+
+        let recording = std::fs::File::open(output_root.join(Recording::FILE_NAME)).unwrap();
+        let frames = replay(std::io::BufReader::new(recording), &root).unwrap();
+        let azimuth = |name: &str| {
+            let frame = frames.iter().find(|f| f.directory == name).unwrap();
+            // Frames are named after the nominal time
+            assert_eq!(frame.time, 3);
+            let (_, star) = frame
+                .observations
+                .iter()
+                .find(|(b, _)| &*b.read().unwrap().get_name() == "1")
+                .unwrap();
+            star.azimuthal_angle
+        };
+
+        // The offset is recorded with the frame, for outputs that compute positions themselves
+        for (name, observation_time) in [("Early", 3.0), ("Late", 9.0)] {
+            let frame = frames.iter().find(|f| f.directory == name).unwrap();
+            assert_float_absolute_eq!(frame.observation_time, observation_time);
+        }
+
+        let difference = (azimuth("Late") - azimuth("Early")).rem_euclid(float::TAU);
+        let quarter_turn = difference.min(float::TAU - difference);
+        assert_float_absolute_eq!(quarter_turn, float::FRAC_PI_2, 1e-3);
+    }
+
+    #[test]
+    fn fully_specified_program_runs_from_file() {
+        use crate::output::config::{LayerConfig, OutputConfig, ProjectionConfig};
+
+        let output_root =
+            std::env::temp_dir().join(format!("astrograph-fully-specified-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        program.set_output_path(&output_root);
+        program.set_epoch(12.0);
+        program.add_output_config(OutputConfig::Svg {
+            projection: ProjectionConfig::Gnomonic {
+                max_half_angle: 1.2,
+                latitude: float::FRAC_PI_2,
+                longitude: 0.0,
+            },
+            layers: vec![LayerConfig::CompassRose, LayerConfig::EffectiveHorizon],
+            thumbnails_deg: Some(0.1),
+            subtitle: true,
+        });
+        program.add_output_config(OutputConfig::Trail {
+            body: "Sol".into(),
+            projection: ProjectionConfig::default(),
+        });
+        program.add_output_config(OutputConfig::EclipseLog);
+
+        let written = serde_json::to_string(&program).unwrap();
+        let mut reread: Program = serde_json::from_str(&written).unwrap();
+        assert_eq!(reread.outputs_len(), 3);
+        assert_eq!(reread.output_configs(), program.output_configs());
+        assert_float_absolute_eq!(reread.epoch(), 12.0);
+
+        // Frame zero is observed at the epoch
+        let name = &reread.observatory_directories()[0];
+        let frame = reread.observe_frame(name, 0).unwrap();
+        let observatory = &reread.observatories()[0];
+        assert_eq!(
+            frame.observations.len(),
+            observatory
+                .observe(12.0 + observatory.time_offset_hours())
+                .len()
+        );
+
+        // Outputs are told the epoch adjusted time the sky was drawn at
+        let memory = crate::output::memory::Memory::new();
+        reread.add_output(Box::new(memory.clone()));
+        reread.make_observations(0, 2, None).unwrap();
+        for frame in memory.frames() {
+            let observatory = reread.observatory_in(&frame.directory).unwrap();
+            #[allow(clippy::cast_precision_loss)]
+            let expected = 12.0 + frame.time as Float + observatory.time_offset_hours();
+            assert_float_absolute_eq!(frame.observation_time, expected);
+        }
+
+        let directory = output_root.join(name);
+        for file in ["0000000000.svg", "0000000001.svg", "trail-Sol.svg"] {
+            assert!(directory.join(file).is_file(), "{file} was not written");
+        }
+        assert!(directory.join("thumbs").is_dir());
+        std::fs::remove_dir_all(&output_root).unwrap();
+    }
+
+    #[test]
+    fn observations_collected_in_order() {
+        use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+        let (root, observer) = crate::testing::make_toy_example(crate::testing::DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let observatory =
+            |name: &str| Observatory::new(Spherical::UP, observer.clone(), Ok(name.into()), vec![]);
+        let program = ProgramBuilder::default()
+            .root_body(root.clone())
+            .add_observatory(observatory("b"))
+            .add_observatory(observatory("a"))
+            .outputs(Vec::new())
+            .build()
+            .unwrap();
+
+        let observations = program.collect_observations(0, 10, Some(2));
+        assert_eq!(observations.len(), 5 * 2);
+        let keys: Vec<_> = observations
+            .iter()
+            .map(|(time, name, _)| (*time, name.as_str()))
+            .collect();
+        assert_eq!(&keys[..3], [(0, "a"), (0, "b"), (2, "a")]);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let (time, name, bodies) = &observations[3];
+        assert_eq!(
+            bodies.len(),
+            program
+                .observe_frame(name, *time)
+                .unwrap()
+                .observations
+                .len()
+        );
+    }
+
+    #[test]
+    fn step_aliasing_rotation_is_found() {
+        use crate::{
+            body::{rotating::Rotating, Body},
+            dynamic::{fixed::Fixed, keplerian::Keplerian},
+        };
+        use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let planet = Body::new(Some(root.clone()), Fixed::new(Vector3::RIGHT));
+        planet.write().unwrap().rotation = Some(Rotating::new(12.0, Spherical::UP));
+        Body::new(
+            Some(planet.clone()),
+            Keplerian::new_with_period(0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 30.0),
+        );
+        Body::hydrate_all(&root, &None);
+        let program = ProgramBuilder::default()
+            .root_body(root)
+            .observatories(vec![])
+            .outputs(vec![])
+            .build()
+            .unwrap();
+
+        let warnings = program.aliasing_warnings(Some(24));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(&*warnings[0].body, "0");
+        assert!(warnings[0].rotation);
+        assert_float_absolute_eq!(warnings[0].multiple, 2.0);
+        assert!(warnings[0].to_string().contains("0 will look frozen"));
+
+        assert!(program.aliasing_warnings(Some(7)).is_empty());
+        // 5 orbits of the moon
+        assert_eq!(program.aliasing_warnings(Some(150)).len(), 1);
+
+        // The run summary carries the same warnings
+        let summary = program.run_summary(0, 48, Some(24));
+        assert_eq!(summary.aliasing, warnings);
+        assert!(summary.imprecise.is_empty());
+        assert!(summary.to_string().contains("\n0 will look frozen"));
+        assert!(program.summary().aliasing.is_empty());
+
+        // So long that the moon's phase is lost to rounding
+        let summary = program.run_summary(0, 10_i128.pow(15), Some(7));
+        assert!(summary.aliasing.is_empty());
+        assert_eq!(summary.imprecise.len(), 1);
+        assert_eq!(summary.warnings().count(), 1);
+    }
+
+    /// Records the time of each frame, panicking instead of writing at `crash_at`
+    #[derive(Clone, Debug)]
+    struct CrashingOutput {
+        /// Time of the frame to panic at, if any
+        crash_at: Option<i128>,
+        /// Times of the frames written so far
+        written: std::sync::Arc<Mutex<Vec<i128>>>,
+    }
+
+    impl Output for CrashingOutput {
+        fn write_observations(
+            &self,
+            _observations: &[crate::LocalObservation],
+            _constellations: &[crate::constellation::Line],
+            frame: FrameInfo<'_>,
+            _output_path_root: &Path,
+        ) -> Result<(), std::io::Error> {
+            let time = frame.time;
+            assert_ne!(Some(time), self.crash_at, "Crashed at t={time}");
+            self.written.lock().unwrap().push(time);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn constellations_only_computed_when_wanted() {
+        use crate::output::{csv::Csv, memory::Memory};
+
+        let root: Arc = serde_json::from_str(include_str!(
+            "../../assets/test/constellation/universe.json"
+        ))
+        .unwrap();
+        let run = |name: &str, outputs: Vec<Box<dyn Output>>| {
+            let output_root = std::env::temp_dir().join(format!(
+                "astrograph-constellations-{name}-{}",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&output_root);
+            let mut program: Program = DeserializedProgram {
+                root_body: root.clone(),
+                observatories: serde_json::from_str(include_str!(
+                    "../../assets/test/constellation/observatories.json"
+                ))
+                .unwrap(),
+                output_file_root: output_root.clone(),
+                directories_by_id: false,
+                cultures: None,
+                outputs: Vec::new(),
+                epoch: 0.0,
+            }
+            .try_into()
+            .unwrap();
+            for output in outputs {
+                program.add_output(output);
+            }
+            program.make_observations(0, 48, None).unwrap();
+            output_root
+        };
+        // Every chart written under a root, by its path from the root
+        let charts = |output_root: &Path| {
+            let mut charts = std::collections::BTreeMap::new();
+            let mut directories = vec![output_root.to_owned()];
+            while let Some(directory) = directories.pop() {
+                for entry in std::fs::read_dir(directory).unwrap() {
+                    let path = entry.unwrap().path();
+                    if path.is_dir() {
+                        directories.push(path);
+                    } else if path.extension().is_some_and(|e| e == "svg") {
+                        let contents = std::fs::read_to_string(&path).unwrap();
+                        charts.insert(path.strip_prefix(output_root).unwrap().to_owned(), contents);
+                    }
+                }
+            }
+            charts
+        };
+
+        // Charts draw the lines, and are the same when drawn alongside outputs that don't want them
+        let svg_only = run(
+            "svg",
+            vec![Box::new(Svg::new(projection::StatelessOrthographic()))],
+        );
+        let mixed = run(
+            "mixed",
+            vec![
+                Box::new(Svg::new(projection::StatelessOrthographic())),
+                Box::new(Csv::new()),
+            ],
+        );
+        let (svg_charts, mixed_charts) = (charts(&svg_only), charts(&mixed));
+        assert!(!svg_charts.is_empty());
+        assert!(svg_charts
+            .values()
+            .any(|chart| chart.contains("class=\"constellation\"")));
+        assert_eq!(svg_charts, mixed_charts);
+
+        // Without an output that wants them no lines are worked out, so outputs are given none
+        let lineless = Memory::without_constellations();
+        let csv_only = run(
+            "csv",
+            vec![Box::new(Csv::new()), Box::new(lineless.clone())],
+        );
+        assert!(!lineless.frames().is_empty());
+        assert!(lineless
+            .frames()
+            .iter()
+            .all(|frame| frame.constellations.is_empty()));
+
+        for output_root in [svg_only, mixed, csv_only] {
+            std::fs::remove_dir_all(output_root).unwrap();
+        }
+    }
+
+    #[test]
+    fn resume_after_crash() {
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-resume-after-crash");
+        let _ = std::fs::remove_dir_all(&output_root);
+        std::fs::create_dir_all(&output_root).unwrap();
+        let checkpoint = output_root.join("checkpoint");
+
+        let program = include_str!("../../assets/solar-system.program.json");
+        let program: Program = serde_json::from_str(program).unwrap();
+        let observatories = program.observatories.len();
+        let run = |crash_at: Option<i128>| {
+            let written = std::sync::Arc::default();
+            let mut program = program.clone();
+            program.add_output(Box::new(CrashingOutput {
+                crash_at,
+                written: std::sync::Arc::clone(&written),
+            }));
+            program.set_output_path(&output_root);
+
+            // One thread so the frames before the crash are known
+            let result = rayon::ThreadPoolBuilder::new()
+                .num_threads(1)
+                .build()
+                .unwrap()
+                .install(|| {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        program.make_observations_resumable(0, 40, Some(4), &checkpoint)
+                    }))
+                });
+            let written = written.lock().unwrap().clone();
+            (result, written)
+        };
+
+        let (result, before_crash) = run(Some(20));
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&checkpoint).unwrap(), "16");
+
+        let (result, after_crash) = run(None);
+        assert!(result.unwrap().is_ok());
+        assert_eq!(std::fs::read_to_string(&checkpoint).unwrap(), "36");
+
+        // Only frames that were complete at the checkpoint are skipped
+        let mut frames: Vec<_> = before_crash
+            .into_iter()
+            .filter(|time| *time <= 16)
+            .chain(after_crash.iter().copied())
+            .collect();
+        frames.sort_unstable();
+        let expected: Vec<_> = (0..40)
+            .step_by(4)
+            .flat_map(|time| std::iter::repeat_n(time, observatories))
+            .collect();
+        assert_eq!(frames, expected);
+        assert!(after_crash.iter().all(|time| *time > 16));
+
+        // Nothing is left to do
+        let (result, after_finishing) = run(None);
+        assert!(result.unwrap().is_ok());
+        assert!(after_finishing.is_empty());
+    }
+
+    /// An output that fails to write frames at one time, and to finalize them
+    #[derive(Clone, Debug)]
+    struct FailingOutput {
+        /// Time of the frames that can't be written
+        fail_at: i128,
+    }
+
+    impl Output for FailingOutput {
+        fn write_observations(
+            &self,
+            _observations: &[crate::LocalObservation],
+            _constellations: &[crate::constellation::Line],
+            frame: FrameInfo<'_>,
+            _output_path_root: &Path,
+        ) -> Result<(), std::io::Error> {
+            if frame.time == self.fail_at {
+                Err(std::io::Error::other("disk full"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn finalize_frame(
+            &self,
+            time: i128,
+            _output_path_root: &Path,
+        ) -> Result<(), std::io::Error> {
+            self.write_observations(&[], &[], FrameInfo::new("", time), Path::new(""))
+        }
+    }
+
+    #[test]
+    fn output_errors_returned() {
+        use crate::output::memory::Memory;
+
+        let mut output_root = std::env::temp_dir();
+        output_root.push("astrograph-program-output-errors-returned");
+        let _ = std::fs::remove_dir_all(&output_root);
+
+        let memory = Memory::new();
+        let mut program: Program =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        program.add_output(Box::new(FailingOutput { fail_at: 2 }));
+        program.add_output(Box::new(memory.clone()));
+        program.set_output_path(&output_root);
+        let observatories = program.observatory_directories();
+
+        let Err(ObservationError::Multiple(errors)) = program.make_observations(0, 4, None) else {
+            panic!("expected an error for each observatory");
+        };
+        assert_eq!(errors.len(), observatories.len() + 1);
+        for (error, directory) in errors.iter().zip(&observatories) {
+            assert!(matches!(
+                error,
+                ObservationError::Write { time: 2, observatory, .. } if observatory == directory
+            ));
+        }
+        assert!(matches!(
+            errors.last(),
+            Some(ObservationError::Finalize { time: 2, .. })
+        ));
+        // The other outputs and frames are still written
+        assert_eq!(memory.frames().len(), 4 * observatories.len());
+
+        let _ = std::fs::remove_dir_all(&output_root);
+    }
+
     #[test]
-    #[cfg_attr(
-        unix,
-        should_panic(expected = "file name contained an unexpected NUL byte")
-    )]
-    #[cfg_attr(
-        windows,
-        should_panic(expected = "message: strings passed to WinAPI cannot contain NULs")
-    )]
     fn write_to_forbidden_path() {
         let program = include_str!("../../assets/solar-system.program.json");
         let mut program: Program = serde_json::from_str(program).unwrap();
@@ -226,6 +2098,139 @@ mod tests {
         println!("{path:?}");
         program.set_output_path(path);
 
-        program.make_observations(0, 1, Some(1));
+        let error = program.make_observations(0, 1, Some(1)).unwrap_err();
+        assert!(matches!(error, ObservationError::OutputRoot(_)));
+        let message = error.to_string();
+        if cfg!(windows) {
+            assert!(message.contains("strings passed to WinAPI cannot contain NULs"));
+        } else {
+            assert!(message.contains("file name contained an unexpected NUL byte"));
+        }
+        assert_eq!(
+            std::io::Error::from(error).kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn spans_record_observatory_and_time() {
+        use crate::body::Body;
+        use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+        /// Collects everything the subscriber writes
+        #[derive(Clone, Default)]
+        struct Buffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output_root =
+            std::env::temp_dir().join(format!("astrograph-spans-{}", std::process::id()));
+        let (root, observer) = crate::testing::make_toy_example(crate::testing::DEFAULT_SEED);
+        Body::hydrate_all(&root, &None);
+        let program = ProgramBuilder::default()
+            .root_body(root)
+            .observatories(vec![Observatory::new(
+                Spherical::UP,
+                observer,
+                Ok("Traced".into()),
+                vec![],
+            )])
+            .add_output(Box::new(crate::output::recording::Recording::new()))
+            .output_file_root(output_root.clone())
+            .build()
+            .unwrap();
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_max_level(tracing::Level::TRACE)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || program.make_observation(7));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let closed_span = |name: &str| {
+            records
+                .iter()
+                .find(|record| {
+                    record["fields"]["message"] == "close" && record["span"]["name"] == name
+                })
+                .unwrap_or_else(|| panic!("no {name} span in {output}"))
+        };
+
+        let observatory = closed_span("observatory");
+        assert_eq!(observatory["span"]["observatory"], "Traced");
+        assert!(observatory["span"]["bodies"].as_u64().unwrap() > 0);
+        assert_eq!(observatory["spans"][0]["time"], "7");
+        for name in ["observe", "write_observations"] {
+            assert_eq!(closed_span(name)["spans"][1]["observatory"], "Traced");
+        }
+        let _ = std::fs::remove_dir_all(&output_root);
+    }
+
+    #[test]
+    fn moon_crossing_sun_is_reported() {
+        use crate::dynamic::{fixed::Fixed, keplerian::Keplerian};
+        use coordinates::prelude::{Spherical, Vector3};
+
+        let sun = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        sun.write().unwrap().radius = Some(1.0);
+        let planet = Body::new(Some(sun.clone()), Fixed::new([10.0, 0.0, 0.0].into()));
+        // Starts on the far side of the planet, passing between it and the sun half way through
+        // a day long orbit
+        let moon = Body::new(
+            Some(planet.clone()),
+            Keplerian::new_with_period(0.0, 0.1, 0.0, 0.0, 0.0, 0.0, 24.0),
+        );
+        moon.write().unwrap().radius = Some(0.01);
+        Body::hydrate_all(&sun, &None);
+        let mut program = ProgramBuilder::default()
+            .root_body(sun.clone())
+            .add_observatory(Observatory::new(
+                Spherical::from(Vector3::<Float>::from([-1.0, 0.0, 0.0])),
+                planet.clone(),
+                Ok("Noon".into()),
+                vec![],
+            ))
+            .outputs(vec![])
+            .build()
+            .unwrap();
+
+        let events = program.eclipse_report(0, 48, None);
+
+        let name = |body: &Arc| body.read().unwrap().get_name().to_string();
+        assert_eq!(events.len(), 2, "{events:#?}");
+        for (event, time) in events.iter().zip([12, 36]) {
+            assert_eq!(event.time, time);
+            assert_eq!(event.observatory, "Noon");
+            assert_eq!(event.near, name(&moon));
+            assert_eq!(event.far, name(&sun));
+            // The same angular size as the sun, and centered on it
+            assert_float_absolute_eq!(event.magnitude, 1.0, 1e-4);
+        }
+        // Nothing between the alignments
+        assert!(program.eclipse_report(13, 36, None).is_empty());
+
+        // Observatories are named like their output directories
+        program.set_directories_by_id(true);
+        let events = program.eclipse_report(0, 24, None);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].observatory, program.observatory_directories()[0]);
+        assert_ne!(events[0].observatory, "Noon");
     }
 }
@@ -14,10 +14,38 @@ pub trait Projection {
     /// Projects from 3D to 2D without taking into account any state the projector has, generally
     /// centered on the Z axis (up/down), but check the implementation you are using to be sure.
     fn project(location: &Spherical<Float>) -> Option<Vector2<Float>>;
+
+    /// Returns the outline of the area points are projected into, as the corners of a polygon in
+    /// order, or `None` for the unit circle most projections of the visible hemisphere fill
+    fn boundary_path(&self) -> Option<Vec<Vector2<Float>>> {
+        None
+    }
+
+    /// Returns true if points on every side of the sphere can be projected, so there is no
+    /// horizon to clip or draw along
+    fn is_full_sky(&self) -> bool {
+        false
+    }
+}
+
+/// How far behind the edge of a projection's hemisphere a point can be and still be drawn, as the
+/// cosine of its angle from the center of projection (roughly the angle in radians below the
+/// horizon). Points right on the edge are kept despite rounding, but not points visibly behind it.
+pub const DEFAULT_CLIP_TOLERANCE: Float = Float::EPSILON;
+
+/// Returns true if a point is on the far side of a hemispherical projection and shouldn't be
+/// drawn, given the cosine of its angle from the center of projection, and a clip tolerance like
+/// [`DEFAULT_CLIP_TOLERANCE`]
+fn is_clipped(cos_from_center: Float, clip_tolerance: Float) -> bool {
+    cos_from_center < -clip_tolerance
 }
 
 /// An [orthographic projector](https://en.wikipedia.org/wiki/Orthographic_map_projection) that is centered on the positive z direction, but thanks to the output
 /// of [`crate::body::observatory::Observatory::observe`] observations are already centered on the z axis.
+///
+/// Points are clipped with the [`DEFAULT_CLIP_TOLERANCE`], for a different one use an
+/// [`Orthographic`] projection centered on the zenith, e.g.
+/// `Orthographic::new(0.0, 0.0).with_clip_tolerance(0.01)`.
 #[derive(Debug, Clone, Copy)]
 pub struct StatelessOrthographic();
 
@@ -28,7 +56,7 @@ impl Projection for StatelessOrthographic {
     /// otherwise.
     fn project(location: &Spherical<Float>) -> Option<Vector2<Float>> {
         // If the location is on the other hemisphere
-        if location.polar_angle > float::FRAC_PI_2 {
+        if is_clipped(location.polar_angle.cos(), DEFAULT_CLIP_TOLERANCE) {
             return None;
         }
         // cos/sin swapped because 90deg north is our zero point, not the equator
@@ -46,12 +74,38 @@ impl Projection for StatelessOrthographic {
 /// An [orthographic projector](https://en.wikipedia.org/wiki/Orthographic_map_projection) that is centered on an orbitrary longitude and latitude. In most cases it will be quicker to use the [`StatelessOrthographic`](self::StatelessOrthographic) projection.
 #[derive(Debug, Clone, Copy)]
 pub struct Orthographic(
+    /// Latitude of the center of projection.
+    Float,
     /// Longitude of center of projection.
     Float,
-    /// Latitude of the center of projection.
+    /// How far behind the edge of the projection points are still drawn, see
+    /// [`DEFAULT_CLIP_TOLERANCE`]
     Float,
 );
 
+impl Orthographic {
+    /// Creates a projection centered on the given latitude and longitude in radians
+    #[must_use]
+    pub fn new(latitude: Float, longitude: Float) -> Self {
+        Self(latitude, longitude, DEFAULT_CLIP_TOLERANCE)
+    }
+
+    /// Draws points up to `clip_tolerance` behind the edge of the projection, as the cosine of
+    /// their angle from the center (roughly the angle in radians). Negative tolerances clip
+    /// points before they reach the edge. See [`DEFAULT_CLIP_TOLERANCE`].
+    #[must_use]
+    pub fn with_clip_tolerance(mut self, clip_tolerance: Float) -> Self {
+        self.2 = clip_tolerance;
+        self
+    }
+
+    /// Returns how far behind the edge of the projection points are still drawn
+    #[must_use]
+    pub fn clip_tolerance(&self) -> Float {
+        self.2
+    }
+}
+
 impl Projection for Orthographic {
     fn project_with_state(&self, location: &Spherical<Float>) -> Option<Vector2<Float>> {
         let (long_sin, long_cos) = (location.azimuthal_angle - self.1).sin_cos();
@@ -60,7 +114,10 @@ impl Projection for Orthographic {
         let (lat_cos, lat_sin) = location.polar_angle.sin_cos();
         let (lat_zero_cos, lat_zero_sin) = self.0.sin_cos();
 
-        if lat_zero_sin * lat_sin + lat_zero_cos * lat_cos * long_cos < 0.0 - Float::EPSILON {
+        if is_clipped(
+            lat_zero_sin * lat_sin + lat_zero_cos * lat_cos * long_cos,
+            self.2,
+        ) {
             // Clip it out because it is on the other hemisphere
             None
         } else {
@@ -77,6 +134,203 @@ impl Projection for Orthographic {
     }
 }
 
+/// A [gnomonic projector](https://en.wikipedia.org/wiki/Gnomonic_projection) centered on the
+/// positive z direction, projecting onto the plane touching the sphere at the zenith. Every great
+/// circle is drawn as a straight line, like the tangent plane telescope frames are reduced to.
+///
+/// Points at or past the horizon can't be projected, and points near it are projected very far
+/// from the center, so use a [`Gnomonic`] projection to only draw the sky near the zenith.
+#[derive(Debug, Clone, Copy)]
+pub struct StatelessGnomonic();
+
+impl Projection for StatelessGnomonic {
+    /// # Returns
+    ///
+    /// None if the point cannot be projected i.e. it is on or over the horizon, the projected
+    /// point otherwise.
+    fn project(location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        // The tangent diverges at the horizon
+        if location.polar_angle >= float::FRAC_PI_2 {
+            return None;
+        }
+        let distance = location.polar_angle.tan();
+        let (azi_sin, azi_cos) = location.azimuthal_angle.sin_cos();
+
+        Some(Vector2 {
+            x: distance * -azi_sin,
+            y: distance * -azi_cos,
+        })
+    }
+}
+
+/// A [gnomonic projector](https://en.wikipedia.org/wiki/Gnomonic_projection) like
+/// [`StatelessGnomonic`] that only draws points up to a maximum angle from the center of
+/// projection, like the field of view of a telescope. It is centered on the zenith unless
+/// [given a center](Self::with_center) like an [`Orthographic`] projection.
+#[derive(Debug, Clone, Copy)]
+pub struct Gnomonic(
+    /// Largest angle in radians from the center that points are drawn at.
+    Float,
+    /// Latitude of the center of projection.
+    Float,
+    /// Longitude of the center of projection.
+    Float,
+);
+
+impl Gnomonic {
+    /// Creates a projection centered on the zenith that draws points up to `max_half_angle`
+    /// radians from it, points 90° or more from the center are never drawn however large it is
+    #[must_use]
+    pub fn new(max_half_angle: Float) -> Self {
+        Self(max_half_angle, float::FRAC_PI_2, 0.0)
+    }
+
+    /// Centers the projection on the given latitude (altitude) and longitude (azimuth) in
+    /// radians, like [`Orthographic::new`]
+    #[must_use]
+    pub fn with_center(mut self, latitude: Float, longitude: Float) -> Self {
+        self.1 = latitude;
+        self.2 = longitude;
+        self
+    }
+
+    /// Returns the largest angle in radians from the center that points are drawn at
+    #[must_use]
+    pub fn max_half_angle(&self) -> Float {
+        self.0
+    }
+
+    /// Returns the latitude and longitude in radians of the center of projection
+    #[must_use]
+    pub fn center(&self) -> (Float, Float) {
+        (self.1, self.2)
+    }
+}
+
+impl Projection for Gnomonic {
+    fn project_with_state(&self, location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        let (long_sin, long_cos) = (location.azimuthal_angle - self.2).sin_cos();
+
+        // cos/sin swapped because 90deg north is our zero point, not the equator
+        let (lat_cos, lat_sin) = location.polar_angle.sin_cos();
+        let (lat_zero_sin, lat_zero_cos) = self.1.sin_cos();
+
+        // Cosine of the angle from the center of projection
+        let cos_from_center = lat_zero_sin * lat_sin + lat_zero_cos * lat_cos * long_cos;
+        // The tangent diverges 90° from the center, allowing for rounding there
+        if cos_from_center <= Float::EPSILON || cos_from_center < self.0.cos() {
+            return None;
+        }
+
+        Some(Vector2 {
+            x: lat_cos * -long_sin / cos_from_center,
+            y: (lat_zero_cos * lat_sin - lat_zero_sin * lat_cos * long_cos) / cos_from_center,
+        })
+    }
+
+    fn project(location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        StatelessGnomonic::project(location)
+    }
+}
+
+/// An [equirectangular projector](https://en.wikipedia.org/wiki/Equirectangular_projection) of the
+/// whole sky, for panoramas. Azimuth runs from -1 to 1 along x, and the angle from the zenith
+/// from 0 at the top to 1 at the nadir along y, so every point can be projected, including those
+/// below the horizon. Observatories only observe bodies below the horizon
+/// [on request](crate::body::observatory::Observatory::with_include_below_horizon).
+#[derive(Debug, Clone, Copy)]
+pub struct Equirectangular();
+
+impl Projection for Equirectangular {
+    /// # Returns
+    ///
+    /// The projected point, it is never `None`.
+    fn project(location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        Some(Vector2 {
+            x: location.azimuthal_angle.rem_euclid(float::TAU) / float::PI - 1.0,
+            y: location.polar_angle / float::PI,
+        })
+    }
+
+    fn boundary_path(&self) -> Option<Vec<Vector2<Float>>> {
+        Some(
+            [(-1.0, 0.0), (1.0, 0.0), (1.0, 1.0), (-1.0, 1.0)]
+                .into_iter()
+                .map(|(x, y)| Vector2 { x, y })
+                .collect(),
+        )
+    }
+
+    fn is_full_sky(&self) -> bool {
+        true
+    }
+}
+
+/// A [Mollweide projector](https://en.wikipedia.org/wiki/Mollweide_projection) of the whole sky
+/// into an ellipse twice as wide as it is tall, keeping the areas of regions of the sky in
+/// proportion. The zenith is at (0, -1) at the top, the nadir at (0, 1), and the horizon runs
+/// along the x axis from -2 to 2, with azimuths increasing to the right like [`Equirectangular`].
+#[derive(Debug, Clone, Copy)]
+pub struct Mollweide();
+
+impl Mollweide {
+    /// Number of points along the edge of the ellipse in [`Projection::boundary_path`]
+    const BOUNDARY_POINTS: u8 = 72;
+
+    /// Solves `2θ + sin 2θ = π sin(latitude)` for the auxiliary angle `θ` with Newton's method
+    fn auxiliary_angle(latitude: Float) -> Float {
+        // The derivative vanishes at the poles, where the answer is the latitude
+        if float::FRAC_PI_2 - latitude.abs() < 1e-4 {
+            return latitude;
+        }
+        let target = float::PI * latitude.sin();
+        let mut double_angle = 2.0 * latitude;
+        for _ in 0..50 {
+            let step = (double_angle + double_angle.sin() - target) / (1.0 + double_angle.cos());
+            double_angle -= step;
+            if step.abs() < Float::EPSILON {
+                break;
+            }
+        }
+        double_angle / 2.0
+    }
+}
+
+impl Projection for Mollweide {
+    /// # Returns
+    ///
+    /// The projected point, it is never `None`.
+    fn project(location: &Spherical<Float>) -> Option<Vector2<Float>> {
+        let latitude = float::FRAC_PI_2 - location.polar_angle;
+        let longitude = location.azimuthal_angle.rem_euclid(float::TAU) - float::PI;
+        let (sin, cos) = Self::auxiliary_angle(latitude).sin_cos();
+
+        Some(Vector2 {
+            x: 2.0 * float::FRAC_1_PI * longitude * cos,
+            y: -sin,
+        })
+    }
+
+    fn boundary_path(&self) -> Option<Vec<Vector2<Float>>> {
+        Some(
+            (0..Self::BOUNDARY_POINTS)
+                .map(|i| {
+                    let angle = float::TAU * Float::from(i) / Float::from(Self::BOUNDARY_POINTS);
+                    let (sin, cos) = angle.sin_cos();
+                    Vector2 {
+                        x: 2.0 * cos,
+                        y: sin,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn is_full_sky(&self) -> bool {
+        true
+    }
+}
+
 // TODO: make macro for this (will speed up implementing projections)
 #[cfg(test)]
 mod test {
@@ -152,7 +406,7 @@ mod test {
         use super::super::*;
 
         // [Null Island](https://en.wikipedia.org/wiki/Null_Island) equivalent
-        const PROJECTOR: Orthographic = Orthographic(float::FRAC_PI_2, 0.0);
+        const PROJECTOR: Orthographic = Orthographic(float::FRAC_PI_2, 0.0, DEFAULT_CLIP_TOLERANCE);
 
         #[test]
         fn up_maps_to_0_0() {
@@ -201,5 +455,251 @@ mod test {
             assert_float_absolute_eq!(output.x, 0.0);
             assert_float_absolute_eq!(output.y, -1.0);
         }
+
+        #[test]
+        fn clip_tolerance_pads_or_tightens_horizon() {
+            let below = |depression: Float| Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2 + depression,
+                azimuthal_angle: 1.0,
+            };
+            let zenith = Orthographic::new(0.0, 0.0);
+
+            assert!(zenith.project_with_state(&below(0.0)).is_some());
+            assert!(zenith.project_with_state(&below(1e-3)).is_none());
+            assert!(StatelessOrthographic::project(&below(1e-3)).is_none());
+
+            let padded = zenith.with_clip_tolerance(2e-3);
+            assert!(padded.project_with_state(&below(1e-3)).is_some());
+            assert!(padded.project_with_state(&below(3e-3)).is_none());
+
+            let tightened = zenith.with_clip_tolerance(-2e-3);
+            assert!(tightened.project_with_state(&below(-1e-3)).is_none());
+            assert!(tightened.project_with_state(&below(-3e-3)).is_some());
+        }
+    }
+
+    mod gnomonic {
+        use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+
+        use crate::consts::float;
+
+        use super::super::*;
+
+        #[test]
+        fn zenith_at_center_and_horizon_clipped() {
+            let output = StatelessGnomonic::project(&Spherical::UP).unwrap();
+            assert_float_absolute_eq!(output.x, 0.0);
+            assert_float_absolute_eq!(output.y, 0.0);
+
+            // 45° towards north is one unit up the plane
+            let north = Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_4,
+                azimuthal_angle: float::PI,
+            };
+            let output = StatelessGnomonic::project(&north).unwrap();
+            assert_float_absolute_eq!(output.x, 0.0);
+            assert_float_absolute_eq!(output.y, 1.0);
+
+            assert_eq!(StatelessGnomonic::project(&Spherical::LEFT), None);
+            assert_eq!(StatelessGnomonic::project(&Spherical::DOWN), None);
+        }
+
+        #[test]
+        fn max_half_angle_clips() {
+            let projector = Gnomonic::new(float::FRAC_PI_4);
+            let at = |polar_angle| Spherical {
+                radius: 1.0,
+                polar_angle,
+                azimuthal_angle: 1.0,
+            };
+
+            assert!(projector.project_with_state(&at(0.7)).is_some());
+            assert!(projector.project_with_state(&at(0.8)).is_none());
+            assert!(StatelessGnomonic::project(&at(0.8)).is_some());
+            assert!(Gnomonic::new(float::PI)
+                .project_with_state(&at(float::FRAC_PI_2))
+                .is_none());
+        }
+
+        #[test]
+        fn great_circles_stay_straight() {
+            // Ends of a constellation edge, and points along the great circle between them
+            let start = Vector3 {
+                x: 0.3,
+                y: -0.5,
+                z: 1.0,
+            };
+            let end = Vector3 {
+                x: -0.6,
+                y: 0.2,
+                z: 0.8,
+            };
+            let projector = Gnomonic::new(1.2);
+            let project = |point: Vector3<Float>| {
+                projector
+                    .project_with_state(&Spherical::from(point))
+                    .unwrap()
+            };
+            let (a, b) = (project(start), project(end));
+
+            for i in 1_u8..10 {
+                let t = Float::from(i) / 10.0;
+                let point = project(start * (1.0 - t) + end * t);
+                // Zero area between the ends and the point, so they are in a line
+                let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+                assert_float_absolute_eq!(cross, 0.0, 1e-5);
+            }
+        }
+
+        #[test]
+        fn center_projected_to_origin() {
+            let at = |polar_angle, azimuthal_angle| Spherical {
+                radius: 1.0,
+                polar_angle,
+                azimuthal_angle,
+            };
+            // Centered on the zenith it matches the stateless projection
+            let zenith = Gnomonic::new(1.0);
+            for location in [at(0.3, 1.0), at(0.9, -2.0)] {
+                let (a, b) = (
+                    zenith.project_with_state(&location).unwrap(),
+                    StatelessGnomonic::project(&location).unwrap(),
+                );
+                assert_float_absolute_eq!(a.x, b.x, 1e-5);
+                assert_float_absolute_eq!(a.y, b.y, 1e-5);
+            }
+
+            // 30° above the horizon towards azimuth 2
+            let projector = Gnomonic::new(0.5).with_center(float::FRAC_PI_6, 2.0);
+            assert_eq!(projector.center(), (float::FRAC_PI_6, 2.0));
+            let center = projector
+                .project_with_state(&at(float::FRAC_PI_3, 2.0))
+                .unwrap();
+            assert_float_absolute_eq!(center.x, 0.0, 1e-5);
+            assert_float_absolute_eq!(center.y, 0.0, 1e-5);
+            // 0.4 radians straight up from the center is tan(0.4) up the plane
+            let above = projector
+                .project_with_state(&at(float::FRAC_PI_3 - 0.4, 2.0))
+                .unwrap();
+            assert_float_absolute_eq!(above.x, 0.0, 1e-5);
+            assert_float_absolute_eq!(above.y.abs(), 0.4_f32.tan() as Float, 1e-5);
+            // The zenith is 60° from the center, past the field of view
+            assert!(projector.project_with_state(&Spherical::UP).is_none());
+
+            // Great circles are still straight away from the zenith
+            let project = |polar_angle, azimuthal_angle| {
+                projector
+                    .project_with_state(&at(polar_angle, azimuthal_angle))
+                    .unwrap()
+            };
+            let start: Vector3<Float> = at(0.9, 1.8).into();
+            let end: Vector3<Float> = at(1.3, 2.2).into();
+            let (a, b) = (project(0.9, 1.8), project(1.3, 2.2));
+            for i in 1_u8..10 {
+                let t = Float::from(i) / 10.0;
+                let point = Spherical::from(start * (1.0 - t) + end * t);
+                let point = project(point.polar_angle, point.azimuthal_angle);
+                let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+                assert_float_absolute_eq!(cross, 0.0, 1e-5);
+            }
+        }
+    }
+
+    mod equirectangular {
+        use coordinates::prelude::ThreeDimensionalConsts;
+
+        use crate::consts::float;
+
+        use super::super::*;
+
+        #[test]
+        fn whole_sky_projected() {
+            let output = Equirectangular::project(&Spherical::UP).unwrap();
+            assert_float_absolute_eq!(output.y, 0.0);
+
+            let output = Equirectangular::project(&Spherical::DOWN).unwrap();
+            assert_float_absolute_eq!(output.y, 1.0);
+
+            let horizon = |azimuthal_angle| Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2,
+                azimuthal_angle,
+            };
+            let output = Equirectangular::project(&horizon(0.0)).unwrap();
+            assert_float_absolute_eq!(output.x, -1.0);
+            assert_float_absolute_eq!(output.y, 0.5);
+            let output = Equirectangular::project(&horizon(float::PI)).unwrap();
+            assert_float_absolute_eq!(output.x, 0.0);
+            // Negative azimuths wrap around to the right hand side
+            let output = Equirectangular::project(&horizon(-float::FRAC_PI_2)).unwrap();
+            assert_float_absolute_eq!(output.x, 0.5);
+            assert!(Equirectangular().is_full_sky());
+        }
+    }
+
+    mod mollweide {
+        use coordinates::prelude::ThreeDimensionalConsts;
+
+        use crate::consts::float;
+
+        use super::super::*;
+
+        /// Returns a point on the sphere at this latitude and azimuth
+        fn at(latitude: Float, azimuthal_angle: Float) -> Spherical<Float> {
+            Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2 - latitude,
+                azimuthal_angle,
+            }
+        }
+
+        #[test]
+        fn poles_and_equator_on_axes() {
+            let zenith = Mollweide::project(&Spherical::UP).unwrap();
+            assert_float_absolute_eq!(zenith.x, 0.0);
+            assert_float_absolute_eq!(zenith.y, -1.0);
+            let nadir = Mollweide::project(&Spherical::DOWN).unwrap();
+            assert_float_absolute_eq!(nadir.x, 0.0);
+            assert_float_absolute_eq!(nadir.y, 1.0);
+
+            // The horizon is the long axis, evenly spaced by azimuth
+            for i in 0_u8..8 {
+                let azimuthal_angle = float::FRAC_PI_4 * Float::from(i);
+                let output = Mollweide::project(&at(0.0, azimuthal_angle)).unwrap();
+                assert_float_absolute_eq!(output.y, 0.0);
+                assert_float_absolute_eq!(
+                    output.x,
+                    2.0 * (azimuthal_angle / float::PI - 1.0),
+                    1e-5
+                );
+            }
+        }
+
+        #[test]
+        fn inside_ellipse() {
+            let mut inner = 0;
+            for latitude in -8_i8..=8 {
+                for azimuth in 0_u8..16 {
+                    let location = at(
+                        Float::from(latitude) / 8.0 * float::FRAC_PI_2,
+                        Float::from(azimuth) / 16.0 * float::TAU,
+                    );
+                    let output = Mollweide::project(&location).unwrap();
+                    let radius = (output.x / 2.0).powi(2) + output.y.powi(2);
+                    assert!(radius <= 1.0 + 1e-5, "{location:?} {output:?}");
+                    inner += usize::from(radius < 0.99);
+                }
+            }
+            assert!(inner > 0);
+
+            // Halfway to the pole is further than halfway up the ellipse, since regions near
+            // the poles are squashed to keep their area
+            let output = Mollweide::project(&at(float::FRAC_PI_4, float::PI)).unwrap();
+            assert!(-output.y > 0.5);
+            assert_eq!(Mollweide().boundary_path().unwrap().len(), 72);
+            assert!(Mollweide().is_full_sky());
+        }
     }
 }
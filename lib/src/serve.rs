@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    output::{recording::Frame, svg::Svg},
+    program::Program,
+    projection::StatelessOrthographic,
+};
+
+/// Number of frames kept by [`Server`] before the oldest are forgotten
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
+
+/// Content type of charts
+pub const SVG_CONTENT_TYPE: &str = "image/svg+xml";
+/// Content type of observations and errors
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// A response to a request to a [`Server`], independent of how it is sent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    /// HTTP status code
+    pub status: u16,
+    /// Value of the `Content-Type` header
+    pub content_type: &'static str,
+    /// Body of the response
+    pub body: String,
+}
+
+impl Response {
+    /// Creates a JSON error response with `message`
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: JSON_CONTENT_TYPE,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+/// Frames kept by a [`Server`], forgetting the oldest first
+#[derive(Default)]
+struct FrameCache {
+    /// Frames by observatory and time
+    frames: HashMap<(String, i128), Arc<Frame>>,
+    /// Keys of [`Self::frames`], oldest first
+    order: VecDeque<(String, i128)>,
+}
+
+/// Answers requests for single frames of a [`Program`] on demand, for serving live charts over
+/// HTTP. The paths it answers are:
+///
+/// - `GET /chart/<observatory>/<time>.svg`, the [`Svg`] chart of the frame
+/// - `GET /observations/<observatory>/<time>.json`, the bodies in the frame and their locations
+///
+/// where `<observatory>` is the [directory name](Program::observatory_directories) of the
+/// observatory and `<time>` is a whole number of hours. Frames are cached, so a chart and its
+/// observations are only observed once.
+pub struct Server {
+    /// Program whose observatories frames are observed from, its outputs are not used
+    program: Program,
+    /// Output that draws charts
+    svg: Svg<StatelessOrthographic>,
+    /// Frames observed so far
+    cache: Mutex<FrameCache>,
+    /// Number of frames kept in [`Self::cache`]
+    capacity: usize,
+}
+
+impl Server {
+    /// Creates a server for the observatories of `program`, drawing charts with `svg`
+    #[must_use]
+    pub fn new(program: Program, svg: Svg<StatelessOrthographic>) -> Self {
+        Self {
+            program,
+            svg,
+            cache: Mutex::default(),
+            capacity: DEFAULT_CACHE_CAPACITY,
+        }
+    }
+
+    /// Keeps at most `capacity` frames, forgetting the oldest first. Defaults to
+    /// [`DEFAULT_CACHE_CAPACITY`].
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+    }
+
+    /// Returns the frame from the observatory at `time`, from the cache if it has been observed
+    /// before, or `None` if there is no such observatory
+    pub fn frame(&self, observatory: &str, time: i128) -> Option<Arc<Frame>> {
+        let key = (observatory.to_owned(), time);
+        if let Some(frame) = self
+            .cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.frames.get(&key).cloned())
+        {
+            return Some(frame);
+        }
+
+        // Observed without holding the lock so other requests aren't held up
+        let frame = Arc::new(self.program.observe_frame(observatory, time)?);
+        if let Ok(mut cache) = self.cache.lock() {
+            if self.capacity > 0 && cache.frames.insert(key.clone(), frame.clone()).is_none() {
+                cache.order.push_back(key);
+                if cache.order.len() > self.capacity {
+                    if let Some(oldest) = cache.order.pop_front() {
+                        cache.frames.remove(&oldest);
+                    }
+                }
+            }
+        }
+        Some(frame)
+    }
+
+    /// Answers a request with `method` for `path`, which may include a query string
+    #[must_use]
+    pub fn respond(&self, method: &str, path: &str) -> Response {
+        if method != "GET" {
+            return Response::error(405, "only GET requests are supported");
+        }
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<_> = path.trim_start_matches('/').split('/').collect();
+        let [kind, observatory, file] = segments[..] else {
+            return Response::error(404, "expected /chart/<observatory>/<time>.svg or /observations/<observatory>/<time>.json");
+        };
+        let extension = match kind {
+            "chart" => ".svg",
+            "observations" => ".json",
+            _ => return Response::error(404, &format!("unknown path /{kind}")),
+        };
+        let Some(time) = file.strip_suffix(extension) else {
+            return Response::error(404, &format!("{kind} must end in {extension}"));
+        };
+        let Ok(time) = time.parse::<i128>() else {
+            return Response::error(400, &format!("{time} is not a whole number of hours"));
+        };
+        let Some(observatory) = percent_decode(observatory) else {
+            return Response::error(400, "observatory name is not valid UTF-8");
+        };
+        let (Some(frame), Some(found)) = (
+            self.frame(&observatory, time),
+            self.program.observatory_in(&observatory),
+        ) else {
+            return Response::error(404, &format!("there is no observatory named {observatory}"));
+        };
+
+        match kind {
+            "chart" => Response {
+                status: 200,
+                content_type: SVG_CONTENT_TYPE,
+                body: self
+                    .svg
+                    .consume_observation(
+                        frame.info().with_observatory(found),
+                        &frame.observations,
+                        &frame.constellations,
+                    )
+                    .to_string(),
+            },
+            _ => Response {
+                status: 200,
+                content_type: JSON_CONTENT_TYPE,
+                body: observations_json(&frame).to_string(),
+            },
+        }
+    }
+}
+
+/// Returns the bodies in `frame` and their locations as JSON, with angles in radians and
+/// distances in light seconds
+fn observations_json(frame: &Frame) -> serde_json::Value {
+    let observations: Vec<_> = frame
+        .observations
+        .iter()
+        .map(|(body, location)| {
+            let name = body
+                .read()
+                .map_or_else(|b| b.into_inner().get_name(), |b| b.get_name());
+            serde_json::json!({
+                "body": &*name,
+                "distance": location.radius,
+                "polarAngle": location.polar_angle,
+                "azimuthalAngle": location.azimuthal_angle,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "observatory": frame.directory,
+        "time": frame.time.to_string(),
+        "observations": observations,
+    })
+}
+
+/// Decodes `%XX` escapes in a path segment, or returns `None` if they aren't valid UTF-8
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| segment.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escaped {
+            result.push(byte);
+            index += 3;
+        } else {
+            result.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(result).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves the solar system program with its first observatory named "Two Words"
+    fn server() -> Server {
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../../assets/solar-system.program.json")).unwrap();
+        json["observatories"][0]["name"] = "Two Words".into();
+        let program: Program = serde_json::from_value(json).unwrap();
+        Server::new(program, Svg::new(StatelessOrthographic()))
+    }
+
+    #[test]
+    fn serves_charts_and_observations() {
+        let server = server();
+
+        let chart = server.respond("GET", "/chart/Two%20Words/12.svg");
+        assert_eq!(chart.status, 200, "{}", chart.body);
+        assert_eq!(chart.content_type, SVG_CONTENT_TYPE);
+        assert!(chart.body.starts_with("<svg"));
+        assert!(chart.body.contains("0000000012"));
+
+        let observations = server.respond("GET", "/observations/Two%20Words/12.json?pretty");
+        assert_eq!(observations.status, 200, "{}", observations.body);
+        assert_eq!(observations.content_type, JSON_CONTENT_TYPE);
+        let json: serde_json::Value = serde_json::from_str(&observations.body).unwrap();
+        let frame = server.frame("Two Words", 12).unwrap();
+        assert_eq!(json["observatory"], "Two Words");
+        assert_eq!(json["time"], "12");
+        assert_eq!(
+            json["observations"].as_array().unwrap().len(),
+            frame.observations.len()
+        );
+        // Both requests were answered from the same frame
+        assert!(Arc::ptr_eq(&frame, &server.frame("Two Words", 12).unwrap()));
+        assert_eq!(server.cache.lock().unwrap().frames.len(), 1);
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let mut server = server();
+        server.set_cache_capacity(2);
+
+        for (method, path, status) in [
+            ("POST", "/chart/Two%20Words/0.svg", 405),
+            ("GET", "/chart/Two%20Words/noon.svg", 400),
+            ("GET", "/chart/Two%20Words/0.png", 404),
+            ("GET", "/chart/Nowhere/0.svg", 404),
+            ("GET", "/chart/Two%20Words", 404),
+            ("GET", "/chart/Two%20Words/0.svg/more", 404),
+            ("GET", "/observations/%FF/0.json", 400),
+            ("GET", "/", 404),
+        ] {
+            let response = server.respond(method, path);
+            assert_eq!(response.status, status, "{method} {path}");
+            assert_eq!(response.content_type, JSON_CONTENT_TYPE);
+            assert!(response.body.contains("\"error\""));
+        }
+
+        for time in 0..5 {
+            assert!(server.frame("Two Words", time).is_some());
+        }
+        let cache = server.cache.lock().unwrap();
+        assert_eq!(cache.frames.len(), 2);
+        assert_eq!(
+            cache.order,
+            [("Two Words".to_owned(), 3), ("Two Words".to_owned(), 4)]
+        );
+    }
+}
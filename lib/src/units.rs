@@ -0,0 +1,52 @@
+use crate::{consts::ASTRONOMICAL_UNIT, Float};
+
+/// Number of kilometres light travels in one second
+pub const KILOMETRES_PER_LIGHT_SECOND: Float = 299_792.458;
+
+/// Mean radius of the Earth in light seconds
+pub const EARTH_RADIUS: Float = 0.021_251_398;
+
+/// Nominal radius of the Sun in light seconds
+pub const SOLAR_RADIUS: Float = 695_700.0 / KILOMETRES_PER_LIGHT_SECOND;
+
+/// Units that lengths such as [radii](crate::body::Body::set_radius) can be given in, converted
+/// to the light seconds used by the rest of the simulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Length {
+    /// Light seconds, the unit used internally
+    LightSeconds,
+    /// Kilometres
+    Kilometres,
+    /// Multiples of the [radius of the Earth](EARTH_RADIUS)
+    EarthRadii,
+    /// Multiples of the [radius of the Sun](SOLAR_RADIUS)
+    SolarRadii,
+    /// Multiples of the [distance from the Earth to the Sun](ASTRONOMICAL_UNIT)
+    AstronomicalUnits,
+}
+
+impl Length {
+    /// Returns the length of one of this unit in light seconds
+    #[must_use]
+    pub fn in_light_seconds(self) -> Float {
+        match self {
+            Self::LightSeconds => 1.0,
+            Self::Kilometres => 1.0 / KILOMETRES_PER_LIGHT_SECOND,
+            Self::EarthRadii => EARTH_RADIUS,
+            Self::SolarRadii => SOLAR_RADIUS,
+            Self::AstronomicalUnits => ASTRONOMICAL_UNIT,
+        }
+    }
+
+    /// Converts `value` of this unit to light seconds
+    #[must_use]
+    pub fn to_light_seconds(self, value: Float) -> Float {
+        value * self.in_light_seconds()
+    }
+
+    /// Converts `light_seconds` to this unit
+    #[must_use]
+    pub fn from_light_seconds(self, light_seconds: Float) -> Float {
+        light_seconds / self.in_light_seconds()
+    }
+}
@@ -0,0 +1,113 @@
+use coordinates::prelude::Magnitude;
+
+use crate::{body::Arc, consts::float, Float};
+
+/// Angular error in radians as seen from a body's parent, above which [`precision_report`] warns
+/// about it. About a pixel in a thousand pixel wide chart of the sky.
+pub const ANGULAR_ERROR_THRESHOLD: Float = 1e-3;
+
+/// A body whose position may be visibly wrong because of the limited precision of [`Float`]. See
+/// [`precision_report`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrecisionWarning {
+    /// Name of the body
+    pub body: std::sync::Arc<str>,
+    /// Estimated error in radians of the body's position as seen from its parent
+    pub angular_error: Float,
+}
+
+impl std::fmt::Display for PrecisionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} may be out of place by up to {}° as seen from its parent",
+            self.body,
+            self.angular_error.to_degrees()
+        )?;
+        if Float::EPSILON > 1e-10 {
+            write!(f, ", build with the `f64` feature for more precision")?;
+        }
+        Ok(())
+    }
+}
+
+/// Estimates how far off the computed position of each body in the tree could be from rounding,
+/// as an angle seen from its parent, up to `max_time` hours. Returns a warning for each body
+/// where it's more than [`ANGULAR_ERROR_THRESHOLD`].
+///
+/// Positions are summed from the root of the tree, so a body picks up rounding errors
+/// proportional to its distance from the root, which matters most for tight orbits far from the
+/// root. Times are rounded too, so bodies also drift along their orbits when `max_time` is many
+/// of their periods.
+#[must_use]
+pub fn precision_report(root: &Arc, max_time: Float) -> Vec<PrecisionWarning> {
+    let mut result = Vec::new();
+    let mut stack: Vec<_> = root
+        .read()
+        .map(|root| root.get_children().clone())
+        .unwrap_or_default();
+    while let Some(body) = stack.pop() {
+        let Ok(body) = body.read() else {
+            continue;
+        };
+        stack.extend(body.get_children().iter().cloned());
+
+        let offset = body.get_dynamic().get_offset(0.0).magnitude();
+        if offset == 0.0 {
+            // Sits on its parent, so there is no direction to be wrong about
+            continue;
+        }
+        let distance_from_root = body
+            .get_absolute_position(0.0)
+            .magnitude()
+            .max(body.get_absolute_position(max_time).magnitude());
+        let positional_error = distance_from_root * Float::EPSILON / offset;
+        let phase_error = body.get_dynamic().get_period().map_or(0.0, |period| {
+            float::TAU * (max_time / period).abs() * Float::EPSILON
+        });
+
+        let angular_error = positional_error + phase_error;
+        if angular_error > ANGULAR_ERROR_THRESHOLD {
+            result.push(PrecisionWarning {
+                body: body.get_name(),
+                angular_error,
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+
+    use super::*;
+    use crate::{
+        body::Body,
+        dynamic::{fixed::Fixed, keplerian::Keplerian},
+    };
+
+    #[test]
+    fn tight_moon_far_from_root_flagged_in_f32() {
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let star = Body::new(Some(root.clone()), Fixed::new(Vector3::RIGHT * 3e12));
+        let planet = Body::new(
+            Some(star.clone()),
+            Keplerian::new(0.01, 500.0, 0.0, 0.0, 0.0, 0.0, 1_048.0),
+        );
+        let _moon = Body::new(
+            Some(planet.clone()),
+            Keplerian::new(0.01, 2.0, 0.0, 0.0, 0.0, 0.0, 1.0),
+        );
+        Body::hydrate_all(&root, &None);
+
+        let report = precision_report(&root, 1e5);
+
+        if std::mem::size_of::<Float>() == 8 {
+            assert!(report.is_empty(), "{report:?}");
+        } else {
+            let moon = report.iter().find(|warning| &*warning.body == "0-0-0");
+            assert!(moon.unwrap().to_string().contains("f64"), "{report:?}");
+        }
+    }
+}
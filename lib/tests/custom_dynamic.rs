@@ -0,0 +1,63 @@
+//! A dynamic defined outside the crate, registered with the re-exported `typetag` so it is read
+//! and written with the rest of a universe.
+
+use astrograph::{
+    body::{Arc, Body},
+    dynamic::{fixed::Fixed, typetag, DynPartialEq, Dynamic},
+    json, Float,
+};
+use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Moves back and forth along a line through its parent, like a body on a spring
+#[derive(Clone, Debug, PartialEq, DynPartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Oscillating {
+    /// Furthest the body gets from its parent, in light seconds
+    amplitude: Vector3<Float>,
+    /// Time to go back and forth once, in hours
+    period: Float,
+}
+
+#[typetag::serde]
+impl Dynamic for Oscillating {
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        self.amplitude * (time / self.period * astrograph::consts::float::TAU).sin()
+    }
+
+    fn get_period(&self) -> Option<Float> {
+        Some(self.period)
+    }
+}
+
+#[test]
+fn custom_dynamic_round_trips() {
+    let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+    let dynamic = Oscillating {
+        amplitude: Vector3::UP * 3.0,
+        period: 8.0,
+    };
+    Body::new(Some(root.clone()), dynamic.clone());
+    Body::new(Some(root.clone()), Fixed::new(Vector3::RIGHT));
+
+    let universe = serde_json::to_string(&root).unwrap();
+    assert!(universe.contains(r#""Oscillating":{"amplitude""#));
+
+    let read: Arc = json::from_str::<Body>(&universe).unwrap().into();
+    Body::hydrate_all(&read, &None);
+    let read = read.read().unwrap();
+    let children = read.get_children();
+    assert_eq!(children.len(), 2);
+    let oscillating = children[0].read().unwrap();
+    assert!(oscillating.get_dynamic().box_eq(dynamic.as_any()));
+    for time in [0.0, 2.0, 5.0] {
+        assert_eq!(
+            oscillating.get_dynamic().get_offset(time),
+            dynamic.get_offset(time)
+        );
+    }
+    assert_eq!(oscillating.get_dynamic().get_period(), Some(8.0));
+
+    // Written back the same way
+    assert_eq!(serde_json::to_string(&*read).unwrap(), universe);
+}
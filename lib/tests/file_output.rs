@@ -47,7 +47,7 @@ fn file_output() -> Result<(), ProgramBuilderError> {
         .observatories(observatories.clone())
         .build()?;
 
-    program.make_observations(0, 100, Some(1));
+    program.make_observations(0, 100, Some(1)).unwrap();
 
     for time in 0..100 {
         for observatory in &observatories {
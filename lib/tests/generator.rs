@@ -11,7 +11,8 @@ fn inclinations() {
         .star_count(5_000)
         .build()
         .unwrap()
-        .generate(&mut rng);
+        .generate(&mut rng)
+        .unwrap();
 
     for star in root.read().unwrap().get_children() {
         let star = star.read().unwrap();
@@ -0,0 +1,192 @@
+//! Renders a fixed set of scenes through each projection and compares them to checked in SVGs in
+//! `tests/golden/`, so changes to rendering are always deliberate.
+//!
+//! Run with `ASTROGRAPH_UPDATE_GOLDEN=1` to regenerate the files after an intended change, once
+//! with the default features and once with `--no-default-features` (f32 and f64 builds render
+//! slightly differently, so each has its own files).
+
+use std::path::PathBuf;
+
+use astrograph::{
+    body::{
+        observatory::{to_observatory, Observatory, WeakObservatory},
+        Arc, Body,
+    },
+    consts::float,
+    generator::{artifexian::ArtifexianBuilder, Generator},
+    output::{
+        svg::{compass_rose, Svg},
+        svg_diff::{self, Difference},
+        FrameInfo,
+    },
+    projection::{Orthographic, Projection, StatelessOrthographic},
+    testing::{make_toy_example, DEFAULT_SEED},
+    Float,
+};
+use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+/// Relative difference allowed between numbers in the goldens and the rendered documents
+const TOLERANCE: Float = 1e-4;
+
+/// Returns the directory goldens are kept in for the current float precision
+fn golden_directory() -> PathBuf {
+    let precision = if cfg!(feature = "f64") { "f64" } else { "f32" };
+    [env!("CARGO_MANIFEST_DIR"), "tests", "golden", precision]
+        .iter()
+        .collect()
+}
+
+/// Renders a frame through every projection, checking each against its golden
+fn check_scene(scene: &str, observatory: &Observatory, time: i128) {
+    let off_center = Orthographic::new(float::FRAC_PI_4, float::FRAC_PI_3);
+    check_projection(
+        &format!("{scene}-stateless-orthographic"),
+        StatelessOrthographic(),
+        observatory,
+        time,
+    );
+    check_projection(
+        &format!("{scene}-orthographic"),
+        off_center,
+        observatory,
+        time,
+    );
+}
+
+/// Renders a frame, writing it as the golden if asked to, otherwise comparing it to the golden
+fn check_projection<P>(name: &str, projector: P, observatory: &Observatory, time: i128)
+where
+    P: Projection + Clone + std::fmt::Debug + Send + Sync + 'static,
+{
+    let path = golden_directory().join(format!("{name}.svg"));
+    let rendered = render(projector, observatory, time);
+
+    if std::env::var_os("ASTROGRAPH_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden_directory()).unwrap();
+        std::fs::write(&path, rendered).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "Could not read {}, run with ASTROGRAPH_UPDATE_GOLDEN=1 to create it: {e}",
+            path.display()
+        )
+    });
+    let differences = svg_diff::compare(
+        &svg_diff::parse(&golden).unwrap(),
+        &svg_diff::parse(&rendered).unwrap(),
+        TOLERANCE,
+    );
+
+    assert!(
+        differences.is_empty(),
+        "{} differs from the rendered frame, run with ASTROGRAPH_UPDATE_GOLDEN=1 if this was intended:\n{}",
+        path.display(),
+        differences
+            .iter()
+            .map(Difference::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+/// Renders a frame from an observatory to a string
+fn render<P>(projector: P, observatory: &Observatory, time: i128) -> String
+where
+    P: Projection + Clone + std::fmt::Debug + Send + Sync + 'static,
+{
+    #[allow(clippy::cast_precision_loss)]
+    let observations = observatory.observe(time as Float);
+    let constellations = observatory.add_constelatations(&observations);
+
+    let mut output = Svg::new(projector);
+    output.add_layer(Box::new(compass_rose));
+    output
+        .consume_observation(
+            FrameInfo::new(&observatory.get_name(), time).with_observatory(observatory),
+            &observations,
+            &constellations,
+        )
+        .to_string()
+}
+
+/// Reads a universe and its observatories from JSON files
+fn load(universe: &str, observatories: &str) -> (Arc, Vec<Observatory>) {
+    let root: Arc = astrograph::json::from_str::<Body>(universe).unwrap().into();
+    let observatories: Vec<WeakObservatory> = serde_json::from_str(observatories).unwrap();
+    let observatories = observatories
+        .into_iter()
+        .map(|o| to_observatory(o, &root).unwrap())
+        .collect();
+    (root, observatories)
+}
+
+#[test]
+fn toy_example() {
+    let (root, observer) = make_toy_example(DEFAULT_SEED);
+    Body::hydrate_all(&root, &None);
+    let observatory = Observatory::new(Spherical::UP, observer, Ok("Toy".into()), vec![]);
+
+    check_scene("toy", &observatory, 0);
+}
+
+#[test]
+fn solar_system() {
+    let (_root, observatories) = load(
+        include_str!("../../assets/solar-system.json"),
+        include_str!("../../assets/solar-system.observatories.json"),
+    );
+
+    // Day, night, and half a year later
+    for time in [0, 12, 4_383] {
+        check_scene(&format!("solar-system-{time}"), &observatories[0], time);
+    }
+}
+
+#[test]
+fn constellations() {
+    let (_root, observatories) = load(
+        include_str!("../../assets/test/constellation/universe.json"),
+        include_str!("../../assets/test/constellation/observatories.json"),
+    );
+
+    check_scene("constellation", &observatories[0], 0);
+}
+
+#[test]
+fn generated() {
+    let mut rng = XorShiftRng::from_seed(DEFAULT_SEED.to_be_bytes());
+    let (root, observatories) = ArtifexianBuilder::default()
+        .star_count(20)
+        .build()
+        .unwrap()
+        .generate(&mut rng)
+        .unwrap();
+    Body::hydrate_all(&root, &None);
+
+    check_scene("generated", &observatories[0], 0);
+}
+
+#[test]
+fn radius_change_is_caught() {
+    let (_root, observatories) = load(
+        include_str!("../../assets/solar-system.json"),
+        include_str!("../../assets/solar-system.observatories.json"),
+    );
+    let rendered = svg_diff::parse(&render(StatelessOrthographic(), &observatories[0], 0)).unwrap();
+
+    let mut changed = rendered.clone();
+    let body = changed
+        .iter_mut()
+        .find(|e| e.name == "circle" && e.attributes["class"] != "outer")
+        .unwrap();
+    let radius: Float = body.attributes["r"].parse().unwrap();
+    body.attributes
+        .insert("r".into(), (radius * 1.01).to_string());
+
+    assert!(svg_diff::compare(&rendered, &rendered, TOLERANCE).is_empty());
+    assert_eq!(svg_diff::compare(&rendered, &changed, TOLERANCE).len(), 2);
+}
@@ -0,0 +1,104 @@
+//! Inputs distilled from crashes found by the fuzz targets in `fuzz/`, which must be rejected
+//! with an error rather than panicking.
+
+use astrograph::{
+    body::{observatory::Observatory, Arc, Body},
+    json::{self, Error},
+    program::Program,
+};
+use coordinates::prelude::{Spherical, ThreeDimensionalConsts};
+
+/// Reads a body tree, then observes it from the first child like the fuzz target does
+fn read_and_observe(json: &str) -> Result<usize, Error> {
+    let root: Arc = json::from_str::<Body>(json)?.into();
+    let observer = root.read().unwrap().get_children().first().cloned();
+    let observatory = Observatory::new(
+        Spherical::UP,
+        observer.unwrap_or(root),
+        Ok("Regression".into()),
+        vec![],
+    );
+    Ok(observatory.observe(1.0).len())
+}
+
+/// Returns true if the error is from reading the JSON, rather than a panic or another kind
+fn is_parse_error<T>(result: &Result<T, Error>) -> bool {
+    matches!(result, Err(Error::Parse(_)))
+}
+
+#[test]
+fn observatory_body_id_out_of_range() {
+    let program = r#"{
+        "rootBody": {"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}},
+        "observatories": [{"location": {"r": 1, "phi": 0, "theta": 0}, "bodyId": [7]}],
+        "outputFileRoot": "output"
+    }"#;
+
+    let result = json::from_str::<Program>(program);
+
+    assert!(is_parse_error(&result));
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("[7]"), "{message}");
+}
+
+#[test]
+fn observatory_body_id_too_deep() {
+    let program = r#"{
+        "rootBody": {"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "children": [
+            {"dynamic": {"Fixed": {"x": 1, "y": 0, "z": 0}}}
+        ]},
+        "observatories": [
+            {"location": {"r": 1, "phi": 0, "theta": 0}, "bodyId": [0, 18446744073709551615]}
+        ],
+        "outputFileRoot": "output"
+    }"#;
+
+    assert!(is_parse_error(&json::from_str::<Program>(program)));
+}
+
+#[test]
+fn numbers_out_of_range() {
+    // Larger than any float, serde_json rejects this itself
+    let json = r#"{"dynamic": {"Fixed": {"x": 1e999, "y": 0, "z": 0}}}"#;
+    assert!(is_parse_error(&read_and_observe(json)));
+
+    // Fits in an f64, but becomes infinite when read as an f32
+    let json = r#"{"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "children": [{
+        "dynamic": {"Keplerian": {
+            "e": 0, "a": 1e300, "i": 0, "ascendingNode": 0, "trueAnomaly": 0, "argPeri": 0,
+            "period": 1
+        }}
+    }]}"#;
+    if cfg!(feature = "f64") {
+        assert!(read_and_observe(json).is_ok());
+    } else {
+        assert!(is_parse_error(&read_and_observe(json)));
+    }
+}
+
+#[test]
+fn negative_radius_and_mass() {
+    let json = r#"{"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "radius": -1}"#;
+    assert!(is_parse_error(&read_and_observe(json)));
+
+    let json = r#"{"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "mass": -1}"#;
+    assert!(is_parse_error(&read_and_observe(json)));
+
+    let json = r#"{"dynamic": {"Fixed": {"x": 0, "y": 0, "z": 0}}, "radius": 0, "mass": 1}"#;
+    assert_eq!(read_and_observe(json).unwrap(), 0);
+}
+
+#[test]
+fn deeply_nested_children() {
+    let mut json = String::new();
+    for _ in 0..10_000 {
+        json.push_str(r#"{"dynamic": {"Fixed": {"x": 1, "y": 0, "z": 0}}, "children": ["#);
+    }
+
+    // Never finished, so always an error, but never a stack overflow
+    let result = read_and_observe(&json);
+    assert!(matches!(
+        result,
+        Err(Error::Parse(_) | Error::TooDeep { .. })
+    ));
+}
@@ -50,7 +50,7 @@ pub fn generate_observations_from_json(
     let observatories: Vec<Observatory> = observatories
         .into_iter()
         .map(|o| observatory::to_observatory(o, &root))
-        .collect();
+        .collect::<Result<_, _>>()?;
 
     // Avoid potential zero step size
     let step_size = step_size.filter(|x| *x != 0);
@@ -62,7 +62,7 @@ pub fn generate_observations_from_json(
         .observatories(observatories)
         .build()?;
 
-    program.make_observations(start_time, end_time, step_size);
+    program.make_observations(start_time, end_time, step_size)?;
     Ok(())
 }
 
@@ -79,7 +79,8 @@ pub fn generate_universe_from_seed(seed: u64) -> JsValue {
             .star_count(1)
             .build()
             .unwrap()
-            .generate(&mut XorShiftRng::seed_from_u64(seed)),
+            .generate(&mut XorShiftRng::seed_from_u64(seed))
+            .unwrap(),
     )
     .unwrap()
 }
@@ -97,7 +98,8 @@ pub fn generate_universe() -> JsValue {
             .star_count(100)
             .build()
             .unwrap()
-            .generate(&mut XorShiftRng::from_entropy()),
+            .generate(&mut XorShiftRng::from_entropy())
+            .unwrap(),
     )
     .unwrap()
 }
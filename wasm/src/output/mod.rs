@@ -2,7 +2,7 @@ use astrograph::projection;
 
 use astrograph::{
     constellation::Line,
-    output::{svg as astro_svg, Output},
+    output::{svg as astro_svg, FrameInfo, Output},
 };
 use rayon::prelude::*;
 use wasm_bindgen::prelude::*;
@@ -25,19 +25,18 @@ impl Output for Web {
         &self,
         observations: &[astrograph::LocalObservation],
         constellations: &[Line],
-        _observatory_name: &str,
-        time: i128,
+        frame: FrameInfo<'_>,
         _output_path_root: &std::path::Path,
     ) -> Result<(), std::io::Error> {
         let observations = astro_svg::new_document(
-            &format!("{time}"),
+            &format!("{}", frame.time),
             observations,
             constellations,
             &projection::StatelessOrthographic(),
         );
 
         if let Ok(mut hash_map) = self.observations.write() {
-            hash_map.insert(time, observations);
+            hash_map.insert(frame.time, observations);
         }
 
         Ok(())
@@ -58,6 +57,11 @@ impl Output for Web {
             ))
         }
     }
+
+    /// Lines are drawn on the charts shown in the page
+    fn wants_constellations(&self) -> bool {
+        true
+    }
 }
 
 impl Default for Web {